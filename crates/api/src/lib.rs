@@ -4,6 +4,7 @@
 pub mod v1;
 
 use serde::{de::Unexpected, Deserialize, Serialize};
+use warg_crypto::signing::KeyID;
 
 /// Relative URL path for the `WellKnownConfig`.
 pub const WELL_KNOWN_PATH: &str = ".well-known/wasm-pkg/registry.json";
@@ -21,6 +22,43 @@ pub struct WellKnownConfig {
     pub warg_url: Option<String>,
 }
 
+/// Relative URL path for the `RegistryDiscovery` document.
+pub const DISCOVERY_PATH: &str = ".well-known/warg/discovery.json";
+
+/// Describes the capabilities of a Warg registry so that clients can detect
+/// incompatibilities before interacting with it.
+///
+/// URLs in the document are relative to the registry URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RegistryDiscovery {
+    /// The supported API versions, such as `v1`.
+    pub api_versions: Vec<String>,
+    /// The supported hash algorithms, such as `sha256`.
+    pub hash_algorithms: Vec<String>,
+    /// The supported signature algorithms, such as `ecdsa-p256`.
+    pub signature_algorithms: Vec<String>,
+    /// The ID of the key with which the operator signs checkpoints.
+    ///
+    /// This is `None` for a registry without an operator key, such as a mirror.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator_key_id: Option<KeyID>,
+    /// The URL of the ledger sources.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ledger_url: Option<String>,
+    /// The URL to which witnesses submit checkpoint cosignatures.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_url: Option<String>,
+    /// The IDs of the witness keys trusted to cosign checkpoints.
+    ///
+    /// This is `None` if cosignatures by any witness key are accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_key_ids: Option<Vec<KeyID>>,
+    /// The maximum size, in bytes, of uploaded content.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_content_size: Option<u64>,
+}
+
 /// A utility type for serializing and deserializing constant status codes.
 struct Status<const CODE: u16>;
 
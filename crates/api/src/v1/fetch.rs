@@ -3,17 +3,18 @@
 use crate::Status;
 use indexmap::IndexMap;
 use serde::{de::Unexpected, Deserialize, Serialize, Serializer};
+use serde_with::{base64::Base64, serde_as};
 use std::borrow::Cow;
 use std::str::FromStr;
 use thiserror::Error;
 use warg_crypto::hash::AnyHash;
 use warg_protocol::{
-    registry::{LogId, PackageName, RegistryLen},
-    PublishedProtoEnvelopeBody,
+    registry::{LogId, PackageName, RegistryLen, TimestampedCheckpoint},
+    PublishedProtoEnvelopeBody, SerdeEnvelope,
 };
 
 /// Wraps the PublishedProtoEnvelopeBody with a fetch token.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishedRecord {
     /// Record proto envelope body with RegistryIndex.
@@ -58,6 +59,49 @@ pub struct FetchLogsResponse {
     pub warnings: Vec<FetchWarning>,
 }
 
+/// Represents a fetch package response.
+///
+/// The response carries everything needed to verify a single package log
+/// against the latest checkpoint without fetching any other package logs.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchPackageResponse {
+    /// The latest checkpoint.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The operator records up to the checkpoint.
+    pub operator: Vec<PublishedRecord>,
+    /// The package records up to the checkpoint.
+    pub records: Vec<PublishedRecord>,
+    /// The bytes of the map multi-proof bundle of the operator and package
+    /// log heads at the checkpoint.
+    #[serde_as(as = "Base64")]
+    pub map: Vec<u8>,
+}
+
+/// Represents the query of a fetch checkpoints request.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct FetchCheckpointsQuery {
+    /// Only checkpoints with a log length greater than this are fetched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<RegistryLen>,
+    /// The limit for the number of checkpoints to fetch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u16>,
+}
+
+/// Represents a fetch checkpoints response.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchCheckpointsResponse {
+    /// Whether there are more checkpoints to fetch.
+    #[serde(default)]
+    pub more: bool,
+    /// The checkpoints, in ascending order of log length.
+    pub checkpoints: Vec<SerdeEnvelope<TimestampedCheckpoint>>,
+}
+
 /// Represents a fetch package names request.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -76,6 +120,36 @@ pub struct FetchPackageNamesResponse {
     pub packages: IndexMap<LogId, Option<PackageName>>,
 }
 
+/// Represents a watch request.
+///
+/// The server responds once a checkpoint beyond `log_length` is available and,
+/// if `packages` is not empty, one of the given package logs has new records;
+/// otherwise it responds when a server-defined timeout elapses.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WatchRequest<'a> {
+    /// The last known checkpoint log length.
+    pub log_length: RegistryLen,
+    /// The package logs to watch; if empty, any new checkpoint is reported.
+    #[serde(default, skip_serializing_if = "<[_]>::is_empty")]
+    pub packages: Cow<'a, [LogId]>,
+}
+
+/// Represents a watch response.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchResponse {
+    /// The latest checkpoint.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The watched package logs with new records between the requested log
+    /// length and the checkpoint.
+    ///
+    /// The server may include logs without new records when the range is
+    /// too large to inspect; clients should verify by fetching the logs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<LogId>,
+}
+
 /// A warning message.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct FetchWarning {
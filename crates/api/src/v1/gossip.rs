@@ -0,0 +1,136 @@
+//! Types relating to the gossip API.
+
+use crate::Status;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use thiserror::Error;
+use warg_crypto::signing::KeyID;
+use warg_protocol::{
+    registry::{RegistryLen, TimestampedCheckpoint},
+    SerdeEnvelope,
+};
+
+/// Represents a request to exchange observed checkpoints with a peer.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GossipRequest {
+    /// The latest checkpoint observed by the requester.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+}
+
+/// Represents a response to a checkpoint gossip request.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GossipResponse {
+    /// The latest checkpoint observed by the peer.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+}
+
+/// Represents a gossip API error.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum GossipError {
+    /// The signature of the gossiped checkpoint is invalid.
+    #[error("gossiped checkpoint has an invalid signature by key `{0}`")]
+    InvalidCheckpointSignature(KeyID),
+    /// The gossiped checkpoint conflicts with the peer's checkpoint at the
+    /// same log length.
+    #[error("gossiped checkpoint conflicts with the checkpoint at log length {log_length}")]
+    ConflictingCheckpoint {
+        /// The log length of the gossiped checkpoint.
+        log_length: RegistryLen,
+        /// The peer's checkpoint at the same log length, if it has one.
+        checkpoint: Option<Box<SerdeEnvelope<TimestampedCheckpoint>>>,
+    },
+    /// An error with a message occurred.
+    #[error("{message}")]
+    Message {
+        /// The HTTP status code.
+        status: u16,
+        /// The error message
+        message: String,
+    },
+}
+
+impl GossipError {
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::InvalidCheckpointSignature(_) => 422,
+            Self::ConflictingCheckpoint { .. } => 409,
+            Self::Message { status, .. } => *status,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged, rename_all = "camelCase")]
+enum RawError<'a> {
+    #[serde(rename_all = "camelCase")]
+    InvalidCheckpointSignature {
+        status: Status<422>,
+        key_id: Cow<'a, KeyID>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ConflictingCheckpoint {
+        status: Status<409>,
+        log_length: RegistryLen,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        checkpoint: Option<Cow<'a, SerdeEnvelope<TimestampedCheckpoint>>>,
+    },
+    Message {
+        status: u16,
+        message: Cow<'a, str>,
+    },
+}
+
+impl Serialize for GossipError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::InvalidCheckpointSignature(key_id) => RawError::InvalidCheckpointSignature {
+                status: Status::<422>,
+                key_id: Cow::Borrowed(key_id),
+            }
+            .serialize(serializer),
+            Self::ConflictingCheckpoint {
+                log_length,
+                checkpoint,
+            } => RawError::ConflictingCheckpoint {
+                status: Status::<409>,
+                log_length: *log_length,
+                checkpoint: checkpoint.as_deref().map(Cow::Borrowed),
+            }
+            .serialize(serializer),
+            Self::Message { status, message } => RawError::Message {
+                status: *status,
+                message: Cow::Borrowed(message),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GossipError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawError::deserialize(deserializer)? {
+            RawError::InvalidCheckpointSignature { status: _, key_id } => {
+                Ok(Self::InvalidCheckpointSignature(key_id.into_owned()))
+            }
+            RawError::ConflictingCheckpoint {
+                status: _,
+                log_length,
+                checkpoint,
+            } => Ok(Self::ConflictingCheckpoint {
+                log_length,
+                checkpoint: checkpoint.map(|c| Box::new(c.into_owned())),
+            }),
+            RawError::Message { status, message } => Ok(Self::Message {
+                status,
+                message: message.into_owned(),
+            }),
+        }
+    }
+}
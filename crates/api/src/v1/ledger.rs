@@ -6,6 +6,15 @@ use thiserror::Error;
 use warg_crypto::hash::HashAlgorithm;
 use warg_protocol::registry::RegistryIndex;
 
+/// Represents the query of a get ledger sources request.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LedgerSourcesQuery {
+    /// The content type of the ledger sources; defaults to packed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<LedgerSourceContentType>,
+}
+
 /// Represents response a get ledger sources request.
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -38,7 +47,7 @@ fn is_false(b: &bool) -> bool {
 }
 
 /// Content type for the ledger source.
-#[derive(Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Default, Clone, Copy, PartialEq, Serialize, Deserialize, Debug)]
 pub enum LedgerSourceContentType {
     /// The content type is binary representation of the LogId and RecordId hashes without padding.
     /// In the case of `sha256` hash algorithm, this is a repeating sequence of 64 bytes (32 bytes
@@ -46,6 +55,12 @@ pub enum LedgerSourceContentType {
     #[default]
     #[serde(rename = "application/vnd.warg.ledger.packed")]
     Packed,
+    /// The content type is a sequence of length-delimited `LedgerRecord` protobuf messages, each
+    /// containing the LogId and RecordId hashes, the registry index, and the record envelope.
+    ///
+    /// See `warg_protocol::ledger::LedgerRecord` for decoding the records.
+    #[serde(rename = "application/vnd.warg.ledger.envelopes")]
+    Envelopes,
 }
 
 impl LedgerSourceContentType {
@@ -53,6 +68,7 @@ impl LedgerSourceContentType {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Packed => "application/vnd.warg.ledger.packed",
+            Self::Envelopes => "application/vnd.warg.ledger.envelopes",
         }
     }
 }
@@ -2,14 +2,21 @@
 
 pub mod content;
 pub mod fetch;
+pub mod gossip;
 pub mod ledger;
 pub mod monitor;
 pub mod package;
 pub mod paths;
 pub mod proof;
+pub mod tile;
+pub mod witness;
 
 use serde::{Deserialize, Serialize};
 
+/// The API version described by this module, as listed in a registry's
+/// discovery document.
+pub const API_VERSION: &str = "v1";
+
 /// The HTTP request and response header name that specifies the registry domain whose data is the
 /// subject of the request. This header is only expected to be used if referring to a different
 /// registry than the host registry.
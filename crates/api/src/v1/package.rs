@@ -52,6 +52,10 @@ pub struct PublishRecordRequest<'a> {
     /// A registry may not support specifying content sources directly.
     #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub content_sources: IndexMap<AnyHash, Vec<ContentSource>>,
+    /// The identity token proving the publisher identity of a record
+    /// signed by an ephemeral key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_token: Option<Cow<'a, str>>,
 }
 
 /// Represents a package record API entity in a registry.
@@ -1,7 +1,7 @@
 //! The paths of the Warg REST API.
 
 use warg_crypto::hash::AnyHash;
-use warg_protocol::registry::{LogId, RecordId};
+use warg_protocol::registry::{LogId, RecordId, RegistryLen};
 
 /// The path of the "fetch logs" API.
 pub fn fetch_logs() -> &'static str {
@@ -13,11 +13,26 @@ pub fn fetch_checkpoint() -> &'static str {
     "v1/fetch/checkpoint"
 }
 
+/// The path of the "fetch checkpoints" API.
+pub fn fetch_checkpoints() -> &'static str {
+    "v1/fetch/checkpoints"
+}
+
+/// The path of the "fetch package" API.
+pub fn fetch_package(log_id: &LogId) -> String {
+    format!("v1/fetch/package/{log_id}")
+}
+
 /// The path of the "fetch package names" API.
 pub fn fetch_package_names() -> &'static str {
     "v1/fetch/names"
 }
 
+/// The path of the "watch" API.
+pub fn watch() -> &'static str {
+    "v1/fetch/watch"
+}
+
 /// The path of the get ledger sources.
 pub fn ledger_sources() -> &'static str {
     "v1/ledger"
@@ -48,7 +63,37 @@ pub fn prove_inclusion() -> &'static str {
     "v1/proof/inclusion"
 }
 
+/// The path for proving package absence.
+pub fn prove_absence() -> &'static str {
+    "v1/proof/absence"
+}
+
+/// The path of a full log tile.
+pub fn log_tile(level: usize, index: usize) -> String {
+    format!("v1/tile/{level}/{index}")
+}
+
+/// The path of a partial log tile with the given number of hashes.
+pub fn partial_log_tile(level: usize, index: usize, width: usize) -> String {
+    format!("v1/tile/{level}/{index}/p/{width}")
+}
+
 /// The path for verifying a checkpoint.
 pub fn verify_checkpoint() -> &'static str {
     "v1/verify/checkpoint"
 }
+
+/// The path for exchanging observed checkpoints.
+pub fn gossip_checkpoint() -> &'static str {
+    "v1/gossip/checkpoint"
+}
+
+/// The path for submitting a witness cosignature.
+pub fn witness_cosign() -> &'static str {
+    "v1/witness/cosignature"
+}
+
+/// The path for a checkpoint and its witness cosignatures.
+pub fn witness_checkpoint(log_length: RegistryLen) -> String {
+    format!("v1/witness/checkpoint/{log_length}")
+}
@@ -36,6 +36,13 @@ pub struct InclusionRequest {
     pub log_length: RegistryLen,
     /// The log leaf indexes in the registry log to check for inclusion.
     pub leafs: Vec<RegistryIndex>,
+    /// Whether to return the map inclusion proofs as a single multi-proof.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub map_multi_proof: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
 }
 
 /// Represents an inclusion proof response.
@@ -51,6 +58,26 @@ pub struct InclusionResponse {
     pub map: Vec<u8>,
 }
 
+/// Represents an absence proof request.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsenceRequest {
+    /// The log length to check for absence.
+    pub log_length: RegistryLen,
+    /// The log identifiers of the packages to check for absence.
+    pub log_ids: Vec<LogId>,
+}
+
+/// Represents an absence proof response.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AbsenceResponse {
+    /// The bytes of the map absence proof bundle.
+    #[serde_as(as = "Base64")]
+    pub map: Vec<u8>,
+}
+
 /// Represents a proof API error.
 #[non_exhaustive]
 #[derive(Debug, Error)]
@@ -64,6 +91,9 @@ pub enum ProofError {
     /// Failed to prove inclusion of a package.
     #[error("failed to prove inclusion of package log `{0}`")]
     PackageLogNotIncluded(LogId),
+    /// Failed to prove absence of a package.
+    #[error("failed to prove absence of package log `{0}`")]
+    PackageLogIncluded(LogId),
     /// The provided root for an inclusion proof was incorrect.
     #[error("failed to prove inclusion: found root `{found}` but was given root `{root}`")]
     IncorrectProof {
@@ -92,6 +122,7 @@ impl ProofError {
             Self::CheckpointNotFound(_) | Self::LeafNotFound(_) => 404,
             Self::BundleFailure(_)
             | Self::PackageLogNotIncluded(_)
+            | Self::PackageLogIncluded(_)
             | Self::IncorrectProof { .. } => 422,
             Self::Message { status, .. } => *status,
         }
@@ -111,6 +142,9 @@ enum BundleError<'a> {
     PackageNotIncluded {
         log_id: Cow<'a, LogId>,
     },
+    PackageIncluded {
+        log_id: Cow<'a, LogId>,
+    },
     IncorrectProof {
         root: Cow<'a, AnyHash>,
         found: Cow<'a, AnyHash>,
@@ -162,6 +196,13 @@ impl Serialize for ProofError {
                 },
             }
             .serialize(serializer),
+            Self::PackageLogIncluded(log_id) => RawError::BundleError {
+                status: Status::<422>,
+                error: BundleError::PackageIncluded {
+                    log_id: Cow::Borrowed(log_id),
+                },
+            }
+            .serialize(serializer),
             Self::IncorrectProof { root, found } => RawError::BundleError {
                 status: Status::<422>,
                 error: BundleError::IncorrectProof {
@@ -200,6 +241,9 @@ impl<'de> Deserialize<'de> for ProofError {
                 BundleError::PackageNotIncluded { log_id } => {
                     Ok(Self::PackageLogNotIncluded(log_id.into_owned()))
                 }
+                BundleError::PackageIncluded { log_id } => {
+                    Ok(Self::PackageLogIncluded(log_id.into_owned()))
+                }
                 BundleError::IncorrectProof { root, found } => Ok(Self::IncorrectProof {
                     root: root.into_owned(),
                     found: found.into_owned(),
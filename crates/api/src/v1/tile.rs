@@ -0,0 +1,118 @@
+//! Types relating to the log tile API.
+
+use crate::Status;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use thiserror::Error;
+
+/// The content type of a log tile.
+///
+/// A tile is the concatenated bytes of its hashes.
+pub const TILE_CONTENT_TYPE: &str = "application/vnd.warg.log.tile";
+
+/// Represents a log tile API error.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum TileError {
+    /// The requested tile was not found.
+    #[error("log tile {level}/{index} with width {width} was not found")]
+    TileNotFound {
+        /// The level of the tile.
+        level: usize,
+        /// The index of the tile within its level.
+        index: usize,
+        /// The number of hashes requested.
+        width: usize,
+    },
+    /// An error with a message occurred.
+    #[error("{message}")]
+    Message {
+        /// The HTTP status code.
+        status: u16,
+        /// The error message
+        message: String,
+    },
+}
+
+impl TileError {
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::TileNotFound { .. } => 404,
+            Self::Message { status, .. } => *status,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum EntityType {
+    Tile,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged, rename_all = "camelCase")]
+enum RawError<'a> {
+    #[serde(rename_all = "camelCase")]
+    NotFound {
+        status: Status<404>,
+        #[serde(rename = "type")]
+        ty: EntityType,
+        level: usize,
+        index: usize,
+        width: usize,
+    },
+    Message {
+        status: u16,
+        message: Cow<'a, str>,
+    },
+}
+
+impl Serialize for TileError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::TileNotFound {
+                level,
+                index,
+                width,
+            } => RawError::NotFound {
+                status: Status::<404>,
+                ty: EntityType::Tile,
+                level: *level,
+                index: *index,
+                width: *width,
+            }
+            .serialize(serializer),
+            Self::Message { status, message } => RawError::Message {
+                status: *status,
+                message: Cow::Borrowed(message),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TileError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawError::deserialize(deserializer)? {
+            RawError::NotFound {
+                status: _,
+                ty: EntityType::Tile,
+                level,
+                index,
+                width,
+            } => Ok(Self::TileNotFound {
+                level,
+                index,
+                width,
+            }),
+            RawError::Message { status, message } => Ok(Self::Message {
+                status,
+                message: message.into_owned(),
+            }),
+        }
+    }
+}
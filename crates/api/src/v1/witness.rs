@@ -0,0 +1,137 @@
+//! Types relating to the witness API.
+
+use crate::Status;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use thiserror::Error;
+use warg_crypto::signing::{KeyID, PublicKey};
+use warg_protocol::registry::{CheckpointCosignature, RegistryLen};
+
+/// Represents a request to submit a witness cosignature of a checkpoint.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CosignRequest {
+    /// The log length of the cosigned checkpoint.
+    pub log_length: RegistryLen,
+    /// The public key of the witness.
+    pub public_key: PublicKey,
+    /// The cosignature of the checkpoint.
+    pub cosignature: CheckpointCosignature,
+}
+
+/// Represents a witness API error.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum WitnessError {
+    /// The checkpoint could not be found for the provided log length.
+    #[error("checkpoint not found for log length {0}")]
+    CheckpointNotFound(RegistryLen),
+    /// The witness key is not trusted by the registry.
+    #[error("witness key `{0}` is not trusted by the registry")]
+    WitnessNotTrusted(KeyID),
+    /// The cosignature is not valid for the checkpoint.
+    #[error("cosignature by witness key `{0}` is invalid")]
+    InvalidCosignature(KeyID),
+    /// An error with a message occurred.
+    #[error("{message}")]
+    Message {
+        /// The HTTP status code.
+        status: u16,
+        /// The error message
+        message: String,
+    },
+}
+
+impl WitnessError {
+    /// Returns the HTTP status code of the error.
+    pub fn status(&self) -> u16 {
+        match self {
+            Self::CheckpointNotFound(_) => 404,
+            Self::WitnessNotTrusted(_) => 403,
+            Self::InvalidCosignature(_) => 422,
+            Self::Message { status, .. } => *status,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum EntityType {
+    LogLength,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged, rename_all = "camelCase")]
+enum RawError<'a> {
+    NotFound {
+        status: Status<404>,
+        #[serde(rename = "type")]
+        ty: EntityType,
+        id: RegistryLen,
+    },
+    #[serde(rename_all = "camelCase")]
+    WitnessNotTrusted {
+        status: Status<403>,
+        key_id: Cow<'a, KeyID>,
+    },
+    #[serde(rename_all = "camelCase")]
+    InvalidCosignature {
+        status: Status<422>,
+        key_id: Cow<'a, KeyID>,
+    },
+    Message {
+        status: u16,
+        message: Cow<'a, str>,
+    },
+}
+
+impl Serialize for WitnessError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::CheckpointNotFound(log_length) => RawError::NotFound {
+                status: Status::<404>,
+                ty: EntityType::LogLength,
+                id: *log_length,
+            }
+            .serialize(serializer),
+            Self::WitnessNotTrusted(key_id) => RawError::WitnessNotTrusted {
+                status: Status::<403>,
+                key_id: Cow::Borrowed(key_id),
+            }
+            .serialize(serializer),
+            Self::InvalidCosignature(key_id) => RawError::InvalidCosignature {
+                status: Status::<422>,
+                key_id: Cow::Borrowed(key_id),
+            }
+            .serialize(serializer),
+            Self::Message { status, message } => RawError::Message {
+                status: *status,
+                message: Cow::Borrowed(message),
+            }
+            .serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WitnessError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match RawError::deserialize(deserializer)? {
+            RawError::NotFound { status: _, ty, id } => match ty {
+                EntityType::LogLength => Ok(Self::CheckpointNotFound(id)),
+            },
+            RawError::WitnessNotTrusted { status: _, key_id } => {
+                Ok(Self::WitnessNotTrusted(key_id.into_owned()))
+            }
+            RawError::InvalidCosignature { status: _, key_id } => {
+                Ok(Self::InvalidCosignature(key_id.into_owned()))
+            }
+            RawError::Message { status, message } => Ok(Self::Message {
+                status,
+                message: message.into_owned(),
+            }),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use bytes::Bytes;
 use futures_util::{future::ready, stream::once, Stream, StreamExt, TryStreamExt};
 use indexmap::IndexMap;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
+    header::{HeaderMap, HeaderValue, CONTENT_RANGE, RANGE},
     Body, IntoUrl, Method, RequestBuilder, Response, StatusCode,
 };
 use secrecy::{ExposeSecret, Secret};
@@ -16,28 +16,42 @@ use warg_api::{
     v1::{
         content::{ContentError, ContentSourcesResponse},
         fetch::{
-            FetchError, FetchLogsRequest, FetchLogsResponse, FetchPackageNamesRequest,
-            FetchPackageNamesResponse,
+            FetchCheckpointsQuery, FetchCheckpointsResponse, FetchError, FetchLogsRequest,
+            FetchLogsResponse, FetchPackageNamesRequest, FetchPackageNamesResponse,
+            FetchPackageResponse, WatchRequest, WatchResponse,
+        },
+        gossip::{GossipError, GossipRequest, GossipResponse},
+        ledger::{
+            LedgerError, LedgerSource, LedgerSourceContentType, LedgerSourcesQuery,
+            LedgerSourcesResponse,
         },
-        ledger::{LedgerError, LedgerSourcesResponse},
         monitor::{CheckpointVerificationResponse, MonitorError},
         package::{ContentSource, PackageError, PackageRecord, PublishRecordRequest},
         paths,
         proof::{
-            ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse,
-            ProofError,
+            AbsenceRequest, AbsenceResponse, ConsistencyRequest, ConsistencyResponse,
+            InclusionRequest, InclusionResponse, ProofError,
         },
+        tile::TileError,
+        witness::{CosignRequest, WitnessError},
         REGISTRY_HEADER_NAME, REGISTRY_HINT_HEADER_NAME,
     },
-    WellKnownConfig, WELL_KNOWN_PATH,
+    RegistryDiscovery, WellKnownConfig, DISCOVERY_PATH, WELL_KNOWN_PATH,
 };
-use warg_crypto::hash::{AnyHash, HashError, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm, HashError, Sha256};
 use warg_protocol::{
-    registry::{Checkpoint, LogId, LogLeaf, MapLeaf, RecordId, TimestampedCheckpoint},
+    ledger::LedgerRecord,
+    registry::{
+        Checkpoint, CosignedCheckpoint, LogId, LogLeaf, MapLeaf, RecordId, RegistryIndex,
+        RegistryLen, TimestampedCheckpoint,
+    },
     SerdeEnvelope,
 };
 use warg_transparency::{
-    log::{ConsistencyProofError, InclusionProofError, LogProofBundle, ProofBundle},
+    log::{
+        ConsistencyProofError, InclusionProofError, LogData, LogProofBundle, Node, ProofBundle,
+        TileError as LogTileError, TileId, TileLog, TILE_WIDTH,
+    },
     map::MapProofBundle,
 };
 
@@ -60,9 +74,18 @@ pub enum ClientError {
     /// An error was returned from the monitor API.
     #[error(transparent)]
     Monitor(#[from] MonitorError),
+    /// An error was returned from the gossip API.
+    #[error(transparent)]
+    Gossip(#[from] GossipError),
     /// An error was returned from the ledger API.
     #[error(transparent)]
     Ledger(#[from] LedgerError),
+    /// An error was returned from the witness API.
+    #[error(transparent)]
+    Witness(#[from] WitnessError),
+    /// An error was returned from the log tile API.
+    #[error(transparent)]
+    Tile(#[from] TileError),
     /// An error occurred while communicating with the registry.
     #[error("failed to send request to registry server: {0}")]
     Communication(#[from] reqwest::Error),
@@ -93,6 +116,9 @@ pub enum ClientError {
     /// The client failed an inclusion proof.
     #[error("the client failed an inclusion proof: {0}")]
     InclusionProof(#[from] InclusionProofError),
+    /// The server returned an invalid log tile.
+    #[error("the server returned an invalid log tile: {0}")]
+    InvalidTile(#[from] LogTileError),
     /// The record was not published.
     #[error("record `{0}` has not been published")]
     RecordNotPublished(RecordId),
@@ -254,6 +280,54 @@ impl Client {
         }
     }
 
+    /// Gets the discovery document describing the capabilities of the registry.
+    ///
+    /// Returns `None` if the registry does not serve a usable discovery document,
+    /// in which case its capabilities are unknown.
+    pub async fn discovery(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+    ) -> Result<Option<RegistryDiscovery>, ClientError> {
+        let url = self.url.join(DISCOVERY_PATH);
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "getting registry discovery document"
+        );
+
+        let res = self
+            .client
+            .get(url)
+            .warg_header(registry_domain)?
+            .auth(self.auth_token())
+            .send()
+            .await?;
+        let registry = registry_domain
+            .cloned()
+            .unwrap_or_else(|| self.url.registry_domain());
+        if res.status() == StatusCode::NOT_FOUND {
+            tracing::debug!("registry `{registry}` does not serve a discovery document");
+            return Ok(None);
+        }
+        if !res.status().is_success() {
+            tracing::warn!(
+                "registry `{registry}` returned status {status} for its discovery document; its capabilities are unknown",
+                status = res.status()
+            );
+            return Ok(None);
+        }
+
+        match res.json::<RegistryDiscovery>().await {
+            Ok(discovery) => Ok(Some(discovery)),
+            Err(e) => {
+                tracing::warn!(
+                    "registry `{registry}` returned an invalid discovery document; its capabilities are unknown: {e}"
+                );
+                Ok(None)
+            }
+        }
+    }
+
     /// Gets the latest checkpoint from the registry.
     pub async fn latest_checkpoint(
         &self,
@@ -276,6 +350,54 @@ impl Client {
         .await
     }
 
+    /// Gets a page of the historical checkpoints of the registry.
+    pub async fn fetch_checkpoints(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        query: FetchCheckpointsQuery,
+    ) -> Result<FetchCheckpointsResponse, ClientError> {
+        let url = self.url.join(paths::fetch_checkpoints());
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "fetching checkpoints",
+        );
+        into_result::<_, FetchError>(
+            self.client
+                .get(url)
+                .query(&query)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Fetches the complete logs of a package and its registry operator along with
+    /// the proofs needed to verify them against the latest checkpoint.
+    pub async fn fetch_package(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        log_id: &LogId,
+    ) -> Result<FetchPackageResponse, ClientError> {
+        let url = self.url.join(&paths::fetch_package(log_id));
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "fetching package with proofs",
+        );
+        into_result::<_, FetchError>(
+            self.client
+                .get(url)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
     /// Verify checkpoint of the registry.
     pub async fn verify_checkpoint(
         &self,
@@ -300,6 +422,79 @@ impl Client {
         into_result::<_, MonitorError>(response).await
     }
 
+    /// Exchanges an observed checkpoint for the latest checkpoint observed
+    /// by the peer at this URL.
+    pub async fn gossip_checkpoint(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        request: GossipRequest,
+    ) -> Result<GossipResponse, ClientError> {
+        let url = self.url.join(paths::gossip_checkpoint());
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "gossiping checkpoint",
+        );
+        into_result::<_, GossipError>(
+            self.client
+                .post(url)
+                .json(&request)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Gets the checkpoint with the given log length along with its witness
+    /// cosignatures.
+    pub async fn cosigned_checkpoint(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        log_length: RegistryLen,
+    ) -> Result<CosignedCheckpoint, ClientError> {
+        let url = self.url.join(&paths::witness_checkpoint(log_length));
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "getting cosigned checkpoint",
+        );
+        into_result::<_, WitnessError>(
+            self.client
+                .get(url)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Submits a witness cosignature of a checkpoint to the registry.
+    pub async fn cosign_checkpoint(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        request: CosignRequest,
+    ) -> Result<CosignedCheckpoint, ClientError> {
+        let url = self.url.join(paths::witness_cosign());
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "submitting checkpoint cosignature",
+        );
+        into_result::<_, WitnessError>(
+            self.client
+                .post(url)
+                .json(&request)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
     /// Fetches package log entries from the registry.
     pub async fn fetch_logs(
         &self,
@@ -355,6 +550,33 @@ impl Client {
         into_result::<_, FetchError>(response).await
     }
 
+    /// Waits for the registry log to grow beyond the given log length.
+    ///
+    /// The registry responds with the latest checkpoint once it has grown or
+    /// when its watch timeout elapses.
+    pub async fn watch(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        request: WatchRequest<'_>,
+    ) -> Result<WatchResponse, ClientError> {
+        let url = self.url.join(paths::watch());
+        tracing::debug!(
+            url,
+            log_length = request.log_length,
+            registry_header = ?registry_domain,
+            "watching registry log",
+        );
+        let response = self
+            .client
+            .post(url)
+            .warg_header(registry_domain)?
+            .auth(self.auth_token())
+            .json(&request)
+            .send()
+            .await?;
+        into_result::<_, FetchError>(response).await
+    }
+
     /// Gets ledger sources from the registry.
     pub async fn ledger_sources(
         &self,
@@ -377,6 +599,130 @@ impl Client {
         .await
     }
 
+    /// Gets ledger sources with record envelopes from the registry.
+    pub async fn ledger_envelope_sources(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+    ) -> Result<LedgerSourcesResponse, ClientError> {
+        let url = self.url.join(paths::ledger_sources());
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "getting ledger envelope sources",
+        );
+        into_result::<_, LedgerError>(
+            self.client
+                .get(url)
+                .query(&LedgerSourcesQuery {
+                    content_type: Some(LedgerSourceContentType::Envelopes),
+                })
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await
+    }
+
+    /// Gets the log leafs of a ledger source from the registry.
+    pub async fn ledger_records(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        algorithm: HashAlgorithm,
+        source: &LedgerSource,
+    ) -> Result<Vec<LogLeaf>, ClientError> {
+        let len = match algorithm {
+            HashAlgorithm::Sha256 => 32,
+            _ => {
+                return Err(ClientError::Other(anyhow!(
+                    "unsupported ledger hash algorithm `{algorithm}`"
+                )))
+            }
+        };
+
+        if source.content_type == LedgerSourceContentType::Envelopes {
+            return Ok(self
+                .ledger_envelopes(registry_domain, algorithm, source)
+                .await?
+                .into_iter()
+                .map(|record| record.leaf)
+                .collect());
+        }
+
+        let bytes = self
+            .ledger_source(registry_domain, source, LedgerSourceContentType::Packed)
+            .await?;
+        if bytes.len() % (len * 2) != 0 {
+            return Err(ClientError::Other(anyhow!(
+                "ledger source `{url}` has an invalid length of {len} bytes",
+                url = source.url,
+                len = bytes.len()
+            )));
+        }
+
+        Ok(bytes
+            .chunks_exact(len * 2)
+            .map(|chunk| LogLeaf {
+                log_id: AnyHash::new(algorithm, chunk[..len].to_vec()).into(),
+                record_id: AnyHash::new(algorithm, chunk[len..].to_vec()).into(),
+            })
+            .collect())
+    }
+
+    /// Gets the records of a ledger source with envelopes from the registry.
+    pub async fn ledger_envelopes(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        algorithm: HashAlgorithm,
+        source: &LedgerSource,
+    ) -> Result<Vec<LedgerRecord>, ClientError> {
+        let bytes = self
+            .ledger_source(registry_domain, source, LedgerSourceContentType::Envelopes)
+            .await?;
+        LedgerRecord::decode_segment(algorithm, &bytes).map_err(|e| {
+            ClientError::Other(anyhow!(
+                "ledger source `{url}` is invalid: {e}",
+                url = source.url
+            ))
+        })
+    }
+
+    async fn ledger_source(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        source: &LedgerSource,
+        content_type: LedgerSourceContentType,
+    ) -> Result<Bytes, ClientError> {
+        if source.content_type != content_type {
+            return Err(ClientError::Other(anyhow!(
+                "unsupported ledger source content type `{content_type:?}`",
+                content_type = source.content_type
+            )));
+        }
+
+        let url = match reqwest::Url::parse(&source.url) {
+            Ok(url) => url.to_string(),
+            Err(_) => self.url.join(&source.url),
+        };
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "getting ledger records",
+        );
+        let response = self
+            .client
+            .get(url)
+            .warg_header(registry_domain)?
+            .auth(self.auth_token())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(deserialize::<LedgerError>(response).await?.into());
+        }
+
+        Ok(response.bytes().await?)
+    }
+
     /// Publish a new record to a package log.
     pub async fn publish_package_record(
         &self,
@@ -466,26 +812,66 @@ impl Client {
             .ok_or(ClientError::AllSourcesFailed(digest.clone()))?;
 
         for source in sources {
-            let ContentSource::HttpGet { url, .. } = source;
+            match self.download_content_source(digest, source, 0).await {
+                Ok((_, stream)) => {
+                    return Ok(validate_stream(digest, stream));
+                }
+                Err(e) => {
+                    tracing::debug!("{e}");
+                    continue;
+                }
+            }
+        }
 
-            tracing::debug!("downloading content `{digest}` from `{url}`");
+        Err(ClientError::AllSourcesFailed(digest.clone()))
+    }
 
-            let response = self.client.get(url).send().await?;
-            if !response.status().is_success() {
-                tracing::debug!(
-                    "failed to download content `{digest}` from `{url}`: {status}",
-                    status = response.status()
-                );
-                continue;
-            }
+    /// Downloads content from the given source starting at the given byte offset.
+    ///
+    /// Returns the offset the returned stream starts at; this is `0` if the
+    /// source does not support range requests.
+    ///
+    /// The returned stream is not validated against the content digest.
+    pub async fn download_content_source(
+        &self,
+        digest: &AnyHash,
+        source: &ContentSource,
+        offset: u64,
+    ) -> Result<(u64, impl Stream<Item = Result<Bytes>>), ClientError> {
+        let ContentSource::HttpGet { url, .. } = source;
 
-            return Ok(validate_stream(
-                digest,
-                response.bytes_stream().map_err(|e| anyhow!(e)),
-            ));
+        tracing::debug!("downloading content `{digest}` from `{url}` at offset {offset}");
+
+        let mut request = self.client.get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={offset}-"));
         }
 
-        Err(ClientError::AllSourcesFailed(digest.clone()))
+        let response = request.send().await?;
+        let offset = match response.status() {
+            StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                let expected = format!("bytes {offset}-");
+                if !response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.starts_with(&expected))
+                {
+                    return Err(ClientError::Other(anyhow!(
+                        "failed to download content `{digest}` from `{url}`: unexpected content range"
+                    )));
+                }
+                offset
+            }
+            status if status.is_success() => 0,
+            status => {
+                return Err(ClientError::Other(anyhow!(
+                    "failed to download content `{digest}` from `{url}`: {status}"
+                )));
+            }
+        };
+
+        Ok((offset, response.bytes_stream().map_err(|e| anyhow!(e))))
     }
 
     /// Set warg-registry header value
@@ -494,13 +880,15 @@ impl Client {
     }
 
     /// Proves the inclusion of the given package log heads in the registry.
+    ///
+    /// Returns the validated inclusion proofs.
     pub async fn prove_inclusion(
         &self,
         registry_domain: Option<&RegistryDomain>,
         request: InclusionRequest,
         checkpoint: &Checkpoint,
         leafs: &[LogLeaf],
-    ) -> Result<(), ClientError> {
+    ) -> Result<InclusionResponse, ClientError> {
         let url = self.url.join(paths::prove_inclusion());
         tracing::debug!(
             url,
@@ -518,17 +906,50 @@ impl Client {
         )
         .await?;
 
-        Self::validate_inclusion_response(response, checkpoint, leafs)
+        Self::validate_inclusion_response(&response, checkpoint, leafs)?;
+        Ok(response)
+    }
+
+    /// Proves the absence of the given package logs in the registry.
+    ///
+    /// Returns the validated absence proofs.
+    pub async fn prove_absence(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        request: AbsenceRequest,
+        checkpoint: &Checkpoint,
+    ) -> Result<AbsenceResponse, ClientError> {
+        let url = self.url.join(paths::prove_absence());
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "proving package absence",
+        );
+        let response = into_result::<AbsenceResponse, ProofError>(
+            self.client
+                .post(url)
+                .json(&request)
+                .warg_header(registry_domain)?
+                .auth(self.auth_token())
+                .send()
+                .await?,
+        )
+        .await?;
+
+        Self::validate_absence_response(&response, checkpoint, &request.log_ids)?;
+        Ok(response)
     }
 
     /// Proves consistency between two log roots.
+    ///
+    /// Returns the validated consistency proof.
     pub async fn prove_log_consistency(
         &self,
         registry_domain: Option<&RegistryDomain>,
         request: ConsistencyRequest,
         from_log_root: Cow<'_, AnyHash>,
         to_log_root: Cow<'_, AnyHash>,
-    ) -> Result<(), ClientError> {
+    ) -> Result<ConsistencyResponse, ClientError> {
         let url = self.url.join(paths::prove_consistency());
         let response = into_result::<ConsistencyResponse, ProofError>(
             self.client
@@ -541,7 +962,17 @@ impl Client {
         )
         .await?;
 
-        let proof = ProofBundle::<Sha256, LogLeaf>::decode(&response.proof).unwrap();
+        Self::validate_consistency_response(&response, from_log_root, to_log_root)?;
+        Ok(response)
+    }
+
+    /// Validates a consistency proof between two log roots.
+    pub fn validate_consistency_response(
+        response: &ConsistencyResponse,
+        from_log_root: Cow<'_, AnyHash>,
+        to_log_root: Cow<'_, AnyHash>,
+    ) -> Result<(), ClientError> {
+        let proof = ProofBundle::<Sha256, LogLeaf>::decode(&response.proof)?;
         let (log_data, consistencies, inclusions) = proof.unbundle();
         if !inclusions.is_empty() {
             return Err(ClientError::Proof(ProofError::BundleFailure(
@@ -578,6 +1009,121 @@ impl Client {
         Ok(())
     }
 
+    /// Gets the encoded hashes of a log tile with the given width.
+    pub async fn log_tile(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        tile: TileId,
+        width: usize,
+    ) -> Result<Bytes, ClientError> {
+        let url = if width == TILE_WIDTH {
+            self.url.join(&paths::log_tile(tile.level, tile.index))
+        } else {
+            self.url
+                .join(&paths::partial_log_tile(tile.level, tile.index, width))
+        };
+        tracing::debug!(
+            url,
+            registry_header = ?registry_domain,
+            "getting log tile",
+        );
+        let response = self
+            .client
+            .get(url)
+            .warg_header(registry_domain)?
+            .auth(self.auth_token())
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(deserialize::<TileError>(response).await?.into());
+        }
+
+        Ok(response.bytes().await?)
+    }
+
+    /// Fetches the given tiles of the log into the tile data.
+    pub async fn fetch_log_tiles(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        log: &mut TileLog<Sha256, LogLeaf>,
+        tiles: impl IntoIterator<Item = TileId>,
+    ) -> Result<(), ClientError> {
+        for tile in tiles {
+            let bytes = self
+                .log_tile(registry_domain, tile, tile.width_at(log.length()))
+                .await?;
+            log.add_tile(tile, &bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Proves the inclusion of the given log leafs in the checkpoint using
+    /// log tiles rather than the proof API.
+    pub async fn prove_log_inclusion_from_tiles(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        checkpoint: &Checkpoint,
+        leafs: &[(RegistryIndex, LogLeaf)],
+    ) -> Result<(), ClientError> {
+        let log_length = checkpoint.log_length;
+        let mut log = TileLog::<Sha256, LogLeaf>::new(log_length);
+        let root = checkpoint.log_root.clone().try_into()?;
+        for (index, leaf) in leafs {
+            let node = Node(index * 2);
+            let tiles = log.tiles_for_inclusion(node)?;
+            self.fetch_log_tiles(registry_domain, &mut log, tiles)
+                .await?;
+
+            let found = log
+                .prove_inclusion(node, log_length)
+                .evaluate_value(&log, leaf)?;
+            if found != root {
+                return Err(ClientError::Proof(ProofError::IncorrectProof {
+                    root: checkpoint.log_root.clone(),
+                    found: found.into(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Proves consistency between two checkpoints using log tiles rather
+    /// than the proof API.
+    pub async fn prove_log_consistency_from_tiles(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        from: &Checkpoint,
+        to: &Checkpoint,
+    ) -> Result<(), ClientError> {
+        let mut log = TileLog::<Sha256, LogLeaf>::new(to.log_length);
+        let tiles = log.tiles_for_consistency(from.log_length)?;
+        self.fetch_log_tiles(registry_domain, &mut log, tiles)
+            .await?;
+
+        let (from_root, to_root) = log
+            .prove_consistency(from.log_length, to.log_length)
+            .evaluate(&log)
+            .map(|(from, to)| (AnyHash::from(from), AnyHash::from(to)))?;
+
+        if from.log_root != from_root {
+            return Err(ClientError::IncorrectConsistencyProof {
+                root: from.log_root.clone(),
+                found: from_root,
+            });
+        }
+
+        if to.log_root != to_root {
+            return Err(ClientError::IncorrectConsistencyProof {
+                root: to.log_root.clone(),
+                found: to_root,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Uploads package content to the registry.
     pub async fn upload_content(
         &self,
@@ -627,14 +1173,23 @@ impl Client {
         Ok(())
     }
 
-    fn validate_inclusion_response(
-        response: InclusionResponse,
+    /// Validates the inclusion of the given log leafs in the checkpoint.
+    pub fn validate_inclusion_response(
+        response: &InclusionResponse,
         checkpoint: &Checkpoint,
         leafs: &[LogLeaf],
     ) -> Result<(), ClientError> {
         let log_proof_bundle: LogProofBundle<Sha256, LogLeaf> =
             LogProofBundle::decode(response.log.as_slice())?;
         let (log_data, _, log_inclusions) = log_proof_bundle.unbundle();
+        if log_inclusions.len() != leafs.len() {
+            return Err(ClientError::Proof(ProofError::BundleFailure(format!(
+                "expected {expected} log inclusion proofs but found {found}",
+                expected = leafs.len(),
+                found = log_inclusions.len()
+            ))));
+        }
+
         for (leaf, proof) in leafs.iter().zip(log_inclusions.iter()) {
             let found = proof.evaluate_value(&log_data, leaf)?;
             let root = checkpoint.log_root.clone().try_into()?;
@@ -646,9 +1201,50 @@ impl Client {
             }
         }
 
-        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> =
-            MapProofBundle::decode(response.map.as_slice())?;
+        Self::validate_map_proof(&response.map, checkpoint, leafs)
+    }
+
+    /// Validates an encoded map proof bundle for the given leafs against a checkpoint's map root.
+    pub fn validate_map_proof(
+        map: &[u8],
+        checkpoint: &Checkpoint,
+        leafs: &[LogLeaf],
+    ) -> Result<(), ClientError> {
+        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> = MapProofBundle::decode(map)?;
+        if let Some(proof) = map_proof_bundle.multi_proof() {
+            let leafs: Vec<_> = leafs
+                .iter()
+                .map(|leaf| {
+                    (
+                        leaf.log_id.clone(),
+                        MapLeaf {
+                            record_id: leaf.record_id.clone(),
+                        },
+                    )
+                })
+                .collect();
+            let found = proof
+                .evaluate(&leafs)
+                .map_err(|e| ClientError::Proof(ProofError::BundleFailure(e.to_string())))?;
+            let root = checkpoint.map_root.clone().try_into()?;
+            if found != root {
+                return Err(ClientError::Proof(ProofError::IncorrectProof {
+                    root: checkpoint.map_root.clone(),
+                    found: found.into(),
+                }));
+            }
+            return Ok(());
+        }
+
         let map_inclusions = map_proof_bundle.unbundle();
+        if map_inclusions.len() != leafs.len() {
+            return Err(ClientError::Proof(ProofError::BundleFailure(format!(
+                "expected {expected} map inclusion proofs but found {found}",
+                expected = leafs.len(),
+                found = map_inclusions.len()
+            ))));
+        }
+
         for (leaf, proof) in leafs.iter().zip(map_inclusions.iter()) {
             let found = proof.evaluate(
                 &leaf.log_id,
@@ -667,6 +1263,37 @@ impl Client {
 
         Ok(())
     }
+
+    /// Validates an absence proof response against the map root of the given checkpoint.
+    pub fn validate_absence_response(
+        response: &AbsenceResponse,
+        checkpoint: &Checkpoint,
+        log_ids: &[LogId],
+    ) -> Result<(), ClientError> {
+        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> =
+            MapProofBundle::decode(response.map.as_slice())?;
+        let map_absences = map_proof_bundle.unbundle_absence();
+        if map_absences.len() != log_ids.len() {
+            return Err(ClientError::Proof(ProofError::BundleFailure(format!(
+                "expected {expected} map absence proofs but found {found}",
+                expected = log_ids.len(),
+                found = map_absences.len()
+            ))));
+        }
+
+        for (log_id, proof) in log_ids.iter().zip(map_absences.iter()) {
+            let found = proof.evaluate(log_id);
+            let root = checkpoint.map_root.clone().try_into()?;
+            if found != root {
+                return Err(ClientError::Proof(ProofError::IncorrectProof {
+                    root: checkpoint.map_root.clone(),
+                    found: found.into(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 fn validate_stream(
@@ -1,8 +1,11 @@
 //! Module for client configuration.
 
-use crate::{ClientError, RegistryUrl};
+use crate::{
+    storage::{OperatorPin, RegistryDomain},
+    ClientError, RegistryUrl, WitnessPolicy,
+};
 use anyhow::{anyhow, Context, Result};
-use indexmap::IndexSet;
+use indexmap::{IndexMap, IndexSet};
 use normpath::PathExt;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
@@ -11,6 +14,7 @@ use std::{
     fs::{self, File},
     path::{Component, Path, PathBuf},
 };
+use warg_crypto::signing::PublicKey;
 
 static CACHE_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::cache_dir);
 static CONFIG_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::config_dir);
@@ -127,9 +131,55 @@ pub struct Config {
     #[serde(default)]
     pub disable_interactive: bool,
 
+    /// Resolve packages and content only from local storage.
+    ///
+    /// Operations that require the registry will fail.
+    #[serde(default)]
+    pub offline: bool,
+
+    /// The path to a vendor directory created by `warg vendor`.
+    ///
+    /// This path is expected to be relative to the configuration file.
+    ///
+    /// If set, packages are resolved only from the vendor directory and the
+    /// client is always offline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor_dir: Option<PathBuf>,
+
     /// Use the specified backend for keyring access.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub keyring_backend: Option<String>,
+
+    /// The public keys of witnesses trusted to cosign registry checkpoints.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witness_keys: Vec<PublicKey>,
+
+    /// The number of trusted witness cosignatures required before a
+    /// checkpoint is accepted.
+    ///
+    /// A threshold of zero does not require any cosignatures.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub witness_threshold: usize,
+
+    /// The path to an external program used to sign published records.
+    ///
+    /// If set, records are signed by the program instead of with a signing
+    /// key from the keyring; see the `signer` module for the protocol.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing_program: Option<PathBuf>,
+
+    /// The operator pins of known registries.
+    ///
+    /// A registry whose operator log does not start with its pinned root is
+    /// refused. Registries without a configured pin are pinned on first
+    /// contact; recorded pins are kept in an `operator-pins` directory next
+    /// to the registries directory and are not removed by a reset.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub operator_pins: IndexMap<RegistryDomain, OperatorPin>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
 }
 
 impl Config {
@@ -150,6 +200,8 @@ impl Config {
         if let Some(parent) = path.parent() {
             config.registries_dir = config.registries_dir.map(|p| parent.join(p));
             config.content_dir = config.content_dir.map(|p| parent.join(p));
+            config.namespace_map_path = config.namespace_map_path.map(|p| parent.join(p));
+            config.vendor_dir = config.vendor_dir.map(|p| parent.join(p));
         }
 
         Ok(config)
@@ -211,7 +263,17 @@ impl Config {
             ignore_federation_hints: self.ignore_federation_hints,
             auto_accept_federation_hints: self.auto_accept_federation_hints,
             disable_interactive: self.disable_interactive,
+            offline: self.offline,
+            vendor_dir: self.vendor_dir.as_ref().map(|p| {
+                let p = normalize_path(parent.join(p).as_path());
+                assert!(p.is_absolute());
+                pathdiff::diff_paths(&p, &parent).unwrap()
+            }),
             keyring_backend: self.keyring_backend.clone(),
+            witness_keys: self.witness_keys.clone(),
+            witness_threshold: self.witness_threshold,
+            signing_program: self.signing_program.clone(),
+            operator_pins: self.operator_pins.clone(),
         };
 
         serde_json::to_writer_pretty(
@@ -298,6 +360,14 @@ impl Config {
             })
     }
 
+    /// Gets the witness policy for checkpoints.
+    pub fn witness_policy(&self) -> WitnessPolicy {
+        WitnessPolicy {
+            keys: self.witness_keys.clone(),
+            threshold: self.witness_threshold,
+        }
+    }
+
     pub(crate) fn storage_paths_for_url(
         &self,
         registry_url: RegistryUrl,
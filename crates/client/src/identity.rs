@@ -0,0 +1,103 @@
+//! Publisher identities used for keyless publishing.
+//!
+//! Instead of signing with a long-lived key, a publisher may sign a record with
+//! an ephemeral key that is bound to an identity issued by an identity provider
+//! (e.g. the OIDC provider of a CI system). The identity token is requested
+//! with the ephemeral key's ID as its audience, so the registry can verify the
+//! token was issued for that key.
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+
+/// The environment variable containing the GitHub Actions token request URL.
+const GITHUB_REQUEST_URL_ENV_VAR: &str = "ACTIONS_ID_TOKEN_REQUEST_URL";
+
+/// The environment variable containing the GitHub Actions token request bearer token.
+const GITHUB_REQUEST_TOKEN_ENV_VAR: &str = "ACTIONS_ID_TOKEN_REQUEST_TOKEN";
+
+/// A source of identity tokens.
+#[async_trait]
+pub trait IdentityTokenSource: Send + Sync {
+    /// Gets an identity token for the given audience.
+    async fn token(&self, audience: &str) -> Result<String>;
+}
+
+/// Represents the identity claimed by an identity token.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IdentityClaims {
+    /// The issuer of the token.
+    #[serde(rename = "iss")]
+    pub issuer: String,
+    /// The subject of the token.
+    #[serde(rename = "sub")]
+    pub subject: String,
+}
+
+impl IdentityClaims {
+    /// Reads the identity claims from an identity token.
+    ///
+    /// The token signature is not verified; that is done by the registry.
+    pub fn from_token(token: &str) -> Result<Self> {
+        let payload = token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| anyhow!("identity token is not a JSON Web Token"))?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .context("identity token payload is not valid base64")?;
+        serde_json::from_slice(&payload).context("identity token payload is not valid")
+    }
+}
+
+/// An identity token source for GitHub Actions workflows.
+///
+/// The workflow must have the `id-token: write` permission.
+pub struct GitHubActionsTokenSource {
+    client: reqwest::Client,
+    request_url: String,
+    request_token: String,
+}
+
+impl GitHubActionsTokenSource {
+    /// Creates a token source from the GitHub Actions environment.
+    ///
+    /// Returns `None` if not running in a GitHub Actions workflow with
+    /// permission to request identity tokens.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            client: reqwest::Client::new(),
+            request_url: std::env::var(GITHUB_REQUEST_URL_ENV_VAR).ok()?,
+            request_token: std::env::var(GITHUB_REQUEST_TOKEN_ENV_VAR).ok()?,
+        })
+    }
+}
+
+#[async_trait]
+impl IdentityTokenSource for GitHubActionsTokenSource {
+    async fn token(&self, audience: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct Response {
+            value: String,
+        }
+
+        let response = self
+            .client
+            .get(&self.request_url)
+            .query(&[("audience", audience)])
+            .bearer_auth(&self.request_token)
+            .send()
+            .await
+            .context("failed to request GitHub Actions identity token")?;
+
+        if !response.status().is_success() {
+            bail!(
+                "failed to request GitHub Actions identity token: {status}",
+                status = response.status()
+            );
+        }
+
+        Ok(response.json::<Response>().await?.value)
+    }
+}
@@ -0,0 +1,270 @@
+//! Encrypted flat-file keyring backend
+//!
+//! This backend stores credentials as flat files in the user's configuration
+//! directory, like the `flat-file` backend, but encrypts each entry with a key
+//! derived from a passphrase using Argon2id. Entries are sealed with
+//! XChaCha20-Poly1305, using the entry's name as associated data so that
+//! entry files cannot be swapped for one another.
+//!
+//! The passphrase is read from the `WARG_KEYRING_PASSPHRASE` environment
+//! variable, or from the file named by `WARG_KEYRING_PASSPHRASE_FILE`. If
+//! neither is set, the user is prompted for it when the first entry is
+//! accessed (interactive terminals only).
+
+use super::flatfile::FlatfileCredentialBuilder;
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use once_cell::sync::OnceCell;
+use rand_core::{OsRng, RngCore};
+use secrecy::{ExposeSecret, Secret};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The environment variable containing the keyring passphrase.
+pub const PASSPHRASE_ENV_VAR: &str = "WARG_KEYRING_PASSPHRASE";
+
+/// The environment variable containing the path to a file with the keyring passphrase.
+pub const PASSPHRASE_FILE_ENV_VAR: &str = "WARG_KEYRING_PASSPHRASE_FILE";
+
+/// Prefix of encrypted entries, identifying the format version.
+const ENTRY_PREFIX: &str = "warg-encrypted-v1:";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Builder for encrypted flat-file credentials
+#[derive(Debug, Clone)]
+pub struct EncryptedFileCredentialBuilder {
+    inner: FlatfileCredentialBuilder,
+    passphrase: Arc<OnceCell<Secret<String>>>,
+}
+
+/// A credential stored in an encrypted flat file
+#[derive(Debug)]
+pub struct EncryptedFileCredential {
+    inner: Box<Credential>,
+    label: String,
+    passphrase: Arc<OnceCell<Secret<String>>>,
+}
+
+impl EncryptedFileCredentialBuilder {
+    /// Construct the credential builder, storing credentials in
+    /// `$XDG_CONFIG_HOME/warg/keyring-encrypted`.
+    pub fn new() -> keyring::Result<Self> {
+        let dir = dirs::config_dir()
+            .ok_or(keyring::Error::NoEntry)?
+            .join("warg")
+            .join("keyring-encrypted");
+        Self::new_with_basepath(dir)
+    }
+
+    /// Construct the credential builder, storing all credentials in the
+    /// given directory. The directory will be created if it is does not exist.
+    ///
+    /// The passphrase is resolved when the first entry is accessed.
+    pub fn new_with_basepath(basepath: PathBuf) -> keyring::Result<Self> {
+        Ok(Self {
+            inner: FlatfileCredentialBuilder::new_with_basepath(basepath)?,
+            passphrase: Default::default(),
+        })
+    }
+
+    /// Construct the credential builder, storing all credentials in the
+    /// given directory and encrypting them with the given passphrase.
+    pub fn new_with_passphrase(
+        basepath: PathBuf,
+        passphrase: Secret<String>,
+    ) -> keyring::Result<Self> {
+        Ok(Self {
+            inner: FlatfileCredentialBuilder::new_with_basepath(basepath)?,
+            passphrase: Arc::new(OnceCell::with_value(passphrase)),
+        })
+    }
+}
+
+impl CredentialBuilderApi for EncryptedFileCredentialBuilder {
+    fn build(
+        &self,
+        target: Option<&str>,
+        service: &str,
+        user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        let label = match target {
+            Some(target) => format!("{target}:{service}:{user}"),
+            None => format!("{service}:{user}"),
+        };
+
+        Ok(Box::new(EncryptedFileCredential {
+            inner: self.inner.build(target, service, user)?,
+            label,
+            passphrase: self.passphrase.clone(),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl EncryptedFileCredential {
+    fn passphrase(&self) -> keyring::Result<&Secret<String>> {
+        self.passphrase.get_or_try_init(read_passphrase)
+    }
+
+    fn derive_cipher(&self, salt: &[u8]) -> keyring::Result<XChaCha20Poly1305> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(
+                self.passphrase()?.expose_secret().as_bytes(),
+                salt,
+                &mut key,
+            )
+            .map_err(|e| keyring::Error::PlatformFailure(e.to_string().into()))?;
+        Ok(XChaCha20Poly1305::new(&key.into()))
+    }
+}
+
+impl CredentialApi for EncryptedFileCredential {
+    fn set_password(&self, password: &str) -> keyring::Result<()> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = self
+            .derive_cipher(&salt)?
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: password.as_bytes(),
+                    aad: self.label.as_bytes(),
+                },
+            )
+            .map_err(|_| keyring::Error::PlatformFailure("failed to encrypt entry".into()))?;
+
+        let mut sealed = Vec::with_capacity(SALT_LEN + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        self.inner
+            .set_password(&format!("{ENTRY_PREFIX}{}", STANDARD.encode(sealed)))
+    }
+
+    fn get_password(&self) -> keyring::Result<String> {
+        let entry = self.inner.get_password()?;
+        let sealed = entry
+            .strip_prefix(ENTRY_PREFIX)
+            .and_then(|encoded| STANDARD.decode(encoded).ok())
+            .filter(|sealed| sealed.len() >= SALT_LEN + NONCE_LEN)
+            .ok_or_else(|| keyring::Error::BadEncoding(entry.clone().into_bytes()))?;
+
+        let (salt, rest) = sealed.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let password = self
+            .derive_cipher(salt)?
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: self.label.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                keyring::Error::PlatformFailure(
+                    "failed to decrypt entry: the passphrase may be incorrect".into(),
+                )
+            })?;
+
+        String::from_utf8(password).map_err(|e| keyring::Error::BadEncoding(e.into_bytes()))
+    }
+
+    fn delete_password(&self) -> keyring::Result<()> {
+        self.inner.delete_password()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Reads the passphrase from the environment, a file, or an interactive prompt.
+fn read_passphrase() -> keyring::Result<Secret<String>> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV_VAR) {
+        return Ok(Secret::new(passphrase));
+    }
+
+    if let Some(path) = std::env::var_os(PASSPHRASE_FILE_ENV_VAR) {
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|e| keyring::Error::NoStorageAccess(Box::new(e)))?;
+        return Ok(Secret::new(
+            contents.trim_end_matches(['\r', '\n']).to_string(),
+        ));
+    }
+
+    #[cfg(feature = "cli-interactive")]
+    {
+        use std::io::IsTerminal;
+        if std::io::stdin().is_terminal() {
+            return dialoguer::Password::with_theme(&dialoguer::theme::ColorfulTheme::default())
+                .with_prompt("Keyring passphrase")
+                .interact()
+                .map(Secret::new)
+                .map_err(|e| keyring::Error::NoStorageAccess(Box::new(e)));
+        }
+    }
+
+    Err(keyring::Error::NoStorageAccess(
+        format!(
+            "no keyring passphrase was provided; set `{PASSPHRASE_ENV_VAR}` or `{PASSPHRASE_FILE_ENV_VAR}`"
+        )
+        .into(),
+    ))
+}
+
+#[test]
+fn test_smoke() {
+    let basepath = tempfile::tempdir().unwrap();
+    let keyring = EncryptedFileCredentialBuilder::new_with_passphrase(
+        basepath.as_ref().to_owned(),
+        Secret::new("hunter2".to_string()),
+    )
+    .unwrap();
+    let cred = keyring.build(None, "service1", "user1").unwrap();
+    assert!(matches!(
+        cred.get_password().unwrap_err(),
+        keyring::Error::NoEntry
+    ));
+    cred.set_password("correct horse battery staple").unwrap();
+    assert_eq!(cred.get_password().unwrap(), "correct horse battery staple");
+
+    // The entry is not stored in plain text
+    let filepath = basepath.as_ref().join("service=service1&user=user1");
+    let contents = std::fs::read_to_string(filepath).unwrap();
+    assert!(contents.starts_with(ENTRY_PREFIX));
+    assert!(!contents.contains("correct horse battery staple"));
+
+    // A different passphrase cannot decrypt the entry
+    let other = EncryptedFileCredentialBuilder::new_with_passphrase(
+        basepath.as_ref().to_owned(),
+        Secret::new("hunter3".to_string()),
+    )
+    .unwrap();
+    assert!(matches!(
+        other
+            .build(None, "service1", "user1")
+            .unwrap()
+            .get_password()
+            .unwrap_err(),
+        keyring::Error::PlatformFailure(_)
+    ));
+
+    cred.delete_password().unwrap();
+    assert!(matches!(
+        cred.get_password().unwrap_err(),
+        keyring::Error::NoEntry
+    ));
+}
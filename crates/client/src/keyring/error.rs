@@ -172,6 +172,21 @@ See `warg config --help` for the options."
                     )?;
                 }
 
+                if *backend == "encrypted-file"
+                    && matches!(
+                        cause,
+                        KeyringErrorCause::Backend(keyring::Error::NoStorageAccess(_))
+                    )
+                {
+                    write!(
+                        f,
+                        "\nThe 'encrypted-file' keyring backend requires a passphrase.
+Set the `{}` environment variable to the passphrase, or `{}` to the path of a file containing it.",
+                        super::encryptedfile::PASSPHRASE_ENV_VAR,
+                        super::encryptedfile::PASSPHRASE_FILE_ENV_VAR,
+                    )?;
+                }
+
                 // The above will be followed by further information returned
                 // from `self.source()`.
                 Ok(())
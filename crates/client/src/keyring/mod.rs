@@ -10,6 +10,7 @@ mod error;
 use error::KeyringAction;
 pub use error::KeyringError;
 
+pub mod encryptedfile;
 pub mod flatfile;
 
 /// Interface to a pluggable keyring backend
@@ -25,21 +26,29 @@ pub type Result<T, E = KeyringError> = std::result::Result<T, E>;
 impl Keyring {
     #[cfg(target_os = "linux")]
     /// List of supported credential store backends
-    pub const SUPPORTED_BACKENDS: &'static [&'static str] =
-        &["secret-service", "flat-file", "linux-keyutils", "mock"];
+    pub const SUPPORTED_BACKENDS: &'static [&'static str] = &[
+        "secret-service",
+        "flat-file",
+        "encrypted-file",
+        "linux-keyutils",
+        "mock",
+    ];
     #[cfg(any(target_os = "freebsd", target_os = "openbsd"))]
     /// List of supported credential store backends
     pub const SUPPORTED_BACKENDS: &'static [&'static str] =
-        &["secret-service", "flat-file", "mock"];
+        &["secret-service", "flat-file", "encrypted-file", "mock"];
     #[cfg(target_os = "windows")]
     /// List of supported credential store backends
-    pub const SUPPORTED_BACKENDS: &'static [&'static str] = &["windows", "flat-file", "mock"];
+    pub const SUPPORTED_BACKENDS: &'static [&'static str] =
+        &["windows", "flat-file", "encrypted-file", "mock"];
     #[cfg(target_os = "macos")]
     /// List of supported credential store backends
-    pub const SUPPORTED_BACKENDS: &'static [&'static str] = &["macos", "flat-file", "mock"];
+    pub const SUPPORTED_BACKENDS: &'static [&'static str] =
+        &["macos", "flat-file", "encrypted-file", "mock"];
     #[cfg(target_os = "ios")]
     /// List of supported credential store backends
-    pub const SUPPORTED_BACKENDS: &'static [&'static str] = &["ios", "flat-file", "mock"];
+    pub const SUPPORTED_BACKENDS: &'static [&'static str] =
+        &["ios", "flat-file", "encrypted-file", "mock"];
     #[cfg(not(any(
         target_os = "linux",
         target_os = "freebsd",
@@ -49,7 +58,8 @@ impl Keyring {
         target_os = "windows",
     )))]
     /// List of supported credential store backends
-    pub const SUPPORTED_BACKENDS: &'static [&'static str] = &["flat-file", "mock"];
+    pub const SUPPORTED_BACKENDS: &'static [&'static str] =
+        &["flat-file", "encrypted-file", "mock"];
 
     /// The default backend when no configuration option is set
     pub const DEFAULT_BACKEND: &'static str = Self::SUPPORTED_BACKENDS[0];
@@ -63,6 +73,7 @@ impl Keyring {
             "macos" => "MacOS Keychain",
             "ios" => "Apple iOS Keychain",
             "flat-file" => "Unencrypted flat files in your warg config directory",
+            "encrypted-file" => "Passphrase-encrypted flat files in your warg config directory",
             "mock" => "Mock credential store with no persistence (for testing only)",
             _ => "(no description available)"
         }
@@ -105,6 +116,13 @@ impl Keyring {
             ));
         }
 
+        if backend == "encrypted-file" {
+            return Ok(Box::new(
+                encryptedfile::EncryptedFileCredentialBuilder::new()
+                    .map_err(|e| KeyringError::backend_init_failure("encrypted-file", e))?,
+            ));
+        }
+
         if backend == "mock" {
             return Ok(keyring::mock::default_credential_builder());
         }
@@ -13,28 +13,42 @@ use semver::{Version, VersionReq};
 use std::cmp::Ordering;
 use std::fs;
 use std::str::FromStr;
-use std::{borrow::Cow, path::PathBuf, time::Duration};
+use std::{
+    borrow::Cow,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 use storage::{
-    ContentStorage, FileSystemContentStorage, FileSystemNamespaceMapStorage,
-    FileSystemRegistryStorage, NamespaceMapStorage, PublishInfo, RegistryDomain, RegistryStorage,
+    ContentEntry, ContentStorage, FileSystemContentStorage, FileSystemNamespaceMapStorage,
+    FileSystemRegistryStorage, NamespaceMapStorage, OperatorInfo, OperatorPin, PublishEntry,
+    PublishInfo, RegistryDomain, RegistryStorage,
 };
 use thiserror::Error;
+use tokio::sync::Mutex;
 use tokio_util::io::ReaderStream;
 use warg_api::v1::{
-    fetch::{FetchError, FetchLogsRequest},
+    content::ContentSourcesResponse,
+    fetch::{FetchCheckpointsQuery, FetchError, FetchLogsRequest, WatchRequest},
+    gossip::{GossipError, GossipRequest},
     package::{
         MissingContent, PackageError, PackageRecord, PackageRecordState, PublishRecordRequest,
         UploadEndpoint,
     },
-    proof::{ConsistencyRequest, InclusionRequest},
+    proof::{AbsenceRequest, ConsistencyRequest, InclusionRequest, ProofError},
+    API_VERSION,
 };
+use warg_api::RegistryDiscovery;
 use warg_crypto::hash::Sha256;
-use warg_crypto::{hash::AnyHash, signing, Encode, Signable};
+use warg_crypto::{
+    hash::{AnyHash, HashAlgorithm},
+    signing::{self, PublicKey, SignatureAlgorithm},
+    Encode, Signable,
+};
 use warg_protocol::package::ReleaseState;
 use warg_protocol::{
     operator, package,
     registry::{LogId, LogLeaf, PackageName, RecordId, RegistryLen, TimestampedCheckpoint},
-    PublishedProtoEnvelope,
+    ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope,
 };
 use wasm_compose::graph::{CompositionGraph, EncodeOptions, ExportIndex, InstanceId};
 
@@ -45,17 +59,21 @@ pub mod api;
 mod config;
 /// Tools for locking and bundling components
 pub mod depsolve;
+pub mod identity;
 use depsolve::{Bundler, LockListBuilder};
 /// Tools for semver
 pub mod version_util;
 use version_util::{kindless_name, locked_package, versioned_package, Import, ImportKind};
 pub mod lock;
 mod registry_url;
+pub mod signer;
 pub mod storage;
+pub mod vendor;
 pub use self::config::*;
 pub use self::registry_url::RegistryUrl;
 
 const DEFAULT_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_CONCURRENT_DOWNLOADS: usize = 8;
 
 /// For Bytecode Alliance projects, the default registry is set to `bytecodealliance.org`.
 /// The `.well-known` config path may resolve to another domain where the registry is hosted.
@@ -75,26 +93,103 @@ where
     ignore_federation_hints: bool,
     auto_accept_federation_hints: bool,
     disable_interactive: bool,
+    offline: bool,
+    witness_policy: WitnessPolicy,
+    operator_pins: IndexMap<RegistryDomain, OperatorPin>,
     keyring_backend: Option<String>,
     keys: IndexSet<String>,
+    signing_program: Option<PathBuf>,
+    discovery: Mutex<IndexMap<Option<RegistryDomain>, Option<RegistryDiscovery>>>,
+}
+
+/// The policy for requiring witness cosignatures of registry checkpoints.
+#[derive(Debug, Clone, Default)]
+pub struct WitnessPolicy {
+    /// The public keys of trusted witnesses.
+    pub keys: Vec<PublicKey>,
+    /// The number of trusted witness cosignatures required before a
+    /// checkpoint is accepted.
+    ///
+    /// A threshold of zero does not require any cosignatures.
+    pub threshold: usize,
+}
+
+/// The options for creating a [`Client`].
+#[derive(Default)]
+pub struct ClientOptions {
+    /// The authentication token to send to the registry.
+    pub auth_token: Option<Secret<String>>,
+    /// Whether to ignore federation hints returned by the registry.
+    pub ignore_federation_hints: bool,
+    /// Whether to accept federation hints without prompting.
+    pub auto_accept_federation_hints: bool,
+    /// Whether to disable interactive prompts.
+    pub disable_interactive: bool,
+    /// Whether to resolve packages and content only from local storage.
+    pub offline: bool,
+    /// The policy for requiring witness cosignatures of registry checkpoints.
+    pub witness_policy: WitnessPolicy,
+    /// The pinned operator log roots of registries.
+    pub operator_pins: IndexMap<RegistryDomain, OperatorPin>,
+    /// The keyring backend used for signing keys.
+    pub keyring_backend: Option<String>,
+    /// The names of the signing keys in the keyring.
+    pub keys: IndexSet<String>,
+    /// The external program used to sign published records.
+    pub signing_program: Option<PathBuf>,
+}
+
+impl ClientOptions {
+    /// Creates client options from the given configuration.
+    fn from_config(
+        config: &Config,
+        auth_token: Option<Secret<String>>,
+        disable_interactive: bool,
+    ) -> Self {
+        let (keyring_backend, keys) = if cfg!(feature = "keyring") {
+            (config.keyring_backend.clone(), config.keys.clone())
+        } else {
+            (None, IndexSet::new())
+        };
+
+        Self {
+            auth_token,
+            ignore_federation_hints: config.ignore_federation_hints,
+            auto_accept_federation_hints: config.auto_accept_federation_hints,
+            disable_interactive,
+            offline: config.offline,
+            witness_policy: config.witness_policy(),
+            operator_pins: config.operator_pins.clone(),
+            keyring_backend,
+            keys,
+            signing_program: config.signing_program.clone(),
+        }
+    }
 }
 
 impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C, N> {
-    /// Creates a new client for the given URL, registry storage, and
-    /// content storage.
-    #[allow(clippy::too_many_arguments)]
+    /// Creates a new client for the given URL, registry storage, content
+    /// storage, and namespace map storage.
     pub fn new(
         url: impl IntoUrl,
         registry: R,
         content: C,
         namespace_map: N,
-        auth_token: Option<Secret<String>>,
-        ignore_federation_hints: bool,
-        auto_accept_federation_hints: bool,
-        disable_interactive: bool,
-        keyring_backend: Option<String>,
-        keys: IndexSet<String>,
+        options: ClientOptions,
     ) -> ClientResult<Self> {
+        let ClientOptions {
+            auth_token,
+            ignore_federation_hints,
+            auto_accept_federation_hints,
+            disable_interactive,
+            offline,
+            witness_policy,
+            operator_pins,
+            keyring_backend,
+            keys,
+            signing_program,
+        } = options;
+
         let api = api::Client::new(url, auth_token)?;
         Ok(Self {
             registry,
@@ -104,8 +199,13 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
             ignore_federation_hints,
             auto_accept_federation_hints,
             disable_interactive,
+            offline,
+            witness_policy,
+            operator_pins,
             keyring_backend,
             keys,
+            signing_program,
+            discovery: Default::default(),
         })
     }
 
@@ -129,6 +229,55 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
         &self.namespace_map
     }
 
+    /// Determines if the client is in offline mode.
+    ///
+    /// An offline client only resolves packages and content from local
+    /// storage; any operation that requires the registry fails with
+    /// `ClientError::OfflineUnavailable`.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    fn ensure_online(&self, operation: &str) -> ClientResult<()> {
+        if self.offline {
+            return Err(ClientError::OfflineUnavailable(operation.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Gets the discovery document describing the capabilities of the home registry.
+    ///
+    /// See [`Client::registry_discovery`].
+    pub async fn discovery(&self) -> ClientResult<Option<RegistryDiscovery>> {
+        self.registry_discovery(None).await
+    }
+
+    /// Gets the discovery document describing the capabilities of a registry.
+    ///
+    /// A registry domain of `None` refers to the home registry. The document is
+    /// fetched once per registry and checked for compatibility with this client;
+    /// `None` is returned if the registry's capabilities are unknown.
+    pub async fn registry_discovery(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+    ) -> ClientResult<Option<RegistryDiscovery>> {
+        self.ensure_online("discover registry capabilities")?;
+        let mut cache = self.discovery.lock().await;
+        if let Some(discovery) = cache.get(&registry_domain.cloned()) {
+            return Ok(discovery.clone());
+        }
+
+        let discovery = self.api.discovery(registry_domain).await?;
+        if let Some(discovery) = &discovery {
+            let registry = registry_domain
+                .cloned()
+                .unwrap_or_else(|| self.url().registry_domain());
+            check_compatibility(registry, discovery)?;
+        }
+        cache.insert(registry_domain.cloned(), discovery.clone());
+        Ok(discovery)
+    }
+
     /// Get warg registry domain.
     pub async fn get_warg_registry(
         &self,
@@ -169,6 +318,32 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
         Ok(())
     }
 
+    /// Removes a namespace mapping from local storage
+    ///
+    /// Returns `Ok(false)` if the namespace was not mapped.
+    pub async fn remove_namespace(&self, namespace: &str) -> Result<bool> {
+        self.namespace_map.remove_namespace(namespace).await
+    }
+
+    /// Fetches the latest operator log of the registry and returns the state
+    /// of the given namespace in it.
+    ///
+    /// Returns `Ok(None)` if the namespace is not known to the registry.
+    pub async fn fetch_namespace_state(
+        &self,
+        namespace: &str,
+    ) -> ClientResult<Option<operator::NamespaceState>> {
+        self.ensure_online("fetch the operator log")?;
+        self.update_packages_and_return_federated_packages(None, [])
+            .await?;
+        let operator = self
+            .registry
+            .load_operator(None)
+            .await?
+            .ok_or(ClientError::NoOperatorRecords)?;
+        Ok(operator.state.namespace_state(namespace).cloned())
+    }
+
     /// Resets the namespace map
     pub async fn reset_namespaces(&self) -> Result<()> {
         self.namespace_map.reset_namespaces().await?;
@@ -193,6 +368,81 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
             .or(Err(ClientError::ClearContentCacheFailed))
     }
 
+    /// Removes content from client storage that is not referenced by a
+    /// package in client storage.
+    ///
+    /// Referenced content is also removed if it is older than
+    /// `options.max_age` or, oldest first, while the content storage exceeds
+    /// `options.max_size`; such content is downloaded again when needed.
+    ///
+    /// Content of a pending publish is never removed. Partially stored
+    /// downloads are always removed, as the client's lock on the content
+    /// storage means no other download can be resuming them.
+    pub async fn gc_content_cache(
+        &self,
+        options: &ContentGcOptions,
+    ) -> ClientResult<ContentGcSummary> {
+        tracing::info!("collecting unreferenced content");
+
+        let mut referenced = IndexSet::new();
+        for info in self.registry.load_all_packages().await?.values().flatten() {
+            referenced.extend(info.state.releases().filter_map(|r| r.content().cloned()));
+        }
+
+        let mut pinned = IndexSet::new();
+        if let Some(info) = self.registry.load_publish().await? {
+            pinned.extend(info.entries.into_iter().filter_map(|e| match e {
+                PublishEntry::Release { content, .. } => Some(content),
+                _ => None,
+            }));
+        }
+
+        let mut entries = self.content.list_content().await?;
+        entries.sort_by_key(|e| e.modified);
+
+        let now = SystemTime::now();
+        let (mut retained, mut removed): (Vec<_>, Vec<_>) =
+            entries.into_iter().partition(|entry| {
+                !entry.partial
+                    && (pinned.contains(&entry.digest)
+                        || (referenced.contains(&entry.digest)
+                            && !options.max_age.is_some_and(|max_age| {
+                                now.duration_since(entry.modified).unwrap_or_default() > max_age
+                            })))
+            });
+
+        let mut retained_size: u64 = retained.iter().map(|e| e.size).sum();
+        if let Some(max_size) = options.max_size {
+            let mut i = 0;
+            while retained_size > max_size && i < retained.len() {
+                if pinned.contains(&retained[i].digest) {
+                    i += 1;
+                    continue;
+                }
+
+                let entry = retained.remove(i);
+                retained_size -= entry.size;
+                removed.push(entry);
+            }
+        }
+
+        if !options.dry_run {
+            for entry in &removed {
+                tracing::debug!("removing content `{digest}`", digest = entry.digest);
+                if entry.partial {
+                    self.content.remove_partial_content(&entry.digest).await?;
+                } else {
+                    self.content.remove_content(&entry.digest).await?;
+                }
+            }
+        }
+
+        Ok(ContentGcSummary {
+            removed,
+            retained_size,
+        })
+    }
+
     /// Locks component
     pub async fn lock_component(&self, info: &PackageInfo) -> ClientResult<Vec<u8>> {
         let mut builder = LockListBuilder::default();
@@ -309,7 +559,10 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
     /// Returns the identifier of the record that was published.
     ///
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
-    pub async fn publish(&self, signing_key: &signing::PrivateKey) -> ClientResult<RecordId> {
+    pub async fn publish(
+        &self,
+        signing_key: &(impl signing::Signer + ?Sized),
+    ) -> ClientResult<RecordId> {
         let info = self
             .registry
             .load_publish()
@@ -322,7 +575,8 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
     }
 
     /// Submits the provided publish information or, if not provided, loads from client
-    /// storage. Uses the keyring to retrieve a key and sign.
+    /// storage. Signs with the configured signing program or, if none is
+    /// configured, uses the keyring to retrieve a key and sign.
     ///
     /// If there's no publishing information in client storage, an error is returned.
     ///
@@ -343,21 +597,28 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
                 .ok_or(ClientError::NotPublishing)?
         };
 
-        let registry_domain = self
-            .get_warg_registry(publish_info.name.namespace())
-            .await?;
-        let signing_key = keyring::Keyring::new(
-            self.keyring_backend
-                .as_deref()
-                .unwrap_or(keyring::Keyring::DEFAULT_BACKEND),
-        )?
-        .get_signing_key(
-            registry_domain.map(|domain| domain.to_string()).as_deref(),
-            &self.keys,
-            Some(&self.url().to_string()),
-        )?;
-
-        let res = self.publish_with_info(&signing_key, publish_info).await;
+        let res = match &self.signing_program {
+            Some(program) => {
+                let signer = signer::block_in_place(|| signer::ExternalSigner::new(program))?;
+                self.publish_with_info(&signer, publish_info).await
+            }
+            None => {
+                let registry_domain = self
+                    .get_warg_registry(publish_info.name.namespace())
+                    .await?;
+                let signing_key = keyring::Keyring::new(
+                    self.keyring_backend
+                        .as_deref()
+                        .unwrap_or(keyring::Keyring::DEFAULT_BACKEND),
+                )?
+                .get_signing_key(
+                    registry_domain.map(|domain| domain.to_string()).as_deref(),
+                    &self.keys,
+                    Some(&self.url().to_string()),
+                )?;
+                self.publish_with_info(&signing_key, publish_info).await
+            }
+        };
         self.registry.store_publish(None).await?;
         res
     }
@@ -371,8 +632,115 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
     pub async fn publish_with_info(
         &self,
-        signing_key: &signing::PrivateKey,
+        signing_key: &(impl signing::Signer + ?Sized),
         publish_info: PublishInfo,
+    ) -> ClientResult<RecordId> {
+        self.publish_record(signing_key, publish_info, None).await
+    }
+
+    /// Submits the provided publish information signed by an ephemeral key
+    /// bound to a publisher identity.
+    ///
+    /// An identity token for the ephemeral key is requested from the given source
+    /// and the identity it claims is recorded in the first entry of the record.
+    ///
+    /// Returns the identifier of the record that was published.
+    ///
+    /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
+    pub async fn publish_with_identity(
+        &self,
+        source: &(impl identity::IdentityTokenSource + ?Sized),
+        mut publish_info: PublishInfo,
+    ) -> ClientResult<RecordId> {
+        if publish_info.initializing() {
+            return Err(ClientError::CannotInitializeWithIdentity {
+                name: publish_info.name,
+            });
+        }
+
+        let (public_key, private_key) = signing::generate_p256_pair();
+        let token = source
+            .token(&public_key.fingerprint().to_string())
+            .await
+            .context("failed to get an identity token")?;
+        let claims = identity::IdentityClaims::from_token(&token)?;
+
+        publish_info.entries.insert(
+            0,
+            PublishEntry::Identity {
+                issuer: claims.issuer,
+                subject: claims.subject,
+            },
+        );
+
+        self.publish_record(&private_key, publish_info, Some(&token))
+            .await
+    }
+
+    /// Prepares the publish information for a release of a package.
+    ///
+    /// The release is checked against the package log in the registry
+    /// without signing or publishing anything; if the package does not exist,
+    /// it is initialized as part of the release. This allows a release to be
+    /// checked against several registries before it is published to any of
+    /// them.
+    ///
+    /// If `key_id` is `None`, the release is checked for publishing with a
+    /// publisher identity, which cannot initialize a package.
+    pub async fn prepare_release(
+        &self,
+        name: &PackageName,
+        version: Version,
+        content: AnyHash,
+        key_id: Option<&signing::KeyID>,
+    ) -> ClientResult<PublishInfo> {
+        let mut entries = Vec::with_capacity(2);
+        match self.fetch_package(name).await {
+            Ok(package) => {
+                if package.state.release(&version).is_some() {
+                    return Err(ClientError::PackageVersionAlreadyReleased {
+                        version,
+                        name: name.clone(),
+                    });
+                }
+
+                if let Some(key_id) = key_id {
+                    if !package
+                        .state
+                        .key_permissions(key_id)
+                        .is_some_and(|permissions| {
+                            permissions.contains(&package::Permission::Release)
+                        })
+                    {
+                        return Err(ClientError::ReleaseNotPermitted {
+                            name: name.clone(),
+                            key_id: key_id.clone(),
+                        });
+                    }
+                }
+            }
+            Err(ClientError::PackageDoesNotExist { .. }) if key_id.is_some() => {
+                entries.push(PublishEntry::Init);
+            }
+            Err(ClientError::PackageDoesNotExist { .. }) => {
+                return Err(ClientError::CannotInitializeWithIdentity { name: name.clone() });
+            }
+            Err(e) => return Err(e),
+        }
+
+        entries.push(PublishEntry::Release { version, content });
+        Ok(PublishInfo {
+            name: name.clone(),
+            head: None,
+            entries,
+        })
+    }
+
+    async fn publish_record(
+        &self,
+        signing_key: &(impl signing::Signer + ?Sized),
+        publish_info: PublishInfo,
+        identity_token: Option<&str>,
     ) -> ClientResult<RecordId> {
         if publish_info.entries.is_empty() {
             return Err(ClientError::NothingToPublish {
@@ -380,6 +748,40 @@ impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C,
             });
         }
 
+        self.ensure_online("publish")?;
+
+        // Fail before signing the record if the target registry would reject its content
+        let registry_domain = self
+            .get_warg_registry(publish_info.name.namespace())
+            .await?;
+        if let Some(max_size) = self
+            .registry_discovery(registry_domain.as_ref())
+            .await?
+            .and_then(|d| d.max_content_size)
+        {
+            for entry in &publish_info.entries {
+                let PublishEntry::Release { content, .. } = entry else {
+                    continue;
+                };
+                let Some(path) = self.content.content_location(content) else {
+                    continue;
+                };
+                let size = tokio::fs::metadata(&path)
+                    .await
+                    .with_context(|| {
+                        format!("failed to read metadata of `{path}`", path = path.display())
+                    })?
+                    .len();
+                if size > max_size {
+                    return Err(ClientError::ContentTooLarge {
+                        digest: content.clone(),
+                        size,
+                        max_size,
+                    });
+                }
+            }
+        }
+
         tracing::info!(
             "publishing {new}package `{name}`",
             name = publish_info.name,
@@ -461,7 +863,7 @@ Attempt to create `{package_name}` and publish the release y/N\n",
             let registry_domain = self.get_warg_registry(package.name.namespace()).await?;
 
             let log_id = LogId::package_log::<Sha256>(&package.name);
-            let record = info.finalize(signing_key)?;
+            let record = signer::block_in_place(|| info.finalize(signing_key))?;
             let record_id = RecordId::package_record::<Sha256>(&record);
             let record = match self
                 .api
@@ -472,6 +874,7 @@ Attempt to create `{package_name}` and publish the release y/N\n",
                         package_name: Cow::Borrowed(&package.name),
                         record: Cow::Owned(record.into()),
                         content_sources: Default::default(),
+                        identity_token: identity_token.map(Cow::Borrowed),
                     },
                 )
                 .await
@@ -629,6 +1032,354 @@ Attempt to create `{package_name}` and publish the release y/N\n",
         Ok(())
     }
 
+    /// Exchanges the latest checkpoint observed by the client with a peer.
+    ///
+    /// The peer's checkpoint must be signed by a key in the operator log and
+    /// consistent with the client's checkpoint; the consistency proof is
+    /// requested from the registry. Two checkpoints that cannot be proven
+    /// consistent are returned as evidence in
+    /// `ClientError::CheckpointEquivocation`.
+    ///
+    /// Returns the peer's checkpoint.
+    pub async fn gossip_checkpoint(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        peer_url: &str,
+    ) -> ClientResult<SerdeEnvelope<TimestampedCheckpoint>> {
+        self.ensure_online("gossip checkpoints")?;
+
+        let ours = match self.registry.load_checkpoint(registry_domain).await? {
+            Some(checkpoint) => checkpoint,
+            None => {
+                self.update_packages_and_return_federated_packages(
+                    registry_domain,
+                    Vec::<&mut PackageInfo>::new(),
+                )
+                .await?;
+                self.registry
+                    .load_checkpoint(registry_domain)
+                    .await?
+                    .ok_or_else(|| anyhow!("client storage has no registry checkpoint"))?
+            }
+        };
+
+        // A peer with a conflicting checkpoint at the same log length returns
+        // it, which is then verified and reported as an equivocation below
+        let peer = api::Client::new(peer_url, None)?;
+        let theirs = match peer
+            .gossip_checkpoint(
+                registry_domain,
+                GossipRequest {
+                    checkpoint: ours.clone(),
+                },
+            )
+            .await
+        {
+            Ok(response) => response.checkpoint,
+            Err(api::ClientError::Gossip(GossipError::ConflictingCheckpoint {
+                checkpoint: Some(checkpoint),
+                ..
+            })) => *checkpoint,
+            Err(e) => return Err(e.into()),
+        };
+
+        let operator = self
+            .registry
+            .load_operator(registry_domain)
+            .await?
+            .unwrap_or_default();
+        TimestampedCheckpoint::verify(
+            operator.state.public_key(theirs.key_id()).ok_or(
+                ClientError::InvalidCheckpointKeyId {
+                    key_id: theirs.key_id().clone(),
+                },
+            )?,
+            &theirs.as_ref().encode(),
+            theirs.signature(),
+        )
+        .or(Err(ClientError::InvalidCheckpointSignature))?;
+
+        let equivocation = || ClientError::CheckpointEquivocation {
+            ours: Box::new(ours.clone()),
+            theirs: Box::new(theirs.clone()),
+        };
+
+        let ours_checkpoint = &ours.as_ref().checkpoint;
+        let theirs_checkpoint = &theirs.as_ref().checkpoint;
+        let (from, to) = match ours_checkpoint
+            .log_length
+            .cmp(&theirs_checkpoint.log_length)
+        {
+            Ordering::Equal if ours_checkpoint == theirs_checkpoint => return Ok(theirs),
+            Ordering::Equal => return Err(equivocation()),
+            Ordering::Less => (ours_checkpoint, theirs_checkpoint),
+            Ordering::Greater => (theirs_checkpoint, ours_checkpoint),
+        };
+
+        match self
+            .api
+            .prove_log_consistency(
+                registry_domain,
+                ConsistencyRequest {
+                    from: from.log_length,
+                    to: to.log_length,
+                },
+                Cow::Borrowed(&from.log_root),
+                Cow::Borrowed(&to.log_root),
+            )
+            .await
+        {
+            Ok(_) => Ok(theirs),
+            Err(
+                api::ClientError::IncorrectConsistencyProof { .. }
+                | api::ClientError::ConsistencyProof(_)
+                | api::ClientError::Proof(ProofError::IncorrectProof { .. }),
+            ) => Err(equivocation()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Audits the checkpoints accepted from the registry.
+    ///
+    /// Every checkpoint in the client's audit trail must be signed by a key
+    /// of the operator log at the checkpoint's log length, be present and
+    /// identical in the registry's checkpoint history, and be proven
+    /// consistent with the checkpoint accepted before it. The last accepted
+    /// checkpoint is also proven consistent with the registry's latest
+    /// checkpoint.
+    pub async fn audit(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+    ) -> ClientResult<AuditSummary> {
+        self.ensure_online("audit checkpoints")?;
+
+        let trail = self.registry.load_audit_trail(registry_domain).await?;
+        let operator = self
+            .registry
+            .load_operator(registry_domain)
+            .await?
+            .unwrap_or_default();
+
+        // Compare the trail with the registry's history since the first accepted checkpoint
+        let mut history = IndexMap::new();
+        if let Some(first) = trail.first() {
+            let mut since = first.as_ref().checkpoint.log_length.checked_sub(1);
+            loop {
+                let page = self
+                    .api
+                    .fetch_checkpoints(
+                        registry_domain,
+                        FetchCheckpointsQuery { since, limit: None },
+                    )
+                    .await?;
+                for ts_checkpoint in page.checkpoints {
+                    let checkpoint = ts_checkpoint.as_ref().checkpoint.clone();
+                    since = Some(checkpoint.log_length);
+                    history.insert(checkpoint.log_length, checkpoint);
+                }
+
+                if !page.more {
+                    break;
+                }
+            }
+        }
+
+        for ts_checkpoint in &trail {
+            let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+            match history.get(&checkpoint.log_length) {
+                Some(theirs) if theirs == checkpoint => {}
+                Some(_) => {
+                    return Err(ClientError::CheckpointHistoryMismatch {
+                        log_length: checkpoint.log_length,
+                    })
+                }
+                None => {
+                    return Err(ClientError::CheckpointHistoryOmitted {
+                        log_length: checkpoint.log_length,
+                    })
+                }
+            }
+        }
+
+        let latest = self.api.latest_checkpoint(registry_domain).await?;
+
+        // Replay the operator log to verify each checkpoint with the keys of
+        // the operator log at the checkpoint's log length
+        let (records, _) = self
+            .fetch_all_records(
+                registry_domain,
+                latest.as_ref().checkpoint.log_length,
+                std::iter::empty(),
+            )
+            .await?;
+        let mut records = records
+            .into_iter()
+            .map(PublishedProtoEnvelope::<operator::OperatorRecord>::try_from)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .peekable();
+        let accepted_head = operator.state.head().as_ref().map(|head| &head.digest);
+        let mut replayed = OperatorInfo::default();
+        let mut found_head = accepted_head.is_none();
+        for ts_checkpoint in trail.iter().chain(std::iter::once(&latest)) {
+            let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+            while let Some(record) = records.next_if(|record| record.registry_index < log_length) {
+                replayed.state = replayed
+                    .state
+                    .validate(&record.envelope)
+                    .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+                found_head |=
+                    replayed.state.head().as_ref().map(|head| &head.digest) == accepted_head;
+            }
+            verify_checkpoint_signature(&replayed, ts_checkpoint)?;
+        }
+
+        // The replayed operator log must contain the operator log the client accepted
+        if !found_head {
+            return Err(ClientError::OperatorLogDiverged);
+        }
+
+        let checkpoints: Vec<_> = trail
+            .iter()
+            .chain(std::iter::once(&latest))
+            .map(|ts_checkpoint| &ts_checkpoint.as_ref().checkpoint)
+            .collect();
+        for pair in checkpoints.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            match from.log_length.cmp(&to.log_length) {
+                Ordering::Greater => {
+                    return Err(ClientError::CheckpointLogLengthRewind {
+                        from: from.log_length,
+                        to: to.log_length,
+                    });
+                }
+                Ordering::Less => {
+                    self.api
+                        .prove_log_consistency(
+                            registry_domain,
+                            ConsistencyRequest {
+                                from: from.log_length,
+                                to: to.log_length,
+                            },
+                            Cow::Borrowed(&from.log_root),
+                            Cow::Borrowed(&to.log_root),
+                        )
+                        .await?;
+                }
+                Ordering::Equal => {
+                    if from != to {
+                        return Err(ClientError::CheckpointChangedLogRootOrMapRoot {
+                            log_length: from.log_length,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(AuditSummary {
+            checkpoints: trail.len(),
+            log_length: latest.as_ref().checkpoint.log_length,
+        })
+    }
+
+    /// Waits for new records in the given package logs.
+    ///
+    /// The registry is watched until one of the package logs has new records;
+    /// the new records are then fetched, verified against the new registry
+    /// checkpoint as with `update`, and stored in client storage.
+    ///
+    /// Returns the updated information of the packages with new records.
+    pub async fn watch(&self, packages: &[PackageName]) -> ClientResult<Vec<PackageInfo>> {
+        self.ensure_online("watch package logs")?;
+
+        let mut registries: IndexMap<Option<RegistryDomain>, Vec<&PackageName>> = IndexMap::new();
+        for name in packages {
+            registries
+                .entry(self.get_warg_registry(name.namespace()).await?)
+                .or_default()
+                .push(name);
+        }
+
+        if registries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (res, _, _) =
+            futures_util::future::select_all(registries.iter().map(|(registry_domain, names)| {
+                Box::pin(self.watch_registry(registry_domain.as_ref(), names))
+            }))
+            .await;
+        res
+    }
+
+    async fn watch_registry(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        packages: &[&PackageName],
+    ) -> ClientResult<Vec<PackageInfo>> {
+        let log_ids: Vec<LogId> = packages
+            .iter()
+            .map(|name| LogId::package_log::<Sha256>(name))
+            .collect();
+
+        loop {
+            let mut infos = Vec::with_capacity(packages.len());
+            for name in packages {
+                infos.push(self.package(name).await?);
+            }
+
+            let log_length = self
+                .registry
+                .load_checkpoint(registry_domain)
+                .await?
+                .map(|ts_checkpoint| ts_checkpoint.as_ref().checkpoint.log_length)
+                .unwrap_or_default();
+
+            let response = self
+                .api
+                .watch(
+                    registry_domain,
+                    WatchRequest {
+                        log_length,
+                        packages: Cow::Borrowed(&log_ids),
+                    },
+                )
+                .await?;
+
+            if response.packages.is_empty() {
+                continue;
+            }
+
+            let mut changed: Vec<PackageInfo> = infos
+                .into_iter()
+                .filter(|info| {
+                    response
+                        .packages
+                        .contains(&LogId::package_log::<Sha256>(&info.name))
+                })
+                .collect();
+            let heads: Vec<Option<RecordId>> = changed
+                .iter()
+                .map(|info| info.state.head().as_ref().map(|head| head.digest.clone()))
+                .collect();
+
+            self.update_checkpoints(changed.iter_mut()).await?;
+
+            let changed: Vec<PackageInfo> = changed
+                .into_iter()
+                .zip(heads)
+                .filter(|(info, head)| {
+                    info.state.head().as_ref().map(|head| &head.digest) != head.as_ref()
+                })
+                .map(|(info, _)| info)
+                .collect();
+
+            if !changed.is_empty() {
+                return Ok(changed);
+            }
+        }
+    }
+
     /// Downloads the latest version of a package into client storage that
     /// satisfies the given version requirement.
     ///
@@ -655,18 +1406,169 @@ Attempt to create `{package_name}` and publish the release y/N\n",
             package = package.as_ref(),
             version_requirement = requirement.to_string(),
             registry_header = ?registry_domain,
-            "downloading",
+            "downloading",
+        );
+
+        match info.state.find_latest_release(requirement) {
+            Some(release) => {
+                let digest = release
+                    .content()
+                    .context("invalid state: not yanked but missing content")?
+                    .clone();
+                let path = self
+                    .download_content(registry_domain.as_ref(), &digest)
+                    .await?;
+                Ok(Some(PackageDownload {
+                    version: release.version.clone(),
+                    digest,
+                    path,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Downloads the latest version of a package that satisfies the given
+    /// version requirement without synchronizing the package log into client
+    /// storage.
+    ///
+    /// The registry returns the complete package and operator logs along with
+    /// its latest checkpoint and a map proof of both log heads; the logs are
+    /// validated and proven against the checkpoint before the content is
+    /// downloaded. If a checkpoint is already stored for the registry, the
+    /// returned checkpoint must be consistent with it.
+    ///
+    /// Neither the package log nor the checkpoint are stored.
+    ///
+    /// If a version satisfying the requirement does not exist, `None` is
+    /// returned.
+    pub async fn download_with_proof(
+        &self,
+        package: &PackageName,
+        requirement: &VersionReq,
+    ) -> Result<Option<PackageDownload>, ClientError> {
+        self.ensure_online("download a package with proof")?;
+
+        let registry_domain = self.get_warg_registry(package.namespace()).await?;
+        let registry_domain = registry_domain.as_ref();
+        self.registry_discovery(registry_domain).await?;
+
+        tracing::debug!(
+            package = package.as_ref(),
+            version_requirement = requirement.to_string(),
+            registry_header = ?registry_domain,
+            "downloading with proof",
         );
 
-        match info.state.find_latest_release(requirement) {
+        let log_id = LogId::package_log::<Sha256>(package);
+        let response = match self.api.fetch_package(registry_domain, &log_id).await {
+            Ok(response) => response,
+            Err(api::ClientError::Fetch(FetchError::LogNotFound(_))) => {
+                return Err(ClientError::PackageDoesNotExist {
+                    name: package.clone(),
+                    has_auth_token: self.api.auth_token().is_some(),
+                });
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut operator = OperatorInfo::default();
+        let mut leafs = Vec::with_capacity(2);
+        for record in response.operator {
+            let proto_envelope: PublishedProtoEnvelope<operator::OperatorRecord> =
+                record.envelope.try_into()?;
+            let root = operator.state.head().is_none();
+            operator.state = operator
+                .state
+                .validate(&proto_envelope.envelope)
+                .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+            if root {
+                self.verify_operator_pin(
+                    registry_domain,
+                    &operator.state,
+                    &proto_envelope.envelope,
+                )
+                .await?;
+            }
+        }
+        match operator.state.head() {
+            Some(head) => leafs.push(LogLeaf {
+                log_id: LogId::operator_log::<Sha256>(),
+                record_id: head.digest.clone(),
+            }),
+            None => return Err(ClientError::NoOperatorRecords),
+        }
+
+        let mut state = package::LogState::default();
+        for record in response.records {
+            let proto_envelope: PublishedProtoEnvelope<package::PackageRecord> =
+                record.envelope.try_into()?;
+            state = state.validate(&proto_envelope.envelope).map_err(|inner| {
+                ClientError::PackageValidationFailed {
+                    name: package.clone(),
+                    inner,
+                }
+            })?;
+        }
+        match state.head() {
+            Some(head) => leafs.push(LogLeaf {
+                log_id,
+                record_id: head.digest.clone(),
+            }),
+            None => {
+                return Err(ClientError::PackageLogEmpty {
+                    name: package.clone(),
+                })
+            }
+        }
+
+        let ts_checkpoint = response.checkpoint;
+        let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+        verify_checkpoint_signature(&operator, &ts_checkpoint)?;
+        self.verify_checkpoint_witnesses(registry_domain, &ts_checkpoint)
+            .await?;
+        api::Client::validate_map_proof(&response.map, checkpoint, &leafs)?;
+
+        // Ensure the checkpoint is consistent with the one previously accepted
+        if let Some(from) = self.registry.load_checkpoint(registry_domain).await? {
+            let from = &from.as_ref().checkpoint;
+            match from.log_length.cmp(&checkpoint.log_length) {
+                Ordering::Greater => {
+                    return Err(ClientError::CheckpointLogLengthRewind {
+                        from: from.log_length,
+                        to: checkpoint.log_length,
+                    });
+                }
+                Ordering::Less => {
+                    self.api
+                        .prove_log_consistency(
+                            registry_domain,
+                            ConsistencyRequest {
+                                from: from.log_length,
+                                to: checkpoint.log_length,
+                            },
+                            Cow::Borrowed(&from.log_root),
+                            Cow::Borrowed(&checkpoint.log_root),
+                        )
+                        .await?;
+                }
+                Ordering::Equal => {
+                    if from != checkpoint {
+                        return Err(ClientError::CheckpointChangedLogRootOrMapRoot {
+                            log_length: from.log_length,
+                        });
+                    }
+                }
+            }
+        }
+
+        match state.find_latest_release(requirement) {
             Some(release) => {
                 let digest = release
                     .content()
                     .context("invalid state: not yanked but missing content")?
                     .clone();
-                let path = self
-                    .download_content(registry_domain.as_ref(), &digest)
-                    .await?;
+                let path = self.download_content(registry_domain, &digest).await?;
                 Ok(Some(PackageDownload {
                     version: release.version.clone(),
                     digest,
@@ -677,6 +1579,96 @@ Attempt to create `{package_name}` and publish the release y/N\n",
         }
     }
 
+    /// Downloads the latest versions of multiple packages into client storage
+    /// that satisfy the given version requirements.
+    ///
+    /// Package logs not present in client storage are fetched from the
+    /// registry together, and the content of the resolved releases is
+    /// downloaded concurrently.
+    ///
+    /// Returns the downloads in the order of the given packages; an entry is
+    /// `None` if a version satisfying its requirement does not exist.
+    pub async fn download_many<'a>(
+        &self,
+        packages: impl IntoIterator<Item = (&'a PackageName, &'a VersionReq)>,
+    ) -> Result<Vec<Option<PackageDownload>>, ClientError> {
+        let packages: Vec<_> = packages.into_iter().collect();
+
+        let mut infos = IndexMap::new();
+        let mut missing = IndexSet::new();
+        for (name, _) in &packages {
+            let registry_domain = self.get_warg_registry(name.namespace()).await?;
+            if !self.offline
+                && self
+                    .registry
+                    .load_package(registry_domain.as_ref(), name)
+                    .await?
+                    .is_none()
+            {
+                missing.insert(*name);
+            } else if !infos.contains_key(*name) {
+                infos.insert((*name).clone(), self.package(name).await?);
+            }
+        }
+
+        if !missing.is_empty() {
+            for info in self.fetch_packages(missing).await? {
+                infos.insert(info.name.clone(), info);
+            }
+        }
+
+        let mut releases = Vec::with_capacity(packages.len());
+        for (name, requirement) in &packages {
+            tracing::debug!(
+                package = name.as_ref(),
+                version_requirement = requirement.to_string(),
+                "downloading",
+            );
+
+            let release = match infos[*name].state.find_latest_release(requirement) {
+                Some(release) => Some((
+                    release.version.clone(),
+                    release
+                        .content()
+                        .context("invalid state: not yanked but missing content")?
+                        .clone(),
+                )),
+                None => None,
+            };
+
+            releases.push((self.get_warg_registry(name.namespace()).await?, release));
+        }
+
+        // Download each distinct content digest once
+        let mut digests = IndexMap::new();
+        for (registry_domain, release) in &releases {
+            if let Some((_, digest)) = release {
+                digests.entry(digest).or_insert(registry_domain.as_ref());
+            }
+        }
+
+        let paths: IndexMap<AnyHash, PathBuf> = futures_util::stream::iter(digests)
+            .map(|(digest, registry_domain)| async move {
+                self.download_content(registry_domain, digest)
+                    .await
+                    .map(|path| (digest.clone(), path))
+            })
+            .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+            .try_collect()
+            .await?;
+
+        Ok(releases
+            .into_iter()
+            .map(|(_, release)| {
+                release.map(|(version, digest)| PackageDownload {
+                    version,
+                    path: paths[&digest].clone(),
+                    digest,
+                })
+            })
+            .collect())
+    }
+
     /// Downloads the latest version of a package.
     ///
     /// If the requested package log is not present in client storage, it
@@ -824,6 +1816,7 @@ Attempt to create `{package_name}` and publish the release y/N\n",
         registry_domain: Option<&RegistryDomain>,
         packages: impl IntoIterator<Item = &'a mut PackageInfo>,
     ) -> Result<IndexMap<Option<RegistryDomain>, Vec<&'a mut PackageInfo>>, ClientError> {
+        self.registry_discovery(registry_domain).await?;
         let ts_checkpoint = self.api.latest_checkpoint(registry_domain).await?;
         let checkpoint = &ts_checkpoint.as_ref().checkpoint;
 
@@ -895,6 +1888,18 @@ Attempt to create `{package_name}` and publish the release y/N\n",
                     api::ClientError::Fetch(FetchError::LogNotFound(log_id))
                     | api::ClientError::Package(PackageError::LogNotFound(log_id)) => {
                         if let Some(name) = packages.get(log_id).map(|p| p.name.clone()) {
+                            // Don't take the registry's word that the package does not exist
+                            self.api
+                                .prove_absence(
+                                    registry_domain,
+                                    AbsenceRequest {
+                                        log_length: checkpoint.log_length,
+                                        log_ids: vec![log_id.clone()],
+                                    },
+                                    checkpoint,
+                                )
+                                .await?;
+
                             Err(ClientError::PackageDoesNotExist {
                                 name,
                                 has_auth_token,
@@ -1012,10 +2017,19 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
                 if operator.head_registry_index.is_none()
                     || proto_envelope.registry_index > operator.head_registry_index.unwrap()
                 {
+                    let root = operator.state.head().is_none();
                     operator.state = operator
                         .state
                         .validate(&proto_envelope.envelope)
                         .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+                    if root {
+                        self.verify_operator_pin(
+                            registry_domain,
+                            &operator.state,
+                            &proto_envelope.envelope,
+                        )
+                        .await?;
+                    }
                     operator.head_registry_index = Some(proto_envelope.registry_index);
                     operator.head_fetch_token = Some(record.fetch_token);
                 }
@@ -1073,6 +2087,10 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         )
         .or(Err(ClientError::InvalidCheckpointSignature))?;
 
+        // verify witness cosignatures
+        self.verify_checkpoint_witnesses(registry_domain, &ts_checkpoint)
+            .await?;
+
         // Prove inclusion for the current log heads
         let mut leaf_indices = Vec::with_capacity(packages.len() + 1 /* for operator */);
         let mut leafs = Vec::with_capacity(leaf_indices.len());
@@ -1110,6 +2128,7 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
                     InclusionRequest {
                         log_length: checkpoint.log_length,
                         leafs: leaf_indices,
+                        map_multi_proof: true,
                     },
                     checkpoint,
                     &leafs,
@@ -1117,7 +2136,8 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
                 .await?;
         }
 
-        if let Some(from) = self.registry.load_checkpoint(registry_domain).await? {
+        let previous = self.registry.load_checkpoint(registry_domain).await?;
+        if let Some(from) = &previous {
             let from_log_length = from.as_ref().checkpoint.log_length;
             let to_log_length = ts_checkpoint.as_ref().checkpoint.log_length;
 
@@ -1139,7 +2159,7 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
                             Cow::Borrowed(&from.as_ref().checkpoint.log_root),
                             Cow::Borrowed(&ts_checkpoint.as_ref().checkpoint.log_root),
                         )
-                        .await?
+                        .await?;
                 }
                 Ordering::Equal => {
                     if from.as_ref().checkpoint.log_root
@@ -1177,6 +2197,14 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
             .store_checkpoint(registry_domain, &ts_checkpoint)
             .await?;
 
+        // Record a newly accepted checkpoint in the audit trail
+        if !matches!(&previous, Some(from) if from.as_ref().checkpoint.log_length == checkpoint.log_length)
+        {
+            self.registry
+                .append_audit_checkpoint(registry_domain, &ts_checkpoint)
+                .await?;
+        }
+
         // return packages to be retrieved from other registries
         Ok(federated_packages)
     }
@@ -1186,6 +2214,8 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         &self,
         packages: impl IntoIterator<Item = &mut PackageInfo>,
     ) -> Result<(), ClientError> {
+        self.ensure_online("fetch package logs")?;
+
         // first collect the packages that we already have namespace mappings for
         let mut federated_packages: IndexMap<Option<RegistryDomain>, Vec<&mut PackageInfo>> =
             IndexMap::new();
@@ -1246,6 +2276,10 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         {
             Some(mut info) => {
                 tracing::info!("log for package `{name}` already exists in storage");
+                if self.offline {
+                    self.verify_stored_checkpoint(registry_domain.as_ref())
+                        .await?;
+                }
                 if info.registry.is_none() {
                     info.registry = registry_domain
                         .clone()
@@ -1253,6 +2287,9 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
                 }
                 Ok(info)
             }
+            None if self.offline => Err(ClientError::OfflineUnavailable(format!(
+                "fetch the log for package `{name}`"
+            ))),
             None => {
                 let mut info = PackageInfo::new(name.clone());
                 self.update_checkpoints([&mut info]).await?;
@@ -1261,6 +2298,101 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         }
     }
 
+    /// Verifies the checkpoint in client storage was signed by a key of the
+    /// operator log in client storage.
+    ///
+    /// This is used in offline mode where the checkpoint cannot be refreshed
+    /// from the registry.
+    async fn verify_stored_checkpoint(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+    ) -> ClientResult<()> {
+        let (Some(ts_checkpoint), Some(operator)) = (
+            self.registry.load_checkpoint(registry_domain).await?,
+            self.registry.load_operator(registry_domain).await?,
+        ) else {
+            return Err(ClientError::OfflineUnavailable(
+                "fetch the registry checkpoint".to_string(),
+            ));
+        };
+
+        verify_checkpoint_signature(&operator, &ts_checkpoint)
+    }
+
+    /// Verifies the init record of a registry's operator log against the
+    /// operator pin of the registry.
+    ///
+    /// If the registry has no configured pin and none was recorded in
+    /// registry storage, the init record is pinned on this first contact.
+    async fn verify_operator_pin(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        state: &operator::LogState,
+        init: &ProtoEnvelope<operator::OperatorRecord>,
+    ) -> ClientResult<()> {
+        let found = OperatorPin {
+            key_id: init.key_id().clone(),
+            root_record: state.head().as_ref().map(|head| head.digest.clone()),
+        };
+        let registry = registry_domain
+            .cloned()
+            .unwrap_or_else(|| self.url().registry_domain());
+
+        let expected = match self.operator_pins.get(&registry) {
+            Some(pin) => Some(pin.clone()),
+            None => self.registry.load_operator_pin(&registry).await?,
+        };
+
+        match expected {
+            Some(expected) if !expected.matches(&found) => Err(ClientError::OperatorPinMismatch {
+                registry,
+                expected,
+                found,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                tracing::info!("pinning operator `{found}` of registry `{registry}`");
+                self.registry.store_operator_pin(&registry, &found).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Verifies that the checkpoint is cosigned by enough trusted witnesses
+    /// to satisfy the client's witness policy.
+    async fn verify_checkpoint_witnesses(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> ClientResult<()> {
+        let required = self.witness_policy.threshold;
+        if required == 0 {
+            return Ok(());
+        }
+
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        let cosigned = self
+            .api
+            .cosigned_checkpoint(registry_domain, log_length)
+            .await?;
+
+        let found = if cosigned.checkpoint.as_ref() == ts_checkpoint.as_ref() {
+            cosigned.witnessed_by(&self.witness_policy.keys)
+        } else {
+            0
+        };
+
+        if found < required {
+            return Err(ClientError::InsufficientWitnessCosignatures {
+                log_length,
+                found,
+                required,
+            });
+        }
+
+        Ok(())
+    }
+
     async fn get_package_record(
         &self,
         registry_domain: Option<&RegistryDomain>,
@@ -1268,6 +2400,8 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         log_id: &LogId,
         record_id: &RecordId,
     ) -> ClientResult<PackageRecord> {
+        self.ensure_online("get package records")?;
+
         let record = self
             .api
             .get_package_record(registry_domain, log_id, record_id)
@@ -1305,15 +2439,14 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         match self.content.content_location(digest) {
             Some(path) => {
                 tracing::info!("content for digest `{digest}` already exists in storage");
+                if self.offline {
+                    verify_stored_content(&self.content, digest).await?;
+                }
                 Ok(path)
             }
             None => {
-                self.content
-                    .store_content(
-                        Box::pin(self.api.download_content(registry_domain, digest).await?),
-                        Some(digest),
-                    )
-                    .await?;
+                self.ensure_online(&format!("download content `{digest}`"))?;
+                self.fetch_content(registry_domain, digest).await?;
 
                 self.content
                     .content_location(digest)
@@ -1324,6 +2457,55 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         }
     }
 
+    /// Fetches the content for the specified digest into client storage.
+    ///
+    /// Each content source is tried in order. A download that fails part way
+    /// is resumed from the bytes already in client storage, either with the
+    /// next source or on a later call.
+    async fn fetch_content(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        digest: &AnyHash,
+    ) -> ClientResult<()> {
+        let ContentSourcesResponse { content_sources } =
+            self.api.content_sources(registry_domain, digest).await?;
+
+        let sources = content_sources
+            .get(digest)
+            .ok_or_else(|| api::ClientError::AllSourcesFailed(digest.clone()))?;
+
+        for source in sources {
+            let (offset, stream) = match self
+                .api
+                .download_content_source(digest, source, self.content.partial_content_len(digest))
+                .await
+            {
+                Ok(res) => res,
+                Err(e) => {
+                    tracing::debug!("{e}");
+                    continue;
+                }
+            };
+
+            if offset > 0 {
+                tracing::info!("resuming download of content `{digest}` at byte {offset}");
+            }
+
+            match self
+                .content
+                .store_content_at(Box::pin(stream), digest, offset)
+                .await
+            {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    tracing::debug!("failed to download content `{digest}`: {e:#}");
+                }
+            }
+        }
+
+        Err(api::ClientError::AllSourcesFailed(digest.clone()).into())
+    }
+
     /// Downloads the content for the specified digest as a stream.
     ///
     /// If the content already exists in client storage, it is read from the client storage.
@@ -1337,14 +2519,20 @@ current_registry = registry_domain.map(|d| d.as_str()).unwrap_or(&self.url().saf
         match self.content.content_location(digest) {
             Some(path) => {
                 tracing::info!("content for digest `{digest}` already exists in storage");
+                if self.offline {
+                    verify_stored_content(&self.content, digest).await?;
+                }
                 let file = tokio::fs::File::open(path)
                     .await
                     .map_err(ClientError::IoError)?;
                 Ok(ReaderStream::new(file).map_err(Into::into).boxed())
             }
-            None => Ok(Box::pin(
-                self.api.download_content(registry_domain, digest).await?,
-            )),
+            None => {
+                self.ensure_online(&format!("download content `{digest}`"))?;
+                Ok(Box::pin(
+                    self.api.download_content(registry_domain, digest).await?,
+                ))
+            }
         }
     }
 }
@@ -1372,11 +2560,16 @@ impl FileSystemClient {
                 .unwrap_or(DEFAULT_REGISTRY),
         )?;
 
-        let url = if let Some(warg_url) =
+        // The `.well-known` config cannot be resolved without network access
+        let well_known = if config.offline {
+            None
+        } else {
             api::Client::new(checking_url_for_well_known.to_string(), None)?
                 .well_known_config()
                 .await?
-        {
+        };
+
+        let url = if let Some(warg_url) = well_known {
             if !disable_interactive && warg_url != checking_url_for_well_known {
                 println!(
                     "Resolved `{well_known}` to registry hosted on `{registry}`",
@@ -1411,6 +2604,10 @@ impl FileSystemClient {
         let disable_interactive =
             cfg!(not(feature = "cli-interactive")) || config.disable_interactive;
 
+        if let Some(dir) = &config.vendor_dir {
+            return Self::try_new_vendored(dir, config, disable_interactive).await;
+        }
+
         let StoragePaths {
             registry_url: url,
             registries_dir,
@@ -1418,12 +2615,6 @@ impl FileSystemClient {
             namespace_map_path,
         } = Self::storage_paths(registry, config, disable_interactive).await?;
 
-        let (keyring_backend, keys) = if cfg!(feature = "keyring") {
-            (config.keyring_backend.clone(), config.keys.clone())
-        } else {
-            (None, IndexSet::new())
-        };
-
         #[cfg(feature = "keyring")]
         if auth_token.is_none() && config.keyring_auth {
             auth_token = crate::keyring::Keyring::from_config(config)?.get_auth_token(&url)?
@@ -1444,12 +2635,7 @@ impl FileSystemClient {
             packages,
             content,
             namespace_map,
-            auth_token,
-            config.ignore_federation_hints,
-            config.auto_accept_federation_hints,
-            disable_interactive,
-            keyring_backend,
-            keys,
+            ClientOptions::from_config(config, auth_token, disable_interactive),
         )?))
     }
 
@@ -1485,6 +2671,10 @@ impl FileSystemClient {
         let disable_interactive =
             cfg!(not(feature = "cli-interactive")) || config.disable_interactive;
 
+        if let Some(dir) = &config.vendor_dir {
+            return Self::new_vendored(dir, config, disable_interactive).await;
+        }
+
         let StoragePaths {
             registry_url: url,
             registries_dir,
@@ -1492,12 +2682,6 @@ impl FileSystemClient {
             namespace_map_path,
         } = Self::storage_paths(registry, config, disable_interactive).await?;
 
-        let (keyring_backend, keys) = if cfg!(feature = "keyring") {
-            (config.keyring_backend.clone(), config.keys.clone())
-        } else {
-            (None, IndexSet::new())
-        };
-
         #[cfg(feature = "keyring")]
         if auth_token.is_none() && config.keyring_auth {
             auth_token = crate::keyring::Keyring::from_config(config)?.get_auth_token(&url)?
@@ -1508,12 +2692,7 @@ impl FileSystemClient {
             FileSystemRegistryStorage::lock(registries_dir)?,
             FileSystemContentStorage::lock(content_dir)?,
             FileSystemNamespaceMapStorage::new(namespace_map_path),
-            auth_token,
-            config.ignore_federation_hints,
-            config.auto_accept_federation_hints,
-            disable_interactive,
-            keyring_backend,
-            keys,
+            ClientOptions::from_config(config, auth_token, disable_interactive),
         )
     }
 
@@ -1550,6 +2729,109 @@ pub struct PackageDownloadInfo {
     pub digest: AnyHash,
 }
 
+/// Represents options for collecting garbage in client content storage.
+#[derive(Debug, Default, Clone)]
+pub struct ContentGcOptions {
+    /// Remove content older than this, even if it is referenced.
+    pub max_age: Option<Duration>,
+    /// Remove the oldest content until the content storage is at most this
+    /// many bytes, even if it is referenced.
+    pub max_size: Option<u64>,
+    /// Only report the content that would be removed.
+    pub dry_run: bool,
+}
+
+/// Represents the result of collecting garbage in client content storage.
+pub struct ContentGcSummary {
+    /// The content that was removed.
+    pub removed: Vec<ContentEntry>,
+    /// The size in bytes of the content that was retained.
+    pub retained_size: u64,
+}
+
+/// Represents the result of auditing the checkpoints accepted from a registry.
+#[derive(Debug, Clone, Copy)]
+pub struct AuditSummary {
+    /// The number of accepted checkpoints that were audited.
+    pub checkpoints: usize,
+    /// The log length of the registry's latest checkpoint.
+    pub log_length: RegistryLen,
+}
+
+/// Ensures a registry supports the API version and algorithms used by this client.
+fn check_compatibility(
+    registry: RegistryDomain,
+    discovery: &RegistryDiscovery,
+) -> ClientResult<()> {
+    let supports = |values: &[String], value: &str| values.iter().any(|v| v == value);
+    let reason = if !supports(&discovery.api_versions, API_VERSION) {
+        format!("API version `{API_VERSION}` is not supported")
+    } else if !supports(
+        &discovery.hash_algorithms,
+        &HashAlgorithm::Sha256.to_string(),
+    ) {
+        format!(
+            "hash algorithm `{algorithm}` is not supported",
+            algorithm = HashAlgorithm::Sha256
+        )
+    } else if !supports(
+        &discovery.signature_algorithms,
+        &SignatureAlgorithm::EcdsaP256.to_string(),
+    ) {
+        format!(
+            "signature algorithm `{algorithm}` is not supported",
+            algorithm = SignatureAlgorithm::EcdsaP256
+        )
+    } else {
+        return Ok(());
+    };
+
+    Err(ClientError::IncompatibleRegistry { registry, reason })
+}
+
+/// Verifies that the content in the given storage matches the given digest.
+pub(crate) async fn verify_stored_content(
+    content: &impl ContentStorage,
+    digest: &AnyHash,
+) -> ClientResult<()> {
+    let mut stream =
+        content
+            .load_content(digest)
+            .await?
+            .ok_or_else(|| ClientError::ContentNotFound {
+                digest: digest.clone(),
+            })?;
+    let mut hasher = digest.algorithm().hasher();
+    while let Some(bytes) = stream.try_next().await? {
+        hasher.update(&bytes);
+    }
+    let found = hasher.finalize();
+    if &found != digest {
+        return Err(ClientError::IncorrectContent {
+            digest: found,
+            expected: digest.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Verifies that a checkpoint was signed by a key of the operator log.
+fn verify_checkpoint_signature(
+    operator: &OperatorInfo,
+    ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+) -> ClientResult<()> {
+    TimestampedCheckpoint::verify(
+        operator.state.public_key(ts_checkpoint.key_id()).ok_or(
+            ClientError::InvalidCheckpointKeyId {
+                key_id: ts_checkpoint.key_id().clone(),
+            },
+        )?,
+        &ts_checkpoint.as_ref().encode(),
+        ts_checkpoint.signature(),
+    )
+    .or(Err(ClientError::InvalidCheckpointSignature))
+}
+
 /// Represents an error returned by Warg registry clients.
 #[derive(Debug, Error)]
 pub enum ClientError {
@@ -1569,10 +2851,28 @@ pub enum ClientError {
     #[error("unauthorized: {0}")]
     Unauthorized(String),
 
+    /// A package cannot be initialized by a publisher identity.
+    #[error("package `{name}` cannot be initialized with a publisher identity; initialize it with a signing key first")]
+    CannotInitializeWithIdentity {
+        /// The package that cannot be initialized.
+        name: PackageName,
+    },
+
     /// Checkpoint signature failed verification
     #[error("invalid checkpoint signature")]
     InvalidCheckpointSignature,
 
+    /// The checkpoint is not cosigned by enough trusted witnesses.
+    #[error("checkpoint with log length {log_length} has {found} trusted witness cosignature(s) but {required} are required")]
+    InsufficientWitnessCosignatures {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+        /// The number of trusted witness cosignatures found.
+        found: usize,
+        /// The number of trusted witness cosignatures required.
+        required: usize,
+    },
+
     /// Checkpoint signature failed verification
     #[error("invalid checkpoint key ID `{key_id}`")]
     InvalidCheckpointKeyId {
@@ -1584,6 +2884,37 @@ pub enum ClientError {
     #[error("the server did not provide any operator records")]
     NoOperatorRecords,
 
+    /// The operator log of the registry does not match its pinned root.
+    #[error("operator log of registry `{registry}` does not match its pin: expected `{expected}` but found `{found}`")]
+    OperatorPinMismatch {
+        /// The registry with the mismatched operator log.
+        registry: RegistryDomain,
+        /// The pinned operator log root.
+        expected: OperatorPin,
+        /// The operator log root provided by the registry.
+        found: OperatorPin,
+    },
+
+    /// The registry does not support the capabilities required by the client.
+    #[error("registry `{registry}` is incompatible with this client: {reason}")]
+    IncompatibleRegistry {
+        /// The incompatible registry.
+        registry: RegistryDomain,
+        /// The reason the registry is incompatible.
+        reason: String,
+    },
+
+    /// The content of a release exceeds the maximum size accepted by the registry.
+    #[error("content `{digest}` is {size} bytes, which exceeds the registry's maximum content size of {max_size} bytes")]
+    ContentTooLarge {
+        /// The digest of the content.
+        digest: AnyHash,
+        /// The size of the content in bytes.
+        size: u64,
+        /// The maximum content size accepted by the registry.
+        max_size: u64,
+    },
+
     /// The operator failed validation.
     #[error("operator failed validation: {inner}")]
     OperatorValidationFailed {
@@ -1651,6 +2982,24 @@ pub enum ClientError {
         name: PackageName,
     },
 
+    /// The package version was already released.
+    #[error("version `{version}` of package `{name}` was already released")]
+    PackageVersionAlreadyReleased {
+        /// The version that was already released.
+        version: Version,
+        /// The package with the released version.
+        name: PackageName,
+    },
+
+    /// The key does not have permission to release the package.
+    #[error("key `{key_id}` does not have permission to release package `{name}`")]
+    ReleaseNotPermitted {
+        /// The package that cannot be released.
+        name: PackageName,
+        /// The key ID without permission.
+        key_id: signing::KeyID,
+    },
+
     /// The package version requirement does not exist.
     #[error("version that satisfies requirement `{version}` was not found for package `{name}`")]
     PackageVersionRequirementDoesNotExist {
@@ -1736,10 +3085,54 @@ pub enum ClientError {
         log_length: RegistryLen,
     },
 
+    /// Two signed checkpoints of the registry could not be proven consistent.
+    #[error("registry equivocated: the checkpoint observed by the client is inconsistent with the checkpoint observed by a peer")]
+    CheckpointEquivocation {
+        /// The checkpoint observed by the client.
+        ours: Box<SerdeEnvelope<TimestampedCheckpoint>>,
+        /// The checkpoint observed by the peer.
+        theirs: Box<SerdeEnvelope<TimestampedCheckpoint>>,
+    },
+
+    /// The registry's checkpoint history differs from a checkpoint accepted
+    /// by the client.
+    #[error("registry checkpoint history has a different checkpoint with log length `{log_length}` than was previously accepted")]
+    CheckpointHistoryMismatch {
+        /// The checkpoint log length.
+        log_length: RegistryLen,
+    },
+
+    /// The registry's checkpoint history omits a checkpoint accepted by the
+    /// client.
+    #[error("registry checkpoint history does not have the checkpoint with log length `{log_length}` that was previously accepted")]
+    CheckpointHistoryOmitted {
+        /// The checkpoint log length.
+        log_length: RegistryLen,
+    },
+
+    /// The registry's operator log does not contain the operator log
+    /// previously accepted by the client.
+    #[error(
+        "registry operator log does not contain the operator log that was previously accepted"
+    )]
+    OperatorLogDiverged,
+
+    /// The vendor directory failed verification.
+    #[error("vendor directory failed verification: {0}")]
+    InvalidVendorDirectory(String),
+
+    /// The operation requires access to the registry but the client is offline.
+    #[error("cannot {0} while in offline mode")]
+    OfflineUnavailable(String),
+
     /// An error occurred while accessing the keyring.
     #[error(transparent)]
     Keyring(#[from] crate::keyring::KeyringError),
 
+    /// An error occurred while signing with an external signer.
+    #[error(transparent)]
+    ExternalSigner(#[from] signer::ExternalSignerError),
+
     /// An error occurred during an API operation.
     #[error(transparent)]
     Api(#[from] api::ClientError),
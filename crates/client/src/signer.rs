@@ -0,0 +1,336 @@
+//! Signers used to sign records published to a registry.
+//!
+//! In addition to signing in-process with a [`PrivateKey`] from the keyring,
+//! records may be signed by an external program so that the private key never
+//! leaves a separate signing service.
+//!
+//! # External signer protocol
+//!
+//! The external program is run once per operation. A single JSON request is
+//! written to its standard input, and it is expected to write a single JSON
+//! response to its standard output and exit successfully.
+//!
+//! To get the public key of the signer:
+//!
+//! ```json
+//! {"operation": "public-key"}
+//! ```
+//!
+//! ```json
+//! {"publicKey": "ecdsa-p256:..."}
+//! ```
+//!
+//! To sign a message, with the message encoded as base64:
+//!
+//! ```json
+//! {"operation": "sign", "keyId": "sha256:...", "message": "..."}
+//! ```
+//!
+//! ```json
+//! {"signature": "ecdsa-p256:..."}
+//! ```
+//!
+//! A program may refuse a request by responding with `{"error": "<message>"}`
+//! or by exiting with a non-zero status.
+//!
+//! [`PrivateKey`]: warg_crypto::signing::PrivateKey
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use thiserror::Error;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use warg_crypto::signing::{KeyID, PublicKey, Signature, SignatureError};
+
+pub use warg_crypto::signing::Signer;
+
+/// Represents an error communicating with an external signer.
+#[derive(Debug, Error)]
+pub enum ExternalSignerError {
+    /// The signer program failed to run.
+    #[error("failed to run signer program `{program}`: {source}")]
+    Io {
+        /// The signer program.
+        program: PathBuf,
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+    /// The signer program exited unsuccessfully.
+    #[error("signer program `{program}` failed ({status}): {stderr}")]
+    Failed {
+        /// The signer program.
+        program: PathBuf,
+        /// The exit status of the program.
+        status: std::process::ExitStatus,
+        /// The standard error output of the program.
+        stderr: String,
+    },
+    /// The signer program returned a response that could not be parsed.
+    #[error("signer program `{program}` returned an invalid response: {source}")]
+    InvalidResponse {
+        /// The signer program.
+        program: PathBuf,
+        /// The underlying parse error.
+        source: serde_json::Error,
+    },
+    /// The signer program refused the request.
+    #[error("signer program `{program}` refused the request: {message}")]
+    Refused {
+        /// The signer program.
+        program: PathBuf,
+        /// The message returned by the program.
+        message: String,
+    },
+    /// The signer program returned a signature that does not verify.
+    #[error("signer program `{program}` returned a signature that does not match key `{key_id}`")]
+    InvalidSignature {
+        /// The signer program.
+        program: PathBuf,
+        /// The identifier of the signer's key.
+        key_id: KeyID,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "operation", rename_all = "kebab-case")]
+enum Request<'a> {
+    PublicKey,
+    #[serde(rename_all = "camelCase")]
+    Sign {
+        key_id: &'a KeyID,
+        message: String,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response<T> {
+    Error { error: String },
+    Ok(T),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PublicKeyResponse {
+    public_key: PublicKey,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: Signature,
+}
+
+/// A signer that delegates signing to an external program.
+///
+/// See the [module documentation](self) for the protocol spoken with the program.
+///
+/// Creating the signer and signing run the program to completion, blocking the
+/// calling thread; async callers should use [`block_in_place`].
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    program: PathBuf,
+    public_key: PublicKey,
+}
+
+impl ExternalSigner {
+    /// Creates a new external signer for the given program.
+    ///
+    /// The program is run to get the public key of the signer.
+    pub fn new(program: impl Into<PathBuf>) -> Result<Self, ExternalSignerError> {
+        let program = program.into();
+        let response: PublicKeyResponse = request(&program, &Request::PublicKey)?;
+        Ok(Self {
+            program,
+            public_key: response.public_key,
+        })
+    }
+
+    /// Gets the signer program.
+    pub fn program(&self) -> &Path {
+        &self.program
+    }
+
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, ExternalSignerError> {
+        let key_id = self.public_key.fingerprint();
+        let response: SignResponse = request(
+            &self.program,
+            &Request::Sign {
+                key_id: &key_id,
+                message: STANDARD.encode(msg),
+            },
+        )?;
+
+        // Don't trust the program to have signed with the expected key
+        self.public_key
+            .verify(msg, &response.signature)
+            .map_err(|_| ExternalSignerError::InvalidSignature {
+                program: self.program.clone(),
+                key_id,
+            })?;
+
+        Ok(response.signature)
+    }
+}
+
+impl Signer for ExternalSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        self.try_sign(msg).map_err(SignatureError::from_source)
+    }
+}
+
+/// Runs a signing operation that may block the current thread.
+///
+/// Within a multi-threaded runtime, the operation is run with
+/// [`tokio::task::block_in_place`] so that other tasks are not held up while
+/// an external signer runs; otherwise, it is run directly.
+pub fn block_in_place<T>(f: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Runs the signer program with the given request and parses its response.
+fn request<T: DeserializeOwned>(
+    program: &Path,
+    request: &Request,
+) -> Result<T, ExternalSignerError> {
+    let io_error = |source| ExternalSignerError::Io {
+        program: program.to_path_buf(),
+        source,
+    };
+
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(io_error)?;
+
+    {
+        let mut stdin = child.stdin.take().expect("stdin should be piped");
+        let mut line = serde_json::to_vec(request).expect("request should serialize");
+        line.push(b'\n');
+        stdin.write_all(&line).map_err(io_error)?;
+    }
+
+    let output = child.wait_with_output().map_err(io_error)?;
+    if !output.status.success() {
+        return Err(ExternalSignerError::Failed {
+            program: program.to_path_buf(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    match serde_json::from_slice(&output.stdout).map_err(|source| {
+        ExternalSignerError::InvalidResponse {
+            program: program.to_path_buf(),
+            source,
+        }
+    })? {
+        Response::Ok(response) => Ok(response),
+        Response::Error { error } => Err(ExternalSignerError::Refused {
+            program: program.to_path_buf(),
+            message: error,
+        }),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use warg_crypto::signing::generate_p256_pair;
+
+    /// Writes a signer program that responds with the given public key and signature.
+    fn signer_program(dir: &Path, public_key: &str, sign_response: &str) -> PathBuf {
+        let path = dir.join("signer");
+        std::fs::write(
+            &path,
+            format!(
+                r#"#!/bin/sh
+read request
+case "$request" in
+  *public-key*) echo '{{"publicKey":"{public_key}"}}' ;;
+  *) echo '{sign_response}' ;;
+esac
+"#
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn external_signer_signs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (public_key, private_key) = generate_p256_pair();
+        let signature = private_key.sign(b"hello").unwrap();
+        let program = signer_program(
+            dir.path(),
+            &public_key.to_string(),
+            &format!(r#"{{"signature":"{signature}"}}"#),
+        );
+
+        let signer = ExternalSigner::new(&program).unwrap();
+        assert_eq!(Signer::public_key(&signer), public_key);
+        assert_eq!(Signer::sign(&signer, b"hello").unwrap(), signature);
+
+        // The signature returned is verified against the signer's key
+        let err = signer.try_sign(b"goodbye").unwrap_err();
+        assert!(matches!(err, ExternalSignerError::InvalidSignature { .. }));
+    }
+
+    #[test]
+    fn external_signer_runs_in_any_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let (public_key, private_key) = generate_p256_pair();
+        let signature = private_key.sign(b"hello").unwrap();
+        let program = signer_program(
+            dir.path(),
+            &public_key.to_string(),
+            &format!(r#"{{"signature":"{signature}"}}"#),
+        );
+
+        for mut builder in [
+            tokio::runtime::Builder::new_current_thread(),
+            tokio::runtime::Builder::new_multi_thread(),
+        ] {
+            let runtime = builder.build().unwrap();
+            let signed = runtime.block_on(async {
+                let signer = block_in_place(|| ExternalSigner::new(&program)).unwrap();
+                block_in_place(|| Signer::sign(&signer, b"hello")).unwrap()
+            });
+            assert_eq!(signed, signature);
+        }
+    }
+
+    #[test]
+    fn external_signer_refuses() {
+        let dir = tempfile::tempdir().unwrap();
+        let (public_key, _) = generate_p256_pair();
+        let program = signer_program(
+            dir.path(),
+            &public_key.to_string(),
+            r#"{"error":"key is locked"}"#,
+        );
+
+        let signer = ExternalSigner::new(&program).unwrap();
+        match signer.try_sign(b"hello").unwrap_err() {
+            ExternalSignerError::Refused { message, .. } => assert_eq!(message, "key is locked"),
+            e => panic!("expected the request to be refused, but got error: {e}"),
+        }
+    }
+}
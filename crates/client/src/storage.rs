@@ -1,6 +1,6 @@
 //! A module for client storage implementations.
 
-use anyhow::{Error, Result};
+use anyhow::{bail, Error, Result};
 use async_trait::async_trait;
 use bytes::Bytes;
 use futures_util::Stream;
@@ -14,7 +14,7 @@ use warg_crypto::{
 };
 use warg_protocol::{
     operator,
-    package::{self, PackageRecord, Permission, PACKAGE_RECORD_VERSION},
+    package::{self, IdentityPattern, PackageRecord, Permission, PACKAGE_RECORD_VERSION},
     registry::{Checkpoint, PackageName, RecordId, RegistryIndex, TimestampedCheckpoint},
     ProtoEnvelope, SerdeEnvelope, Version,
 };
@@ -87,6 +87,28 @@ pub trait RegistryStorage: Send + Sync {
         ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
     ) -> Result<()>;
 
+    /// Appends an accepted checkpoint to the audit trail of the registry.
+    ///
+    /// Storage that does not keep an audit trail ignores the checkpoint.
+    async fn append_audit_checkpoint(
+        &self,
+        _namespace_registry: Option<&RegistryDomain>,
+        _ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Loads the audit trail of every checkpoint accepted from the registry,
+    /// in the order they were accepted.
+    ///
+    /// Storage that does not keep an audit trail returns an empty trail.
+    async fn load_audit_trail(
+        &self,
+        _namespace_registry: Option<&RegistryDomain>,
+    ) -> Result<Vec<SerdeEnvelope<TimestampedCheckpoint>>> {
+        Ok(Vec::new())
+    }
+
     /// Loads the operator information from the storage.
     ///
     /// Returns `Ok(None)` if the information is not present.
@@ -102,6 +124,25 @@ pub trait RegistryStorage: Send + Sync {
         operator: OperatorInfo,
     ) -> Result<()>;
 
+    /// Loads the operator pin recorded for the given registry.
+    ///
+    /// Returns `Ok(None)` if no pin has been recorded.
+    async fn load_operator_pin(&self, _registry: &RegistryDomain) -> Result<Option<OperatorPin>> {
+        Ok(None)
+    }
+
+    /// Records the operator pin for the given registry.
+    ///
+    /// Recorded pins must survive a reset of the registry storage; storage
+    /// that does not record pins ignores the pin.
+    async fn store_operator_pin(
+        &self,
+        _registry: &RegistryDomain,
+        _pin: &OperatorPin,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     /// Loads the package information for all packages.
     async fn load_all_packages(&self) -> Result<IndexMap<RegistryDomain, Vec<PackageInfo>>>;
 
@@ -167,6 +208,74 @@ pub trait ContentStorage: Send + Sync {
         stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
         expected_digest: Option<&AnyHash>,
     ) -> Result<AnyHash>;
+
+    /// Lists the content entries in storage.
+    ///
+    /// Partially stored downloads are included as partial entries. Storage
+    /// that cannot list its content returns no entries.
+    async fn list_content(&self) -> Result<Vec<ContentEntry>> {
+        Ok(Vec::new())
+    }
+
+    /// Removes the content associated with the given digest.
+    ///
+    /// Returns `true` if the content was removed or `false` if it was not
+    /// present.
+    async fn remove_content(&self, _digest: &AnyHash) -> Result<bool> {
+        bail!("content storage does not support removing content")
+    }
+
+    /// Removes the partially stored download for the given digest.
+    ///
+    /// Returns `true` if the partial download was removed or `false` if it
+    /// was not present.
+    async fn remove_partial_content(&self, _digest: &AnyHash) -> Result<bool> {
+        bail!("content storage does not support removing partial downloads")
+    }
+
+    /// Gets the number of bytes of a partially stored download for the given
+    /// digest.
+    ///
+    /// Returns `0` if there is no partial download or if the storage does not
+    /// support resuming downloads.
+    fn partial_content_len(&self, _digest: &AnyHash) -> u64 {
+        0
+    }
+
+    /// Stores the given stream as the content for `expected_digest`, where the
+    /// stream begins at byte `offset` of the content.
+    ///
+    /// Storage that supports resuming downloads keeps the bytes written when the
+    /// stream fails so that the download can later be resumed at
+    /// `partial_content_len`.
+    ///
+    /// The complete content is verified to match the expected digest; on a
+    /// mismatch the partial download is discarded and an error is returned.
+    async fn store_content_at(
+        &self,
+        stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+        expected_digest: &AnyHash,
+        offset: u64,
+    ) -> Result<AnyHash> {
+        if offset != 0 {
+            bail!("content storage does not support resuming downloads");
+        }
+
+        self.store_content(stream, Some(expected_digest)).await
+    }
+}
+
+/// Represents content in content storage.
+#[derive(Debug, Clone)]
+pub struct ContentEntry {
+    /// The digest of the content.
+    pub digest: AnyHash,
+    /// The size of the content in bytes.
+    pub size: u64,
+    /// The time the content was last modified in storage.
+    pub modified: SystemTime,
+    /// Whether the content is a partially stored download.
+    pub partial: bool,
 }
 
 /// Trait for namespace map storage implementations.
@@ -185,6 +294,12 @@ pub trait NamespaceMapStorage: Send + Sync {
         namespace: String,
         registry_domain: RegistryDomain,
     ) -> Result<()>;
+    /// Remove namespace mapping
+    ///
+    /// Returns `Ok(false)` if the namespace was not mapped.
+    async fn remove_namespace(&self, _namespace: &str) -> Result<bool> {
+        bail!("removing namespace mappings is not supported by this storage")
+    }
 }
 
 /// Represents information about a registry operator.
@@ -208,6 +323,58 @@ pub struct OperatorInfo {
     pub head_fetch_token: Option<String>,
 }
 
+/// Represents the pinned root of a registry's operator log.
+///
+/// A registry whose operator log does not start with the pinned root is
+/// refused by the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorPin {
+    /// The key ID of the operator key of the log's init record.
+    pub key_id: KeyID,
+    /// The record ID of the log's init record.
+    ///
+    /// If `None`, only the operator key is pinned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_record: Option<RecordId>,
+}
+
+impl OperatorPin {
+    /// Determines if the pin matches the root of an operator log.
+    pub fn matches(&self, root: &OperatorPin) -> bool {
+        self.key_id == root.key_id
+            && match &self.root_record {
+                Some(record) => root.root_record.as_ref() == Some(record),
+                None => true,
+            }
+    }
+}
+
+impl fmt::Display for OperatorPin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.root_record {
+            Some(record) => write!(f, "{key_id},{record}", key_id = self.key_id),
+            None => write!(f, "{key_id}", key_id = self.key_id),
+        }
+    }
+}
+
+impl FromStr for OperatorPin {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key_id, root_record) = match s.split_once(',') {
+            Some((key_id, record)) => (key_id, Some(record.parse::<AnyHash>()?.into())),
+            None => (s, None),
+        };
+        let key_id: AnyHash = key_id.parse()?;
+        Ok(Self {
+            key_id: key_id.to_string().into(),
+            root_record,
+        })
+    }
+}
+
 /// Represents information about a registry package.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -277,6 +444,29 @@ pub enum PublishEntry {
         /// The permission(s) being revoked.
         permissions: Vec<Permission>,
     },
+    /// An identity pattern is being granted permission(s).
+    GrantIdentity {
+        /// The identity pattern being granted to.
+        identity: IdentityPattern,
+        /// The permission(s) being granted.
+        permissions: Vec<Permission>,
+    },
+    /// An identity pattern's permission(s) are being revoked.
+    RevokeIdentity {
+        /// The identity pattern being revoked from.
+        identity: IdentityPattern,
+        /// The permission(s) being revoked.
+        permissions: Vec<Permission>,
+    },
+    /// The signing key is being bound to a publisher identity.
+    ///
+    /// Must be the first entry of the record.
+    Identity {
+        /// The issuer of the identity.
+        issuer: String,
+        /// The subject of the identity.
+        subject: String,
+    },
 }
 
 /// Represents information about a package publish.
@@ -302,7 +492,7 @@ impl PublishInfo {
 
     pub(crate) fn finalize(
         self,
-        signing_key: &signing::PrivateKey,
+        signing_key: &(impl signing::Signer + ?Sized),
     ) -> Result<ProtoEnvelope<PackageRecord>> {
         let mut entries = Vec::with_capacity(self.entries.len());
         for entry in self.entries {
@@ -329,6 +519,27 @@ impl PublishInfo {
                     key_id,
                     permissions,
                 }),
+                PublishEntry::GrantIdentity {
+                    identity,
+                    permissions,
+                } => entries.push(package::PackageEntry::GrantIdentity {
+                    identity,
+                    permissions,
+                }),
+                PublishEntry::RevokeIdentity {
+                    identity,
+                    permissions,
+                } => entries.push(package::PackageEntry::RevokeIdentity {
+                    identity,
+                    permissions,
+                }),
+                PublishEntry::Identity { issuer, subject } => {
+                    entries.push(package::PackageEntry::Identity {
+                        issuer,
+                        subject,
+                        key: signing_key.public_key(),
+                    })
+                }
             }
         }
 
@@ -1,8 +1,8 @@
 //! A module for file system client storage.
 
 use super::{
-    ContentStorage, NamespaceMapStorage, OperatorInfo, PackageInfo, PublishInfo, RegistryDomain,
-    RegistryStorage,
+    ContentEntry, ContentStorage, NamespaceMapStorage, OperatorInfo, OperatorPin, PackageInfo,
+    PublishInfo, RegistryDomain, RegistryStorage,
 };
 use crate::lock::FileLock;
 use anyhow::{anyhow, bail, Context, Result};
@@ -14,12 +14,13 @@ use serde::{Deserialize, Serialize};
 use std::{
     ffi::OsStr,
     fs,
+    io::SeekFrom,
     path::{Path, PathBuf},
     pin::Pin,
     str::FromStr,
 };
 use tempfile::NamedTempFile;
-use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter};
 use tokio_util::io::ReaderStream;
 use walkdir::WalkDir;
 use warg_crypto::hash::{AnyHash, Digest, Hash, Sha256};
@@ -32,12 +33,14 @@ const TEMP_DIRECTORY: &str = "temp";
 const PENDING_PUBLISH_FILE: &str = "pending-publish.json";
 const LOCK_FILE_NAME: &str = ".lock";
 const PACKAGE_LOGS_DIR: &str = "package-logs";
+const OPERATOR_PINS_DIR: &str = "operator-pins";
 
 /// Represents a package storage using the local file system.
 pub struct FileSystemRegistryStorage {
     _lock: FileLock,
     base_dir: PathBuf,
     registries_dir: PathBuf,
+    read_only: bool,
 }
 
 impl FileSystemRegistryStorage {
@@ -57,6 +60,7 @@ impl FileSystemRegistryStorage {
                 _lock: lock,
                 base_dir,
                 registries_dir: registries_dir.to_path_buf(),
+                read_only: false,
             })),
             None => Ok(None),
         }
@@ -79,9 +83,62 @@ impl FileSystemRegistryStorage {
             _lock: lock,
             base_dir,
             registries_dir: registries_dir.to_path_buf(),
+            read_only: false,
         })
     }
 
+    /// Attempts to lock an existing package storage for reading only.
+    ///
+    /// This is used for storage that must not be modified, such as a vendor
+    /// directory.
+    ///
+    /// If the lock cannot be acquired, `Ok(None)` is returned.
+    pub fn try_lock_read_only(base_dir: impl Into<PathBuf>) -> Result<Option<Self>> {
+        let base_dir = base_dir.into();
+        let registries_dir = base_dir
+            .parent()
+            .context("base_dir cannot be empty")?
+            .to_path_buf();
+        match FileLock::try_open_ro(base_dir.join(LOCK_FILE_NAME))? {
+            Some(lock) => Ok(Some(Self {
+                _lock: lock,
+                base_dir,
+                registries_dir,
+                read_only: true,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Locks an existing package storage for reading only.
+    ///
+    /// If the lock cannot be immediately acquired, this function
+    /// will block.
+    pub fn lock_read_only(base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let base_dir = base_dir.into();
+        let lock = FileLock::open_ro(base_dir.join(LOCK_FILE_NAME))?;
+        let registries_dir = base_dir
+            .parent()
+            .context("base_dir cannot be empty")?
+            .to_path_buf();
+        Ok(Self {
+            _lock: lock,
+            base_dir,
+            registries_dir,
+            read_only: true,
+        })
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            bail!(
+                "registry storage `{path}` is read-only",
+                path = self.base_dir.display()
+            );
+        }
+        Ok(())
+    }
+
     fn operator_path(&self, namespace_registry: Option<&RegistryDomain>) -> PathBuf {
         if let Some(nm) = namespace_registry {
             return self
@@ -92,6 +149,18 @@ impl FileSystemRegistryStorage {
         self.base_dir.join("operator.log")
     }
 
+    /// Gets the path of the operator pin for a registry.
+    ///
+    /// Pins are kept next to the registries directory rather than in it so
+    /// that resetting registry storage does not forget them.
+    fn operator_pin_path(&self, registry: &RegistryDomain) -> PathBuf {
+        self.registries_dir
+            .parent()
+            .unwrap_or(&self.registries_dir)
+            .join(OPERATOR_PINS_DIR)
+            .join(registry.to_string())
+    }
+
     fn package_path(
         &self,
         namespace_registry: Option<&RegistryDomain>,
@@ -115,6 +184,13 @@ impl FileSystemRegistryStorage {
         )
     }
 
+    fn audit_path(&self, namespace_registry: Option<&RegistryDomain>) -> PathBuf {
+        match namespace_registry {
+            Some(nm) => self.registries_dir.join(nm.to_string()).join("audit"),
+            None => self.base_dir.join("audit"),
+        }
+    }
+
     fn pending_publish_path(&self) -> PathBuf {
         self.base_dir.join(PENDING_PUBLISH_FILE)
     }
@@ -123,6 +199,7 @@ impl FileSystemRegistryStorage {
 #[async_trait]
 impl RegistryStorage for FileSystemRegistryStorage {
     async fn reset(&self, all_registries: bool) -> Result<()> {
+        self.check_writable()?;
         if all_registries {
             remove(self.base_dir.parent().unwrap()).await
         } else {
@@ -145,6 +222,7 @@ impl RegistryStorage for FileSystemRegistryStorage {
         namespace_registry: Option<&RegistryDomain>,
         ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
     ) -> Result<()> {
+        self.check_writable()?;
         if let Some(nm) = namespace_registry {
             return store(
                 &self.registries_dir.join(nm.to_string()).join("checkpoint"),
@@ -155,6 +233,22 @@ impl RegistryStorage for FileSystemRegistryStorage {
         store(&self.base_dir.join("checkpoint"), ts_checkpoint).await
     }
 
+    async fn append_audit_checkpoint(
+        &self,
+        namespace_registry: Option<&RegistryDomain>,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<()> {
+        self.check_writable()?;
+        append_line(&self.audit_path(namespace_registry), ts_checkpoint).await
+    }
+
+    async fn load_audit_trail(
+        &self,
+        namespace_registry: Option<&RegistryDomain>,
+    ) -> Result<Vec<SerdeEnvelope<TimestampedCheckpoint>>> {
+        load_lines(&self.audit_path(namespace_registry)).await
+    }
+
     async fn load_all_packages(&self) -> Result<IndexMap<RegistryDomain, Vec<PackageInfo>>> {
         let mut all_packages = IndexMap::new();
         let regs = fs::read_dir(self.registries_dir.clone())?;
@@ -204,9 +298,19 @@ impl RegistryStorage for FileSystemRegistryStorage {
         namespace_registry: Option<&RegistryDomain>,
         info: OperatorInfo,
     ) -> Result<()> {
+        self.check_writable()?;
         store(&self.operator_path(namespace_registry), info).await
     }
 
+    async fn load_operator_pin(&self, registry: &RegistryDomain) -> Result<Option<OperatorPin>> {
+        Ok(load(&self.operator_pin_path(registry)).await?)
+    }
+
+    async fn store_operator_pin(&self, registry: &RegistryDomain, pin: &OperatorPin) -> Result<()> {
+        self.check_writable()?;
+        store(&self.operator_pin_path(registry), pin).await
+    }
+
     async fn load_package(
         &self,
         namespace_registry: Option<&RegistryDomain>,
@@ -220,6 +324,7 @@ impl RegistryStorage for FileSystemRegistryStorage {
         namespace_registry: Option<&RegistryDomain>,
         info: &PackageInfo,
     ) -> Result<()> {
+        self.check_writable()?;
         store(&self.package_path(namespace_registry, &info.name), info).await
     }
 
@@ -230,6 +335,7 @@ impl RegistryStorage for FileSystemRegistryStorage {
     }
 
     async fn store_publish(&self, info: Option<&PublishInfo>) -> Result<()> {
+        self.check_writable()?;
         let path = self.pending_publish_path();
         match info {
             Some(info) => store(&path, info).await,
@@ -243,6 +349,7 @@ pub struct FileSystemContentStorage {
     _lock: FileLock,
     base_dir: PathBuf,
     temp_dir: PathBuf,
+    read_only: bool,
 }
 
 impl FileSystemContentStorage {
@@ -259,6 +366,7 @@ impl FileSystemContentStorage {
                 _lock: lock,
                 base_dir,
                 temp_dir,
+                read_only: false,
             })),
             None => Ok(None),
         }
@@ -278,9 +386,56 @@ impl FileSystemContentStorage {
             _lock: lock,
             base_dir,
             temp_dir,
+            read_only: false,
         })
     }
 
+    /// Attempts to lock an existing content storage for reading only.
+    ///
+    /// This is used for storage that must not be modified, such as a vendor
+    /// directory.
+    ///
+    /// If the lock cannot be acquired, `Ok(None)` is returned.
+    pub fn try_lock_read_only(base_dir: impl Into<PathBuf>) -> Result<Option<Self>> {
+        let base_dir = base_dir.into();
+        let temp_dir = base_dir.join(TEMP_DIRECTORY);
+        match FileLock::try_open_ro(base_dir.join(LOCK_FILE_NAME))? {
+            Some(lock) => Ok(Some(Self {
+                _lock: lock,
+                base_dir,
+                temp_dir,
+                read_only: true,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Locks an existing content storage for reading only.
+    ///
+    /// If the lock cannot be immediately acquired, this function
+    /// will block.
+    pub fn lock_read_only(base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let base_dir = base_dir.into();
+        let temp_dir = base_dir.join(TEMP_DIRECTORY);
+        let lock = FileLock::open_ro(base_dir.join(LOCK_FILE_NAME))?;
+        Ok(Self {
+            _lock: lock,
+            base_dir,
+            temp_dir,
+            read_only: true,
+        })
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.read_only {
+            bail!(
+                "content storage `{path}` is read-only",
+                path = self.base_dir.display()
+            );
+        }
+        Ok(())
+    }
+
     fn temp_file(&self) -> Result<NamedTempFile> {
         fs::create_dir_all(&self.temp_dir).with_context(|| {
             format!(
@@ -300,11 +455,19 @@ impl FileSystemContentStorage {
     fn content_path(&self, digest: &AnyHash) -> PathBuf {
         self.base_dir.join(digest.to_string().replace(':', "/"))
     }
+
+    fn partial_content_path(&self, digest: &AnyHash) -> PathBuf {
+        self.temp_dir.join(format!(
+            "{digest}.partial",
+            digest = digest.to_string().replace(':', "-")
+        ))
+    }
 }
 
 #[async_trait]
 impl ContentStorage for FileSystemContentStorage {
     async fn clear(&self) -> Result<()> {
+        self.check_writable()?;
         remove(&self.base_dir).await
     }
 
@@ -341,6 +504,7 @@ impl ContentStorage for FileSystemContentStorage {
         mut stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
         expected_digest: Option<&AnyHash>,
     ) -> Result<AnyHash> {
+        self.check_writable()?;
         let (file, path) = self.temp_file()?.into_parts();
         let mut writer = BufWriter::new(tokio::fs::File::from_std(file));
         let mut hasher = Sha256::new();
@@ -391,6 +555,210 @@ impl ContentStorage for FileSystemContentStorage {
 
         Ok(hash)
     }
+
+    async fn list_content(&self) -> Result<Vec<ContentEntry>> {
+        let mut entries = Vec::new();
+        if !self.base_dir.is_dir() {
+            return Ok(entries);
+        }
+
+        // Content is stored at `<base>/<algorithm>/<hex>`
+        for entry in WalkDir::new(&self.base_dir).min_depth(2).max_depth(2) {
+            let entry = entry.with_context(|| {
+                format!(
+                    "failed to read directory `{path}`",
+                    path = self.base_dir.display()
+                )
+            })?;
+            let path = entry.path();
+            if !entry.file_type().is_file() || path.starts_with(&self.temp_dir) {
+                continue;
+            }
+
+            let (Some(algorithm), Some(hex)) = (
+                path.parent()
+                    .and_then(Path::file_name)
+                    .and_then(OsStr::to_str),
+                path.file_name().and_then(OsStr::to_str),
+            ) else {
+                continue;
+            };
+
+            let Ok(digest) = AnyHash::from_str(&format!("{algorithm}:{hex}")) else {
+                tracing::debug!(
+                    "skipping unrecognized content storage file `{path}`",
+                    path = path.display()
+                );
+                continue;
+            };
+
+            entries.push(content_entry(path, digest, false)?);
+        }
+
+        // Partial downloads are stored at `<temp>/<algorithm>-<hex>.partial`
+        if self.temp_dir.is_dir() {
+            for entry in WalkDir::new(&self.temp_dir).min_depth(1).max_depth(1) {
+                let entry = entry.with_context(|| {
+                    format!(
+                        "failed to read directory `{path}`",
+                        path = self.temp_dir.display()
+                    )
+                })?;
+                let path = entry.path();
+                let Some(name) = path
+                    .file_name()
+                    .and_then(OsStr::to_str)
+                    .and_then(|name| name.strip_suffix(".partial"))
+                else {
+                    continue;
+                };
+
+                let Ok(digest) = AnyHash::from_str(&name.replacen('-', ":", 1)) else {
+                    tracing::debug!(
+                        "skipping unrecognized partial download `{path}`",
+                        path = path.display()
+                    );
+                    continue;
+                };
+
+                if entry.file_type().is_file() {
+                    entries.push(content_entry(path, digest, true)?);
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn remove_content(&self, digest: &AnyHash) -> Result<bool> {
+        self.check_writable()?;
+        let path = self.content_path(digest);
+        if !path.is_file() {
+            return Ok(false);
+        }
+
+        fs::remove_file(&path)
+            .with_context(|| format!("failed to remove `{path}`", path = path.display()))?;
+        Ok(true)
+    }
+
+    async fn remove_partial_content(&self, digest: &AnyHash) -> Result<bool> {
+        self.check_writable()?;
+        let path = self.partial_content_path(digest);
+        if !path.is_file() {
+            return Ok(false);
+        }
+
+        fs::remove_file(&path)
+            .with_context(|| format!("failed to remove `{path}`", path = path.display()))?;
+        Ok(true)
+    }
+
+    fn partial_content_len(&self, digest: &AnyHash) -> u64 {
+        fs::metadata(self.partial_content_path(digest))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    async fn store_content_at(
+        &self,
+        mut stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
+        expected_digest: &AnyHash,
+        offset: u64,
+    ) -> Result<AnyHash> {
+        self.check_writable()?;
+        fs::create_dir_all(&self.temp_dir).with_context(|| {
+            format!(
+                "failed to create directory `{path}`",
+                path = self.temp_dir.display()
+            )
+        })?;
+
+        let path = self.partial_content_path(expected_digest);
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to open `{path}`", path = path.display()))?;
+
+        let len = file
+            .metadata()
+            .await
+            .with_context(|| format!("failed to read metadata of `{path}`", path = path.display()))?
+            .len();
+        if offset > len {
+            bail!(
+                "cannot resume download of `{expected_digest}` at byte {offset} as only {len} bytes were stored"
+            );
+        }
+
+        file.set_len(offset)
+            .await
+            .with_context(|| format!("failed to truncate `{path}`", path = path.display()))?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .with_context(|| format!("failed to seek in `{path}`", path = path.display()))?;
+
+        // Keep what was written if the stream fails so the download can be resumed
+        let mut writer = BufWriter::new(file);
+        let res = async {
+            while let Some(bytes) = stream.next().await.transpose()? {
+                writer.write_all(&bytes).await.with_context(|| {
+                    format!("failed to write to `{path}`", path = path.display())
+                })?;
+            }
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        writer
+            .shutdown()
+            .await
+            .with_context(|| format!("failed to write `{path}`", path = path.display()))?;
+        drop(writer);
+        res?;
+
+        let mut hasher = expected_digest.algorithm().hasher();
+        let mut reader = ReaderStream::new(BufReader::new(
+            tokio::fs::File::open(&path)
+                .await
+                .with_context(|| format!("failed to open `{path}`", path = path.display()))?,
+        ));
+        while let Some(bytes) = reader.next().await.transpose()? {
+            hasher.update(&bytes);
+        }
+
+        let hash = hasher.finalize();
+        if hash != *expected_digest {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove `{path}`", path = path.display()))?;
+            bail!(
+                "stored content has digest `{hash}` but a digest of `{expected_digest}` was expected",
+            );
+        }
+
+        let content_path = self.content_path(&hash);
+        if let Some(parent) = content_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "failed to create directory `{path}`",
+                    path = parent.display()
+                )
+            })?;
+        }
+
+        fs::rename(&path, &content_path).with_context(|| {
+            format!(
+                "failed to move `{path}` to `{content_path}`",
+                path = path.display(),
+                content_path = content_path.display()
+            )
+        })?;
+
+        Ok(hash)
+    }
 }
 
 /// Represents a namespace_domain map storage using the local file system.
@@ -429,6 +797,16 @@ impl NamespaceMapStorage for FileSystemNamespaceMapStorage {
         fs::write(&self.path, json)?;
         Ok(())
     }
+
+    async fn remove_namespace(&self, namespace: &str) -> Result<bool> {
+        let mut mapping = self.load_namespace_map().await?.unwrap_or_default();
+        if mapping.shift_remove(namespace).is_none() {
+            return Ok(false);
+        }
+        let json = serde_json::to_string(&mapping)?;
+        fs::write(&self.path, json)?;
+        Ok(true)
+    }
 }
 
 async fn remove(path: &Path) -> Result<()> {
@@ -482,6 +860,58 @@ async fn store(path: &Path, value: impl Serialize) -> Result<()> {
         .with_context(|| format!("failed to write `{path}`", path = path.display()))
 }
 
+async fn load_lines<T: for<'a> Deserialize<'a>>(path: &Path) -> Result<Vec<T>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read `{path}`", path = path.display()))?;
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).with_context(|| {
+                format!(
+                    "failed to deserialize contents of `{path}`",
+                    path = path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+async fn append_line(path: &Path, value: impl Serialize) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "failed to create parent directory for `{path}`",
+                path = path.display()
+            )
+        })?;
+    }
+
+    let mut contents = serde_json::to_vec(&value).with_context(|| {
+        format!(
+            "failed to serialize contents of `{path}`",
+            path = path.display()
+        )
+    })?;
+    contents.push(b'\n');
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("failed to open `{path}`", path = path.display()))?;
+    file.write_all(&contents)
+        .await
+        .with_context(|| format!("failed to write `{path}`", path = path.display()))
+}
+
 async fn delete(path: &Path) -> Result<()> {
     if path.is_file() {
         tokio::fs::remove_file(path)
@@ -491,3 +921,19 @@ async fn delete(path: &Path) -> Result<()> {
 
     Ok(())
 }
+
+fn content_entry(path: &Path, digest: AnyHash, partial: bool) -> Result<ContentEntry> {
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("failed to read metadata of `{path}`", path = path.display()))?;
+    Ok(ContentEntry {
+        digest,
+        size: metadata.len(),
+        modified: metadata.modified().with_context(|| {
+            format!(
+                "failed to read modification time of `{path}`",
+                path = path.display()
+            )
+        })?,
+        partial,
+    })
+}
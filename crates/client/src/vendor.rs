@@ -0,0 +1,744 @@
+//! A module for vendoring packages into a local directory.
+//!
+//! A vendor directory contains the content of the resolved packages, the
+//! signed records of their package logs and the operator log, and the proofs
+//! that the log heads are included in a signed registry checkpoint.
+//!
+//! A client created from a vendor directory re-verifies all of the above and
+//! then resolves packages without contacting the registry.
+
+use crate::{
+    api,
+    depsolve::LockListBuilder,
+    storage::{
+        ContentStorage, FileSystemContentStorage, FileSystemNamespaceMapStorage,
+        FileSystemRegistryStorage, NamespaceMapStorage, PackageInfo, RegistryDomain,
+        RegistryStorage,
+    },
+    verify_stored_content, Client, ClientError, ClientOptions, ClientResult, Config,
+    FileSystemClient, RegistryUrl, StorageLockResult, StoragePaths,
+};
+use anyhow::{anyhow, Context};
+use indexmap::{IndexMap, IndexSet};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    fs::{self, File},
+    path::Path,
+};
+use warg_api::v1::{
+    fetch::FetchLogsRequest,
+    proof::{ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse},
+};
+use warg_crypto::{
+    hash::{AnyHash, Sha256},
+    Encode, Signable,
+};
+use warg_protocol::{
+    operator, package,
+    registry::{Checkpoint, LogId, LogLeaf, PackageName, RegistryLen, TimestampedCheckpoint},
+    PublishedProtoEnvelope, PublishedProtoEnvelopeBody, SerdeEnvelope,
+};
+
+/// The name of the manifest file of a vendor directory.
+pub const VENDOR_MANIFEST_FILE: &str = "vendor.json";
+
+const REGISTRIES_DIR: &str = "registries";
+const CONTENT_DIR: &str = "content";
+const NAMESPACE_MAP_FILE: &str = "namespaces";
+
+/// Represents the manifest of a vendor directory.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VendorManifest {
+    /// The URL of the registry the packages were vendored from.
+    pub registry_url: String,
+    /// The vendored package logs, grouped by the registry they were fetched from.
+    pub registries: Vec<VendoredRegistry>,
+}
+
+/// Represents the package logs vendored from a single registry.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VendoredRegistry {
+    /// The domain of the registry for federated namespaces.
+    ///
+    /// This is `None` for the registry at `VendorManifest::registry_url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<RegistryDomain>,
+    /// The checkpoint the package logs were vendored at.
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The records of the operator log.
+    pub operator: Vec<PublishedProtoEnvelopeBody>,
+    /// The vendored packages.
+    pub packages: Vec<VendoredPackage>,
+    /// The inclusion proofs for the operator log head followed by each package
+    /// log head, in order.
+    pub inclusion: InclusionResponse,
+    /// The consistency proof from the previously vendored checkpoint, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub consistency: Option<VendoredConsistency>,
+}
+
+/// Represents a vendored package.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VendoredPackage {
+    /// The name of the package.
+    pub name: PackageName,
+    /// The records of the package log.
+    pub records: Vec<PublishedProtoEnvelopeBody>,
+    /// The content digests of the resolved releases.
+    pub content: Vec<AnyHash>,
+}
+
+/// Represents a consistency proof between two vendored checkpoints.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VendoredConsistency {
+    /// The previously vendored checkpoint.
+    pub from: Checkpoint,
+    /// The consistency proof between the previous and the current checkpoint.
+    pub proof: ConsistencyResponse,
+}
+
+impl VendorManifest {
+    /// Reads the manifest of the given vendor directory.
+    pub fn from_dir(dir: &Path) -> ClientResult<Self> {
+        let path = dir.join(VENDOR_MANIFEST_FILE);
+        let contents = fs::read_to_string(&path).with_context(|| {
+            format!(
+                "failed to read vendor manifest `{path}`",
+                path = path.display()
+            )
+        })?;
+        Ok(serde_json::from_str(&contents).with_context(|| {
+            format!(
+                "failed to deserialize vendor manifest `{path}`",
+                path = path.display()
+            )
+        })?)
+    }
+
+    fn write_to_dir(&self, dir: &Path) -> ClientResult<()> {
+        let path = dir.join(VENDOR_MANIFEST_FILE);
+        serde_json::to_writer_pretty(
+            File::create(&path).with_context(|| {
+                format!("failed to create file `{path}`", path = path.display())
+            })?,
+            self,
+        )
+        .with_context(|| format!("failed to serialize file `{path}`", path = path.display()))?;
+        Ok(())
+    }
+
+    /// Gets the storage paths of the given vendor directory.
+    pub fn storage_paths(&self, dir: &Path) -> ClientResult<StoragePaths> {
+        Ok(storage_paths(
+            dir,
+            RegistryUrl::new(self.registry_url.as_str())?,
+        ))
+    }
+
+    /// Verifies the vendored package logs, proofs, and content against the
+    /// given storage.
+    ///
+    /// The operator and package logs are replayed from their records and must
+    /// match the stored log states, the checkpoint signature is verified with
+    /// the operator log keys, the log heads are proven to be included in the
+    /// checkpoint, and the content is checked against its digest.
+    pub async fn verify(
+        &self,
+        registry: &impl RegistryStorage,
+        content: &impl ContentStorage,
+    ) -> ClientResult<()> {
+        for vendored in &self.registries {
+            vendored.verify(registry, content).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl VendoredRegistry {
+    async fn verify(
+        &self,
+        registry: &impl RegistryStorage,
+        content: &impl ContentStorage,
+    ) -> ClientResult<()> {
+        let registry_domain = self.registry.as_ref();
+        let ts_checkpoint = &self.checkpoint;
+        let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+
+        let mut operator = operator::LogState::default();
+        let mut operator_index = None;
+        for record in &self.operator {
+            let record: PublishedProtoEnvelope<operator::OperatorRecord> =
+                record.clone().try_into()?;
+            operator = operator
+                .validate(&record.envelope)
+                .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+            operator_index = Some(record.registry_index);
+        }
+
+        let (Some(operator_index), Some(operator_head)) = (operator_index, operator.head()) else {
+            return Err(ClientError::NoOperatorRecords);
+        };
+
+        TimestampedCheckpoint::verify(
+            operator.public_key(ts_checkpoint.key_id()).ok_or(
+                ClientError::InvalidCheckpointKeyId {
+                    key_id: ts_checkpoint.key_id().clone(),
+                },
+            )?,
+            &ts_checkpoint.as_ref().encode(),
+            ts_checkpoint.signature(),
+        )
+        .or(Err(ClientError::InvalidCheckpointSignature))?;
+
+        if registry
+            .load_checkpoint(registry_domain)
+            .await?
+            .map(|c| c.as_ref().checkpoint != *checkpoint)
+            .unwrap_or(true)
+        {
+            return Err(ClientError::InvalidVendorDirectory(
+                "the stored checkpoint does not match the vendor manifest".to_string(),
+            ));
+        }
+
+        // The stored operator log must be exactly the one replayed from the records
+        if registry
+            .load_operator(registry_domain)
+            .await?
+            .map(|info| info.state != operator || info.head_registry_index != Some(operator_index))
+            .unwrap_or(true)
+        {
+            return Err(ClientError::InvalidVendorDirectory(
+                "the stored operator log does not match the vendor manifest".to_string(),
+            ));
+        }
+
+        let mut leafs = Vec::with_capacity(self.packages.len() + 1 /* for operator */);
+        leafs.push(LogLeaf {
+            log_id: LogId::operator_log::<Sha256>(),
+            record_id: operator_head.digest.clone(),
+        });
+
+        for vendored in &self.packages {
+            let mut state = package::LogState::default();
+            let mut head_index = None;
+            for record in &vendored.records {
+                let record: PublishedProtoEnvelope<package::PackageRecord> =
+                    record.clone().try_into()?;
+                state = state.validate(&record.envelope).map_err(|inner| {
+                    ClientError::PackageValidationFailed {
+                        name: vendored.name.clone(),
+                        inner,
+                    }
+                })?;
+                head_index = Some(record.registry_index);
+            }
+
+            let head = state
+                .head()
+                .as_ref()
+                .ok_or_else(|| ClientError::PackageLogEmpty {
+                    name: vendored.name.clone(),
+                })?;
+
+            if registry
+                .load_package(registry_domain, &vendored.name)
+                .await?
+                .map(|info| info.state != state || info.head_registry_index != head_index)
+                .unwrap_or(true)
+            {
+                return Err(ClientError::InvalidVendorDirectory(format!(
+                    "the stored log for package `{name}` does not match the vendor manifest",
+                    name = vendored.name
+                )));
+            }
+
+            leafs.push(LogLeaf {
+                log_id: LogId::package_log::<Sha256>(&vendored.name),
+                record_id: head.digest.clone(),
+            });
+
+            for digest in &vendored.content {
+                verify_stored_content(content, digest).await?;
+            }
+        }
+
+        api::Client::validate_inclusion_response(&self.inclusion, checkpoint, &leafs)?;
+
+        if let Some(consistency) = &self.consistency {
+            api::Client::validate_consistency_response(
+                &consistency.proof,
+                Cow::Borrowed(&consistency.from.log_root),
+                Cow::Borrowed(&checkpoint.log_root),
+            )?;
+        }
+
+        tracing::debug!(
+            registry_header = ?registry_domain,
+            log_length = checkpoint.log_length,
+            operator_index,
+            "verified vendored package logs",
+        );
+
+        Ok(())
+    }
+}
+
+fn storage_paths(dir: &Path, registry_url: RegistryUrl) -> StoragePaths {
+    let label = registry_url.safe_label();
+    StoragePaths {
+        registry_url,
+        registries_dir: dir.join(REGISTRIES_DIR).join(label),
+        content_dir: dir.join(CONTENT_DIR),
+        namespace_map_path: dir.join(NAMESPACE_MAP_FILE),
+    }
+}
+
+type FetchedRecords = (
+    Vec<PublishedProtoEnvelopeBody>,
+    IndexMap<LogId, Vec<PublishedProtoEnvelopeBody>>,
+);
+
+impl<R: RegistryStorage, C: ContentStorage, N: NamespaceMapStorage> Client<R, C, N> {
+    /// Vendors packages and their dependencies into the given directory.
+    ///
+    /// If `packages` is empty, every package in client storage is vendored.
+    ///
+    /// The package logs are updated to the latest registry checkpoint before
+    /// vendoring. If the directory was previously vendored from the same
+    /// registry, a consistency proof from the previous checkpoint is included,
+    /// and content that is no longer vendored is removed.
+    ///
+    /// Returns the manifest written to the vendor directory.
+    pub async fn vendor(
+        &self,
+        dir: &Path,
+        packages: &[PackageName],
+    ) -> ClientResult<VendorManifest> {
+        self.ensure_online("vendor packages")?;
+
+        // Resolve the packages and their dependencies
+        let mut resolved: IndexMap<PackageName, VersionReq> = IndexMap::new();
+        if packages.is_empty() {
+            for info in self
+                .registry
+                .load_all_packages()
+                .await?
+                .into_values()
+                .flatten()
+            {
+                resolved.insert(info.name, VersionReq::STAR);
+            }
+        } else {
+            for name in packages {
+                self.download(name, &VersionReq::STAR).await?;
+                let info = self.package(name).await?;
+                let mut builder = LockListBuilder::default();
+                builder.build_list(self, &info).await?;
+                resolved.insert(name.clone(), VersionReq::STAR);
+                for import in builder.lock_list {
+                    resolved
+                        .entry(PackageName::new(import.name)?)
+                        .or_insert(import.req);
+                }
+            }
+        }
+
+        // Update the package logs to the latest checkpoint
+        let infos = self.fetch_packages(resolved.keys()).await?;
+
+        let downloads = self.download_many(resolved.iter()).await?;
+
+        let mut registries: IndexMap<Option<RegistryDomain>, Vec<(PackageInfo, Vec<AnyHash>)>> =
+            IndexMap::new();
+        for (info, download) in infos.into_iter().zip(downloads) {
+            let download =
+                download.ok_or_else(|| ClientError::PackageVersionRequirementDoesNotExist {
+                    version: resolved[&info.name].clone(),
+                    name: info.name.clone(),
+                })?;
+            registries
+                .entry(self.get_warg_registry(info.name.namespace()).await?)
+                .or_default()
+                .push((info, vec![download.digest]));
+        }
+
+        let previous = VendorManifest::from_dir(dir).ok().filter(|m| {
+            RegistryUrl::new(m.registry_url.as_str()).ok().as_ref() == Some(self.url())
+        });
+
+        let mut manifest = VendorManifest {
+            registry_url: self.url().to_string(),
+            registries: Vec::with_capacity(registries.len()),
+        };
+
+        for (registry_domain, packages) in &registries {
+            let previous = previous.as_ref().and_then(|m| {
+                m.registries
+                    .iter()
+                    .find(|r| r.registry.as_ref() == registry_domain.as_ref())
+            });
+            manifest.registries.push(
+                self.vendor_registry(registry_domain.as_ref(), packages, previous)
+                    .await?,
+            );
+        }
+
+        // Replace any previously vendored package logs
+        let registries_dir = dir.join(REGISTRIES_DIR);
+        if registries_dir.is_dir() {
+            fs::remove_dir_all(&registries_dir).with_context(|| {
+                format!(
+                    "failed to remove directory `{path}`",
+                    path = registries_dir.display()
+                )
+            })?;
+        }
+
+        let paths = storage_paths(dir, self.url().clone());
+        let registry = FileSystemRegistryStorage::lock(&paths.registries_dir)?;
+        let content = FileSystemContentStorage::lock(&paths.content_dir)?;
+        let namespace_map = FileSystemNamespaceMapStorage::new(&paths.namespace_map_path);
+        namespace_map.reset_namespaces().await?;
+
+        for (vendored, (registry_domain, packages)) in manifest.registries.iter().zip(&registries) {
+            registry
+                .store_checkpoint(registry_domain.as_ref(), &vendored.checkpoint)
+                .await?;
+            registry
+                .store_operator(
+                    registry_domain.as_ref(),
+                    self.registry
+                        .load_operator(registry_domain.as_ref())
+                        .await?
+                        .ok_or(ClientError::NoOperatorRecords)?,
+                )
+                .await?;
+
+            let mut namespaces = IndexSet::new();
+            for (info, digests) in packages {
+                registry
+                    .store_package(registry_domain.as_ref(), info)
+                    .await?;
+                namespaces.insert(info.name.namespace().to_string());
+
+                for digest in digests {
+                    if content.content_location(digest).is_some() {
+                        continue;
+                    }
+
+                    content
+                        .store_content(
+                            self.content.load_content(digest).await?.ok_or_else(|| {
+                                ClientError::ContentNotFound {
+                                    digest: digest.clone(),
+                                }
+                            })?,
+                            Some(digest),
+                        )
+                        .await?;
+                }
+            }
+
+            if let Some(registry_domain) = registry_domain {
+                for namespace in namespaces {
+                    namespace_map
+                        .store_namespace(namespace, registry_domain.clone())
+                        .await?;
+                }
+            }
+        }
+
+        // Remove content that is no longer vendored
+        let vendored: IndexSet<_> = registries
+            .values()
+            .flatten()
+            .flat_map(|(_, digests)| digests)
+            .collect();
+        for entry in content.list_content().await? {
+            if entry.partial {
+                content.remove_partial_content(&entry.digest).await?;
+            } else if !vendored.contains(&entry.digest) {
+                content.remove_content(&entry.digest).await?;
+            }
+        }
+
+        manifest.verify(&registry, &content).await?;
+        manifest.write_to_dir(dir)?;
+
+        Ok(manifest)
+    }
+
+    async fn vendor_registry(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        packages: &[(PackageInfo, Vec<AnyHash>)],
+        previous: Option<&VendoredRegistry>,
+    ) -> ClientResult<VendoredRegistry> {
+        let ts_checkpoint = self
+            .registry
+            .load_checkpoint(registry_domain)
+            .await?
+            .ok_or_else(|| anyhow!("checkpoint for registry is missing from client storage"))?;
+        let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+
+        let (operator, mut records) = self
+            .fetch_all_records(
+                registry_domain,
+                checkpoint.log_length,
+                packages
+                    .iter()
+                    .map(|(info, _)| LogId::package_log::<Sha256>(&info.name)),
+            )
+            .await?;
+
+        // Prove inclusion for the operator log head followed by the package log heads
+        let mut leaf_indices = Vec::with_capacity(packages.len() + 1 /* for operator */);
+        let mut leafs = Vec::with_capacity(leaf_indices.len());
+
+        let operator_info = self
+            .registry
+            .load_operator(registry_domain)
+            .await?
+            .ok_or(ClientError::NoOperatorRecords)?;
+        match (
+            operator_info.head_registry_index,
+            operator_info.state.head().as_ref(),
+        ) {
+            (Some(index), Some(head)) => {
+                leaf_indices.push(index);
+                leafs.push(LogLeaf {
+                    log_id: LogId::operator_log::<Sha256>(),
+                    record_id: head.digest.clone(),
+                });
+            }
+            _ => return Err(ClientError::NoOperatorRecords),
+        }
+
+        let mut vendored_packages = Vec::with_capacity(packages.len());
+        for (info, digests) in packages {
+            let log_id = LogId::package_log::<Sha256>(&info.name);
+            match (info.head_registry_index, info.state.head().as_ref()) {
+                (Some(index), Some(head)) => {
+                    leaf_indices.push(index);
+                    leafs.push(LogLeaf {
+                        log_id: log_id.clone(),
+                        record_id: head.digest.clone(),
+                    });
+                }
+                _ => {
+                    return Err(ClientError::PackageLogEmpty {
+                        name: info.name.clone(),
+                    })
+                }
+            }
+
+            vendored_packages.push(VendoredPackage {
+                name: info.name.clone(),
+                records: records.shift_remove(&log_id).unwrap_or_default(),
+                content: digests.clone(),
+            });
+        }
+
+        let inclusion = self
+            .api
+            .prove_inclusion(
+                registry_domain,
+                InclusionRequest {
+                    log_length: checkpoint.log_length,
+                    leafs: leaf_indices,
+                    map_multi_proof: true,
+                },
+                checkpoint,
+                &leafs,
+            )
+            .await?;
+
+        let consistency = match previous {
+            Some(previous) => {
+                let from = &previous.checkpoint.as_ref().checkpoint;
+                match from.log_length.cmp(&checkpoint.log_length) {
+                    Ordering::Greater => {
+                        return Err(ClientError::CheckpointLogLengthRewind {
+                            from: from.log_length,
+                            to: checkpoint.log_length,
+                        });
+                    }
+                    Ordering::Less => Some(VendoredConsistency {
+                        from: from.clone(),
+                        proof: self
+                            .api
+                            .prove_log_consistency(
+                                registry_domain,
+                                ConsistencyRequest {
+                                    from: from.log_length,
+                                    to: checkpoint.log_length,
+                                },
+                                Cow::Borrowed(&from.log_root),
+                                Cow::Borrowed(&checkpoint.log_root),
+                            )
+                            .await?,
+                    }),
+                    Ordering::Equal => {
+                        if from != checkpoint {
+                            return Err(ClientError::CheckpointChangedLogRootOrMapRoot {
+                                log_length: from.log_length,
+                            });
+                        }
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        Ok(VendoredRegistry {
+            registry: registry_domain.cloned(),
+            checkpoint: ts_checkpoint,
+            operator,
+            packages: vendored_packages,
+            inclusion,
+            consistency,
+        })
+    }
+
+    /// Fetches every record of the operator log and the given package logs
+    /// up to the given log length.
+    pub(crate) async fn fetch_all_records(
+        &self,
+        registry_domain: Option<&RegistryDomain>,
+        log_length: RegistryLen,
+        log_ids: impl IntoIterator<Item = LogId>,
+    ) -> ClientResult<FetchedRecords> {
+        let mut operator_fetch_token: Option<String> = None;
+        let mut fetch_tokens: IndexMap<LogId, Option<String>> =
+            log_ids.into_iter().map(|id| (id, None)).collect();
+        let mut operator = Vec::new();
+        let mut packages: IndexMap<LogId, Vec<PublishedProtoEnvelopeBody>> = IndexMap::new();
+
+        loop {
+            let response = self
+                .api
+                .fetch_logs(
+                    registry_domain,
+                    FetchLogsRequest {
+                        log_length,
+                        operator: operator_fetch_token.as_deref().map(Cow::Borrowed),
+                        limit: None,
+                        packages: Cow::Borrowed(&fetch_tokens),
+                    },
+                )
+                .await?;
+
+            for record in response.operator {
+                operator_fetch_token = Some(record.fetch_token);
+                operator.push(record.envelope);
+            }
+
+            for (log_id, records) in response.packages {
+                let fetch_token = fetch_tokens.get_mut(&log_id).ok_or_else(|| {
+                    anyhow!("received records for unknown package log `{log_id}`")
+                })?;
+                let vendored = packages.entry(log_id).or_default();
+                for record in records {
+                    *fetch_token = Some(record.fetch_token);
+                    vendored.push(record.envelope);
+                }
+            }
+
+            if !response.more {
+                break;
+            }
+        }
+
+        Ok((operator, packages))
+    }
+}
+
+/// Gets the options of a client that resolves packages from a vendor directory.
+fn vendored_options(config: &Config, disable_interactive: bool) -> ClientOptions {
+    ClientOptions {
+        ignore_federation_hints: config.ignore_federation_hints,
+        auto_accept_federation_hints: config.auto_accept_federation_hints,
+        disable_interactive,
+        offline: true,
+        ..Default::default()
+    }
+}
+
+impl FileSystemClient {
+    /// Attempts to create a client that resolves packages from a vendor directory.
+    ///
+    /// The vendor directory is opened read-only and verified before the client
+    /// is returned; the client is always in offline mode.
+    pub(crate) async fn try_new_vendored(
+        dir: &Path,
+        config: &Config,
+        disable_interactive: bool,
+    ) -> ClientResult<StorageLockResult<Self>> {
+        let manifest = VendorManifest::from_dir(dir)?;
+        let StoragePaths {
+            registry_url: url,
+            registries_dir,
+            content_dir,
+            namespace_map_path,
+        } = manifest.storage_paths(dir)?;
+
+        let (registry, content) = match (
+            FileSystemRegistryStorage::try_lock_read_only(registries_dir.clone())?,
+            FileSystemContentStorage::try_lock_read_only(content_dir.clone())?,
+        ) {
+            (Some(registry), Some(content)) => (registry, content),
+            (None, _) => return Ok(StorageLockResult::NotAcquired(registries_dir)),
+            (_, None) => return Ok(StorageLockResult::NotAcquired(content_dir)),
+        };
+
+        manifest.verify(&registry, &content).await?;
+
+        Ok(StorageLockResult::Acquired(Self::new(
+            url.into_url(),
+            registry,
+            content,
+            FileSystemNamespaceMapStorage::new(namespace_map_path),
+            vendored_options(config, disable_interactive),
+        )?))
+    }
+
+    /// Creates a client that resolves packages from a vendor directory.
+    ///
+    /// This method blocks if storage locks cannot be acquired.
+    pub(crate) async fn new_vendored(
+        dir: &Path,
+        config: &Config,
+        disable_interactive: bool,
+    ) -> ClientResult<Self> {
+        let manifest = VendorManifest::from_dir(dir)?;
+        let StoragePaths {
+            registry_url: url,
+            registries_dir,
+            content_dir,
+            namespace_map_path,
+        } = manifest.storage_paths(dir)?;
+
+        let registry = FileSystemRegistryStorage::lock_read_only(registries_dir)?;
+        let content = FileSystemContentStorage::lock_read_only(content_dir)?;
+
+        manifest.verify(&registry, &content).await?;
+
+        Self::new(
+            url.into_url(),
+            registry,
+            content,
+            FileSystemNamespaceMapStorage::new(namespace_map_path),
+            vendored_options(config, disable_interactive),
+        )
+    }
+}
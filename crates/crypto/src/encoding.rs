@@ -34,10 +34,10 @@ pub trait Signable: Encode {
 
     fn sign(
         &self,
-        private_key: &signing::PrivateKey,
+        signer: &(impl signing::Signer + ?Sized),
     ) -> Result<signing::Signature, SignatureError> {
         let prefixed_content = [Self::PREFIX, b":", self.encode().as_slice()].concat();
-        private_key.sign(&prefixed_content)
+        signer.sign(&prefixed_content)
     }
 
     fn verify(
@@ -56,6 +56,42 @@ pub struct SignatureAlgorithmParseError {
     value: String,
 }
 
+/// A signer of messages.
+///
+/// This is implemented by [`PrivateKey`] for in-process signing, and may be
+/// implemented by signers that keep the private key elsewhere, such as in a
+/// separate signing service.
+pub trait Signer: Send + Sync {
+    /// Gets the public key of the signer.
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs the given message.
+    ///
+    /// Signers that keep the private key elsewhere may block the calling
+    /// thread until the message is signed.
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError>;
+}
+
+impl<S: Signer + ?Sized> Signer for &S {
+    fn public_key(&self) -> PublicKey {
+        (**self).public_key()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        (**self).sign(msg)
+    }
+}
+
+impl<S: Signer + ?Sized> Signer for Box<S> {
+    fn public_key(&self) -> PublicKey {
+        (**self).public_key()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        (**self).sign(msg)
+    }
+}
+
 pub fn generate_p256_pair() -> (PublicKey, PrivateKey) {
     let private_key = p256::ecdsa::SigningKey::random(&mut OsRng);
     let public_key = p256::ecdsa::VerifyingKey::from(&private_key);
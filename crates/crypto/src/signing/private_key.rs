@@ -74,6 +74,16 @@ impl PrivateKey {
     }
 }
 
+impl super::Signer for PrivateKey {
+    fn public_key(&self) -> PublicKey {
+        PrivateKey::public_key(self)
+    }
+
+    fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        PrivateKey::sign(self, msg)
+    }
+}
+
 // Note: FromStr isn't used because it makes it too easy to leave behind an
 // unzeroized copy of the sensitive encoded key.
 impl TryFrom<String> for PrivateKey {
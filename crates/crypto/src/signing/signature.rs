@@ -26,6 +26,25 @@ impl Signature {
             Signature::P256(key) => key.to_der().to_bytes().to_vec(),
         }
     }
+
+    /// Get the signature's fixed-size representation as bytes, as used by JSON Web Signatures
+    pub fn raw_bytes(&self) -> Vec<u8> {
+        match self {
+            Signature::P256(key) => key.to_bytes().to_vec(),
+        }
+    }
+
+    /// Construct a signature from its fixed-size representation, as used by JSON Web Signatures
+    pub fn from_raw_bytes(
+        algorithm: SignatureAlgorithm,
+        bytes: &[u8],
+    ) -> Result<Self, SignatureError> {
+        match algorithm {
+            SignatureAlgorithm::EcdsaP256 => {
+                Ok(Signature::P256(p256::ecdsa::Signature::from_slice(bytes)?))
+            }
+        }
+    }
 }
 
 impl fmt::Display for Signature {
@@ -0,0 +1,111 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use secrecy::SecretString;
+use std::{fs, path::PathBuf, time::Duration};
+use tokio::signal;
+use tracing_subscriber::filter::LevelFilter;
+use warg_crypto::signing::PrivateKey;
+use warg_monitor::{Monitor, MonitorError};
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Use verbose output
+    #[arg(short, long, env = "WARG_VERBOSE", action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// The URL of the registry to monitor.
+    #[arg(long, env = "WARG_REGISTRY")]
+    registry: String,
+
+    /// The path of the file used to persist the monitor's view of the registry.
+    #[arg(long, env = "WARG_MONITOR_STATE", default_value = "warg-monitor.json")]
+    state: PathBuf,
+
+    /// The number of seconds to wait between checks of the registry.
+    #[arg(long, env = "WARG_MONITOR_INTERVAL", default_value = "10")]
+    interval: u64,
+
+    /// Check the registry once and exit.
+    #[arg(long)]
+    once: bool,
+
+    /// The key used to cosign verified checkpoints as a witness.
+    ///
+    /// Prefer using `witness-key-file`, or environment variable variation.
+    #[arg(long, env = "WARG_WITNESS_KEY")]
+    witness_key: Option<SecretString>,
+
+    /// The path to the key used to cosign verified checkpoints as a witness.
+    #[arg(long, env = "WARG_WITNESS_KEY_FILE", conflicts_with = "witness_key")]
+    witness_key_file: Option<PathBuf>,
+}
+
+impl Args {
+    fn init_tracing(&self) {
+        let level_filter = match self.verbose {
+            0 => LevelFilter::INFO,
+            1 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        };
+        tracing_subscriber::fmt()
+            .with_max_level(level_filter)
+            .init();
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    args.init_tracing();
+    tracing::debug!("args: {args:?}");
+
+    let witness_key = match (&args.witness_key_file, &args.witness_key) {
+        (Some(path), _) => Some(SecretString::from(fs::read_to_string(path).with_context(
+            || format!("failed to read file `{path}`", path = path.display()),
+        )?)),
+        (None, key) => key.clone(),
+    };
+
+    let mut monitor = Monitor::new(&args.registry, &args.state)?;
+    if let Some(key) = witness_key {
+        let key = PrivateKey::decode(key).context("failed to parse witness key")?;
+        tracing::info!(key_id = %key.public_key().fingerprint(), "acting as a witness");
+        monitor = monitor.with_witness_key(key);
+    }
+    tracing::info!(
+        registry = monitor.state().registry,
+        log_length = monitor.state().log_length,
+        "monitoring registry"
+    );
+
+    loop {
+        match monitor.check().await {
+            Ok(_) => {}
+            Err(e) if e.is_violation() => {
+                if let MonitorError::Equivocation { first, second, .. } = &e {
+                    tracing::error!(
+                        first = serde_json::to_string(first)?,
+                        second = serde_json::to_string(second)?,
+                        "conflicting checkpoints"
+                    );
+                }
+                tracing::error!("{e}");
+                bail!(
+                    "registry `{registry}` failed monitoring",
+                    registry = args.registry
+                );
+            }
+            Err(e) if args.once => return Err(e.into()),
+            Err(e) => tracing::warn!("failed to check registry: {e}"),
+        }
+
+        if args.once {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(args.interval)) => {}
+            _ = signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
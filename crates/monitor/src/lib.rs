@@ -0,0 +1,740 @@
+//! An independent monitor for Warg component registries.
+//!
+//! The monitor follows the checkpoints of a registry, replaying every record
+//! of the registry log to rebuild the log and map roots locally. Successive
+//! checkpoints are checked for consistency, and any two checkpoints for the
+//! same log length that differ are reported as an equivocation.
+//!
+//! A monitor configured with a witness key also acts as a witness: each
+//! verified checkpoint is cosigned and the cosignature submitted to the
+//! registry.
+
+#![deny(missing_docs)]
+
+use anyhow::{anyhow, bail, Context};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use warg_api::v1::{fetch::FetchLogsRequest, proof::ConsistencyRequest, witness::CosignRequest};
+use warg_client::api;
+use warg_crypto::{
+    hash::{AnyHash, Sha256},
+    signing::{KeyID, PrivateKey},
+    Encode, Signable,
+};
+use warg_protocol::{
+    operator, package,
+    registry::{
+        CheckpointCosignature, LogId, LogLeaf, MapLeaf, RecordId, RegistryIndex, RegistryLen,
+        TimestampedCheckpoint,
+    },
+    PublishedProtoEnvelope, SerdeEnvelope,
+};
+use warg_transparency::{
+    log::{LogBuilder, StackLog},
+    map::Map,
+};
+
+/// Represents an error detected while monitoring a registry.
+#[derive(Debug, Error)]
+pub enum MonitorError {
+    /// The registry signed two different checkpoints for the same log length.
+    #[error("registry equivocated: found two different checkpoints for log length {log_length}")]
+    Equivocation {
+        /// The log length of the checkpoints.
+        log_length: RegistryLen,
+        /// The previously seen checkpoint.
+        first: Box<SerdeEnvelope<TimestampedCheckpoint>>,
+        /// The conflicting checkpoint.
+        second: Box<SerdeEnvelope<TimestampedCheckpoint>>,
+    },
+
+    /// The checkpoint was signed with a key unknown to the operator log.
+    #[error("checkpoint for log length {log_length} was signed with unknown key `{key_id}`")]
+    UnknownCheckpointKey {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+        /// The key ID of the signature.
+        key_id: KeyID,
+    },
+
+    /// The checkpoint signature is invalid.
+    #[error("checkpoint for log length {log_length} has an invalid signature")]
+    InvalidCheckpointSignature {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+    },
+
+    /// The checkpoint root does not match the root of the replayed log.
+    #[error("checkpoint for log length {log_length} has {kind} root `{found}` but the replayed {kind} has root `{expected}`")]
+    IncorrectRoot {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+        /// The kind of root (`log` or `map`).
+        kind: &'static str,
+        /// The root of the replayed log or map.
+        expected: AnyHash,
+        /// The root of the checkpoint.
+        found: AnyHash,
+    },
+
+    /// A record in the registry log was not returned by the registry.
+    #[error("record at registry index {0} was not returned by the registry")]
+    MissingRecord(RegistryIndex),
+
+    /// A record returned by the registry does not match the registry ledger.
+    #[error("record `{record_id}` at registry index {index} does not match the registry ledger")]
+    UnexpectedRecord {
+        /// The registry index of the record.
+        index: RegistryIndex,
+        /// The record identifier.
+        record_id: RecordId,
+    },
+
+    /// The operator log failed validation.
+    #[error("operator failed validation: {0}")]
+    OperatorValidationFailed(operator::ValidationError),
+
+    /// A package log failed validation.
+    #[error("package log `{log_id}` failed validation: {inner}")]
+    PackageValidationFailed {
+        /// The package log identifier.
+        log_id: LogId,
+        /// The validation error.
+        inner: package::ValidationError,
+    },
+
+    /// The state file was created for a different registry.
+    #[error("monitor state `{path}` is for registry `{registry}`", path = path.display())]
+    RegistryMismatch {
+        /// The path to the state file.
+        path: PathBuf,
+        /// The registry URL in the state file.
+        registry: String,
+    },
+
+    /// An error occurred while communicating with the registry.
+    #[error(transparent)]
+    Api(#[from] api::ClientError),
+
+    /// An error occurred while performing a monitor operation.
+    #[error("{0:?}")]
+    Other(#[from] anyhow::Error),
+}
+
+impl MonitorError {
+    /// Determines if the error is evidence of registry misbehavior rather
+    /// than a failure to reach the registry or access local storage.
+    pub fn is_violation(&self) -> bool {
+        !matches!(
+            self,
+            Self::RegistryMismatch { .. }
+                | Self::Other(_)
+                | Self::Api(
+                    api::ClientError::Communication(_)
+                        | api::ClientError::UnexpectedResponse { .. }
+                )
+        )
+    }
+}
+
+/// Represents the state of a log as replayed by the monitor.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogView<S> {
+    /// The validated log state.
+    #[serde(default)]
+    pub state: S,
+    /// The registry log index of the most recent record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_registry_index: Option<RegistryIndex>,
+    /// The fetch token for the most recent record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_fetch_token: Option<String>,
+}
+
+/// Represents the persisted view of a registry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorState {
+    /// The URL of the monitored registry.
+    pub registry: String,
+    /// The checkpoints seen, keyed by log length.
+    #[serde(default)]
+    pub checkpoints: BTreeMap<RegistryLen, SerdeEnvelope<TimestampedCheckpoint>>,
+    /// The length of the replayed registry log.
+    ///
+    /// The leafs of the log are appended to a separate leaf file so that each
+    /// check only writes the leafs it replayed.
+    #[serde(default)]
+    pub log_length: RegistryLen,
+    /// The replayed operator log.
+    #[serde(default)]
+    pub operator: LogView<operator::LogState>,
+    /// The replayed package logs.
+    #[serde(default)]
+    pub packages: IndexMap<LogId, LogView<package::LogState>>,
+    /// The log length of the latest checkpoint cosigned as a witness.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosigned: Option<RegistryLen>,
+}
+
+impl MonitorState {
+    /// Gets the most recent checkpoint seen.
+    pub fn latest(&self) -> Option<&SerdeEnvelope<TimestampedCheckpoint>> {
+        self.checkpoints.last_key_value().map(|(_, c)| c)
+    }
+}
+
+/// A monitor that follows a single registry.
+pub struct Monitor {
+    api: api::Client,
+    path: PathBuf,
+    leafs_path: PathBuf,
+    state: MonitorState,
+    log: StackLog<Sha256, LogLeaf>,
+    map: Map<Sha256, LogId, MapLeaf>,
+    witness_key: Option<PrivateKey>,
+}
+
+impl Monitor {
+    /// Creates a monitor for the given registry, persisting its view to the
+    /// given state file.
+    ///
+    /// The leafs of the registry log are persisted next to the state file,
+    /// with a `leafs` extension.
+    ///
+    /// If the state file exists, monitoring resumes from the persisted view.
+    pub fn new(url: &str, path: impl Into<PathBuf>) -> Result<Self, MonitorError> {
+        let api = api::Client::new(url, None)?;
+        let path = path.into();
+        let leafs_path = path.with_extension("leafs");
+        let registry = api.url().to_string();
+
+        let state = match fs::read(&path) {
+            Ok(bytes) => {
+                let state: MonitorState = serde_json::from_slice(&bytes).with_context(|| {
+                    format!(
+                        "failed to parse monitor state `{path}`",
+                        path = path.display()
+                    )
+                })?;
+                if state.registry != registry {
+                    return Err(MonitorError::RegistryMismatch {
+                        path,
+                        registry: state.registry,
+                    });
+                }
+                state
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => MonitorState {
+                registry,
+                ..Default::default()
+            },
+            Err(e) => {
+                return Err(anyhow!(e)
+                    .context(format!(
+                        "failed to read monitor state `{path}`",
+                        path = path.display()
+                    ))
+                    .into())
+            }
+        };
+
+        let leafs = read_leafs(&leafs_path, state.log_length).with_context(|| {
+            format!(
+                "failed to read monitor leafs `{path}`",
+                path = leafs_path.display()
+            )
+        })?;
+
+        let mut log = StackLog::default();
+        let mut map = Map::default();
+        for leaf in &leafs {
+            log.push(leaf);
+            map = map.insert(
+                leaf.log_id.clone(),
+                MapLeaf {
+                    record_id: leaf.record_id.clone(),
+                },
+            );
+        }
+
+        Ok(Self {
+            api,
+            path,
+            leafs_path,
+            state,
+            log,
+            map,
+            witness_key: None,
+        })
+    }
+
+    /// Sets the key used to cosign verified checkpoints as a witness.
+    pub fn with_witness_key(mut self, key: PrivateKey) -> Self {
+        self.witness_key = Some(key);
+        self
+    }
+
+    /// Gets the current view of the registry.
+    pub fn state(&self) -> &MonitorState {
+        &self.state
+    }
+
+    /// Checks the latest checkpoint of the registry.
+    ///
+    /// Returns the new log length if the registry log has grown since the
+    /// last check.
+    pub async fn check(&mut self) -> Result<Option<RegistryLen>, MonitorError> {
+        let ts_checkpoint = self.api.latest_checkpoint(None).await?;
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        let previous = self.state.log_length;
+
+        if log_length <= previous {
+            Self::verify_signature(&self.state.operator.state, &ts_checkpoint)?;
+            self.record_checkpoint(ts_checkpoint).await?;
+            self.cosign_latest().await?;
+            return Ok(None);
+        }
+
+        // Replay onto a copy of the view so nothing is kept if verification fails
+        let mut state = self.state.clone();
+        let mut log = self.log.clone();
+        let mut map = self.map.clone();
+        let leafs = self.replay(&mut state, previous, log_length).await?;
+        for leaf in &leafs {
+            log.push(leaf);
+            map = map.insert(
+                leaf.log_id.clone(),
+                MapLeaf {
+                    record_id: leaf.record_id.clone(),
+                },
+            );
+        }
+        state.log_length = log_length;
+
+        Self::verify_signature(&state.operator.state, &ts_checkpoint)?;
+
+        let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+        let log_root: AnyHash = log.checkpoint().root().into();
+        if log_root != checkpoint.log_root {
+            return Err(MonitorError::IncorrectRoot {
+                log_length,
+                kind: "log",
+                expected: log_root,
+                found: checkpoint.log_root.clone(),
+            });
+        }
+
+        let map_root: AnyHash = map.root().into();
+        if map_root != checkpoint.map_root {
+            return Err(MonitorError::IncorrectRoot {
+                log_length,
+                kind: "map",
+                expected: map_root,
+                found: checkpoint.map_root.clone(),
+            });
+        }
+
+        if let Some(from) = self.state.latest() {
+            self.prove_consistency(from, &ts_checkpoint).await?;
+        }
+
+        tracing::info!(
+            log_length,
+            records = log_length - previous,
+            "verified registry checkpoint"
+        );
+
+        // The leafs are appended before the state is saved so that the state
+        // never refers to leafs that were not persisted
+        append_leafs(&self.leafs_path, &leafs).with_context(|| {
+            format!(
+                "failed to write monitor leafs `{path}`",
+                path = self.leafs_path.display()
+            )
+        })?;
+
+        state.checkpoints.insert(log_length, ts_checkpoint);
+        self.state = state;
+        self.log = log;
+        self.map = map;
+        self.save()?;
+        self.cosign_latest().await?;
+        Ok(Some(log_length))
+    }
+
+    /// Cosigns the latest verified checkpoint if the monitor is a witness.
+    ///
+    /// A checkpoint is only cosigned once.
+    async fn cosign_latest(&mut self) -> Result<(), MonitorError> {
+        let (Some(key), Some(latest)) = (&self.witness_key, self.state.latest()) else {
+            return Ok(());
+        };
+
+        let log_length = latest.as_ref().checkpoint.log_length;
+        if self.state.cosigned.is_some_and(|l| l >= log_length) {
+            return Ok(());
+        }
+
+        let cosignature = CheckpointCosignature::sign(key, latest.as_ref())
+            .map_err(|e| anyhow!("failed to cosign checkpoint: {e}"))?;
+        self.api
+            .cosign_checkpoint(
+                None,
+                CosignRequest {
+                    log_length,
+                    public_key: key.public_key(),
+                    cosignature,
+                },
+            )
+            .await?;
+
+        tracing::info!(log_length, "cosigned registry checkpoint");
+
+        self.state.cosigned = Some(log_length);
+        self.save()
+    }
+
+    /// Records a checkpoint for a log length already replayed.
+    ///
+    /// The checkpoint must match any previously seen checkpoint for the same
+    /// log length and be consistent with the latest checkpoint.
+    async fn record_checkpoint(
+        &mut self,
+        ts_checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), MonitorError> {
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        if log_length == 0 {
+            return Ok(());
+        }
+
+        if let Some(first) = self.state.checkpoints.get(&log_length) {
+            if first.as_ref().checkpoint != ts_checkpoint.as_ref().checkpoint {
+                return Err(MonitorError::Equivocation {
+                    log_length,
+                    first: Box::new(first.clone()),
+                    second: Box::new(ts_checkpoint),
+                });
+            }
+
+            return Ok(());
+        }
+
+        // An older checkpoint must be a prefix of the latest one
+        if let Some(latest) = self.state.latest() {
+            self.prove_consistency(&ts_checkpoint, latest).await?;
+        }
+
+        self.state.checkpoints.insert(log_length, ts_checkpoint);
+        self.save()
+    }
+
+    /// Proves the consistency of two checkpoints.
+    async fn prove_consistency(
+        &self,
+        from: &SerdeEnvelope<TimestampedCheckpoint>,
+        to: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), MonitorError> {
+        let from = &from.as_ref().checkpoint;
+        let to = &to.as_ref().checkpoint;
+        self.api
+            .prove_log_consistency(
+                None,
+                ConsistencyRequest {
+                    from: from.log_length,
+                    to: to.log_length,
+                },
+                Cow::Borrowed(&from.log_root),
+                Cow::Borrowed(&to.log_root),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Replays the records of the registry log between the given log lengths.
+    ///
+    /// Returns the log leafs of the replayed records in registry log order.
+    async fn replay(
+        &self,
+        state: &mut MonitorState,
+        from: RegistryLen,
+        to: RegistryLen,
+    ) -> Result<Vec<LogLeaf>, MonitorError> {
+        let leafs = self.fetch_ledger(from, to).await?;
+        let mut seen = vec![false; leafs.len()];
+
+        // Every record must match the ledger leaf at its registry index
+        let mut check = |index: RegistryIndex, log_id: &LogId, record_id: RecordId| match index
+            .checked_sub(from)
+            .and_then(|i| leafs.get(i).map(|l| (i, l)))
+        {
+            Some((i, leaf)) if &leaf.log_id == log_id && leaf.record_id == record_id => {
+                seen[i] = true;
+                Ok(())
+            }
+            _ => Err(MonitorError::UnexpectedRecord { index, record_id }),
+        };
+
+        let operator_log_id = LogId::operator_log::<Sha256>();
+        let log_ids: IndexSet<LogId> = leafs
+            .iter()
+            .filter(|leaf| leaf.log_id != operator_log_id)
+            .map(|leaf| leaf.log_id.clone())
+            .collect();
+
+        loop {
+            let response = self
+                .api
+                .fetch_logs(
+                    None,
+                    FetchLogsRequest {
+                        log_length: to,
+                        operator: state
+                            .operator
+                            .head_fetch_token
+                            .as_deref()
+                            .map(Cow::Borrowed),
+                        limit: None,
+                        packages: Cow::Owned(
+                            log_ids
+                                .iter()
+                                .map(|id| {
+                                    (
+                                        id.clone(),
+                                        state
+                                            .packages
+                                            .get(id)
+                                            .and_then(|p| p.head_fetch_token.clone()),
+                                    )
+                                })
+                                .collect(),
+                        ),
+                    },
+                )
+                .await?;
+
+            for record in response.operator {
+                let envelope: PublishedProtoEnvelope<operator::OperatorRecord> =
+                    record.envelope.try_into()?;
+                let operator = &mut state.operator;
+                if operator
+                    .head_registry_index
+                    .is_some_and(|head| envelope.registry_index <= head)
+                {
+                    continue;
+                }
+
+                check(
+                    envelope.registry_index,
+                    &operator_log_id,
+                    RecordId::operator_record::<Sha256>(&envelope.envelope),
+                )?;
+                operator.state = std::mem::take(&mut operator.state)
+                    .validate(&envelope.envelope)
+                    .map_err(MonitorError::OperatorValidationFailed)?;
+                operator.head_registry_index = Some(envelope.registry_index);
+                operator.head_fetch_token = Some(record.fetch_token);
+            }
+
+            for (log_id, records) in response.packages {
+                if !log_ids.contains(&log_id) {
+                    return Err(
+                        anyhow!("received records for unknown package log `{log_id}`").into(),
+                    );
+                }
+
+                let package = state.packages.entry(log_id.clone()).or_default();
+                for record in records {
+                    let envelope: PublishedProtoEnvelope<package::PackageRecord> =
+                        record.envelope.try_into()?;
+                    if package
+                        .head_registry_index
+                        .is_some_and(|head| envelope.registry_index <= head)
+                    {
+                        continue;
+                    }
+
+                    check(
+                        envelope.registry_index,
+                        &log_id,
+                        RecordId::package_record::<Sha256>(&envelope.envelope),
+                    )?;
+                    package.state = std::mem::take(&mut package.state)
+                        .validate(&envelope.envelope)
+                        .map_err(|inner| MonitorError::PackageValidationFailed {
+                            log_id: log_id.clone(),
+                            inner,
+                        })?;
+                    package.head_registry_index = Some(envelope.registry_index);
+                    package.head_fetch_token = Some(record.fetch_token);
+                }
+            }
+
+            if !response.more {
+                break;
+            }
+        }
+
+        if let Some(i) = seen.iter().position(|seen| !seen) {
+            return Err(MonitorError::MissingRecord(from + i));
+        }
+
+        Ok(leafs)
+    }
+
+    /// Fetches the ledger leafs between the given log lengths.
+    async fn fetch_ledger(
+        &self,
+        from: RegistryLen,
+        to: RegistryLen,
+    ) -> Result<Vec<LogLeaf>, MonitorError> {
+        let sources = self.api.ledger_sources(None).await?;
+        let mut leafs = Vec::with_capacity(to - from);
+        for source in &sources.sources {
+            if source.last_registry_index < from {
+                continue;
+            }
+
+            if source.first_registry_index >= to {
+                break;
+            }
+
+            let records = self
+                .api
+                .ledger_records(None, sources.hash_algorithm, source)
+                .await?;
+            leafs.extend(
+                records
+                    .into_iter()
+                    .zip(source.first_registry_index..)
+                    .filter(|(_, index)| (from..to).contains(index))
+                    .map(|(leaf, _)| leaf),
+            );
+        }
+
+        if leafs.len() != to - from {
+            return Err(MonitorError::MissingRecord(from + leafs.len()));
+        }
+
+        Ok(leafs)
+    }
+
+    /// Verifies the signature of a checkpoint with the operator log keys.
+    fn verify_signature(
+        operator: &operator::LogState,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), MonitorError> {
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        let key = operator.public_key(ts_checkpoint.key_id()).ok_or_else(|| {
+            MonitorError::UnknownCheckpointKey {
+                log_length,
+                key_id: ts_checkpoint.key_id().clone(),
+            }
+        })?;
+
+        TimestampedCheckpoint::verify(
+            key,
+            &ts_checkpoint.as_ref().encode(),
+            ts_checkpoint.signature(),
+        )
+        .map_err(|_| MonitorError::InvalidCheckpointSignature { log_length })
+    }
+
+    /// Persists the monitor state.
+    fn save(&self) -> Result<(), MonitorError> {
+        write_state(&self.path, &self.state)
+            .with_context(|| {
+                format!(
+                    "failed to write monitor state `{path}`",
+                    path = self.path.display()
+                )
+            })
+            .map_err(Into::into)
+    }
+}
+
+fn write_state(path: &Path, state: &MonitorState) -> anyhow::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir)?;
+
+    // Write to a temporary file first so a crash never leaves a partial state
+    let file = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer(file.as_file(), state)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Reads the first `log_length` leafs of a leaf file.
+///
+/// Leafs past `log_length` were appended by a check that did not complete, so
+/// they are truncated from the file.
+fn read_leafs(path: &Path, log_length: RegistryLen) -> anyhow::Result<Vec<LogLeaf>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && log_length == 0 => {
+            return Ok(Vec::new())
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut leafs = Vec::with_capacity(log_length);
+    let mut offset = 0;
+    let mut line = String::new();
+    while leafs.len() < log_length {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        if read == 0 {
+            bail!(
+                "expected {log_length} leafs but found {found}",
+                found = leafs.len()
+            );
+        }
+
+        leafs.push(serde_json::from_str(&line)?);
+        offset += read as u64;
+    }
+
+    let file = reader.into_inner();
+    if file.metadata()?.len() > offset {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(path)?
+            .set_len(offset)?;
+    }
+
+    Ok(leafs)
+}
+
+/// Appends leafs to a leaf file, one JSON leaf per line.
+fn append_leafs(path: &Path, leafs: &[LogLeaf]) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut buf = Vec::new();
+    for leaf in leafs {
+        serde_json::to_writer(&mut buf, leaf)?;
+        buf.push(b'\n');
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(&buf)?;
+    file.sync_data()?;
+    Ok(())
+}
@@ -0,0 +1,152 @@
+//! Types for streaming the registry ledger with its records.
+
+use crate::{
+    proto_envelope::ParseEnvelopeError,
+    registry::{LogId, LogLeaf, RecordId, RegistryIndex},
+    ProtoEnvelopeBody, PublishedProtoEnvelopeBody,
+};
+use prost::Message;
+use thiserror::Error;
+use warg_crypto::hash::{AnyHash, HashAlgorithm};
+use warg_protobuf::protocol as protobuf;
+
+/// A record of the registry ledger.
+///
+/// A ledger segment is a sequence of records, each encoded as a `LedgerRecord`
+/// protobuf message prefixed with its length as a varint.
+#[derive(Debug, Clone)]
+pub struct LedgerRecord {
+    /// The log leaf of the record.
+    pub leaf: LogLeaf,
+    /// The record envelope with its registry index.
+    pub envelope: PublishedProtoEnvelopeBody,
+}
+
+impl LedgerRecord {
+    /// Appends the length-delimited encoding of the record to a segment.
+    pub fn encode_to(&self, segment: &mut Vec<u8>) {
+        protobuf::LedgerRecord {
+            log_id: self.leaf.log_id.as_ref().to_vec(),
+            record_id: self.leaf.record_id.as_ref().to_vec(),
+            registry_index: self.envelope.registry_index as u64,
+            envelope: Some(self.envelope.envelope.to_protobuf()),
+        }
+        .encode_length_delimited(segment)
+        .expect("a vector has sufficient capacity");
+    }
+
+    /// Decodes the records of a ledger segment that uses the given hash
+    /// algorithm.
+    pub fn decode_segment(
+        algorithm: HashAlgorithm,
+        mut segment: &[u8],
+    ) -> Result<Vec<Self>, ParseLedgerError> {
+        let len = algorithm.hasher().finalize().bytes().len();
+        let hash = |bytes: Vec<u8>| {
+            if bytes.len() != len {
+                return Err(ParseLedgerError::InvalidDigest(algorithm));
+            }
+            Ok(AnyHash::new(algorithm, bytes))
+        };
+
+        let mut records = Vec::new();
+        while !segment.is_empty() {
+            let record = protobuf::LedgerRecord::decode_length_delimited(&mut segment)?;
+            let registry_index = record.registry_index as RegistryIndex;
+            let envelope = record
+                .envelope
+                .ok_or(ParseLedgerError::MissingEnvelope(registry_index))
+                .and_then(|envelope| {
+                    ProtoEnvelopeBody::from_protobuf(envelope)
+                        .map_err(|e| ParseLedgerError::Envelope(registry_index, e))
+                })?;
+
+            records.push(Self {
+                leaf: LogLeaf {
+                    log_id: LogId::from(hash(record.log_id)?),
+                    record_id: RecordId::from(hash(record.record_id)?),
+                },
+                envelope: PublishedProtoEnvelopeBody {
+                    envelope,
+                    registry_index,
+                },
+            });
+        }
+
+        Ok(records)
+    }
+}
+
+/// Errors that occur in the process of parsing a ledger segment.
+#[derive(Error, Debug)]
+pub enum ParseLedgerError {
+    #[error("failed to parse the ledger record protobuf message")]
+    Protobuf(#[from] prost::DecodeError),
+
+    #[error("ledger record digest is not a valid `{0}` digest")]
+    InvalidDigest(HashAlgorithm),
+
+    #[error("ledger record at registry index {0} has no envelope")]
+    MissingEnvelope(RegistryIndex),
+
+    #[error("ledger record at registry index {0} has an invalid envelope")]
+    Envelope(RegistryIndex, #[source] ParseEnvelopeError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{operator, ProtoEnvelope};
+    use std::time::SystemTime;
+    use warg_crypto::{hash::Sha256, signing::generate_p256_pair};
+
+    #[test]
+    fn test_segment_roundtrip() {
+        let (public_key, private_key) = generate_p256_pair();
+        let record = ProtoEnvelope::signed_contents(
+            &private_key,
+            operator::OperatorRecord {
+                prev: None,
+                version: 0,
+                timestamp: SystemTime::now(),
+                entries: vec![operator::OperatorEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: public_key,
+                }],
+            },
+        )
+        .unwrap();
+        let leaf = LogLeaf {
+            log_id: LogId::operator_log::<Sha256>(),
+            record_id: RecordId::operator_record::<Sha256>(&record),
+        };
+
+        let mut segment = Vec::new();
+        for registry_index in 0..2 {
+            LedgerRecord {
+                leaf: leaf.clone(),
+                envelope: PublishedProtoEnvelopeBody {
+                    envelope: record.clone().into(),
+                    registry_index,
+                },
+            }
+            .encode_to(&mut segment);
+        }
+
+        let records = LedgerRecord::decode_segment(HashAlgorithm::Sha256, &segment).unwrap();
+        assert_eq!(records.len(), 2);
+        for (registry_index, decoded) in records.into_iter().enumerate() {
+            assert_eq!(decoded.leaf, leaf);
+            assert_eq!(decoded.envelope.registry_index, registry_index);
+            let decoded =
+                ProtoEnvelope::<operator::OperatorRecord>::try_from(decoded.envelope.envelope)
+                    .unwrap();
+            assert_eq!(decoded, record);
+        }
+
+        assert!(matches!(
+            LedgerRecord::decode_segment(HashAlgorithm::Sha256, &segment[..segment.len() - 1]),
+            Err(ParseLedgerError::Protobuf(_))
+        ));
+    }
+}
@@ -2,6 +2,7 @@ use indexmap::IndexSet;
 use serde::{de::DeserializeOwned, Serialize};
 use warg_crypto::{hash::AnyHash, Decode};
 
+pub mod ledger;
 pub mod operator;
 pub mod package;
 mod proto_envelope;
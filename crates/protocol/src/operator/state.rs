@@ -165,6 +165,13 @@ impl LogState {
         self.namespaces.get(namespace).map(|def| &def.state)
     }
 
+    /// Gets the known namespaces and their states.
+    pub fn namespaces(&self) -> impl Iterator<Item = (&str, &NamespaceState)> {
+        self.namespaces
+            .iter()
+            .map(|(namespace, def)| (namespace.as_str(), &def.state))
+    }
+
     /// Checks the key has permission to sign checkpoints.
     pub fn key_has_permission_to_sign_checkpoints(&self, key_id: &signing::KeyID) -> bool {
         self.check_key_permissions(key_id, &[model::Permission::Commit])
@@ -9,8 +9,8 @@ use crate::{pbjson_to_prost_timestamp, prost_to_pbjson_timestamp, registry::Reco
 mod model;
 mod state;
 
-pub use model::{PackageEntry, PackageRecord, Permission};
-pub use state::{LogState, Release, ReleaseState, ValidationError};
+pub use model::{IdentityPattern, PackageEntry, PackageRecord, Permission};
+pub use state::{Identity, IdentityGrant, LogState, Release, ReleaseState, ValidationError};
 
 /// The currently supported package protocol version.
 pub const PACKAGE_RECORD_VERSION: u32 = 0;
@@ -93,6 +93,33 @@ impl TryFrom<protobuf::PackageEntry> for model::PackageEntry {
             Contents::Yank(yank) => model::PackageEntry::Yank {
                 version: yank.version.parse()?,
             },
+            Contents::GrantIdentity(grant) => model::PackageEntry::GrantIdentity {
+                identity: model::IdentityPattern {
+                    issuer: grant.issuer,
+                    subject: grant.subject,
+                },
+                permissions: grant
+                    .permissions
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            },
+            Contents::RevokeIdentity(revoke) => model::PackageEntry::RevokeIdentity {
+                identity: model::IdentityPattern {
+                    issuer: revoke.issuer,
+                    subject: revoke.subject,
+                },
+                permissions: revoke
+                    .permissions
+                    .into_iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<_, _>>()?,
+            },
+            Contents::Identity(identity) => model::PackageEntry::Identity {
+                issuer: identity.issuer,
+                subject: identity.subject,
+                key: identity.key.parse()?,
+            },
         };
         Ok(output)
     }
@@ -181,6 +208,31 @@ impl<'a> From<&'a model::PackageEntry> for protobuf::PackageEntry {
             model::PackageEntry::Yank { version } => Contents::Yank(protobuf::PackageYank {
                 version: version.to_string(),
             }),
+            model::PackageEntry::GrantIdentity {
+                identity,
+                permissions,
+            } => Contents::GrantIdentity(protobuf::PackageGrantIdentity {
+                issuer: identity.issuer.clone(),
+                subject: identity.subject.clone(),
+                permissions: permissions.iter().map(Into::into).collect(),
+            }),
+            model::PackageEntry::RevokeIdentity {
+                identity,
+                permissions,
+            } => Contents::RevokeIdentity(protobuf::PackageRevokeIdentity {
+                issuer: identity.issuer.clone(),
+                subject: identity.subject.clone(),
+                permissions: permissions.iter().map(Into::into).collect(),
+            }),
+            model::PackageEntry::Identity {
+                issuer,
+                subject,
+                key,
+            } => Contents::Identity(protobuf::PackageIdentity {
+                issuer: issuer.clone(),
+                subject: subject.clone(),
+                key: key.to_string(),
+            }),
         };
         let contents = Some(contents);
         protobuf::PackageEntry { contents }
@@ -236,6 +288,20 @@ mod tests {
                     version: Version::new(1, 0, 0),
                     content: HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
                 },
+                model::PackageEntry::GrantIdentity {
+                    identity: model::IdentityPattern {
+                        issuer: "https://issuer.example".to_string(),
+                        subject: "repo:example/*".to_string(),
+                    },
+                    permissions: vec![model::Permission::Release],
+                },
+                model::PackageEntry::RevokeIdentity {
+                    identity: model::IdentityPattern {
+                        issuer: "https://issuer.example".to_string(),
+                        subject: "repo:example/*".to_string(),
+                    },
+                    permissions: vec![model::Permission::Release],
+                },
             ],
         };
 
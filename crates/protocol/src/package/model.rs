@@ -66,6 +66,76 @@ impl FromStr for Permission {
     }
 }
 
+/// A pattern matching publisher identities issued by an identity provider.
+///
+/// The subject may contain `*` wildcards, each matching any sequence of characters.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityPattern {
+    /// The issuer of the identity, e.g. `https://token.actions.githubusercontent.com`.
+    pub issuer: String,
+    /// The subject pattern of the identity.
+    pub subject: String,
+}
+
+impl IdentityPattern {
+    /// Checks whether the given identity matches this pattern.
+    pub fn matches(&self, issuer: &str, subject: &str) -> bool {
+        self.issuer == issuer && glob_match(&self.subject, subject)
+    }
+}
+
+impl fmt::Display for IdentityPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{issuer}#{subject}",
+            issuer = self.issuer,
+            subject = self.subject
+        )
+    }
+}
+
+impl FromStr for IdentityPattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('#') {
+            Some((issuer, subject)) if !issuer.is_empty() && !subject.is_empty() => Ok(Self {
+                issuer: issuer.to_string(),
+                subject: subject.to_string(),
+            }),
+            _ => Err(format!(
+                "invalid identity pattern {s:?}: expected `<issuer>#<subject>`"
+            )),
+        }
+    }
+}
+
+/// Matches `text` against `pattern`, where `*` matches any sequence of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<_> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcards in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum PackageEntry {
@@ -95,13 +165,41 @@ pub enum PackageEntry {
     /// Yank a version of a package.
     /// The version must have been released and not yanked.
     Yank { version: Version },
+    /// Grant publisher identities matching a pattern a permission.
+    /// The author of this entry must have the permission.
+    GrantIdentity {
+        identity: IdentityPattern,
+        permissions: Vec<Permission>,
+    },
+    /// Remove a permission from an identity pattern.
+    /// The author of this entry must have the permission.
+    RevokeIdentity {
+        identity: IdentityPattern,
+        permissions: Vec<Permission>,
+    },
+    /// Binds an ephemeral key to a publisher identity for this record.
+    /// Must be the first entry of the record and the key must be the signer's.
+    /// The claims are recorded so that publishes can be audited.
+    Identity {
+        /// The issuer of the identity token
+        issuer: String,
+        /// The subject of the identity token
+        subject: String,
+        /// The ephemeral key that signed the record
+        key: signing::PublicKey,
+    },
 }
 
 impl PackageEntry {
     /// Check permission is required to submit this entry
     pub fn required_permission(&self) -> Option<Permission> {
         match self {
-            Self::Init { .. } | Self::GrantFlat { .. } | Self::RevokeFlat { .. } => None,
+            Self::Init { .. }
+            | Self::GrantFlat { .. }
+            | Self::RevokeFlat { .. }
+            | Self::GrantIdentity { .. }
+            | Self::RevokeIdentity { .. }
+            | Self::Identity { .. } => None,
             Self::Release { .. } => Some(Permission::Release),
             Self::Yank { .. } => Some(Permission::Yank),
         }
@@ -117,3 +215,28 @@ impl PackageEntry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_pattern_matches() {
+        let pattern: IdentityPattern = "https://issuer.example#repo:example/*:ref:refs/tags/*"
+            .parse()
+            .unwrap();
+        assert_eq!(pattern.issuer, "https://issuer.example");
+        assert!(pattern.matches(
+            "https://issuer.example",
+            "repo:example/foo:ref:refs/tags/v1"
+        ));
+        assert!(!pattern.matches("https://issuer.example", "repo:other/foo:ref:refs/tags/v1"));
+        assert!(!pattern.matches("https://other.example", "repo:example/foo:ref:refs/tags/v1"));
+
+        let exact: IdentityPattern = "https://issuer.example#alice".parse().unwrap();
+        assert!(exact.matches("https://issuer.example", "alice"));
+        assert!(!exact.matches("https://issuer.example", "alice2"));
+
+        assert!("no-subject".parse::<IdentityPattern>().is_err());
+    }
+}
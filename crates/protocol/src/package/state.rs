@@ -67,6 +67,24 @@ pub enum ValidationError {
 
     #[error("record has lower timestamp than previous")]
     TimestampLowerThanPrevious,
+
+    #[error("an \"identity\" entry must be the first entry of a record")]
+    IdentityNotFirstEntry,
+
+    #[error("the key of the \"identity\" entry does not match the key ID {key_id} used to sign the record")]
+    IdentityKeyMismatch { key_id: signing::KeyID },
+
+    #[error("the key with ID {key_id} bound to an identity is already known to this package log")]
+    IdentityKeyAlreadyKnown { key_id: signing::KeyID },
+
+    #[error("identity `{subject}` issued by `{issuer}` has not been granted any permissions")]
+    IdentityNotAuthorized { issuer: String, subject: String },
+
+    #[error("attempted to remove permission {permission} from identity `{identity}` which did not have it")]
+    IdentityPermissionNotFoundToRevoke {
+        permission: model::Permission,
+        identity: model::IdentityPattern,
+    },
 }
 
 /// Represents the current state of a release.
@@ -122,6 +140,26 @@ impl Release {
     }
 }
 
+/// Represents permissions granted to publisher identities matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdentityGrant {
+    /// The pattern of identities granted the permissions.
+    pub identity: model::IdentityPattern,
+    /// The permissions granted.
+    pub permissions: IndexSet<model::Permission>,
+}
+
+/// Represents the publisher identity an ephemeral key was bound to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    /// The issuer of the identity.
+    pub issuer: String,
+    /// The subject of the identity.
+    pub subject: String,
+}
+
 /// Information about the current head of the package log.
 ///
 /// A head is the last validated record digest and timestamp.
@@ -155,6 +193,12 @@ pub struct LogState {
     /// The keys known to the state.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     keys: IndexMap<signing::KeyID, signing::PublicKey>,
+    /// The permissions granted to identity patterns.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    identity_permissions: Vec<IdentityGrant>,
+    /// The identities that ephemeral keys were bound to.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    identities: IndexMap<signing::KeyID, Identity>,
 }
 
 impl LogState {
@@ -225,6 +269,18 @@ impl LogState {
         self.permissions.get(key_id)
     }
 
+    /// Gets the permissions granted to identity patterns.
+    pub fn identity_permissions(&self) -> &[IdentityGrant] {
+        &self.identity_permissions
+    }
+
+    /// Gets the publisher identity the given key was bound to.
+    ///
+    /// Returns `None` if the key was not bound to an identity.
+    pub fn identity(&self, key_id: &signing::KeyID) -> Option<&Identity> {
+        self.identities.get(key_id)
+    }
+
     fn initialized(&self) -> bool {
         // The package log is initialized if the hash algorithm is set
         self.algorithm.is_some()
@@ -333,7 +389,8 @@ impl LogState {
         timestamp: SystemTime,
         entries: &[model::PackageEntry],
     ) -> Result<(), ValidationError> {
-        for entry in entries {
+        let mut ephemeral = false;
+        for (index, entry) in entries.iter().enumerate() {
             if let Some(permission) = entry.required_permission() {
                 self.check_key_permissions(signer_key_id, &[permission])?;
             }
@@ -372,9 +429,34 @@ impl LogState {
                 model::PackageEntry::Yank { version } => {
                     self.validate_yank_entry(signer_key_id, timestamp, version)?
                 }
+                model::PackageEntry::GrantIdentity {
+                    identity,
+                    permissions,
+                } => self.validate_grant_identity_entry(signer_key_id, identity, permissions)?,
+                model::PackageEntry::RevokeIdentity {
+                    identity,
+                    permissions,
+                } => self.validate_revoke_identity_entry(signer_key_id, identity, permissions)?,
+                model::PackageEntry::Identity {
+                    issuer,
+                    subject,
+                    key,
+                } => {
+                    if index != 0 {
+                        return Err(ValidationError::IdentityNotFirstEntry);
+                    }
+
+                    self.validate_identity_entry(signer_key_id, issuer, subject, key)?;
+                    ephemeral = true;
+                }
             }
         }
 
+        // Permissions granted to an identity only apply to the record it signed
+        if ephemeral {
+            self.permissions.shift_remove(signer_key_id);
+        }
+
         Ok(())
     }
 
@@ -452,6 +534,105 @@ impl LogState {
         Ok(())
     }
 
+    fn validate_grant_identity_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        identity: &model::IdentityPattern,
+        permissions: &[model::Permission],
+    ) -> Result<(), ValidationError> {
+        // Check that the current key has the permission they're trying to grant
+        self.check_key_permissions(signer_key_id, permissions)?;
+
+        match self
+            .identity_permissions
+            .iter_mut()
+            .find(|grant| &grant.identity == identity)
+        {
+            Some(grant) => grant.permissions.extend(permissions),
+            None => self.identity_permissions.push(IdentityGrant {
+                identity: identity.clone(),
+                permissions: permissions.iter().copied().collect(),
+            }),
+        }
+
+        Ok(())
+    }
+
+    fn validate_revoke_identity_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        identity: &model::IdentityPattern,
+        permissions: &[model::Permission],
+    ) -> Result<(), ValidationError> {
+        // Check that the current key has the permission they're trying to revoke
+        self.check_key_permissions(signer_key_id, permissions)?;
+
+        for permission in permissions {
+            if !self
+                .identity_permissions
+                .iter_mut()
+                .find(|grant| &grant.identity == identity)
+                .map(|grant| grant.permissions.swap_remove(permission))
+                .unwrap_or(false)
+            {
+                return Err(ValidationError::IdentityPermissionNotFoundToRevoke {
+                    permission: *permission,
+                    identity: identity.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_identity_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        issuer: &str,
+        subject: &str,
+        key: &signing::PublicKey,
+    ) -> Result<(), ValidationError> {
+        if signer_key_id != &key.fingerprint() {
+            return Err(ValidationError::IdentityKeyMismatch {
+                key_id: signer_key_id.clone(),
+            });
+        }
+
+        // Ephemeral keys must not be reused, otherwise they could carry
+        // permissions beyond the record they signed
+        if self.keys.contains_key(signer_key_id) {
+            return Err(ValidationError::IdentityKeyAlreadyKnown {
+                key_id: signer_key_id.clone(),
+            });
+        }
+
+        let permissions: IndexSet<_> = self
+            .identity_permissions
+            .iter()
+            .filter(|grant| grant.identity.matches(issuer, subject))
+            .flat_map(|grant| grant.permissions.iter().copied())
+            .collect();
+
+        if permissions.is_empty() {
+            return Err(ValidationError::IdentityNotAuthorized {
+                issuer: issuer.to_string(),
+                subject: subject.to_string(),
+            });
+        }
+
+        self.keys.insert(signer_key_id.clone(), key.clone());
+        self.permissions.insert(signer_key_id.clone(), permissions);
+        self.identities.insert(
+            signer_key_id.clone(),
+            Identity {
+                issuer: issuer.to_string(),
+                subject: subject.to_string(),
+            },
+        );
+
+        Ok(())
+    }
+
     fn validate_release_entry(
         &mut self,
         record_id: &RecordId,
@@ -581,6 +762,8 @@ mod tests {
                 )]),
                 releases: IndexMap::default(),
                 keys: IndexMap::from([(alice_id, alice_pub)]),
+                identity_permissions: Vec::default(),
+                identities: IndexMap::default(),
             }
         );
     }
@@ -723,10 +906,126 @@ mod tests {
                     }
                 )]),
                 keys: IndexMap::from([(alice_id, alice_pub), (bob_id, bob_pub),]),
+                identity_permissions: Vec::default(),
+                identities: IndexMap::default(),
             }
         );
     }
 
+    #[test]
+    fn test_validate_identity_log() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let issuer = "https://issuer.example";
+        let identity = model::IdentityPattern {
+            issuer: issuer.to_string(),
+            subject: "repo:example/*".to_string(),
+        };
+
+        // In envelope 0: alice inits and grants release to the identity pattern
+        let timestamp0 = SystemTime::now();
+        let record0 = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp0,
+            entries: vec![
+                model::PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub.clone(),
+                },
+                model::PackageEntry::GrantIdentity {
+                    identity: identity.clone(),
+                    permissions: vec![model::Permission::Release],
+                },
+            ],
+        };
+        let envelope0 = ProtoEnvelope::signed_contents(&alice_priv, record0).unwrap();
+        let state = LogState::default().validate(&envelope0).unwrap();
+        assert_eq!(
+            state.identity_permissions(),
+            &[IdentityGrant {
+                identity: identity.clone(),
+                permissions: IndexSet::from([model::Permission::Release]),
+            }]
+        );
+
+        // In envelope 1: an ephemeral key bound to a matching identity releases 1.0.0
+        let (ephemeral_pub, ephemeral_priv) = generate_p256_pair();
+        let ephemeral_id = ephemeral_pub.fingerprint();
+        let timestamp1 = timestamp0 + Duration::from_secs(1);
+        let record1 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope0)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp1,
+            entries: vec![
+                model::PackageEntry::Identity {
+                    issuer: issuer.to_string(),
+                    subject: "repo:example/foo".to_string(),
+                    key: ephemeral_pub.clone(),
+                },
+                model::PackageEntry::Release {
+                    version: Version::new(1, 0, 0),
+                    content: HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
+                },
+            ],
+        };
+        let envelope1 = ProtoEnvelope::signed_contents(&ephemeral_priv, record1).unwrap();
+        let state = state.validate(&envelope1).unwrap();
+
+        let release = state.release(&Version::new(1, 0, 0)).unwrap();
+        assert_eq!(release.by, ephemeral_id);
+        assert_eq!(
+            state.identity(&ephemeral_id),
+            Some(&Identity {
+                issuer: issuer.to_string(),
+                subject: "repo:example/foo".to_string(),
+            })
+        );
+
+        // The ephemeral key has no permissions beyond the record it signed
+        assert!(state.key_permissions(&ephemeral_id).is_none());
+
+        // The ephemeral key cannot be reused
+        let record2 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope1)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp1 + Duration::from_secs(1),
+            entries: vec![
+                model::PackageEntry::Identity {
+                    issuer: issuer.to_string(),
+                    subject: "repo:example/foo".to_string(),
+                    key: ephemeral_pub,
+                },
+                model::PackageEntry::Release {
+                    version: Version::new(1, 1, 0),
+                    content: HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
+                },
+            ],
+        };
+        let envelope2 = ProtoEnvelope::signed_contents(&ephemeral_priv, record2).unwrap();
+        assert!(matches!(
+            state.clone().validate(&envelope2).unwrap_err(),
+            ValidationError::IdentityKeyAlreadyKnown { .. }
+        ));
+
+        // An identity not matching the pattern is not authorized
+        let (other_pub, other_priv) = generate_p256_pair();
+        let record3 = model::PackageRecord {
+            prev: Some(RecordId::package_record::<Sha256>(&envelope1)),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp1 + Duration::from_secs(1),
+            entries: vec![model::PackageEntry::Identity {
+                issuer: issuer.to_string(),
+                subject: "repo:other/foo".to_string(),
+                key: other_pub,
+            }],
+        };
+        let envelope3 = ProtoEnvelope::signed_contents(&other_priv, record3).unwrap();
+        assert!(matches!(
+            state.validate(&envelope3).unwrap_err(),
+            ValidationError::IdentityNotAuthorized { .. }
+        ));
+    }
+
     #[test]
     fn test_rollback() {
         let (alice_pub, alice_priv) = generate_p256_pair();
@@ -761,6 +1060,8 @@ mod tests {
                 IndexSet::from([model::Permission::Release, model::Permission::Yank]),
             )]),
             keys: IndexMap::from([(alice_id, alice_pub)]),
+            identity_permissions: Vec::default(),
+            identities: IndexMap::default(),
         };
 
         assert_eq!(state, expected);
@@ -34,9 +34,9 @@ pub struct ProtoEnvelope<Contents> {
 }
 
 impl<Contents> ProtoEnvelope<Contents> {
-    /// Create an envelope for some contents using a signature.
+    /// Create an envelope for some contents signed by the given signer.
     pub fn signed_contents(
-        private_key: &signing::PrivateKey,
+        signer: &(impl signing::Signer + ?Sized),
         contents: Contents,
     ) -> Result<Self, signing::SignatureError>
     where
@@ -44,8 +44,8 @@ impl<Contents> ProtoEnvelope<Contents> {
     {
         let content_bytes: Vec<u8> = contents.encode();
 
-        let key_id = private_key.public_key().fingerprint();
-        let signature = contents.sign(private_key)?;
+        let key_id = signer.public_key().fingerprint();
+        let signature = contents.sign(signer)?;
         Ok(ProtoEnvelope {
             contents,
             content_bytes,
@@ -136,6 +136,24 @@ pub struct ProtoEnvelopeBody {
     signature: signing::Signature,
 }
 
+impl ProtoEnvelopeBody {
+    pub(crate) fn to_protobuf(&self) -> protobuf::Envelope {
+        protobuf::Envelope {
+            contents: self.content_bytes.clone(),
+            key_id: self.key_id.to_string(),
+            signature: self.signature.to_string(),
+        }
+    }
+
+    pub(crate) fn from_protobuf(envelope: protobuf::Envelope) -> Result<Self, ParseEnvelopeError> {
+        Ok(Self {
+            content_bytes: envelope.contents,
+            key_id: envelope.key_id.into(),
+            signature: envelope.signature.parse()?,
+        })
+    }
+}
+
 impl<Content> TryFrom<ProtoEnvelopeBody> for ProtoEnvelope<Content>
 where
     Content: Decode,
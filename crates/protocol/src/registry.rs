@@ -1,12 +1,14 @@
-use crate::{operator::OperatorRecord, package::PackageRecord, ProtoEnvelope};
+use crate::{operator::OperatorRecord, package::PackageRecord, ProtoEnvelope, SerdeEnvelope};
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 use std::time::SystemTime;
 use warg_crypto::hash::{AnyHash, Hash, HashAlgorithm, SupportedDigest};
 use warg_crypto::prefix::VisitPrefixEncode;
-use warg_crypto::{prefix, ByteVisitor, Signable, VisitBytes};
+use warg_crypto::signing::{KeyID, PrivateKey, PublicKey, Signature, SignatureError};
+use warg_crypto::{prefix, ByteVisitor, Encode, Signable, VisitBytes};
 use wasmparser::names::KebabStr;
 
 /// Type alias for registry log index
@@ -81,6 +83,81 @@ impl VisitBytes for TimestampedCheckpoint {
     }
 }
 
+/// A witness cosignature of a timestamped checkpoint.
+///
+/// A witness cosigns a checkpoint after verifying that it is consistent with
+/// the last checkpoint the witness saw.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointCosignature {
+    /// The hash of the witness key that cosigned the checkpoint.
+    pub key_id: KeyID,
+    /// The cosignature of the checkpoint.
+    pub signature: Signature,
+}
+
+impl CheckpointCosignature {
+    const PREFIX: &'static [u8] = b"WARG-CHECKPOINT-COSIGNATURE-V0";
+
+    /// Cosigns a checkpoint with the given witness key.
+    pub fn sign(
+        private_key: &PrivateKey,
+        ts_checkpoint: &TimestampedCheckpoint,
+    ) -> Result<Self, SignatureError> {
+        let prefixed_content = [Self::PREFIX, b":", ts_checkpoint.encode().as_slice()].concat();
+        Ok(Self {
+            key_id: private_key.public_key().fingerprint(),
+            signature: private_key.sign(&prefixed_content)?,
+        })
+    }
+
+    /// Verifies the cosignature of a checkpoint with the given witness key.
+    pub fn verify(
+        &self,
+        public_key: &PublicKey,
+        ts_checkpoint: &TimestampedCheckpoint,
+    ) -> Result<(), SignatureError> {
+        if public_key.fingerprint() != self.key_id {
+            return Err(SignatureError::new());
+        }
+
+        let prefixed_content = [Self::PREFIX, b":", ts_checkpoint.encode().as_slice()].concat();
+        public_key.verify(&prefixed_content, &self.signature)
+    }
+}
+
+/// A checkpoint signed by the registry operator along with the cosignatures
+/// of any witnesses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CosignedCheckpoint {
+    /// The checkpoint signed by the registry operator.
+    #[serde(flatten)]
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The witness cosignatures of the checkpoint.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cosignatures: Vec<CheckpointCosignature>,
+}
+
+impl CosignedCheckpoint {
+    /// Counts the distinct given witnesses with a valid cosignature of the
+    /// checkpoint.
+    ///
+    /// A witness listed more than once is only counted once.
+    pub fn witnessed_by<'a>(&self, witnesses: impl IntoIterator<Item = &'a PublicKey>) -> usize {
+        let mut counted = HashSet::new();
+        witnesses
+            .into_iter()
+            .filter(|key| counted.insert(key.fingerprint()))
+            .filter(|key| {
+                self.cosignatures
+                    .iter()
+                    .any(|c| c.verify(key, self.checkpoint.as_ref()).is_ok())
+            })
+            .count()
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct MapLeaf {
     pub record_id: RecordId,
@@ -319,6 +396,46 @@ mod tests {
     use warg_crypto::hash::Sha256;
     use warg_transparency::map::Map;
 
+    #[test]
+    fn checkpoint_cosignature() {
+        let operator_key = PrivateKey::decode(
+            "ecdsa-p256:I+UlDo0HxyBBFeelhPPWmD+LnklOpqZDkrFP5VduASk=".to_string(),
+        )
+        .unwrap();
+        let witness_key = PrivateKey::decode(
+            "ecdsa-p256:2CV1EpLaSYEn4In4OAEDAj5O4Hzu8AFAxgHXuG310Ew=".to_string(),
+        )
+        .unwrap();
+
+        let ts_checkpoint = TimestampedCheckpoint {
+            checkpoint: Checkpoint {
+                log_root: Hash::<Sha256>::of("log").into(),
+                log_length: 1,
+                map_root: Hash::<Sha256>::of("map").into(),
+            },
+            timestamp: 0,
+        };
+
+        let cosignature = CheckpointCosignature::sign(&witness_key, &ts_checkpoint).unwrap();
+        let mut cosigned = CosignedCheckpoint {
+            checkpoint: SerdeEnvelope::signed_contents(&operator_key, ts_checkpoint).unwrap(),
+            cosignatures: vec![cosignature],
+        };
+
+        let witnesses = [witness_key.public_key(), operator_key.public_key()];
+        assert_eq!(cosigned.witnessed_by(&witnesses), 1);
+
+        // A witness listed more than once is counted once
+        let witnesses = [witness_key.public_key(), witness_key.public_key()];
+        assert_eq!(cosigned.witnessed_by(&witnesses), 1);
+
+        // A cosignature of a different checkpoint is not counted
+        let mut other = cosigned.checkpoint.as_ref().clone();
+        other.timestamp = 1;
+        cosigned.cosignatures = vec![CheckpointCosignature::sign(&witness_key, &other).unwrap()];
+        assert_eq!(cosigned.witnessed_by(&witnesses), 0);
+    }
+
     #[test]
     fn log_id() {
         let first = Map::<Sha256, LogId, &'static str>::default();
@@ -28,16 +28,16 @@ impl<Contents> SerdeEnvelope<Contents> {
         }
     }
 
-    /// Create an envelope for some contents using a signature.
+    /// Create an envelope for some contents signed by the given signer.
     pub fn signed_contents(
-        private_key: &signing::PrivateKey,
+        signer: &(impl signing::Signer + ?Sized),
         contents: Contents,
     ) -> Result<Self, signing::SignatureError>
     where
         Contents: Signable,
     {
-        let key_id = private_key.public_key().fingerprint();
-        let signature = contents.sign(private_key)?;
+        let key_id = signer.public_key().fingerprint();
+        let signature = contents.sign(signer)?;
         Ok(SerdeEnvelope {
             contents,
             key_id,
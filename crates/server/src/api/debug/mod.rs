@@ -77,6 +77,8 @@ struct EntryInfo {
     key: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     key_id: Option<KeyID>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    identity: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     permissions: Vec<Permission>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -158,6 +160,34 @@ async fn get_package_info(
                             version: Some(version.clone()),
                             ..Default::default()
                         },
+                        GrantIdentity {
+                            identity,
+                            permissions,
+                        } => EntryInfo {
+                            kind: "grant-identity",
+                            identity: Some(identity.to_string()),
+                            permissions: permissions.clone(),
+                            ..Default::default()
+                        },
+                        RevokeIdentity {
+                            identity,
+                            permissions,
+                        } => EntryInfo {
+                            kind: "revoke-identity",
+                            identity: Some(identity.to_string()),
+                            permissions: permissions.clone(),
+                            ..Default::default()
+                        },
+                        Identity {
+                            issuer,
+                            subject,
+                            key,
+                        } => EntryInfo {
+                            kind: "identity",
+                            key: Some(key.to_string()),
+                            identity: Some(format!("{issuer}#{subject}")),
+                            ..Default::default()
+                        },
                         _ => EntryInfo {
                             kind: "UNKNOWN",
                             ..Default::default()
@@ -1,8 +1,8 @@
 use crate::{
-    policy::{content::ContentPolicy, record::RecordPolicy},
-    services::CoreService,
+    policy::{content::ContentPolicy, identity::IdentityVerifier, record::RecordPolicy},
+    services::{CoreService, ProxyService},
 };
-use axum::{body::Body, http::Request, Router};
+use axum::{body::Body, http::Request, routing::get, Json, Router};
 use std::{path::PathBuf, sync::Arc};
 use tower::ServiceBuilder;
 use tower_http::{
@@ -13,6 +13,8 @@ use tower_http::{
 };
 use tracing::{Level, Span};
 use url::Url;
+use warg_api::{RegistryDiscovery, DISCOVERY_PATH};
+use warg_crypto::signing::PublicKey;
 
 pub mod v1;
 
@@ -20,14 +22,22 @@ pub mod v1;
 pub mod debug;
 
 /// Creates the router for the API.
+#[allow(clippy::too_many_arguments)]
 pub fn create_router(
     content_base_url: Url,
     core: CoreService,
     temp_dir: PathBuf,
     files_dir: PathBuf,
+    ledger_dir: PathBuf,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witness_keys: Option<Vec<PublicKey>>,
+    identity_verifier: Arc<IdentityVerifier>,
+    mirror_of: Option<Url>,
+    proxy: Option<ProxyService>,
+    discovery: RegistryDiscovery,
 ) -> Router {
+    let max_content_size = discovery.max_content_size;
     let router = Router::new();
     #[cfg(feature = "debug")]
     let router = router.nest("/debug", debug::Config::new(core.clone()).into_router());
@@ -39,11 +49,22 @@ pub fn create_router(
                 core,
                 temp_dir,
                 files_dir.clone(),
+                ledger_dir.clone(),
                 content_policy,
                 record_policy,
+                witness_keys,
+                identity_verifier,
+                mirror_of,
+                proxy,
+                max_content_size,
             ),
         )
+        .route(
+            &format!("/{DISCOVERY_PATH}"),
+            get(move || async move { Json(discovery) }),
+        )
         .nest_service("/content", ServeDir::new(files_dir))
+        .nest_service("/ledger", ServeDir::new(ledger_dir))
         .layer(
             ServiceBuilder::new()
                 .layer(
@@ -1,17 +1,35 @@
-use super::{Json, Path, RegistryHeader};
+use super::{Json, Path, ProxiedRegistryHeader};
+use crate::services::ProxyService;
+use anyhow::{anyhow, bail, Result};
 use axum::{
     debug_handler, extract::State, http::StatusCode, response::IntoResponse, routing::get, Router,
 };
+use futures::StreamExt;
 use indexmap::IndexMap;
 use std::path::PathBuf;
+use tempfile::NamedTempFile;
+use tokio::io::AsyncWriteExt;
 use url::Url;
-use warg_api::v1::content::{ContentError, ContentSource, ContentSourcesResponse};
+use warg_api::v1::{
+    content::{ContentError, ContentSource, ContentSourcesResponse},
+    paths,
+};
 use warg_crypto::hash::AnyHash;
 
 #[derive(Clone)]
 pub struct Config {
     content_base_url: Url,
     files_dir: PathBuf,
+    upstream: Option<Upstream>,
+    proxy: Option<(ProxyService, PathBuf)>,
+}
+
+// The registry from which content missing locally is fetched
+#[derive(Clone)]
+struct Upstream {
+    url: Url,
+    client: reqwest::Client,
+    temp_dir: PathBuf,
 }
 
 impl Config {
@@ -19,9 +37,47 @@ impl Config {
         Self {
             content_base_url,
             files_dir,
+            upstream: None,
+            proxy: None,
         }
     }
 
+    /// Fetches content missing locally from the given upstream registry,
+    /// caching it in the files directory.
+    pub fn with_upstream(mut self, mut url: Url, temp_dir: PathBuf) -> Self {
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{path}/", path = url.path()));
+        }
+
+        self.upstream = Some(Upstream {
+            url,
+            client: reqwest::Client::new(),
+            temp_dir,
+        });
+        self
+    }
+
+    /// Fetches content of proxied packages missing locally from their
+    /// upstream registries, caching it in the files directory.
+    pub fn with_proxy(mut self, proxy: ProxyService, temp_dir: PathBuf) -> Self {
+        self.proxy = Some((proxy, temp_dir));
+        self
+    }
+
+    // Gets the registry from which the given content missing locally is fetched
+    async fn upstream(&self, digest: &AnyHash) -> Option<Upstream> {
+        if let Some(upstream) = &self.upstream {
+            return Some(upstream.clone());
+        }
+
+        let (proxy, temp_dir) = self.proxy.as_ref()?;
+        Some(Upstream {
+            url: proxy.content_upstream(digest).await?,
+            client: proxy.client().clone(),
+            temp_dir: temp_dir.clone(),
+        })
+    }
+
     pub fn into_router(self) -> Router {
         Router::new()
             .route("/:digest", get(get_content))
@@ -48,6 +104,54 @@ impl Config {
             .unwrap()
             .to_string()
     }
+
+    // Fetches content from the upstream registry, returning `false` if the
+    // upstream registry does not have it
+    async fn fetch_upstream_content(&self, upstream: &Upstream, digest: &AnyHash) -> Result<bool> {
+        let response = upstream
+            .client
+            .get(upstream.url.join(&paths::content_sources(digest))?)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        let mut sources = response
+            .error_for_status()?
+            .json::<ContentSourcesResponse>()
+            .await?
+            .content_sources;
+        let ContentSource::HttpGet { url, .. } = sources
+            .swap_remove(digest)
+            .and_then(|sources| sources.into_iter().next())
+            .ok_or_else(|| anyhow!("upstream registry has no sources for `{digest}`"))?;
+
+        let mut stream = upstream
+            .client
+            .get(upstream.url.join(&url)?)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes_stream();
+
+        let tmp_path = NamedTempFile::new_in(&upstream.temp_dir)?.into_temp_path();
+        let mut tmp_file = tokio::fs::File::create(&tmp_path).await?;
+        let mut hasher = digest.algorithm().hasher();
+        while let Some(chunk) = stream.next().await.transpose()? {
+            hasher.update(&chunk);
+            tmp_file.write_all(&chunk).await?;
+        }
+        tmp_file.flush().await?;
+
+        let result = hasher.finalize();
+        if &result != digest {
+            bail!("upstream content digest `{result}` does not match expected digest `{digest}`");
+        }
+
+        tmp_path.persist(self.content_path(digest))?;
+        Ok(true)
+    }
 }
 
 struct ContentApiError(ContentError);
@@ -62,10 +166,34 @@ impl IntoResponse for ContentApiError {
 async fn get_content(
     State(config): State<Config>,
     Path(digest): Path<AnyHash>,
-    RegistryHeader(_registry_header): RegistryHeader,
+    ProxiedRegistryHeader(registry_header): ProxiedRegistryHeader,
 ) -> Result<Json<ContentSourcesResponse>, ContentApiError> {
+    // Content is addressed by digest, so a proxy serves it regardless of the registry
+    if registry_header.is_some() && config.proxy.is_none() {
+        return Err(ContentApiError(ContentError::Message {
+            status: StatusCode::NOT_IMPLEMENTED.as_u16(),
+            message: "`Warg-Registry` header is not supported".into(),
+        }));
+    }
+
     if !config.content_present(&digest) {
-        return Err(ContentApiError(ContentError::ContentDigestNotFound(digest)));
+        let Some(upstream) = config.upstream(&digest).await else {
+            return Err(ContentApiError(ContentError::ContentDigestNotFound(digest)));
+        };
+
+        match config.fetch_upstream_content(&upstream, &digest).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err(ContentApiError(ContentError::ContentDigestNotFound(digest)));
+            }
+            Err(e) => {
+                tracing::error!("failed to fetch content `{digest}` from upstream: {e:?}");
+                return Err(ContentApiError(ContentError::Message {
+                    status: StatusCode::BAD_GATEWAY.as_u16(),
+                    message: "failed to fetch content from the upstream registry".into(),
+                }));
+            }
+        }
     }
 
     let mut content_sources = IndexMap::with_capacity(1);
@@ -1,43 +1,65 @@
-use super::{Json, RegistryHeader};
+use super::{Json, Path, ProxiedRegistryHeader, RegistryHeader};
 use crate::datastore::DataStoreError;
-use crate::services::CoreService;
+use crate::services::{CoreService, CoreServiceError, ProxyError, ProxyService};
 use axum::http::StatusCode;
 use axum::{
     debug_handler,
-    extract::State,
+    extract::{Query, State},
     response::IntoResponse,
     routing::{get, post},
     Router,
 };
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
+use std::time::Duration;
+use tokio::time::Instant;
 use warg_api::v1::fetch::{
-    FetchError, FetchLogsRequest, FetchLogsResponse, FetchPackageNamesRequest,
-    FetchPackageNamesResponse, PublishedRecord,
+    FetchCheckpointsQuery, FetchCheckpointsResponse, FetchError, FetchLogsRequest,
+    FetchLogsResponse, FetchPackageNamesRequest, FetchPackageNamesResponse, FetchPackageResponse,
+    PublishedRecord, WatchRequest, WatchResponse,
 };
 use warg_crypto::hash::{AnyHash, Sha256};
-use warg_protocol::registry::{LogId, RecordId, TimestampedCheckpoint};
+use warg_protocol::registry::{LogId, RecordId, RegistryIndex, RegistryLen, TimestampedCheckpoint};
 use warg_protocol::SerdeEnvelope;
 
 const DEFAULT_RECORDS_LIMIT: u16 = 100;
 const MAX_RECORDS_LIMIT: u16 = 1000;
 
+const DEFAULT_CHECKPOINTS_LIMIT: u16 = 100;
+const MAX_CHECKPOINTS_LIMIT: u16 = 1000;
+
 const MAX_PACKAGE_NAMES_LIMIT: usize = 1000;
 
+const WATCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_WATCH_LEAFS: RegistryLen = 1000;
+
 #[derive(Clone)]
 pub struct Config {
     core_service: CoreService,
+    proxy: Option<ProxyService>,
 }
 
 impl Config {
     pub fn new(core_service: CoreService) -> Self {
-        Self { core_service }
+        Self {
+            core_service,
+            proxy: None,
+        }
+    }
+
+    /// Serves packages of imported namespaces from their upstream registries.
+    pub fn with_proxy(mut self, proxy: ProxyService) -> Self {
+        self.proxy = Some(proxy);
+        self
     }
 
     pub fn into_router(self) -> Router {
         Router::new()
             .route("/checkpoint", get(fetch_checkpoint))
+            .route("/checkpoints", get(fetch_checkpoints))
+            .route("/package/:log_id", get(fetch_package))
             .route("/logs", post(fetch_logs))
             .route("/names", post(fetch_package_names))
+            .route("/watch", post(watch))
             .with_state(self)
     }
 }
@@ -75,6 +97,40 @@ impl From<DataStoreError> for FetchApiError {
     }
 }
 
+impl From<CoreServiceError> for FetchApiError {
+    fn from(e: CoreServiceError) -> Self {
+        match e {
+            CoreServiceError::CheckpointNotFound(log_length) => {
+                Self(FetchError::CheckpointNotFound(log_length))
+            }
+            CoreServiceError::DataStore(e) => e.into(),
+            e => {
+                tracing::error!("unexpected core service error: {e}");
+                Self(FetchError::Message {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "an error occurred while processing the request".into(),
+                })
+            }
+        }
+    }
+}
+
+impl From<ProxyError> for FetchApiError {
+    fn from(e: ProxyError) -> Self {
+        match e {
+            ProxyError::RegistryNotImported(_) => Self::bad_request(e),
+            ProxyError::DataStore(e) => e.into(),
+            e => {
+                tracing::error!("failed to proxy package: {e}");
+                Self(FetchError::Message {
+                    status: StatusCode::BAD_GATEWAY.as_u16(),
+                    message: "failed to fetch the package from the upstream registry".into(),
+                })
+            }
+        }
+    }
+}
+
 impl IntoResponse for FetchApiError {
     fn into_response(self) -> axum::response::Response {
         (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
@@ -174,6 +230,150 @@ async fn fetch_checkpoint(
     ))
 }
 
+#[debug_handler]
+async fn fetch_checkpoints(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Query(query): Query<FetchCheckpointsQuery>,
+) -> Result<Json<FetchCheckpointsResponse>, FetchApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_CHECKPOINTS_LIMIT);
+    if limit == 0 || limit > MAX_CHECKPOINTS_LIMIT {
+        return Err(FetchApiError::bad_request(format!(
+            "invalid checkpoints limit value `{limit}`: must be between 1 and {MAX_CHECKPOINTS_LIMIT}"
+        )));
+    }
+
+    // Fetch one more than the limit to determine if there are more checkpoints
+    let mut checkpoints = config
+        .core_service
+        .store()
+        .get_checkpoints(query.since, limit + 1)
+        .await?;
+    let more = checkpoints.len() > limit as usize;
+    checkpoints.truncate(limit as usize);
+
+    Ok(Json(FetchCheckpointsResponse { more, checkpoints }))
+}
+
+#[debug_handler]
+async fn fetch_package(
+    State(config): State<Config>,
+    Path(log_id): Path<LogId>,
+    ProxiedRegistryHeader(registry_header): ProxiedRegistryHeader,
+) -> Result<Json<FetchPackageResponse>, FetchApiError> {
+    if let Some(registry) = registry_header {
+        let Some(proxy) = &config.proxy else {
+            return Err(FetchApiError(FetchError::Message {
+                status: StatusCode::NOT_IMPLEMENTED.as_u16(),
+                message: "`Warg-Registry` header is not supported".into(),
+            }));
+        };
+
+        return match proxy.fetch_package(Some(&registry), &log_id).await? {
+            Some(response) => Ok(Json(response)),
+            None => Err(FetchApiError(FetchError::LogNotFound(log_id))),
+        };
+    }
+
+    match (local_package(&config, &log_id).await, &config.proxy) {
+        // Packages of imported namespaces are only found upstream
+        (Err(FetchApiError(FetchError::LogNotFound(_))), Some(proxy)) => {
+            match proxy.fetch_package(None, &log_id).await? {
+                Some(response) => Ok(Json(response)),
+                None => Err(FetchApiError(FetchError::LogNotFound(log_id))),
+            }
+        }
+        (result, _) => result.map(Json),
+    }
+}
+
+/// Gets the complete logs of a package published to this registry.
+async fn local_package(
+    config: &Config,
+    log_id: &LogId,
+) -> Result<FetchPackageResponse, FetchApiError> {
+    let store = config.core_service.store();
+    let checkpoint = store.get_latest_checkpoint().await?;
+    let log_length = checkpoint.as_ref().checkpoint.log_length;
+
+    // Fetch the complete operator and package logs as of the checkpoint
+    let operator_log_id = LogId::operator_log::<Sha256>();
+    let mut operator = Vec::new();
+    let mut operator_head = None;
+    loop {
+        let records = store
+            .get_operator_records(
+                &operator_log_id,
+                log_length,
+                operator.last().map(|(id, _)| id),
+                MAX_RECORDS_LIMIT,
+            )
+            .await?;
+        let more = records.len() == MAX_RECORDS_LIMIT as usize;
+        for envelope in records {
+            operator_head = Some(envelope.registry_index);
+            operator.push((
+                RecordId::operator_record::<Sha256>(&envelope.envelope),
+                envelope.into(),
+            ));
+        }
+        if !more {
+            break;
+        }
+    }
+
+    let mut records = Vec::new();
+    let mut package_head = None;
+    loop {
+        let page = store
+            .get_package_records(
+                log_id,
+                log_length,
+                records.last().map(|(id, _)| id),
+                MAX_RECORDS_LIMIT,
+            )
+            .await?;
+        let more = page.len() == MAX_RECORDS_LIMIT as usize;
+        for envelope in page {
+            package_head = Some(envelope.registry_index);
+            records.push((
+                RecordId::package_record::<Sha256>(&envelope.envelope),
+                envelope.into(),
+            ));
+        }
+        if !more {
+            break;
+        }
+    }
+
+    let (Some(operator_head), Some(package_head)) = (operator_head, package_head) else {
+        return Err(FetchApiError(FetchError::LogNotFound(log_id.clone())));
+    };
+
+    let map = config
+        .core_service
+        .map_inclusion_proofs(log_length, &[operator_head, package_head], true)
+        .await?;
+
+    // use the record ID as the fetch token
+    let published = |records: Vec<(RecordId, _)>| {
+        records
+            .into_iter()
+            .map(|(id, envelope)| PublishedRecord {
+                envelope,
+                fetch_token: id.to_string(),
+            })
+            .collect()
+    };
+
+    Ok(FetchPackageResponse {
+        checkpoint,
+        operator: published(operator),
+        records: published(records),
+        map: map.encode(),
+    })
+}
+
 #[debug_handler]
 async fn fetch_package_names(
     State(config): State<Config>,
@@ -194,3 +394,69 @@ async fn fetch_package_names(
 
     Ok(Json(FetchPackageNamesResponse { packages }))
 }
+
+#[debug_handler]
+async fn watch(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<WatchRequest<'static>>,
+) -> Result<Json<WatchResponse>, FetchApiError> {
+    let deadline = Instant::now() + WATCH_TIMEOUT;
+    let watched: IndexSet<LogId> = body.packages.iter().cloned().collect();
+    let mut changed = IndexSet::new();
+    let mut from = body.log_length;
+
+    while let Some(log_length) = config
+        .core_service
+        .wait_for_checkpoint(from, deadline)
+        .await
+    {
+        if watched.is_empty() {
+            break;
+        }
+
+        changed.extend(changed_logs(&config, &watched, from, log_length).await?);
+        from = log_length;
+        if !changed.is_empty() {
+            break;
+        }
+    }
+
+    let checkpoint = config.core_service.store().get_latest_checkpoint().await?;
+
+    // A checkpoint may have been stored after the wait completed
+    let log_length = checkpoint.as_ref().checkpoint.log_length;
+    if !watched.is_empty() && log_length > from {
+        changed.extend(changed_logs(&config, &watched, from, log_length).await?);
+    }
+
+    Ok(Json(WatchResponse {
+        checkpoint,
+        packages: changed.into_iter().collect(),
+    }))
+}
+
+/// Gets the watched logs with records between the given log lengths.
+///
+/// If the range is too large to inspect, all watched logs are returned.
+async fn changed_logs(
+    config: &Config,
+    watched: &IndexSet<LogId>,
+    from: RegistryLen,
+    to: RegistryLen,
+) -> Result<Vec<LogId>, FetchApiError> {
+    if to.saturating_sub(from) > MAX_WATCH_LEAFS {
+        return Ok(watched.iter().cloned().collect());
+    }
+
+    let indexes: Vec<RegistryIndex> = (from..to).collect();
+    Ok(config
+        .core_service
+        .store()
+        .get_log_leafs_with_registry_index(&indexes)
+        .await?
+        .into_iter()
+        .filter(|leaf| watched.contains(&leaf.log_id))
+        .map(|leaf| leaf.log_id)
+        .collect())
+}
@@ -0,0 +1,108 @@
+use super::{Json, RegistryHeader};
+use crate::datastore::DataStoreError;
+use crate::services::CoreService;
+use axum::{
+    debug_handler, extract::State, http::StatusCode, response::IntoResponse, routing::post, Router,
+};
+use warg_api::v1::gossip::{GossipError, GossipRequest, GossipResponse};
+use warg_crypto::hash::Sha256;
+use warg_protocol::registry::LogId;
+
+#[derive(Clone)]
+pub struct Config {
+    core_service: CoreService,
+}
+
+impl Config {
+    pub fn new(core_service: CoreService) -> Self {
+        Self { core_service }
+    }
+
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route("/checkpoint", post(gossip_checkpoint))
+            .with_state(self)
+    }
+}
+
+struct GossipApiError(GossipError);
+
+impl From<DataStoreError> for GossipApiError {
+    fn from(e: DataStoreError) -> Self {
+        tracing::error!("unexpected data store error: {e}");
+
+        Self(GossipError::Message {
+            status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            message: "an error occurred while processing the request".into(),
+        })
+    }
+}
+
+impl IntoResponse for GossipApiError {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
+    }
+}
+
+/// Exchanges a checkpoint observed by a peer for the latest checkpoint.
+///
+/// The gossiped checkpoint must be signed by a key authorized in the
+/// operator log and must match this registry's checkpoint at the same log
+/// length; the peer is expected to prove consistency between the two
+/// checkpoints.
+#[debug_handler]
+async fn gossip_checkpoint(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<GossipRequest>,
+) -> Result<Json<GossipResponse>, GossipApiError> {
+    match config
+        .core_service
+        .store()
+        .verify_timestamped_checkpoint_signature(&LogId::operator_log::<Sha256>(), &body.checkpoint)
+        .await
+    {
+        Ok(_) => {}
+        Err(
+            DataStoreError::UnknownKey(_)
+            | DataStoreError::SignatureVerificationFailed(_)
+            | DataStoreError::KeyUnauthorized(_),
+        ) => {
+            return Err(GossipApiError(GossipError::InvalidCheckpointSignature(
+                body.checkpoint.key_id().clone(),
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let core = &config.core_service;
+    let gossiped = &body.checkpoint.as_ref().checkpoint;
+    let log_length = gossiped.log_length;
+    match core.store().get_checkpoint(log_length).await {
+        Ok(local) if &local.as_ref().checkpoint != gossiped => {
+            return Err(GossipApiError(GossipError::ConflictingCheckpoint {
+                log_length,
+                checkpoint: Some(Box::new(local)),
+            }));
+        }
+        Ok(_) => {}
+        Err(DataStoreError::CheckpointNotFound(_)) => {
+            // A mirror only stores the checkpoints it synchronized, so fall back
+            // to the log root; the operator stores every checkpoint it signs
+            let conflicting = match core.log_root_at(log_length).await {
+                Some(log_root) => !core.is_mirror() || log_root != gossiped.log_root,
+                None => false,
+            };
+            if conflicting {
+                return Err(GossipApiError(GossipError::ConflictingCheckpoint {
+                    log_length,
+                    checkpoint: None,
+                }));
+            }
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    let checkpoint = core.store().get_latest_checkpoint().await?;
+    Ok(Json(GossipResponse { checkpoint }))
+}
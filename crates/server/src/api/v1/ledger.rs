@@ -3,32 +3,82 @@ use crate::datastore::DataStoreError;
 use crate::services::CoreService;
 use axum::http::StatusCode;
 use axum::{
-    debug_handler, extract::State, response::IntoResponse, response::Response, routing::get, Router,
+    debug_handler,
+    extract::{Query, State},
+    response::IntoResponse,
+    response::Response,
+    routing::get,
+    Router,
 };
+use std::{io::Write, path::PathBuf};
+use tempfile::NamedTempFile;
+use url::Url;
 use warg_api::v1::ledger::{
-    LedgerError, LedgerSource, LedgerSourceContentType, LedgerSourcesResponse,
+    LedgerError, LedgerSource, LedgerSourceContentType, LedgerSourcesQuery, LedgerSourcesResponse,
+};
+use warg_crypto::hash::{HashAlgorithm, Sha256};
+use warg_protocol::{
+    ledger::LedgerRecord,
+    registry::{LogId, LogLeaf, RegistryIndex},
+    PublishedProtoEnvelopeBody,
 };
-use warg_crypto::hash::HashAlgorithm;
-use warg_protocol::registry::RegistryIndex;
 
 const MAX_LEDGER_RECORDS_LIMIT: usize = 1000;
 
 #[derive(Clone)]
 pub struct Config {
     core_service: CoreService,
+    content_base_url: Url,
+    segments_dir: PathBuf,
 }
 
 impl Config {
-    pub fn new(core_service: CoreService) -> Self {
-        Self { core_service }
+    pub fn new(core_service: CoreService, content_base_url: Url, segments_dir: PathBuf) -> Self {
+        Self {
+            core_service,
+            content_base_url,
+            segments_dir,
+        }
     }
 
     pub fn into_router(self) -> Router {
         Router::new()
             .route("/", get(get_ledger_sources))
             .route("/records/:start", get(get_ledger_records))
+            .route("/envelopes/:start", get(get_ledger_envelopes))
             .with_state(self)
     }
+
+    fn segment_file_name(&self, start: RegistryIndex) -> String {
+        format!("{start}.envelopes")
+    }
+
+    fn segment_path(&self, start: RegistryIndex) -> PathBuf {
+        self.segments_dir.join(self.segment_file_name(start))
+    }
+
+    fn persist_segment(&self, start: RegistryIndex, segment: &[u8]) -> std::io::Result<()> {
+        let mut file = NamedTempFile::new_in(&self.segments_dir)?;
+        file.write_all(segment)?;
+        file.persist(self.segment_path(start))?;
+        Ok(())
+    }
+
+    // Complete segments never change, so they are served as static files
+    // once they have been written
+    fn envelopes_url(&self, start: RegistryIndex) -> String {
+        if self.segment_path(start).is_file() {
+            return self
+                .content_base_url
+                .join("ledger/")
+                .unwrap()
+                .join(&self.segment_file_name(start))
+                .unwrap()
+                .to_string();
+        }
+
+        format!("v1/ledger/envelopes/{start}")
+    }
 }
 
 struct LedgerApiError(LedgerError);
@@ -53,8 +103,10 @@ impl IntoResponse for LedgerApiError {
 #[debug_handler]
 async fn get_ledger_sources(
     State(config): State<Config>,
+    Query(query): Query<LedgerSourcesQuery>,
     RegistryHeader(_registry_header): RegistryHeader,
 ) -> Result<Json<LedgerSourcesResponse>, LedgerApiError> {
+    let content_type = query.content_type.unwrap_or_default();
     let log_length = config
         .core_service
         .store()
@@ -76,9 +128,12 @@ async fn get_ledger_sources(
             LedgerSource {
                 first_registry_index: start_index,
                 last_registry_index: end_index,
-                url: format!("v1/ledger/records/{start_index}"),
+                url: match content_type {
+                    LedgerSourceContentType::Packed => format!("v1/ledger/records/{start_index}"),
+                    LedgerSourceContentType::Envelopes => config.envelopes_url(start_index),
+                },
                 accept_ranges: false,
-                content_type: LedgerSourceContentType::Packed,
+                content_type,
             }
         })
         .collect::<Vec<LedgerSource>>();
@@ -117,3 +172,71 @@ async fn get_ledger_records(
         .body(body.into())
         .unwrap())
 }
+
+#[debug_handler]
+async fn get_ledger_envelopes(
+    State(config): State<Config>,
+    Path(start): Path<RegistryIndex>,
+    RegistryHeader(_registry_header): RegistryHeader,
+) -> Result<Response, LedgerApiError> {
+    let store = config.core_service.store();
+    let log_length = store
+        .get_latest_checkpoint()
+        .await?
+        .into_contents()
+        .checkpoint
+        .log_length;
+
+    let log_leafs = store
+        .get_log_leafs_starting_with_registry_index(start, MAX_LEDGER_RECORDS_LIMIT)
+        .await?;
+
+    let operator_log_id = LogId::operator_log::<Sha256>();
+    let mut body: Vec<u8> = Vec::new();
+    for (registry_index, leaf) in log_leafs.iter() {
+        let LogLeaf { log_id, record_id } = leaf;
+        let envelope = if log_id == &operator_log_id {
+            store
+                .get_operator_record(log_id, record_id)
+                .await?
+                .envelope
+                .into()
+        } else {
+            store
+                .get_package_record(log_id, record_id)
+                .await?
+                .envelope
+                .into()
+        };
+
+        LedgerRecord {
+            leaf: leaf.clone(),
+            envelope: PublishedProtoEnvelopeBody {
+                envelope,
+                registry_index: *registry_index,
+            },
+        }
+        .encode_to(&mut body);
+    }
+
+    // Persist complete, checkpointed segments so they can be served statically;
+    // only segments advertised by the ledger sources are persisted
+    if start % MAX_LEDGER_RECORDS_LIMIT == 0
+        && log_leafs.len() == MAX_LEDGER_RECORDS_LIMIT
+        && start + MAX_LEDGER_RECORDS_LIMIT <= log_length
+        && !config.segment_path(start).exists()
+    {
+        if let Err(e) = config.persist_segment(start, &body) {
+            tracing::warn!("failed to persist ledger segment {start}: {e}");
+        }
+    }
+
+    Ok(Response::builder()
+        .status(200)
+        .header(
+            axum::http::header::CONTENT_TYPE,
+            LedgerSourceContentType::Envelopes.as_str(),
+        )
+        .body(body.into())
+        .unwrap())
+}
@@ -1,6 +1,6 @@
 use crate::{
-    policy::{content::ContentPolicy, record::RecordPolicy},
-    services::CoreService,
+    policy::{content::ContentPolicy, identity::IdentityVerifier, record::RecordPolicy},
+    services::{CoreService, ProxyService},
 };
 use anyhow::Result;
 use axum::{
@@ -17,13 +17,17 @@ use serde::{Serialize, Serializer};
 use std::{path::PathBuf, str::FromStr, sync::Arc};
 use url::Url;
 use warg_api::v1::REGISTRY_HEADER_NAME;
+use warg_crypto::signing::PublicKey;
 
 pub mod content;
 pub mod fetch;
+pub mod gossip;
 pub mod ledger;
 pub mod monitor;
 pub mod package;
 pub mod proof;
+pub mod tile;
+pub mod witness;
 
 /// An extractor that wraps the JSON extractor of Axum.
 ///
@@ -117,6 +121,36 @@ where
     }
 }
 
+/// An extractor for the `Warg-Registry` header on routes that may proxy
+/// requests for imported namespaces.
+///
+/// Unlike [`RegistryHeader`], the header is always accepted; handlers must
+/// reject it if the server is not proxying imported namespaces.
+pub struct ProxiedRegistryHeader(Option<String>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ProxiedRegistryHeader
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(REGISTRY_HEADER_NAME) {
+            Some(value) => value
+                .to_str()
+                .map(|value| ProxiedRegistryHeader(Some(value.to_string())))
+                .map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        "`Warg-Registry` header is not a valid string",
+                    )
+                }),
+            None => Ok(ProxiedRegistryHeader(None)),
+        }
+    }
+}
+
 impl FromStr for RegistryHeader {
     type Err = std::convert::Infallible;
     fn from_str(src: &str) -> Result<Self, Self::Err> {
@@ -124,33 +158,62 @@ impl FromStr for RegistryHeader {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_router(
     content_base_url: Url,
     core: CoreService,
     temp_dir: PathBuf,
     files_dir: PathBuf,
+    ledger_dir: PathBuf,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witness_keys: Option<Vec<PublicKey>>,
+    identity_verifier: Arc<IdentityVerifier>,
+    mirror_of: Option<Url>,
+    proxy: Option<ProxyService>,
+    max_content_size: Option<u64>,
 ) -> Router {
     let proof_config = proof::Config::new(core.clone());
     let package_config = package::Config::new(
         core.clone(),
         files_dir.clone(),
-        temp_dir,
+        temp_dir.clone(),
         content_policy,
         record_policy,
+        identity_verifier,
     );
+    let package_config = match max_content_size {
+        Some(size) => package_config.with_max_content_size(size),
+        None => package_config,
+    };
     let fetch_config = fetch::Config::new(core.clone());
-    let content_config = content::Config::new(content_base_url, files_dir);
+    let content_config = content::Config::new(content_base_url.clone(), files_dir);
+    let content_config = match mirror_of {
+        Some(upstream) => content_config.with_upstream(upstream, temp_dir.clone()),
+        None => content_config,
+    };
+    let (fetch_config, content_config) = match proxy {
+        Some(proxy) => (
+            fetch_config.with_proxy(proxy.clone()),
+            content_config.with_proxy(proxy, temp_dir),
+        ),
+        None => (fetch_config, content_config),
+    };
+    let gossip_config = gossip::Config::new(core.clone());
     let monitor_config = monitor::Config::new(core.clone());
-    let ledger_config = ledger::Config::new(core);
+    let witness_config = witness::Config::new(core.clone(), witness_keys);
+    let tile_config = tile::Config::new(core.clone());
+    let ledger_config = ledger::Config::new(core, content_base_url, ledger_dir);
 
     Router::new()
         .nest("/content", content_config.into_router())
         .nest("/fetch", fetch_config.into_router())
+        .nest("/gossip", gossip_config.into_router())
         .nest("/ledger", ledger_config.into_router())
         .nest("/package", package_config.into_router())
         .nest("/proof", proof_config.into_router())
+        .nest("/tile", tile_config.into_router())
         .nest("/verify", monitor_config.into_router())
+        .nest("/witness", witness_config.into_router())
         .fallback(not_found)
 }
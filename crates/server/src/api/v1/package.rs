@@ -3,6 +3,7 @@ use crate::{
     datastore::{DataStoreError, RecordStatus},
     policy::{
         content::{ContentPolicy, ContentPolicyError},
+        identity::{IdentityError, IdentityVerifier},
         record::{RecordPolicy, RecordPolicyError},
     },
     services::CoreService,
@@ -40,6 +41,8 @@ pub struct Config {
     temp_dir: PathBuf,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    identity_verifier: Arc<IdentityVerifier>,
+    max_content_size: Option<u64>,
 }
 
 impl Config {
@@ -49,6 +52,7 @@ impl Config {
         temp_dir: PathBuf,
         content_policy: Option<Arc<dyn ContentPolicy>>,
         record_policy: Option<Arc<dyn RecordPolicy>>,
+        identity_verifier: Arc<IdentityVerifier>,
     ) -> Self {
         Self {
             core_service,
@@ -56,9 +60,17 @@ impl Config {
             temp_dir,
             content_policy,
             record_policy,
+            identity_verifier,
+            max_content_size: None,
         }
     }
 
+    /// Rejects uploaded content larger than the given size in bytes.
+    pub fn with_max_content_size(mut self, size: u64) -> Self {
+        self.max_content_size = Some(size);
+        self
+    }
+
     pub fn into_router(self) -> Router {
         Router::new()
             .route("/:log_id/record", post(publish_record))
@@ -131,6 +143,19 @@ impl PackageApiError {
             message: message.to_string(),
         })
     }
+
+    fn not_accepting_submissions() -> Self {
+        Self(PackageError::Message {
+            status: StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+            message: "the registry is not accepting submissions".into(),
+        })
+    }
+
+    fn read_only_mirror() -> Self {
+        Self(PackageError::NotSupported(
+            "this registry is a read-only mirror; publish to the upstream registry instead".into(),
+        ))
+    }
 }
 
 impl From<DataStoreError> for PackageApiError {
@@ -175,6 +200,12 @@ impl From<RecordPolicyError> for PackageApiError {
     }
 }
 
+impl From<IdentityError> for PackageApiError {
+    fn from(e: IdentityError) -> Self {
+        Self(PackageError::Unauthorized(e.to_string()))
+    }
+}
+
 impl IntoResponse for PackageApiError {
     fn into_response(self) -> axum::response::Response {
         (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
@@ -188,6 +219,14 @@ async fn publish_record(
     RegistryHeader(_registry_header): RegistryHeader,
     Json(body): Json<PublishRecordRequest<'static>>,
 ) -> Result<impl IntoResponse, PackageApiError> {
+    if config.core_service.is_mirror() {
+        return Err(PackageApiError::read_only_mirror());
+    }
+
+    if !config.core_service.is_accepting_submissions() {
+        return Err(PackageApiError::not_accepting_submissions());
+    }
+
     let expected_log_id = LogId::package_log::<Sha256>(&body.package_name);
     if expected_log_id != log_id {
         return Err(PackageApiError::bad_request(format!(
@@ -224,6 +263,23 @@ async fn publish_record(
         policy.check(&body.package_name, &record)?;
     }
 
+    // A record signed by an ephemeral key must prove the identity it is bound to
+    if let Some(package::PackageEntry::Identity {
+        issuer, subject, ..
+    }) = record.as_ref().entries.first()
+    {
+        let token = body.identity_token.as_deref().ok_or_else(|| {
+            PackageApiError(PackageError::Unauthorized(
+                "an identity token is required to publish a record bound to an identity"
+                    .to_string(),
+            ))
+        })?;
+
+        config
+            .identity_verifier
+            .verify(token, issuer, subject, record.key_id())?;
+    }
+
     // Verify the signature on the record itself before storing it
     config
         .core_service
@@ -246,7 +302,8 @@ async fn publish_record(
         config
             .core_service
             .submit_package_record(log_id, record_id.clone())
-            .await;
+            .await
+            .map_err(|_| PackageApiError::not_accepting_submissions())?;
 
         return Ok((
             StatusCode::ACCEPTED,
@@ -314,6 +371,10 @@ async fn upload_content(
     RegistryHeader(_registry_header): RegistryHeader,
     body: Body,
 ) -> Result<impl IntoResponse, PackageApiError> {
+    if config.core_service.is_mirror() {
+        return Err(PackageApiError::read_only_mirror());
+    }
+
     match config
         .core_service
         .store()
@@ -346,6 +407,7 @@ async fn upload_content(
         &digest,
         body.into_data_stream(),
         config.content_policy.as_deref(),
+        config.max_content_size,
     )
     .await;
 
@@ -379,7 +441,8 @@ async fn upload_content(
         config
             .core_service
             .submit_package_record(log_id, record_id.clone())
-            .await;
+            .await
+            .map_err(|_| PackageApiError::not_accepting_submissions())?;
     }
 
     Ok(StatusCode::CREATED)
@@ -390,6 +453,7 @@ async fn process_content(
     digest: &AnyHash,
     mut stream: BodyDataStream,
     policy: Option<&dyn ContentPolicy>,
+    max_size: Option<u64>,
 ) -> Result<(), PackageApiError> {
     let mut tmp_file = tokio::fs::File::create(&path)
         .await
@@ -397,6 +461,7 @@ async fn process_content(
 
     let mut hasher = digest.algorithm().hasher();
     let mut policy = policy.map(|p| p.new_stream_policy(digest)).transpose()?;
+    let mut size = 0u64;
 
     while let Some(chunk) = stream
         .next()
@@ -404,6 +469,15 @@ async fn process_content(
         .transpose()
         .map_err(PackageApiError::internal_error)?
     {
+        size += chunk.len() as u64;
+        if let Some(max_size) = max_size {
+            if size > max_size {
+                return Err(PackageApiError(PackageError::Rejection(format!(
+                    "content exceeds the maximum size of {max_size} bytes"
+                ))));
+            }
+        }
+
         if let Some(policy) = policy.as_mut() {
             policy.check(&chunk)?;
         }
@@ -4,7 +4,8 @@ use axum::{
     debug_handler, extract::State, http::StatusCode, response::IntoResponse, routing::post, Router,
 };
 use warg_api::v1::proof::{
-    ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse, ProofError,
+    AbsenceRequest, AbsenceResponse, ConsistencyRequest, ConsistencyResponse, InclusionRequest,
+    InclusionResponse, ProofError,
 };
 use warg_protocol::registry::{RegistryIndex, RegistryLen};
 
@@ -22,6 +23,7 @@ impl Config {
         Router::new()
             .route("/consistency", post(prove_consistency))
             .route("/inclusion", post(prove_inclusion))
+            .route("/absence", post(prove_absence))
             .with_state(self)
     }
 }
@@ -37,6 +39,7 @@ impl From<CoreServiceError> for ProofApiError {
             CoreServiceError::LeafNotFound(leaf) => ProofError::LeafNotFound(leaf),
             CoreServiceError::BundleFailure(e) => ProofError::BundleFailure(e.to_string()),
             CoreServiceError::PackageNotIncluded(id) => ProofError::PackageLogNotIncluded(id),
+            CoreServiceError::PackageIncluded(id) => ProofError::PackageLogIncluded(id),
             CoreServiceError::IncorrectProof { root, found } => {
                 ProofError::IncorrectProof { root, found }
             }
@@ -87,10 +90,29 @@ async fn prove_inclusion(
         .collect::<Vec<RegistryIndex>>();
 
     let log_bundle = config.core.log_inclusion_proofs(log_length, &leafs).await?;
-    let map_bundle = config.core.map_inclusion_proofs(log_length, &leafs).await?;
+    let map_bundle = config
+        .core
+        .map_inclusion_proofs(log_length, &leafs, body.map_multi_proof)
+        .await?;
 
     Ok(Json(InclusionResponse {
         log: log_bundle.encode(),
         map: map_bundle.encode(),
     }))
 }
+
+#[debug_handler]
+async fn prove_absence(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<AbsenceRequest>,
+) -> Result<Json<AbsenceResponse>, ProofApiError> {
+    let map_bundle = config
+        .core
+        .map_absence_proofs(body.log_length, &body.log_ids)
+        .await?;
+
+    Ok(Json(AbsenceResponse {
+        map: map_bundle.encode(),
+    }))
+}
@@ -0,0 +1,102 @@
+use super::{Json, Path, RegistryHeader};
+use crate::services::{CoreService, CoreServiceError};
+use axum::{
+    debug_handler,
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use warg_api::v1::tile::{TileError, TILE_CONTENT_TYPE};
+use warg_transparency::log::{TileId, TILE_WIDTH};
+
+/// Tiles never change, so they may be cached indefinitely.
+const TILE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+#[derive(Clone)]
+pub struct Config {
+    core_service: CoreService,
+}
+
+impl Config {
+    pub fn new(core_service: CoreService) -> Self {
+        Self { core_service }
+    }
+
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route("/:level/:index", get(get_tile))
+            .route("/:level/:index/p/:width", get(get_partial_tile))
+            .with_state(self)
+    }
+}
+
+struct TileApiError(TileError);
+
+impl From<CoreServiceError> for TileApiError {
+    fn from(e: CoreServiceError) -> Self {
+        Self(match e {
+            CoreServiceError::TileNotFound(tile, width) => TileError::TileNotFound {
+                level: tile.level,
+                index: tile.index,
+                width,
+            },
+            e => {
+                tracing::error!("unexpected core service error: {e}");
+                TileError::Message {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "an error occurred while processing the request".into(),
+                }
+            }
+        })
+    }
+}
+
+impl IntoResponse for TileApiError {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
+    }
+}
+
+async fn tile_response(
+    config: &Config,
+    tile: TileId,
+    width: usize,
+) -> Result<Response, TileApiError> {
+    let body = config.core_service.log_tile(tile, width).await?;
+
+    Ok(Response::builder()
+        .status(200)
+        .header(header::CONTENT_TYPE, TILE_CONTENT_TYPE)
+        .header(header::CACHE_CONTROL, TILE_CACHE_CONTROL)
+        .body(body.into())
+        .unwrap())
+}
+
+#[debug_handler]
+async fn get_tile(
+    State(config): State<Config>,
+    Path((level, index)): Path<(usize, usize)>,
+    RegistryHeader(_registry_header): RegistryHeader,
+) -> Result<Response, TileApiError> {
+    tile_response(&config, TileId { level, index }, TILE_WIDTH).await
+}
+
+#[debug_handler]
+async fn get_partial_tile(
+    State(config): State<Config>,
+    Path((level, index, width)): Path<(usize, usize, usize)>,
+    RegistryHeader(_registry_header): RegistryHeader,
+) -> Result<Response, TileApiError> {
+    // Full tiles are only served at the path without a width
+    if width >= TILE_WIDTH {
+        return Err(TileApiError(TileError::TileNotFound {
+            level,
+            index,
+            width,
+        }));
+    }
+
+    tile_response(&config, TileId { level, index }, width).await
+}
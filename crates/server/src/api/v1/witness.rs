@@ -0,0 +1,129 @@
+use super::{Json, Path, RegistryHeader};
+use crate::datastore::DataStoreError;
+use crate::services::CoreService;
+use axum::{
+    debug_handler,
+    extract::State,
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use std::sync::Arc;
+use warg_api::v1::witness::{CosignRequest, WitnessError};
+use warg_crypto::signing::PublicKey;
+use warg_protocol::registry::{CosignedCheckpoint, RegistryLen};
+
+#[derive(Clone)]
+pub struct Config {
+    core_service: CoreService,
+    witness_keys: Option<Arc<Vec<PublicKey>>>,
+}
+
+impl Config {
+    pub fn new(core_service: CoreService, witness_keys: Option<Vec<PublicKey>>) -> Self {
+        Self {
+            core_service,
+            witness_keys: witness_keys.map(Arc::new),
+        }
+    }
+
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route("/cosignature", post(cosign_checkpoint))
+            .route("/checkpoint/:log_length", get(get_cosigned_checkpoint))
+            .with_state(self)
+    }
+}
+
+struct WitnessApiError(WitnessError);
+
+impl From<DataStoreError> for WitnessApiError {
+    fn from(e: DataStoreError) -> Self {
+        Self(match e {
+            DataStoreError::CheckpointNotFound(log_length) => {
+                WitnessError::CheckpointNotFound(log_length)
+            }
+            e => {
+                tracing::error!("unexpected data store error: {e}");
+                WitnessError::Message {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "an error occurred while processing the request".into(),
+                }
+            }
+        })
+    }
+}
+
+impl IntoResponse for WitnessApiError {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
+    }
+}
+
+async fn cosigned_checkpoint(
+    core_service: &CoreService,
+    log_length: RegistryLen,
+) -> Result<CosignedCheckpoint, WitnessApiError> {
+    let checkpoint = core_service.store().get_checkpoint(log_length).await?;
+    let cosignatures = core_service
+        .store()
+        .get_checkpoint_cosignatures(log_length)
+        .await?;
+
+    Ok(CosignedCheckpoint {
+        checkpoint,
+        cosignatures,
+    })
+}
+
+/// Stores a witness cosignature of a checkpoint.
+///
+/// Only cosignatures by the witness keys the registry is configured with are
+/// accepted, which bounds the number of cosignatures stored per checkpoint.
+#[debug_handler]
+async fn cosign_checkpoint(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<CosignRequest>,
+) -> Result<Json<CosignedCheckpoint>, WitnessApiError> {
+    let key_id = body.public_key.fingerprint();
+    let trusted = config
+        .witness_keys
+        .as_ref()
+        .is_some_and(|keys| keys.iter().any(|k| k.fingerprint() == key_id));
+    if !trusted {
+        return Err(WitnessApiError(WitnessError::WitnessNotTrusted(key_id)));
+    }
+
+    let checkpoint = config
+        .core_service
+        .store()
+        .get_checkpoint(body.log_length)
+        .await?;
+
+    body.cosignature
+        .verify(&body.public_key, checkpoint.as_ref())
+        .map_err(|_| WitnessApiError(WitnessError::InvalidCosignature(key_id)))?;
+
+    config
+        .core_service
+        .store()
+        .store_checkpoint_cosignature(body.log_length, body.cosignature)
+        .await?;
+
+    Ok(Json(
+        cosigned_checkpoint(&config.core_service, body.log_length).await?,
+    ))
+}
+
+#[debug_handler]
+async fn get_cosigned_checkpoint(
+    State(config): State<Config>,
+    Path(log_length): Path<RegistryLen>,
+    RegistryHeader(_registry_header): RegistryHeader,
+) -> Result<Json<CosignedCheckpoint>, WitnessApiError> {
+    Ok(Json(
+        cosigned_checkpoint(&config.core_service, log_length).await?,
+    ))
+}
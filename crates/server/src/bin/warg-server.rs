@@ -5,7 +5,7 @@ use std::{net::SocketAddr, path::PathBuf};
 use tokio::signal;
 use tracing_subscriber::filter::LevelFilter;
 use url::Url;
-use warg_crypto::signing::PrivateKey;
+use warg_crypto::signing::{PrivateKey, PublicKey};
 use warg_protocol::operator;
 use warg_server::{args::get_opt_secret, policy::record::AuthorizedKeyPolicy, Config, Server};
 
@@ -67,6 +67,17 @@ struct Args {
     #[arg(long, env = "WARG_OPERATOR_KEY_FILE", conflicts_with = "operator_key")]
     operator_key_file: Option<PathBuf>,
 
+    /// The URL of a registry to run this server as a read-only mirror of.
+    ///
+    /// A mirror replicates the upstream registry's ledger and caches its
+    /// content; it has no operator key of its own.
+    #[arg(
+        long,
+        env = "WARG_MIRROR_OF",
+        conflicts_with_all = ["operator_key", "operator_key_file", "namespace"]
+    )]
+    mirror_of: Option<Url>,
+
     /// The path to the authorized keys record policy file.
     #[arg(long, env = "WARG_AUTHORIZED_KEYS_FILE")]
     authorized_keys_file: Option<PathBuf>,
@@ -74,6 +85,66 @@ struct Args {
     /// The initial namespace defined for this registry.
     #[arg(long, env = "WARG_NAMESPACE")]
     namespace: Option<String>,
+
+    /// The namespaces imported by this registry from other registries,
+    /// specified as `<namespace>=<registry>`.
+    #[arg(
+        long = "import-namespace",
+        env = "WARG_IMPORT_NAMESPACES",
+        value_delimiter = ',',
+        value_parser = parse_imported_namespace,
+        conflicts_with = "mirror_of"
+    )]
+    imported_namespaces: Vec<(String, String)>,
+
+    /// Serve the packages of imported namespaces by fetching them from the
+    /// registries they are imported from.
+    #[arg(long, env = "WARG_FEDERATION_PROXY")]
+    federation_proxy: bool,
+
+    /// The public keys of witnesses trusted to cosign checkpoints.
+    ///
+    /// If not specified, witness cosignatures are not accepted.
+    #[arg(long = "witness-key", env = "WARG_WITNESS_KEYS", value_delimiter = ',')]
+    witness_keys: Vec<PublicKey>,
+
+    /// The identity token issuers trusted to bind publisher identities to
+    /// ephemeral keys, specified as `<issuer>=<public-key>`.
+    ///
+    /// If not specified, records bound to a publisher identity are rejected.
+    #[arg(
+        long = "identity-issuer",
+        env = "WARG_IDENTITY_ISSUERS",
+        value_delimiter = ',',
+        value_parser = parse_identity_issuer
+    )]
+    identity_issuers: Vec<(String, PublicKey)>,
+
+    /// The directory in which to persist the registry log.
+    ///
+    /// If not specified, the log is kept in memory and rebuilt from the
+    /// data store on startup.
+    #[arg(long, env = "WARG_LOG_DIR")]
+    log_dir: Option<PathBuf>,
+
+    /// The directory in which to persist the registry map.
+    ///
+    /// If not specified, the map is kept in memory and rebuilt from the
+    /// data store on startup.
+    #[arg(long, env = "WARG_MAP_DIR")]
+    map_dir: Option<PathBuf>,
+
+    /// The number of recent checkpoints for which map proofs are served.
+    ///
+    /// If not specified, map proofs are served for all checkpoints.
+    #[arg(long, env = "WARG_MAP_RETENTION")]
+    map_retention: Option<usize>,
+
+    /// The maximum size, in bytes, of uploaded content.
+    ///
+    /// If not specified, content of any size is accepted.
+    #[arg(long, env = "WARG_MAX_CONTENT_SIZE")]
+    max_content_size: Option<u64>,
 }
 
 impl Args {
@@ -95,16 +166,32 @@ async fn main() -> Result<()> {
     args.init_tracing();
     tracing::debug!("args: {args:?}");
 
-    let operator_key_str =
-        get_opt_secret("operator-key", args.operator_key_file, args.operator_key)?;
-    let operator_key =
-        PrivateKey::decode(operator_key_str).context("failed to parse operator key")?;
-    let namespaces = args
-        .namespace
-        .as_ref()
-        .map(|namespace| vec![(namespace.to_lowercase(), operator::NamespaceState::Defined)]);
+    let config = match args.mirror_of {
+        Some(upstream) => {
+            tracing::info!("mirroring registry `{upstream}`");
+            Config::new_mirror(upstream, args.content_dir)
+        }
+        None => {
+            let operator_key_str =
+                get_opt_secret("operator-key", args.operator_key_file, args.operator_key)?;
+            let operator_key =
+                PrivateKey::decode(operator_key_str).context("failed to parse operator key")?;
+            let mut namespaces = Vec::new();
+            if let Some(namespace) = &args.namespace {
+                namespaces.push((namespace.to_lowercase(), operator::NamespaceState::Defined));
+            }
+            for (namespace, registry) in args.imported_namespaces {
+                namespaces.push((
+                    namespace.to_lowercase(),
+                    operator::NamespaceState::Imported { registry },
+                ));
+            }
+            let namespaces = (!namespaces.is_empty()).then_some(namespaces);
+            Config::new(operator_key, namespaces, args.content_dir)
+        }
+    };
 
-    let mut config = Config::new(operator_key, namespaces, args.content_dir)
+    let mut config = config
         .with_addr(args.listen)
         .with_shutdown(shutdown_signal());
 
@@ -112,6 +199,34 @@ async fn main() -> Result<()> {
         config = config.with_content_base_url(url);
     }
 
+    if let Some(dir) = args.log_dir {
+        config = config.with_log_dir(dir);
+    }
+
+    if let Some(dir) = args.map_dir {
+        config = config.with_map_dir(dir);
+    }
+
+    if let Some(checkpoints) = args.map_retention {
+        config = config.with_map_retention(checkpoints);
+    }
+
+    if let Some(size) = args.max_content_size {
+        config = config.with_max_content_size(size);
+    }
+
+    if args.federation_proxy {
+        config = config.with_federation_proxy();
+    }
+
+    if !args.witness_keys.is_empty() {
+        config = config.with_witness_keys(args.witness_keys);
+    }
+
+    for (issuer, key) in args.identity_issuers {
+        config = config.with_identity_issuer(issuer, key);
+    }
+
     if let Some(path) = args.authorized_keys_file {
         let authorized_keys_data = std::fs::read_to_string(&path)
             .with_context(|| format!("failed to read authorized keys from {path:?}"))?;
@@ -170,3 +285,17 @@ async fn shutdown_signal() {
         _ = terminate => {},
     }
 }
+
+fn parse_identity_issuer(s: &str) -> Result<(String, PublicKey)> {
+    let (issuer, key) = s
+        .split_once('=')
+        .context("expected an identity issuer of the form `<issuer>=<public-key>`")?;
+    Ok((issuer.to_string(), key.parse()?))
+}
+
+fn parse_imported_namespace(s: &str) -> Result<(String, String)> {
+    let (namespace, registry) = s
+        .split_once('=')
+        .context("expected an imported namespace of the form `<namespace>=<registry>`")?;
+    Ok((namespace.to_string(), registry.to_string()))
+}
@@ -3,12 +3,13 @@ use futures::Stream;
 use indexmap::{IndexMap, IndexSet};
 use std::{pin::Pin, sync::Arc};
 use tokio::sync::RwLock;
-use warg_crypto::{hash::AnyHash, Encode, Signable};
+use warg_crypto::{hash::AnyHash, signing::KeyID, Encode, Signable};
 use warg_protocol::{
     operator,
     package::{self, PackageEntry},
     registry::{
-        LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen, TimestampedCheckpoint,
+        CheckpointCosignature, LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen,
+        TimestampedCheckpoint,
     },
     ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope,
 };
@@ -75,6 +76,7 @@ struct State {
     packages: IndexMap<LogId, Log<package::LogState, package::PackageRecord>>,
     package_names: IndexMap<LogId, Option<PackageName>>,
     checkpoints: IndexMap<RegistryLen, SerdeEnvelope<TimestampedCheckpoint>>,
+    cosignatures: IndexMap<RegistryLen, IndexMap<KeyID, CheckpointCosignature>>,
     records: IndexMap<LogId, IndexMap<RecordId, RecordStatus>>,
     log_leafs: IndexMap<RegistryIndex, LogLeaf>,
 }
@@ -85,6 +87,7 @@ struct State {
 ///
 /// Note: this is mainly used for testing, so it is not very efficient as
 /// it shares a single RwLock for all operations.
+#[derive(Clone)]
 pub struct MemoryDataStore(Arc<RwLock<State>>);
 
 impl MemoryDataStore {
@@ -107,7 +110,13 @@ impl DataStore for MemoryDataStore {
         Pin<Box<dyn Stream<Item = Result<TimestampedCheckpoint, DataStoreError>> + Send>>,
         DataStoreError,
     > {
-        Ok(Box::pin(futures::stream::empty()))
+        let state = self.0.read().await;
+        let checkpoints = state
+            .checkpoints
+            .values()
+            .map(|checkpoint| Ok(checkpoint.as_ref().clone()))
+            .collect::<Vec<_>>();
+        Ok(Box::pin(futures::stream::iter(checkpoints)))
     }
 
     async fn get_all_validated_records(
@@ -493,6 +502,21 @@ impl DataStore for MemoryDataStore {
         Ok(checkpoint.clone())
     }
 
+    async fn get_checkpoints(
+        &self,
+        since: Option<RegistryLen>,
+        limit: u16,
+    ) -> Result<Vec<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        let state = self.0.read().await;
+        Ok(state
+            .checkpoints
+            .iter()
+            .skip_while(|(log_length, _)| since.is_some_and(|since| **log_length <= since))
+            .take(limit as usize)
+            .map(|(_, checkpoint)| checkpoint.clone())
+            .collect())
+    }
+
     async fn get_checkpoint(
         &self,
         log_length: RegistryLen,
@@ -505,6 +529,38 @@ impl DataStore for MemoryDataStore {
         Ok(checkpoint.clone())
     }
 
+    async fn store_checkpoint_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: CheckpointCosignature,
+    ) -> Result<(), DataStoreError> {
+        let mut state = self.0.write().await;
+
+        if !state.checkpoints.contains_key(&log_length) {
+            return Err(DataStoreError::CheckpointNotFound(log_length));
+        }
+
+        state
+            .cosignatures
+            .entry(log_length)
+            .or_default()
+            .insert(cosignature.key_id.clone(), cosignature);
+
+        Ok(())
+    }
+
+    async fn get_checkpoint_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<CheckpointCosignature>, DataStoreError> {
+        let state = self.0.read().await;
+        Ok(state
+            .cosignatures
+            .get(&log_length)
+            .map(|c| c.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
@@ -704,7 +760,9 @@ impl DataStore for MemoryDataStore {
         {
             Some(key) => Some(key),
             None => match record.as_ref().entries.first() {
-                Some(PackageEntry::Init { key, .. }) => Some(key),
+                Some(PackageEntry::Init { key, .. } | PackageEntry::Identity { key, .. }) => {
+                    Some(key)
+                }
                 _ => return Err(DataStoreError::UnknownKey(record.key_id().clone())),
             },
         }
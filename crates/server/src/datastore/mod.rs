@@ -9,7 +9,8 @@ use warg_crypto::{
 use warg_protocol::{
     operator, package,
     registry::{
-        LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen, TimestampedCheckpoint,
+        CheckpointCosignature, LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen,
+        TimestampedCheckpoint,
     },
     ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope,
 };
@@ -241,12 +242,35 @@ pub trait DataStore: Send + Sync {
         &self,
     ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, DataStoreError>;
 
+    /// Gets up to `limit` checkpoints with a log length greater than `since`,
+    /// in ascending order of log length.
+    async fn get_checkpoints(
+        &self,
+        since: Option<RegistryLen>,
+        limit: u16,
+    ) -> Result<Vec<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError>;
+
     /// Get checkpoint by log length.
     async fn get_checkpoint(
         &self,
         log_length: RegistryLen,
     ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, DataStoreError>;
 
+    /// Stores a witness cosignature for the checkpoint with the given log length.
+    ///
+    /// A previous cosignature by the same witness key is replaced.
+    async fn store_checkpoint_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: CheckpointCosignature,
+    ) -> Result<(), DataStoreError>;
+
+    /// Gets the witness cosignatures for the checkpoint with the given log length.
+    async fn get_checkpoint_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<CheckpointCosignature>, DataStoreError>;
+
     /// Gets package names from log IDs. If package name is unavailable, a corresponding `None` is returned.
     async fn get_package_names(
         &self,
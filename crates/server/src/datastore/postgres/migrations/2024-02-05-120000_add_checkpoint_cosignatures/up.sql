@@ -0,0 +1,11 @@
+CREATE TABLE checkpoint_cosignatures (
+  id SERIAL PRIMARY KEY,
+  log_length BIGINT NOT NULL,
+  key_id TEXT NOT NULL,
+  signature TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (log_length, key_id)
+);
+
+SELECT diesel_manage_updated_at('checkpoint_cosignatures');
@@ -1,6 +1,6 @@
 use self::models::{
-    CheckpointData, NewCheckpoint, NewContent, NewLog, NewRecord, ParsedText, RecordContent,
-    RecordStatus, TextRef,
+    CheckpointCosignatureData, CheckpointData, NewCheckpoint, NewCheckpointCosignature, NewContent,
+    NewLog, NewRecord, ParsedText, RecordContent, RecordStatus, TextRef,
 };
 use super::{DataStore, DataStoreError, Record};
 use anyhow::{anyhow, Result};
@@ -24,8 +24,8 @@ use warg_protocol::{
     operator,
     package::{self, PackageEntry},
     registry::{
-        Checkpoint, LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen,
-        TimestampedCheckpoint,
+        Checkpoint, CheckpointCosignature, LogId, LogLeaf, PackageName, RecordId, RegistryIndex,
+        RegistryLen, TimestampedCheckpoint,
     },
     ProtoEnvelope, PublishedProtoEnvelope, Record as _, SerdeEnvelope, Validator,
 };
@@ -846,6 +846,42 @@ impl DataStore for PostgresDataStore {
         ))
     }
 
+    async fn get_checkpoints(
+        &self,
+        since: Option<RegistryLen>,
+        limit: u16,
+    ) -> Result<Vec<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        let mut query = schema::checkpoints::table
+            .order_by(schema::checkpoints::log_length.asc())
+            .limit(limit as i64)
+            .into_boxed();
+        if let Some(since) = since {
+            query = query.filter(schema::checkpoints::log_length.gt(since as i64));
+        }
+
+        Ok(query
+            .load::<CheckpointData>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|checkpoint| {
+                SerdeEnvelope::from_parts_unchecked(
+                    TimestampedCheckpoint {
+                        checkpoint: Checkpoint {
+                            log_root: checkpoint.log_root.0,
+                            log_length: checkpoint.log_length.try_into().unwrap(),
+                            map_root: checkpoint.map_root.0,
+                        },
+                        timestamp: checkpoint.timestamp.try_into().unwrap(),
+                    },
+                    checkpoint.key_id.0,
+                    checkpoint.signature.0,
+                )
+            })
+            .collect())
+    }
+
     async fn get_checkpoint(
         &self,
         log_length: RegistryLen,
@@ -873,6 +909,63 @@ impl DataStore for PostgresDataStore {
         ))
     }
 
+    async fn store_checkpoint_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: CheckpointCosignature,
+    ) -> Result<(), DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        let exists = schema::checkpoints::table
+            .select(schema::checkpoints::id)
+            .filter(schema::checkpoints::log_length.eq(log_length as i64))
+            .first::<i32>(&mut conn)
+            .await
+            .optional()?
+            .is_some();
+        if !exists {
+            return Err(DataStoreError::CheckpointNotFound(log_length));
+        }
+
+        // Replacing any existing cosignature by the same witness key
+        diesel::insert_into(schema::checkpoint_cosignatures::table)
+            .values(NewCheckpointCosignature {
+                log_length: log_length as i64,
+                key_id: TextRef(&cosignature.key_id),
+                signature: TextRef(&cosignature.signature),
+            })
+            .on_conflict((
+                schema::checkpoint_cosignatures::log_length,
+                schema::checkpoint_cosignatures::key_id,
+            ))
+            .do_update()
+            .set(schema::checkpoint_cosignatures::signature.eq(TextRef(&cosignature.signature)))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_checkpoint_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<CheckpointCosignature>, DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        Ok(schema::checkpoint_cosignatures::table
+            .select(CheckpointCosignatureData::as_select())
+            .filter(schema::checkpoint_cosignatures::log_length.eq(log_length as i64))
+            .order(schema::checkpoint_cosignatures::id)
+            .load::<CheckpointCosignatureData>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|c| CheckpointCosignature {
+                key_id: c.key_id.0,
+                signature: c.signature.0,
+            })
+            .collect())
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
@@ -950,7 +1043,7 @@ impl DataStore for PostgresDataStore {
         {
             Some(key) => key,
             None => match record.as_ref().entries.get(0) {
-                Some(PackageEntry::Init { key, .. }) => key,
+                Some(PackageEntry::Init { key, .. } | PackageEntry::Identity { key, .. }) => key,
                 _ => return Err(DataStoreError::UnknownKey(record.key_id().clone())),
             },
         };
@@ -1,4 +1,4 @@
-use super::schema::{checkpoints, contents, logs, records};
+use super::schema::{checkpoint_cosignatures, checkpoints, contents, logs, records};
 use chrono::{DateTime, Utc};
 use diesel::{
     deserialize::{self, FromSql},
@@ -105,6 +105,22 @@ pub struct CheckpointData {
     pub timestamp: i64,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = checkpoint_cosignatures)]
+pub struct NewCheckpointCosignature<'a> {
+    pub log_length: i64,
+    pub key_id: TextRef<'a, KeyID>,
+    pub signature: TextRef<'a, Signature>,
+}
+
+/// Selects only the key id and signature of a cosignature
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = checkpoint_cosignatures)]
+pub struct CheckpointCosignatureData {
+    pub key_id: Text<KeyID>,
+    pub signature: ParsedText<Signature>,
+}
+
 /// Selects only the record content and status
 #[derive(Queryable, Selectable)]
 #[diesel(table_name = records)]
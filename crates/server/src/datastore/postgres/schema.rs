@@ -6,6 +6,17 @@ pub mod sql_types {
     pub struct RecordStatus;
 }
 
+diesel::table! {
+    checkpoint_cosignatures (id) {
+        id -> Int4,
+        log_length -> Int8,
+        key_id -> Text,
+        signature -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     checkpoints (id) {
         id -> Int4,
@@ -63,4 +74,10 @@ diesel::table! {
 diesel::joinable!(contents -> records (record_id));
 diesel::joinable!(records -> logs (log_id));
 
-diesel::allow_tables_to_appear_in_same_query!(checkpoints, contents, logs, records,);
+diesel::allow_tables_to_appear_in_same_query!(
+    checkpoint_cosignatures,
+    checkpoints,
+    contents,
+    logs,
+    records,
+);
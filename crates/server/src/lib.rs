@@ -3,12 +3,19 @@ use anyhow::{Context, Result};
 use axum::Router;
 use datastore::DataStore;
 use futures::Future;
-use policy::{content::ContentPolicy, record::RecordPolicy};
-use services::CoreService;
+use policy::{content::ContentPolicy, identity::IdentityVerifier, record::RecordPolicy};
+use services::{CoreService, CoreServiceError, MirrorService, ProxyService};
 use std::{fs, net::SocketAddr, path::PathBuf, pin::Pin, sync::Arc, time::Duration};
 use tokio::{net::TcpListener, task::JoinHandle};
 use url::Url;
-use warg_crypto::signing::PrivateKey;
+use warg_api::{
+    v1::{paths, API_VERSION},
+    RegistryDiscovery,
+};
+use warg_crypto::{
+    hash::HashAlgorithm,
+    signing::{PrivateKey, PublicKey, SignatureAlgorithm},
+};
 use warg_protocol::operator;
 
 pub mod api;
@@ -24,7 +31,8 @@ type ShutdownFut = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
 
 /// The server configuration.
 pub struct Config {
-    operator_key: PrivateKey,
+    operator_key: Option<PrivateKey>,
+    mirror_of: Option<Url>,
     namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     addr: Option<SocketAddr>,
     data_store: Option<Box<dyn DataStore>>,
@@ -34,12 +42,23 @@ pub struct Config {
     checkpoint_interval: Option<Duration>,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witness_keys: Option<Vec<PublicKey>>,
+    identity_verifier: IdentityVerifier,
+    log_dir: Option<PathBuf>,
+    map_dir: Option<PathBuf>,
+    map_retention: Option<usize>,
+    federation_proxy: bool,
+    max_content_size: Option<u64>,
 }
 
 impl std::fmt::Debug for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Config")
-            .field("operator_key", &"<redacted>")
+            .field(
+                "operator_key",
+                &self.operator_key.as_ref().map(|_| "<redacted>"),
+            )
+            .field("mirror_of", &self.mirror_of)
             .field("namespaces", &self.namespaces)
             .field("addr", &self.addr)
             .field(
@@ -57,6 +76,13 @@ impl std::fmt::Debug for Config {
                 "record_policy",
                 &self.record_policy.as_ref().map(|_| "dyn RecordPolicy"),
             )
+            .field("witness_keys", &self.witness_keys)
+            .field("identity_verifier", &self.identity_verifier)
+            .field("log_dir", &self.log_dir)
+            .field("map_dir", &self.map_dir)
+            .field("map_retention", &self.map_retention)
+            .field("federation_proxy", &self.federation_proxy)
+            .field("max_content_size", &self.max_content_size)
             .finish()
     }
 }
@@ -67,9 +93,29 @@ impl Config {
         operator_key: PrivateKey,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
         content_dir: PathBuf,
+    ) -> Self {
+        Self::with_defaults(Some(operator_key), None, namespaces, content_dir)
+    }
+
+    /// Creates a new configuration for a read-only mirror of another registry.
+    ///
+    /// The mirror replicates the upstream registry's ledger, verifying every
+    /// record against the upstream checkpoints, and caches content fetched
+    /// from the upstream registry on demand. The checkpoint interval is the
+    /// interval at which the upstream registry is polled.
+    pub fn new_mirror(upstream: Url, content_dir: PathBuf) -> Self {
+        Self::with_defaults(None, Some(upstream), None, content_dir)
+    }
+
+    fn with_defaults(
+        operator_key: Option<PrivateKey>,
+        mirror_of: Option<Url>,
+        namespaces: Option<Vec<(String, operator::NamespaceState)>>,
+        content_dir: PathBuf,
     ) -> Self {
         Self {
             operator_key,
+            mirror_of,
             namespaces,
             addr: None,
             data_store: None,
@@ -79,6 +125,13 @@ impl Config {
             checkpoint_interval: None,
             content_policy: None,
             record_policy: None,
+            witness_keys: None,
+            identity_verifier: IdentityVerifier::default(),
+            log_dir: None,
+            map_dir: None,
+            map_retention: None,
+            federation_proxy: false,
+            max_content_size: None,
         }
     }
 
@@ -140,6 +193,72 @@ impl Config {
         self.record_policy = Some(Arc::new(policy));
         self
     }
+
+    /// Sets the witness keys trusted to cosign checkpoints.
+    ///
+    /// If not set, witness cosignatures are not accepted.
+    pub fn with_witness_keys(mut self, keys: impl IntoIterator<Item = PublicKey>) -> Self {
+        self.witness_keys = Some(keys.into_iter().collect());
+        self
+    }
+
+    /// Trusts identity tokens from the given issuer signed with the given key.
+    ///
+    /// Records bound to a publisher identity are only accepted if the identity
+    /// was issued by a trusted issuer.
+    pub fn with_identity_issuer(mut self, issuer: impl Into<String>, key: PublicKey) -> Self {
+        self.identity_verifier = self.identity_verifier.with_issuer(issuer, key);
+        self
+    }
+
+    /// Sets the directory in which to persist the registry log.
+    ///
+    /// If not set, the log is kept in memory and rebuilt from the data
+    /// store on startup.
+    pub fn with_log_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.log_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the directory in which to persist the registry map.
+    ///
+    /// If not set, the map is kept in memory and rebuilt from the data
+    /// store on startup.
+    pub fn with_map_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.map_dir = Some(dir.into());
+        self
+    }
+
+    /// Sets the number of recent checkpoints for which map proofs are served.
+    ///
+    /// Older map snapshots are pruned; if not set, all are retained.
+    pub fn with_map_retention(mut self, checkpoints: usize) -> Self {
+        self.map_retention = Some(checkpoints);
+        self
+    }
+
+    /// Serves the packages of imported namespaces on behalf of the registries
+    /// from which they are imported.
+    ///
+    /// Package logs and content are fetched from the upstream registry,
+    /// verified against its checkpoint, and cached; the logs are served with
+    /// proofs rooted in the upstream checkpoint.
+    ///
+    /// The operator logs and checkpoints pinned for the upstream registries
+    /// are stored in `upstream-pins.json` of the content directory.
+    pub fn with_federation_proxy(mut self) -> Self {
+        self.federation_proxy = true;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of uploaded content.
+    ///
+    /// Records with larger content are rejected; if not set, content of any
+    /// size is accepted.
+    pub fn with_max_content_size(mut self, size: u64) -> Self {
+        self.max_content_size = Some(size);
+        self
+    }
 }
 
 /// Represents the warg registry server.
@@ -183,20 +302,63 @@ impl Server {
             config = self.config
         );
 
+        let discovery = RegistryDiscovery {
+            api_versions: vec![API_VERSION.to_string()],
+            hash_algorithms: vec![HashAlgorithm::Sha256.to_string()],
+            signature_algorithms: vec![SignatureAlgorithm::EcdsaP256.to_string()],
+            operator_key_id: self
+                .config
+                .operator_key
+                .as_ref()
+                .map(|key| key.public_key().fingerprint()),
+            ledger_url: Some(paths::ledger_sources().to_string()),
+            witness_url: self
+                .config
+                .witness_keys
+                .as_ref()
+                .map(|_| paths::witness_cosign().to_string()),
+            witness_key_ids: self
+                .config
+                .witness_keys
+                .as_ref()
+                .map(|keys| keys.iter().map(PublicKey::fingerprint).collect()),
+            max_content_size: self.config.max_content_size,
+        };
+
         let store = self
             .config
             .data_store
             .unwrap_or_else(|| Box::<MemoryDataStore>::default());
+        let checkpoint_interval = self
+            .config
+            .checkpoint_interval
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL);
         let (core, core_handle) = CoreService::start(
             self.config.operator_key,
             self.config.namespaces,
             store,
-            self.config
-                .checkpoint_interval
-                .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+            checkpoint_interval,
+            self.config.log_dir,
+            self.config.map_dir,
+            self.config.map_retention,
         )
         .await?;
 
+        let mirror_handle = match &self.config.mirror_of {
+            Some(upstream) => {
+                let mut mirror = MirrorService::new(core.clone(), upstream.clone()).await?;
+                // A mirror that has never synchronized has nothing to serve
+                if let Err(e) = mirror.sync().await {
+                    if core.checkpoint_length() == 0 {
+                        return Err(e.context(format!("failed to mirror `{upstream}`")));
+                    }
+                    tracing::warn!("failed to mirror `{upstream}`: {e:?}");
+                }
+                Some(mirror.spawn(checkpoint_interval))
+            }
+            None => None,
+        };
+
         let temp_dir = self.config.content_dir.join("tmp");
         fs::create_dir_all(&temp_dir).with_context(|| {
             format!(
@@ -213,6 +375,24 @@ impl Server {
             )
         })?;
 
+        let ledger_dir = self.config.content_dir.join("ledger");
+        fs::create_dir_all(&ledger_dir).with_context(|| {
+            format!(
+                "failed to create ledger segments directory `{path}`",
+                path = ledger_dir.display()
+            )
+        })?;
+
+        let proxy = if self.config.federation_proxy {
+            Some(ProxyService::new(
+                core.clone(),
+                checkpoint_interval,
+                self.config.content_dir.join("upstream-pins.json"),
+            )?)
+        } else {
+            None
+        };
+
         let content_base_url = self
             .config
             .content_base_url
@@ -223,14 +403,21 @@ impl Server {
             core,
             temp_dir,
             files_dir,
+            ledger_dir,
             self.config.content_policy,
             self.config.record_policy,
+            self.config.witness_keys,
+            Arc::new(self.config.identity_verifier),
+            self.config.mirror_of,
+            proxy,
+            discovery,
         );
 
         Ok(InitializedServer {
             listener,
             router,
             core_handle,
+            mirror_handle,
             shutdown: self.config.shutdown,
         })
     }
@@ -240,7 +427,8 @@ impl Server {
 pub struct InitializedServer {
     listener: TcpListener,
     router: Router,
-    core_handle: JoinHandle<()>,
+    core_handle: JoinHandle<Result<(), CoreServiceError>>,
+    mirror_handle: Option<JoinHandle<()>>,
     shutdown: Option<ShutdownFut>,
 }
 
@@ -253,6 +441,8 @@ impl InitializedServer {
 
     /// Serves the server's services. On server shutdown, awaits completion of
     /// background task(s) before returning.
+    ///
+    /// Returns an error without waiting for shutdown if the core service fails.
     pub async fn serve(self) -> Result<()> {
         let addr = self.local_addr()?;
 
@@ -260,16 +450,41 @@ impl InitializedServer {
 
         tracing::info!("listening on {addr}");
 
-        if let Some(shutdown) = self.shutdown {
-            tracing::debug!("server is running with a shutdown signal");
-            server.with_graceful_shutdown(shutdown).await?;
-        } else {
-            tracing::debug!("server is running without a shutdown signal");
-            server.await?;
+        let mut core_handle = self.core_handle;
+        let server = async move {
+            if let Some(shutdown) = self.shutdown {
+                tracing::debug!("server is running with a shutdown signal");
+                server.with_graceful_shutdown(shutdown).await
+            } else {
+                tracing::debug!("server is running without a shutdown signal");
+                server.await
+            }
+        };
+
+        let core_result = tokio::select! {
+            res = server => {
+                res?;
+                None
+            }
+            res = &mut core_handle => Some(res?),
+        };
+
+        if let Some(mirror_handle) = self.mirror_handle {
+            tracing::info!("stopping mirror service");
+            mirror_handle.abort();
+            _ = mirror_handle.await;
         }
 
-        tracing::info!("waiting for core service to stop");
-        self.core_handle.await?;
+        match core_result {
+            Some(res) => {
+                res.context("core service failed")?;
+                anyhow::bail!("core service stopped unexpectedly");
+            }
+            None => {
+                tracing::info!("waiting for core service to stop");
+                core_handle.await?.context("core service failed")?;
+            }
+        }
 
         tracing::info!("server shutdown complete");
         Ok(())
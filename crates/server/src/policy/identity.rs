@@ -0,0 +1,252 @@
+//! Module for verifying publisher identity tokens.
+//!
+//! Records signed by an ephemeral key begin with an `identity` entry that
+//! binds the key to an identity (an issuer and a subject). The publisher
+//! proves the identity by submitting an identity token along with the record.
+//!
+//! Identity tokens are ES256-signed JSON Web Tokens issued by a trusted
+//! issuer. The token's `aud` claim must contain the key ID of the ephemeral
+//! key so that a token cannot be replayed to bind a different key.
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use warg_crypto::signing::{KeyID, PublicKey, Signature, SignatureAlgorithm};
+
+/// Represents an identity token verification error.
+#[derive(Debug, Error)]
+pub enum IdentityError {
+    /// The token is not a well-formed JSON Web Token.
+    #[error("identity token is malformed: {0}")]
+    Malformed(String),
+    /// The token uses an unsupported signing algorithm.
+    #[error("identity token uses unsupported algorithm `{0}`")]
+    UnsupportedAlgorithm(String),
+    /// The token was issued by an issuer that is not trusted.
+    #[error("identity token issuer `{0}` is not trusted by this registry")]
+    UntrustedIssuer(String),
+    /// The token signature is invalid.
+    #[error("identity token signature is invalid")]
+    InvalidSignature,
+    /// The token has expired or is not yet valid.
+    #[error("identity token is expired or not yet valid")]
+    Expired,
+    /// The token claims do not match the identity in the record.
+    #[error("identity token claims do not match identity `{subject}` issued by `{issuer}`")]
+    ClaimMismatch {
+        /// The issuer in the record.
+        issuer: String,
+        /// The subject in the record.
+        subject: String,
+    },
+    /// The token audience does not include the signing key.
+    #[error("identity token audience does not include key id `{0}`")]
+    AudienceMismatch(KeyID),
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, audience: &str) -> bool {
+        match self {
+            Self::One(a) => a == audience,
+            Self::Many(a) => a.iter().any(|a| a == audience),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    iss: String,
+    sub: String,
+    aud: Audience,
+    exp: u64,
+    #[serde(default)]
+    nbf: Option<u64>,
+}
+
+/// Verifies identity tokens against a set of trusted issuers.
+#[derive(Default, Debug, Clone)]
+pub struct IdentityVerifier {
+    issuers: IndexMap<String, PublicKey>,
+}
+
+impl IdentityVerifier {
+    /// Creates a new identity verifier that trusts no issuers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts tokens from the given issuer signed with the given key.
+    pub fn with_issuer(mut self, issuer: impl Into<String>, key: PublicKey) -> Self {
+        self.issuers.insert(issuer.into(), key);
+        self
+    }
+
+    /// Determines if no issuers are trusted.
+    pub fn is_empty(&self) -> bool {
+        self.issuers.is_empty()
+    }
+
+    /// Verifies that the given token proves the identity of an ephemeral key.
+    pub fn verify(
+        &self,
+        token: &str,
+        issuer: &str,
+        subject: &str,
+        key_id: &KeyID,
+    ) -> Result<(), IdentityError> {
+        // The signature covers the encoded header and payload
+        let Some(((header, payload), signature)) = token
+            .rsplit_once('.')
+            .and_then(|(signed, signature)| Some((signed.split_once('.')?, signature)))
+            .filter(|((_, payload), _)| !payload.contains('.'))
+        else {
+            return Err(IdentityError::Malformed(
+                "expected three dot-separated parts".to_string(),
+            ));
+        };
+        let signed = &token[..header.len() + payload.len() + 1];
+
+        let header: Header = decode_json(header)?;
+        if header.alg != "ES256" {
+            return Err(IdentityError::UnsupportedAlgorithm(header.alg));
+        }
+
+        let claims: Claims = decode_json(payload)?;
+        let key = self
+            .issuers
+            .get(&claims.iss)
+            .ok_or_else(|| IdentityError::UntrustedIssuer(claims.iss.clone()))?;
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_raw_bytes(SignatureAlgorithm::EcdsaP256, &bytes).ok())
+            .ok_or(IdentityError::InvalidSignature)?;
+
+        key.verify(signed.as_bytes(), &signature)
+            .map_err(|_| IdentityError::InvalidSignature)?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if claims.exp <= now || claims.nbf.map(|nbf| nbf > now).unwrap_or(false) {
+            return Err(IdentityError::Expired);
+        }
+
+        if claims.iss != issuer || claims.sub != subject {
+            return Err(IdentityError::ClaimMismatch {
+                issuer: issuer.to_string(),
+                subject: subject.to_string(),
+            });
+        }
+
+        if !claims.aud.contains(&key_id.to_string()) {
+            return Err(IdentityError::AudienceMismatch(key_id.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+fn decode_json<T: serde::de::DeserializeOwned>(part: &str) -> Result<T, IdentityError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|e| IdentityError::Malformed(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| IdentityError::Malformed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use warg_crypto::signing::{generate_p256_pair, PrivateKey};
+
+    const ISSUER: &str = "https://issuer.example";
+
+    fn mint(key: &PrivateKey, claims: serde_json::Value) -> String {
+        let header = URL_SAFE_NO_PAD.encode(json!({ "alg": "ES256", "typ": "JWT" }).to_string());
+        let payload = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signed = format!("{header}.{payload}");
+        let signature = key.sign(signed.as_bytes()).unwrap();
+        format!("{signed}.{}", URL_SAFE_NO_PAD.encode(signature.raw_bytes()))
+    }
+
+    fn exp() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 60
+    }
+
+    #[test]
+    fn test_verify_identity_token() {
+        let (issuer_pub, issuer_priv) = generate_p256_pair();
+        let (ephemeral_pub, _) = generate_p256_pair();
+        let key_id = ephemeral_pub.fingerprint();
+        let verifier = IdentityVerifier::new().with_issuer(ISSUER, issuer_pub);
+
+        let token = mint(
+            &issuer_priv,
+            json!({ "iss": ISSUER, "sub": "alice", "aud": [key_id.to_string()], "exp": exp() }),
+        );
+        verifier.verify(&token, ISSUER, "alice", &key_id).unwrap();
+
+        assert!(matches!(
+            verifier.verify(&token, ISSUER, "bob", &key_id).unwrap_err(),
+            IdentityError::ClaimMismatch { .. }
+        ));
+
+        let (other_pub, _) = generate_p256_pair();
+        assert!(matches!(
+            verifier
+                .verify(&token, ISSUER, "alice", &other_pub.fingerprint())
+                .unwrap_err(),
+            IdentityError::AudienceMismatch(_)
+        ));
+
+        let expired = mint(
+            &issuer_priv,
+            json!({ "iss": ISSUER, "sub": "alice", "aud": key_id.to_string(), "exp": 1 }),
+        );
+        assert!(matches!(
+            verifier
+                .verify(&expired, ISSUER, "alice", &key_id)
+                .unwrap_err(),
+            IdentityError::Expired
+        ));
+
+        let (_, forger) = generate_p256_pair();
+        let forged = mint(
+            &forger,
+            json!({ "iss": ISSUER, "sub": "alice", "aud": key_id.to_string(), "exp": exp() }),
+        );
+        assert!(matches!(
+            verifier
+                .verify(&forged, ISSUER, "alice", &key_id)
+                .unwrap_err(),
+            IdentityError::InvalidSignature
+        ));
+
+        assert!(matches!(
+            IdentityVerifier::new()
+                .verify(&token, ISSUER, "alice", &key_id)
+                .unwrap_err(),
+            IdentityError::UntrustedIssuer(_)
+        ));
+    }
+}
@@ -1,4 +1,5 @@
 //! Module for server policy implementations.
 
 pub mod content;
+pub mod identity;
 pub mod record;
@@ -1,15 +1,16 @@
 use std::{
+    path::PathBuf,
     sync::Arc,
     time::{Duration, SystemTime},
 };
 
-use futures::{pin_mut, StreamExt};
+use futures::StreamExt;
 use indexmap::IndexMap;
 use thiserror::Error;
 use tokio::{
-    sync::{mpsc, RwLock},
+    sync::{mpsc, watch, RwLock},
     task::JoinHandle,
-    time::MissedTickBehavior,
+    time::{Instant, MissedTickBehavior},
 };
 use warg_crypto::{
     hash::{AnyHash, Hash, Sha256, SupportedDigest},
@@ -24,12 +25,18 @@ use warg_protocol::{
     ProtoEnvelope, SerdeEnvelope,
 };
 use warg_transparency::{
-    log::{LogBuilder, LogData, LogProofBundle, Node, VecLog},
-    map::{Map, MapProofBundle},
+    log::{
+        encode_tile, Checkpoint as LogCheckpoint, FileLog, LogBuilder, LogData, LogProofBundle,
+        Node, StackLog, TileId, VecLog,
+    },
+    map::{AbsenceProof, FileMap, Map, MapProofBundle, Proof},
 };
 
 use crate::datastore::{DataStore, DataStoreError};
 
+// The number of entries read from the data store at a time when replaying
+const REPLAY_BATCH_SIZE: usize = 1000;
+
 #[derive(Clone)]
 pub struct CoreService<Digest: SupportedDigest = Sha256> {
     inner: Arc<Inner<Digest>>,
@@ -42,17 +49,51 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
     /// Starts the `CoreService`, returning a `clone`able handle to the
     /// service and a [`JoinHandle`] which should be awaited after dropping all
     /// copies of the service handle to allow for graceful shutdown.
+    ///
+    /// The task completes early with an error if the registry state can no
+    /// longer be updated; the service then rejects further submissions.
+    ///
+    /// If no operator key is given, the service mirrors another registry:
+    /// it starts empty, never signs checkpoints of its own, and is only
+    /// updated via [`CoreService::apply_mirrored_checkpoint`].
     pub async fn start(
-        operator_key: PrivateKey,
+        operator_key: Option<PrivateKey>,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
         store: Box<dyn DataStore>,
         checkpoint_interval: Duration,
-    ) -> Result<(Self, JoinHandle<()>), CoreServiceError> {
+        log_dir: Option<PathBuf>,
+        map_dir: Option<PathBuf>,
+        map_retention: Option<usize>,
+    ) -> Result<(Self, JoinHandle<Result<(), CoreServiceError>>), CoreServiceError> {
+        let log = match log_dir {
+            Some(dir) => RegistryLog::File(FileLog::open(&dir).map_err(|e| {
+                CoreServiceError::InitializationFailure(format!(
+                    "failed to open log directory `{dir}`: {e}",
+                    dir = dir.display()
+                ))
+            })?),
+            None => RegistryLog::Memory(VecLog::default()),
+        };
+        let map = match map_dir {
+            Some(dir) => RegistryMap::File(FileMap::open(&dir).map_err(|e| {
+                CoreServiceError::InitializationFailure(format!(
+                    "failed to open map directory `{dir}`: {e}",
+                    dir = dir.display()
+                ))
+            })?),
+            None => RegistryMap::Memory {
+                map: Default::default(),
+                index: Default::default(),
+            },
+        };
+
         // Build service
         let mut inner = Inner {
             operator_key,
             store,
-            state: Default::default(),
+            state: RwLock::new(State::new(log, map)),
+            checkpoint_tx: watch::Sender::new(0),
+            map_retention,
         };
         inner.initialize(namespaces).await?;
 
@@ -81,8 +122,9 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
         let state = self.inner.state.read().await;
 
         let proof = state.log.prove_consistency(from_log_length, to_log_length);
-        LogProofBundle::bundle(vec![proof], vec![], &state.log)
-            .map_err(CoreServiceError::BundleFailure)
+        let bundle = LogProofBundle::bundle(vec![proof], vec![], &state.log);
+        state.log.take_read_error().map_err(log_io_error)?;
+        bundle.map_err(CoreServiceError::BundleFailure)
     }
 
     /// Constructs log inclusion proofs for the given entries at the given log tree root.
@@ -96,29 +138,47 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
         let proofs = entries
             .iter()
             .map(|&index| {
-                let node = if index < state.leaf_index.len() as RegistryIndex {
-                    state.leaf_index[index]
-                } else {
+                if index >= state.length() as RegistryIndex {
                     return Err(CoreServiceError::LeafNotFound(index));
-                };
-                Ok(state.log.prove_inclusion(node, log_length))
+                }
+                Ok(state.log.prove_inclusion(Node(index * 2), log_length))
             })
             .collect::<Result<Vec<_>, CoreServiceError>>()?;
 
-        LogProofBundle::bundle(vec![], proofs, &state.log).map_err(CoreServiceError::BundleFailure)
+        let bundle = LogProofBundle::bundle(vec![], proofs, &state.log);
+        state.log.take_read_error().map_err(log_io_error)?;
+        bundle.map_err(CoreServiceError::BundleFailure)
+    }
+
+    /// Gets the encoded hashes of a log tile with the given width.
+    pub async fn log_tile(&self, tile: TileId, width: usize) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.inner.state.read().await;
+
+        if width == 0 || width > tile.width_at(state.length()) {
+            return Err(CoreServiceError::TileNotFound(tile, width));
+        }
+
+        let hashes = tile.read(&state.log, width);
+        state.log.take_read_error().map_err(log_io_error)?;
+        hashes
+            .map(|hashes| encode_tile(&hashes))
+            .ok_or(CoreServiceError::TileNotFound(tile, width))
     }
 
     /// Constructs map inclusion proofs for the given entries at the given map tree root.
+    ///
+    /// If `multi_proof` is set, the proofs are combined into a single multi-proof.
     pub async fn map_inclusion_proofs(
         &self,
         log_length: RegistryLen,
         entries: &[RegistryIndex],
+        multi_proof: bool,
     ) -> Result<MapProofBundle<Digest, LogId, MapLeaf>, CoreServiceError> {
         let state = self.inner.state.read().await;
 
-        let (map_root, map) = state
-            .map_index
-            .get(&log_length)
+        let map_root = state
+            .map
+            .root_at(log_length)
             .ok_or_else(|| CoreServiceError::CheckpointNotFound(log_length))?;
 
         let indexes = self
@@ -133,17 +193,60 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
             .map(|log_leaf| {
                 let LogLeaf { log_id, record_id } = log_leaf;
 
-                let proof = map
-                    .prove(log_id.clone())
+                let proof = state
+                    .map
+                    .prove(log_length, log_id.clone())?
                     .ok_or_else(|| CoreServiceError::PackageNotIncluded(log_id.clone()))?;
 
                 let map_leaf = MapLeaf {
                     record_id: record_id.clone(),
                 };
                 let found_root = proof.evaluate(log_id, &map_leaf);
-                if &found_root != map_root {
+                if found_root != map_root {
+                    return Err(CoreServiceError::IncorrectProof {
+                        root: (&map_root).into(),
+                        found: found_root.into(),
+                    });
+                }
+
+                Ok((log_id.clone(), proof))
+            })
+            .collect::<Result<Vec<_>, CoreServiceError>>()?;
+
+        if multi_proof {
+            Ok(MapProofBundle::bundle_multi(proofs))
+        } else {
+            Ok(MapProofBundle::bundle(
+                proofs.into_iter().map(|(_, proof)| proof).collect(),
+            ))
+        }
+    }
+
+    /// Constructs map absence proofs for the given log IDs.
+    pub async fn map_absence_proofs(
+        &self,
+        log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<MapProofBundle<Digest, LogId, MapLeaf>, CoreServiceError> {
+        let state = self.inner.state.read().await;
+
+        let map_root = state
+            .map
+            .root_at(log_length)
+            .ok_or_else(|| CoreServiceError::CheckpointNotFound(log_length))?;
+
+        let proofs = log_ids
+            .iter()
+            .map(|log_id| {
+                let proof = state
+                    .map
+                    .prove_absence(log_length, log_id.clone())?
+                    .ok_or_else(|| CoreServiceError::PackageIncluded(log_id.clone()))?;
+
+                let found_root = proof.evaluate(log_id);
+                if found_root != map_root {
                     return Err(CoreServiceError::IncorrectProof {
-                        root: map_root.into(),
+                        root: (&map_root).into(),
                         found: found_root.into(),
                     });
                 }
@@ -152,7 +255,130 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
             })
             .collect::<Result<Vec<_>, CoreServiceError>>()?;
 
-        Ok(MapProofBundle::bundle(proofs))
+        Ok(MapProofBundle::bundle_absence(proofs))
+    }
+
+    /// Waits until a checkpoint with a log length greater than `log_length`
+    /// is stored or until the deadline elapses.
+    ///
+    /// Returns the log length of the latest checkpoint or `None` if the
+    /// deadline elapsed first.
+    pub async fn wait_for_checkpoint(
+        &self,
+        log_length: RegistryLen,
+        deadline: Instant,
+    ) -> Option<RegistryLen> {
+        let mut checkpoint_rx = self.inner.checkpoint_tx.subscribe();
+        let res = tokio::time::timeout_at(
+            deadline,
+            checkpoint_rx.wait_for(|&latest| latest > log_length),
+        )
+        .await;
+
+        match res {
+            Ok(Ok(latest)) => Some(*latest),
+            _ => None,
+        }
+    }
+
+    /// Gets the root of the registry log at the given log length.
+    ///
+    /// Returns `None` if the given log length is beyond the latest checkpoint.
+    pub async fn log_root_at(&self, log_length: RegistryLen) -> Option<AnyHash> {
+        if log_length > self.checkpoint_length() {
+            return None;
+        }
+
+        let state = self.inner.state.read().await;
+        state.log.root_at(log_length).map(Into::into)
+    }
+
+    /// Determines if the service mirrors another registry.
+    pub fn is_mirror(&self) -> bool {
+        self.inner.operator_key.is_none()
+    }
+
+    /// Gets the number of entries in the registry log.
+    ///
+    /// This may include entries that are not yet part of a checkpoint.
+    pub async fn log_length(&self) -> RegistryLen {
+        self.inner.state.read().await.length() as RegistryLen
+    }
+
+    /// Gets the log length of the latest stored checkpoint.
+    pub fn checkpoint_length(&self) -> RegistryLen {
+        *self.inner.checkpoint_tx.borrow()
+    }
+
+    /// Verifies that appending entries mirrored from another registry
+    /// results in the given checkpoint of that registry.
+    ///
+    /// The registry state is not modified.
+    pub async fn verify_mirrored_checkpoint(
+        &self,
+        entries: &[LogLeaf],
+        checkpoint: &Checkpoint,
+    ) -> Result<(), CoreServiceError> {
+        let mut state = self.inner.state.write().await;
+        state.verify_extension(entries, checkpoint)
+    }
+
+    /// Appends entries mirrored from another registry and stores the
+    /// checkpoint of that registry which covers them.
+    ///
+    /// The records of the given entries must already be stored and validated
+    /// against their logs, and the checkpoint's signature must already be
+    /// verified against the mirrored registry's operator log. Nothing is
+    /// committed unless the checkpoint matches the registry state resulting
+    /// from the entries.
+    pub async fn apply_mirrored_checkpoint(
+        &self,
+        entries: &[LogLeaf],
+        ts_checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), CoreServiceError> {
+        let operator_log_id = LogId::operator_log::<Digest>();
+        let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+
+        {
+            let mut state = self.inner.state.write().await;
+
+            // Verify the checkpoint before anything is committed
+            state.verify_extension(entries, checkpoint)?;
+
+            for entry in entries {
+                let LogLeaf { log_id, record_id } = entry;
+                let registry_index = state.length() as RegistryIndex;
+                if log_id == &operator_log_id {
+                    self.inner
+                        .store
+                        .commit_operator_record(log_id, record_id, registry_index)
+                        .await?;
+                } else {
+                    self.inner
+                        .store
+                        .commit_package_record(log_id, record_id, registry_index)
+                        .await?;
+                }
+
+                state.push_entry(entry.clone()).map_err(map_io_error)?;
+            }
+
+            state.checkpoint().map_err(map_io_error)?;
+
+            if let Some(retain) = self.inner.map_retention {
+                if let Err(err) = state.prune_map(retain) {
+                    tracing::warn!("Error pruning map snapshots: {err}");
+                }
+            }
+
+            state.log.flush().map_err(log_io_error)?;
+        }
+
+        let checkpoint_id = Hash::<Digest>::of(checkpoint).into();
+        self.inner
+            .store_checkpoint(&checkpoint_id, ts_checkpoint)
+            .await?;
+        Ok(())
     }
 
     /// Gets the data store associated with the transparency service.
@@ -160,61 +386,132 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
         self.inner.store.as_ref()
     }
 
+    /// Determines if the service accepts package records for processing.
+    ///
+    /// A service stops accepting submissions if it fails to apply a record.
+    pub fn is_accepting_submissions(&self) -> bool {
+        !self.submit_entry_tx.is_closed()
+    }
+
     /// Submits a package record to be processed.
-    pub async fn submit_package_record(&self, log_id: LogId, record_id: RecordId) {
+    pub async fn submit_package_record(
+        &self,
+        log_id: LogId,
+        record_id: RecordId,
+    ) -> Result<(), CoreServiceError> {
         self.submit_entry_tx
             .send(LogLeaf { log_id, record_id })
             .await
-            .unwrap()
+            .map_err(|_| CoreServiceError::NotAcceptingSubmissions)
     }
 }
 
 struct Inner<Digest: SupportedDigest> {
-    // Operator signing key
-    operator_key: PrivateKey,
+    // Operator signing key; not set when mirroring another registry
+    operator_key: Option<PrivateKey>,
 
     // DataStore persists transparency state.
     store: Box<dyn DataStore>,
 
     // In-memory transparency state.
     state: RwLock<State<Digest>>,
+
+    // Notifies watchers of the log length of the latest stored checkpoint.
+    checkpoint_tx: watch::Sender<RegistryLen>,
+
+    // The number of recent checkpoints to retain map snapshots for.
+    map_retention: Option<usize>,
 }
 
 impl<Digest: SupportedDigest> Inner<Digest> {
-    // Load state from DataStore or initialize empty state, returning any
-    // entries that are not yet part of a checkpoint.
+    // Load state from DataStore or initialize empty state.
+    //
+    // A persisted log and map are resumed from the latest checkpoint they
+    // both contain, so only the entries after it are replayed.
     async fn initialize(
         &mut self,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     ) -> Result<(), CoreServiceError> {
         tracing::debug!("Initializing CoreService");
 
-        let published = self.store.get_all_validated_records().await?.peekable();
-        pin_mut!(published);
-
         // If there are no published records, initialize a new state
-        if published.as_mut().peek().await.is_none() {
+        if self
+            .store
+            .get_log_leafs_starting_with_registry_index(0, 1)
+            .await?
+            .is_empty()
+        {
             tracing::debug!("No existing records; initializing new state");
+            let state = self.state.get_mut();
+            state.truncate_log(0).map_err(log_io_error)?;
+            state.truncate_map(0).map_err(map_io_error)?;
+            if self.operator_key.is_none() {
+                // Mirrors start empty until the first checkpoint is mirrored
+                return Ok(());
+            }
             return self.initialize_new(namespaces).await;
         }
 
-        // Reconstruct internal state from previously-stored data
         let mut checkpoints = self.store.get_all_checkpoints().await?;
         let mut checkpoints_by_len: IndexMap<RegistryLen, Checkpoint> = Default::default();
         while let Some(checkpoint) = checkpoints.next().await {
             let checkpoint = checkpoint?.checkpoint;
             checkpoints_by_len.insert(checkpoint.log_length, checkpoint);
         }
+        let checkpointed = checkpoints_by_len.keys().max().copied().unwrap_or_default();
+        self.checkpoint_tx.send_replace(checkpointed);
 
+        // Resume from the latest checkpoint retained by both the persisted log and map
         let state = self.state.get_mut();
-        while let Some(entry) = published.next().await {
-            state.push_entry(entry?);
-            if let Some(stored_checkpoint) =
-                checkpoints_by_len.get(&(state.log.length() as RegistryLen))
+        let resume = state
+            .map
+            .versions()
+            .into_iter()
+            .filter(|&version| {
+                version <= state.log.length() && checkpoints_by_len.contains_key(&version)
+            })
+            .max()
+            .unwrap_or_default();
+        if let Some(checkpoint) = checkpoints_by_len.get(&resume) {
+            let log_root = state.log.root_at(resume).map(Into::into);
+            let map_root = state.map.root_at(resume).map(Into::into);
+            if log_root.as_ref() != Some(&checkpoint.log_root)
+                || map_root.as_ref() != Some(&checkpoint.map_root)
             {
-                // Validate stored checkpoint (and update internal state as a side-effect)
-                let computed_checkpoint = state.checkpoint();
-                assert!(stored_checkpoint == &computed_checkpoint);
+                return Err(CoreServiceError::InitializationFailure(format!(
+                    "persisted log and map do not match the stored checkpoint at log length {resume}"
+                )));
+            }
+        }
+        state.truncate_log(resume).map_err(log_io_error)?;
+        state.truncate_map(resume).map_err(map_io_error)?;
+        state.length = resume;
+        if resume > 0 {
+            tracing::debug!("Resuming from persisted log and map at log length {resume}");
+        }
+
+        // Replay the entries after the resumed checkpoint
+        loop {
+            let leafs = self
+                .store
+                .get_log_leafs_starting_with_registry_index(state.length(), REPLAY_BATCH_SIZE)
+                .await?;
+            if leafs.is_empty() {
+                break;
+            }
+
+            for (_, entry) in leafs {
+                state.push_entry(entry).map_err(map_io_error)?;
+                let length = state.length() as RegistryLen;
+                if let Some(stored_checkpoint) = checkpoints_by_len.get(&length) {
+                    // Validate stored checkpoint (and update internal state as a side-effect)
+                    let computed_checkpoint = state.checkpoint().map_err(map_io_error)?;
+                    if stored_checkpoint != &computed_checkpoint {
+                        return Err(CoreServiceError::InitializationFailure(format!(
+                            "stored checkpoint at log length {length} does not match the registry log"
+                        )));
+                    }
+                }
             }
         }
 
@@ -226,11 +523,15 @@ impl<Digest: SupportedDigest> Inner<Digest> {
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     ) -> Result<(), CoreServiceError> {
         let state = self.state.get_mut();
+        let operator_key = self
+            .operator_key
+            .as_ref()
+            .expect("a new registry requires an operator key");
 
         // Construct operator init record
         let init = operator::OperatorEntry::Init {
             hash_algorithm: Digest::ALGORITHM,
-            key: self.operator_key.public_key(),
+            key: operator_key.public_key(),
         };
         let entries = if let Some(namespaces) = namespaces {
             let mut entries = Vec::with_capacity(1 + namespaces.len());
@@ -259,8 +560,7 @@ impl<Digest: SupportedDigest> Inner<Digest> {
             timestamp: SystemTime::now(),
             entries,
         };
-        let signed_init_record =
-            ProtoEnvelope::signed_contents(&self.operator_key, init_record).unwrap();
+        let signed_init_record = ProtoEnvelope::signed_contents(operator_key, init_record).unwrap();
         let log_id = LogId::operator_log::<Digest>();
         let record_id = RecordId::operator_record::<Digest>(&signed_init_record);
 
@@ -273,7 +573,9 @@ impl<Digest: SupportedDigest> Inner<Digest> {
             .await?;
 
         // Update state with init record
-        state.push_entry(LogLeaf { log_id, record_id });
+        state
+            .push_entry(LogLeaf { log_id, record_id })
+            .map_err(map_io_error)?;
 
         // "zero" checkpoint to be updated
         let mut checkpoint = Checkpoint {
@@ -291,12 +593,17 @@ impl<Digest: SupportedDigest> Inner<Digest> {
         self: Arc<Self>,
         mut submit_entry_rx: mpsc::Receiver<LogLeaf>,
         checkpoint_interval: Duration,
-    ) {
+    ) -> Result<(), CoreServiceError> {
+        if self.operator_key.is_none() {
+            // Mirrors do not accept submissions or sign their own checkpoints
+            while submit_entry_rx.recv().await.is_some() {}
+            return Ok(());
+        }
+
         let mut checkpoint = self
             .store
             .get_latest_checkpoint()
-            .await
-            .unwrap()
+            .await?
             .into_contents()
             .checkpoint;
 
@@ -306,23 +613,36 @@ impl<Digest: SupportedDigest> Inner<Digest> {
         loop {
             tokio::select! {
                 entry = submit_entry_rx.recv() => match entry {
-                    Some(entry) => self.process_package_entry(&entry).await,
-                    None => break, // Channel closed
+                    Some(entry) => {
+                        if let Err(err) = self.process_package_entry(&entry).await {
+                            // The entry was committed, so the state can no longer
+                            // be kept consistent with the store; closing the
+                            // channel rejects further submissions
+                            tracing::error!(
+                                "failed to apply entry {entry:?}; no longer accepting submissions: {err}"
+                            );
+                            return Err(err);
+                        }
+                    }
+                    None => return Ok(()), // Channel closed
                 },
                 _ = checkpoint_interval.tick() => self.update_checkpoint(&mut checkpoint).await,
             }
         }
     }
 
-    // Processes a submitted package entry
-    async fn process_package_entry(&self, entry: &LogLeaf) {
+    // Processes a submitted package entry.
+    //
+    // Returns an error if the entry was committed but could not be applied
+    // to the registry state.
+    async fn process_package_entry(&self, entry: &LogLeaf) -> Result<(), CoreServiceError> {
         tracing::debug!("Processing entry {entry:?}");
 
         let mut state = self.state.write().await;
         let LogLeaf { log_id, record_id } = entry;
 
         // Validate and commit the package entry to the store
-        let registry_index = state.log.length() as RegistryIndex;
+        let registry_index = state.length() as RegistryIndex;
         let commit_res = self
             .store
             .commit_package_record(log_id, record_id, registry_index)
@@ -342,10 +662,10 @@ impl<Digest: SupportedDigest> Inner<Digest> {
                     tracing::error!("failed to validate package record `{record_id}`: {e}");
                 }
             }
-            return;
+            return Ok(());
         }
 
-        state.push_entry(entry.clone());
+        state.push_entry(entry.clone()).map_err(map_io_error)
     }
 
     // Store a checkpoint including the given new entries
@@ -353,9 +673,27 @@ impl<Digest: SupportedDigest> Inner<Digest> {
         {
             // Recalculate the checkpoint if necessary
             let mut state = self.state.write().await;
-            if state.log.length() as RegistryLen != checkpoint.log_length {
-                *checkpoint = state.checkpoint();
+            if state.length() as RegistryLen != checkpoint.log_length {
+                *checkpoint = match state.checkpoint() {
+                    Ok(checkpoint) => checkpoint,
+                    Err(err) => {
+                        tracing::error!("Error persisting map for checkpoint: {err}");
+                        return;
+                    }
+                };
                 tracing::debug!("Updating to checkpoint {checkpoint:?}");
+
+                if let Some(retain) = self.map_retention {
+                    if let Err(err) = state.prune_map(retain) {
+                        tracing::warn!("Error pruning map snapshots: {err}");
+                    }
+                }
+            }
+
+            // Persist the log before the checkpoint is published
+            if let Err(err) = state.log.flush() {
+                tracing::error!("Error persisting log for checkpoint {checkpoint:?}: {err}");
+                return;
             }
         }
 
@@ -365,71 +703,435 @@ impl<Digest: SupportedDigest> Inner<Digest> {
     }
 
     async fn sign_and_store_checkpoint(&self, checkpoint: Checkpoint) -> anyhow::Result<()> {
+        let operator_key = self
+            .operator_key
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("mirrors cannot sign checkpoints"))?;
         let checkpoint_id = Hash::<Digest>::of(&checkpoint).into();
-        let timestamped = TimestampedCheckpoint::now(checkpoint.clone())?;
-        let signed = SerdeEnvelope::signed_contents(&self.operator_key, timestamped)?;
-        self.store.store_checkpoint(&checkpoint_id, signed).await?;
+        let timestamped = TimestampedCheckpoint::now(checkpoint)?;
+        let signed = SerdeEnvelope::signed_contents(operator_key, timestamped)?;
+        self.store_checkpoint(&checkpoint_id, signed).await?;
+        Ok(())
+    }
+
+    async fn store_checkpoint(
+        &self,
+        checkpoint_id: &AnyHash,
+        ts_checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), DataStoreError> {
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        self.store
+            .store_checkpoint(checkpoint_id, ts_checkpoint)
+            .await?;
+        self.checkpoint_tx.send_if_modified(|latest| {
+            if *latest == log_length {
+                return false;
+            }
+            *latest = log_length;
+            true
+        });
         Ok(())
     }
 }
 
 type VerifiableMap<Digest> = Map<Digest, LogId, MapLeaf>;
 
-#[derive(Default)]
 struct State<Digest: SupportedDigest> {
     // The verifiable log of all package log entries
-    log: VecLog<Digest, LogLeaf>,
-    // Index log tree nodes by registry log index of the record
-    leaf_index: Vec<Node>,
-
+    log: RegistryLog<Digest>,
     // The verifiable map of package logs' latest entries (log_id -> record_id)
-    map: VerifiableMap<Digest>,
-    // Index verifiable map snapshots by log length (at checkpoints only)
-    map_index: IndexMap<RegistryLen, (Hash<Digest>, VerifiableMap<Digest>)>,
+    map: RegistryMap<Digest>,
+    // The number of entries in the registry log
+    length: usize,
+    // The number of entries applied to the map; a persisted map may be ahead
+    // of the registry while entries are replayed on startup
+    map_length: usize,
 }
 
 impl<Digest: SupportedDigest> State<Digest> {
-    fn push_entry(&mut self, log_leaf: LogLeaf) {
-        let node = self.log.push(&log_leaf);
-        self.leaf_index.push(node);
+    fn new(log: RegistryLog<Digest>, map: RegistryMap<Digest>) -> Self {
+        let map_length = map.latest_version();
+        Self {
+            log,
+            map,
+            length: 0,
+            map_length,
+        }
+    }
 
-        let LogLeaf { log_id, record_id } = log_leaf;
-        self.map = self.map.insert(log_id, MapLeaf { record_id });
+    // The number of entries in the registry log
+    fn length(&self) -> usize {
+        self.length
     }
 
-    fn checkpoint(&mut self) -> Checkpoint {
-        let log_checkpoint = self.log.checkpoint();
-        let map_root = self.map.root();
-        let log_length = log_checkpoint.length() as RegistryLen;
+    fn push_entry(&mut self, log_leaf: LogLeaf) -> std::io::Result<()> {
+        if self.length == self.log.length() {
+            self.log.push(&log_leaf);
+        }
+        if self.length == self.map_length {
+            let LogLeaf { log_id, record_id } = log_leaf;
+            self.map.insert(log_id, MapLeaf { record_id })?;
+            self.map_length += 1;
+        }
+        self.length += 1;
+        Ok(())
+    }
+
+    fn truncate_log(&mut self, length: usize) -> std::io::Result<()> {
+        self.log.truncate(length)?;
+        self.length = self.length.min(length);
+        Ok(())
+    }
+
+    fn truncate_map(&mut self, length: usize) -> std::io::Result<()> {
+        self.map.truncate(length)?;
+        self.map_length = self.map.latest_version();
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> std::io::Result<Checkpoint> {
+        if self.map_length != self.length {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "map of length {map_length} is not up to date with the registry log of length {length}",
+                    map_length = self.map_length,
+                    length = self.length
+                ),
+            ));
+        }
+        let log_root = self.log.root_at(self.length).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "log of length {log_length} is behind the registry log of length {length}",
+                    log_length = self.log.length(),
+                    length = self.length
+                ),
+            )
+        })?;
+        let log_length = self.length as RegistryLen;
 
         // Update map snapshot
         if log_length > 0 {
-            self.map_index
-                .insert(log_length, (map_root.clone(), self.map.clone()));
+            self.map.snapshot(self.length)?;
         }
 
-        Checkpoint {
+        Ok(Checkpoint {
             log_length,
-            log_root: log_checkpoint.root().into(),
-            map_root: map_root.into(),
+            log_root: log_root.into(),
+            map_root: self.map.root().into(),
+        })
+    }
+
+    // Verifies that extending the registry with the given entries results in
+    // the given checkpoint, without modifying the registry
+    fn verify_extension(
+        &mut self,
+        entries: &[LogLeaf],
+        checkpoint: &Checkpoint,
+    ) -> Result<(), CoreServiceError> {
+        if self.length + entries.len() != checkpoint.log_length
+            || &self.extended_checkpoint(entries).map_err(map_io_error)? != checkpoint
+        {
+            return Err(CoreServiceError::CheckpointMismatch(checkpoint.log_length));
+        }
+        Ok(())
+    }
+
+    // Computes the checkpoint of the registry extended with the given
+    // entries without modifying the registry
+    fn extended_checkpoint(&mut self, entries: &[LogLeaf]) -> std::io::Result<Checkpoint> {
+        if self.map_length != self.length {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "map should be up to date with the log",
+            ));
+        }
+
+        let mut log =
+            StackLog::<Digest, LogLeaf>::resume(&self.log, self.length).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "log is missing the roots of its balanced subtrees",
+                )
+            })?;
+        for entry in entries {
+            log.push(entry);
+        }
+
+        Ok(Checkpoint {
+            log_length: log.length() as RegistryLen,
+            log_root: log.checkpoint().root().into(),
+            map_root: self.map.extended_root(entries)?.into(),
+        })
+    }
+
+    // Prunes map snapshots once more than twice the number to retain have
+    // accumulated, so that pruning a persisted map is amortized
+    fn prune_map(&mut self, retain: usize) -> std::io::Result<()> {
+        let versions = self.map.versions();
+        let retain = retain.max(1);
+        if versions.len() > retain * 2 {
+            self.map.prune(versions[versions.len() - retain])?;
+        }
+        Ok(())
+    }
+}
+
+// The verifiable map of the registry, kept in memory or persisted to disk
+enum RegistryMap<Digest: SupportedDigest> {
+    Memory {
+        map: VerifiableMap<Digest>,
+        // Index verifiable map snapshots by log length (at checkpoints only)
+        index: IndexMap<usize, VerifiableMap<Digest>>,
+    },
+    File(FileMap<Digest, LogId, MapLeaf>),
+}
+
+impl<Digest: SupportedDigest> RegistryMap<Digest> {
+    // The log length of the latest snapshot of the map
+    fn latest_version(&self) -> usize {
+        match self {
+            Self::Memory { index, .. } => index.last().map(|(len, _)| *len),
+            Self::File(map) => map.latest_version(),
+        }
+        .unwrap_or_default()
+    }
+
+    fn versions(&self) -> Vec<usize> {
+        match self {
+            Self::Memory { index, .. } => index.keys().copied().collect(),
+            Self::File(map) => map.versions().collect(),
+        }
+    }
+
+    fn root(&self) -> Hash<Digest> {
+        match self {
+            Self::Memory { map, .. } => map.root().clone(),
+            Self::File(map) => map.root().clone(),
+        }
+    }
+
+    fn root_at(&self, length: usize) -> Option<Hash<Digest>> {
+        match self {
+            Self::Memory { index, .. } => index.get(&length).map(|map| map.root().clone()),
+            Self::File(map) => map.root_at(length).cloned(),
+        }
+    }
+
+    fn insert(&mut self, log_id: LogId, leaf: MapLeaf) -> std::io::Result<()> {
+        match self {
+            Self::Memory { map, .. } => {
+                *map = map.insert(log_id, leaf);
+                Ok(())
+            }
+            Self::File(map) => map.insert(log_id, leaf),
+        }
+    }
+
+    // Computes the root of the current map with the given entries inserted,
+    // leaving the current map unchanged
+    fn extended_root(&mut self, entries: &[LogLeaf]) -> std::io::Result<Hash<Digest>> {
+        let leafs = entries.iter().map(|LogLeaf { log_id, record_id }| {
+            (
+                log_id.clone(),
+                MapLeaf {
+                    record_id: record_id.clone(),
+                },
+            )
+        });
+        match self {
+            Self::Memory { map, .. } => Ok(leafs
+                .fold(map.clone(), |map, (log_id, leaf)| map.insert(log_id, leaf))
+                .root()
+                .clone()),
+            Self::File(map) => {
+                // Inserts are pending until a snapshot, so they are discarded
+                // by resetting to the latest snapshot
+                let version = map.latest_version().unwrap_or_default();
+                map.extend(leafs)?;
+                let root = map.root().clone();
+                map.truncate(version)?;
+                Ok(root)
+            }
         }
     }
+
+    fn snapshot(&mut self, length: usize) -> std::io::Result<()> {
+        match self {
+            Self::Memory { map, index } => {
+                index.insert(length, map.clone());
+                Ok(())
+            }
+            Self::File(map) => map.snapshot(length),
+        }
+    }
+
+    fn truncate(&mut self, length: usize) -> std::io::Result<()> {
+        match self {
+            Self::Memory { map, index } => {
+                index.retain(|len, _| *len <= length);
+                *map = index.last().map(|(_, map)| map.clone()).unwrap_or_default();
+                Ok(())
+            }
+            Self::File(map) => map.truncate(length),
+        }
+    }
+
+    fn prune(&mut self, before: usize) -> std::io::Result<()> {
+        match self {
+            Self::Memory { index, .. } => {
+                index.retain(|len, _| *len >= before);
+                Ok(())
+            }
+            Self::File(map) => map.prune(before),
+        }
+    }
+
+    fn prove(
+        &self,
+        length: usize,
+        log_id: LogId,
+    ) -> Result<Option<Proof<Digest, LogId, MapLeaf>>, CoreServiceError> {
+        match self {
+            Self::Memory { index, .. } => Ok(index
+                .get(&length)
+                .ok_or(CoreServiceError::CheckpointNotFound(length as RegistryLen))?
+                .prove(log_id)),
+            Self::File(map) => map.prove(length, log_id).map_err(map_io_error),
+        }
+    }
+
+    fn prove_absence(
+        &self,
+        length: usize,
+        log_id: LogId,
+    ) -> Result<Option<AbsenceProof<Digest, LogId>>, CoreServiceError> {
+        match self {
+            Self::Memory { index, .. } => Ok(index
+                .get(&length)
+                .ok_or(CoreServiceError::CheckpointNotFound(length as RegistryLen))?
+                .prove_absence(log_id)),
+            Self::File(map) => map.prove_absence(length, log_id).map_err(map_io_error),
+        }
+    }
+}
+
+// The verifiable log of the registry, kept in memory or persisted to disk
+enum RegistryLog<Digest: SupportedDigest> {
+    Memory(VecLog<Digest, LogLeaf>),
+    File(FileLog<Digest, LogLeaf>),
+}
+
+impl<Digest: SupportedDigest> RegistryLog<Digest> {
+    fn length(&self) -> usize {
+        match self {
+            Self::Memory(log) => log.length(),
+            Self::File(log) => log.length(),
+        }
+    }
+
+    fn root_at(&self, length: usize) -> Option<Hash<Digest>> {
+        if length == self.length() {
+            return Some(self.checkpoint().root());
+        }
+
+        match self {
+            Self::Memory(log) => log.root_at(length),
+            Self::File(log) => log.root_at(length),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::File(log) => log.flush(),
+        }
+    }
+
+    fn take_read_error(&self) -> std::io::Result<()> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::File(log) => log.take_read_error(),
+        }
+    }
+
+    fn truncate(&mut self, length: usize) -> std::io::Result<()> {
+        match self {
+            // The in-memory log is always empty when truncated
+            Self::Memory(_) => Ok(()),
+            Self::File(log) => log.truncate(length),
+        }
+    }
+}
+
+impl<Digest: SupportedDigest> LogBuilder<Digest, LogLeaf> for RegistryLog<Digest> {
+    fn checkpoint(&self) -> LogCheckpoint<Digest> {
+        match self {
+            Self::Memory(log) => log.checkpoint(),
+            Self::File(log) => log.checkpoint(),
+        }
+    }
+
+    fn push(&mut self, entry: &LogLeaf) -> Node {
+        match self {
+            Self::Memory(log) => log.push(entry),
+            Self::File(log) => log.push(entry),
+        }
+    }
+}
+
+impl<Digest: SupportedDigest> LogData<Digest, LogLeaf> for RegistryLog<Digest> {
+    fn hash_for(&self, node: Node) -> Option<Hash<Digest>> {
+        match self {
+            Self::Memory(log) => log.hash_for(node),
+            Self::File(log) => log.hash_for(node),
+        }
+    }
+
+    fn has_hash(&self, node: Node) -> bool {
+        match self {
+            Self::Memory(log) => log.has_hash(node),
+            Self::File(log) => log.has_hash(node),
+        }
+    }
+}
+
+fn log_io_error(e: std::io::Error) -> CoreServiceError {
+    CoreServiceError::LogFailure(e)
+}
+
+fn map_io_error(e: std::io::Error) -> CoreServiceError {
+    CoreServiceError::MapFailure(e)
 }
 
 #[derive(Debug, Error)]
 pub enum CoreServiceError {
     #[error("checkpoint at log length `{0}` was not found")]
     CheckpointNotFound(RegistryLen),
+    #[error("log tile `{0}` with width {1} was not found")]
+    TileNotFound(TileId, usize),
     #[error("log leaf `{0}` was not found")]
     LeafNotFound(RegistryIndex),
     #[error("failed to bundle proofs: `{0}`")]
     BundleFailure(anyhow::Error),
     #[error("failed to prove inclusion of package `{0}`")]
     PackageNotIncluded(LogId),
+    #[error("failed to prove absence of package `{0}`")]
+    PackageIncluded(LogId),
     #[error("failed to prove inclusion: found root `{found}` but was given root `{root}`")]
     IncorrectProof { root: AnyHash, found: AnyHash },
+    #[error("failed to access persisted log: {0}")]
+    LogFailure(std::io::Error),
+    #[error("failed to access persisted map: {0}")]
+    MapFailure(std::io::Error),
     #[error("data store error: {0}")]
     DataStore(#[from] DataStoreError),
+    #[error("mirrored checkpoint at log length `{0}` does not match the mirrored records")]
+    CheckpointMismatch(RegistryLen),
     #[error("initialization failed: {0}")]
     InitializationFailure(String),
+    #[error("the registry is no longer accepting submissions")]
+    NotAcceptingSubmissions,
 }
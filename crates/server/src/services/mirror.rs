@@ -0,0 +1,488 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use futures::StreamExt;
+use indexmap::{IndexMap, IndexSet};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use url::Url;
+use warg_api::v1::{
+    fetch::{
+        FetchLogsRequest, FetchLogsResponse, FetchPackageNamesRequest, FetchPackageNamesResponse,
+    },
+    ledger::{LedgerSourceContentType, LedgerSourcesResponse},
+    paths,
+};
+use warg_crypto::{
+    hash::{AnyHash, HashAlgorithm, Sha256},
+    Encode, Signable,
+};
+use warg_protocol::{
+    operator, package,
+    registry::{LogId, LogLeaf, PackageName, RecordId, RegistryLen, TimestampedCheckpoint},
+    ProtoEnvelope, ProtoEnvelopeBody, Record as _, SerdeEnvelope,
+};
+
+use super::CoreService;
+use crate::datastore::DataStoreError;
+
+const MAX_RECORDS_LIMIT: u16 = 1000;
+
+/// Replicates the ledger of an upstream registry into a mirror's
+/// [`CoreService`].
+///
+/// Every record is fetched from the upstream registry and validated by the
+/// mirror's data store; an upstream checkpoint is only stored once its
+/// signature is verified against the mirrored operator log and the mirror's
+/// own log and map match it.
+pub struct MirrorService {
+    core: CoreService,
+    upstream: Url,
+    client: reqwest::Client,
+    // The fetch token (last record) of each mirrored log
+    fetch_tokens: IndexMap<LogId, RecordId>,
+    // The state of the mirrored operator log
+    operator: operator::LogState,
+}
+
+impl MirrorService {
+    /// Creates a new mirror service for the given upstream registry.
+    pub async fn new(core: CoreService, mut upstream: Url) -> Result<Self> {
+        // Ensure API paths are joined to the upstream URL rather than replacing its last segment
+        if !upstream.path().ends_with('/') {
+            upstream.set_path(&format!("{path}/", path = upstream.path()));
+        }
+
+        let mut fetch_tokens = IndexMap::new();
+        let mut leafs = core.store().get_all_validated_records().await?;
+        while let Some(leaf) = leafs.next().await {
+            let LogLeaf { log_id, record_id } = leaf?;
+            fetch_tokens.insert(log_id, record_id);
+        }
+
+        let operator = operator_state(&core).await?;
+
+        Ok(Self {
+            core,
+            upstream,
+            client: reqwest::Client::new(),
+            fetch_tokens,
+            operator,
+        })
+    }
+
+    /// Synchronizes with the upstream registry every interval until aborted.
+    pub fn spawn(mut self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.sync().await {
+                    tracing::error!(
+                        "failed to mirror `{upstream}`: {e:?}",
+                        upstream = self.upstream
+                    );
+                }
+            }
+        })
+    }
+
+    /// Mirrors the latest checkpoint of the upstream registry.
+    ///
+    /// Returns the log length of the latest mirrored checkpoint.
+    pub async fn sync(&mut self) -> Result<RegistryLen> {
+        let ts_checkpoint: SerdeEnvelope<TimestampedCheckpoint> =
+            self.get(paths::fetch_checkpoint()).await?;
+        let log_length = ts_checkpoint.as_ref().checkpoint.log_length;
+        if log_length <= self.core.checkpoint_length() {
+            return Ok(self.core.checkpoint_length());
+        }
+
+        let start = self.core.log_length().await;
+        ensure!(
+            start <= log_length,
+            "upstream checkpoint log length {log_length} is behind the mirrored log length {start}"
+        );
+
+        tracing::debug!(
+            "mirroring entries {start}..{log_length} of `{upstream}`",
+            upstream = self.upstream
+        );
+        let entries = self.ledger_entries(start, log_length).await?;
+        let mut records = self.fetch_records(&entries, log_length).await?;
+        let names = self.fetch_package_names(&entries).await?;
+
+        // Verify the checkpoint against the operator log it covers before storing anything
+        let mut operator = self.operator.clone();
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.log_id == LogId::operator_log::<Sha256>())
+        {
+            let record = records.get(&entry.record_id).ok_or_else(|| {
+                anyhow!(
+                    "upstream did not provide record `{id}`",
+                    id = entry.record_id
+                )
+            })?;
+            let record = ProtoEnvelope::<operator::OperatorRecord>::try_from(record.clone())?;
+            operator = operator.validate(&record)?;
+        }
+        verify_checkpoint_signature(&operator, &ts_checkpoint)?;
+        self.core
+            .verify_mirrored_checkpoint(&entries, &ts_checkpoint.as_ref().checkpoint)
+            .await?;
+
+        // Validate every package record before committing anything so that the
+        // data store does not reject a record partway through the batch
+        let mut packages: IndexMap<LogId, package::LogState> = IndexMap::new();
+        for entry in entries
+            .iter()
+            .filter(|entry| entry.log_id != LogId::operator_log::<Sha256>())
+        {
+            let LogLeaf { log_id, record_id } = entry;
+            if !packages.contains_key(log_id) {
+                let state = package_state(&self.core, log_id).await?;
+                packages.insert(log_id.clone(), state);
+            }
+
+            let record = records
+                .get(record_id)
+                .ok_or_else(|| anyhow!("upstream did not provide record `{record_id}`"))?;
+            let record = ProtoEnvelope::<package::PackageRecord>::try_from(record.clone())?;
+            let state = packages.get_mut(log_id).unwrap();
+            *state = std::mem::take(state)
+                .validate(&record)
+                .with_context(|| format!("upstream record `{record_id}` is invalid"))?;
+        }
+
+        for entry in &entries {
+            let LogLeaf { log_id, record_id } = entry;
+            let record = records
+                .swap_remove(record_id)
+                .ok_or_else(|| anyhow!("upstream did not provide record `{record_id}`"))?;
+
+            if log_id == &LogId::operator_log::<Sha256>() {
+                let record = ProtoEnvelope::<operator::OperatorRecord>::try_from(record)?;
+                self.core
+                    .store()
+                    .store_operator_record(log_id, record_id, &record)
+                    .await?;
+            } else {
+                let name = names
+                    .get(log_id)
+                    .ok_or_else(|| anyhow!("upstream did not provide a name for log `{log_id}`"))?;
+                let record = ProtoEnvelope::<package::PackageRecord>::try_from(record)?;
+
+                // Content is fetched from the upstream registry on demand
+                let missing = record.as_ref().contents();
+                self.core
+                    .store()
+                    .store_package_record(log_id, name, record_id, &record, &missing)
+                    .await?;
+            }
+        }
+
+        self.core
+            .apply_mirrored_checkpoint(&entries, ts_checkpoint)
+            .await?;
+
+        for LogLeaf { log_id, record_id } in entries {
+            self.fetch_tokens.insert(log_id, record_id);
+        }
+        self.operator = operator;
+
+        Ok(log_length)
+    }
+
+    // Walks the upstream ledger for the entries in the given range
+    async fn ledger_entries(&self, start: RegistryLen, end: RegistryLen) -> Result<Vec<LogLeaf>> {
+        let mut entries = Vec::with_capacity(end - start);
+        if start == end {
+            return Ok(entries);
+        }
+
+        let response: LedgerSourcesResponse = self.get(paths::ledger_sources()).await?;
+        ensure!(
+            response.hash_algorithm == HashAlgorithm::Sha256,
+            "unsupported upstream ledger hash algorithm `{algorithm}`",
+            algorithm = response.hash_algorithm
+        );
+
+        for source in response.sources {
+            if source.last_registry_index < start || source.first_registry_index >= end {
+                continue;
+            }
+            ensure!(
+                source.content_type == LedgerSourceContentType::Packed,
+                "unsupported upstream ledger source content type `{content_type:?}`",
+                content_type = source.content_type
+            );
+
+            let bytes = self
+                .client
+                .get(self.url(&source.url)?)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            ensure!(
+                bytes.len() % 64 == 0,
+                "upstream ledger source `{url}` has an invalid length of {len} bytes",
+                url = source.url,
+                len = bytes.len()
+            );
+
+            let leafs = bytes.chunks_exact(64).map(|chunk| LogLeaf {
+                log_id: AnyHash::new(HashAlgorithm::Sha256, chunk[..32].to_vec()).into(),
+                record_id: AnyHash::new(HashAlgorithm::Sha256, chunk[32..].to_vec()).into(),
+            });
+            for (index, leaf) in (source.first_registry_index..).zip(leafs) {
+                if index == start + entries.len() && index < end {
+                    entries.push(leaf);
+                }
+            }
+        }
+
+        ensure!(
+            entries.len() == end - start,
+            "upstream ledger is missing entries {first}..{end}",
+            first = start + entries.len()
+        );
+        Ok(entries)
+    }
+
+    // Fetches the records of the given entries, keyed by record identifier
+    async fn fetch_records(
+        &self,
+        entries: &[LogLeaf],
+        log_length: RegistryLen,
+    ) -> Result<IndexMap<RecordId, ProtoEnvelopeBody>> {
+        let operator_log_id = LogId::operator_log::<Sha256>();
+        let token = |log_id: &LogId| self.fetch_tokens.get(log_id).map(ToString::to_string);
+
+        let mut operator = token(&operator_log_id);
+        let mut packages: IndexMap<LogId, Option<String>> = entries
+            .iter()
+            .filter(|entry| entry.log_id != operator_log_id)
+            .map(|entry| (entry.log_id.clone(), token(&entry.log_id)))
+            .collect();
+
+        let mut records = IndexMap::with_capacity(entries.len());
+        loop {
+            let response: FetchLogsResponse = self
+                .post(
+                    paths::fetch_logs(),
+                    &FetchLogsRequest {
+                        log_length,
+                        limit: None,
+                        operator: operator.as_deref().map(Into::into),
+                        packages: std::borrow::Cow::Borrowed(&packages),
+                    },
+                )
+                .await?;
+
+            for record in response.operator {
+                let envelope =
+                    ProtoEnvelope::<operator::OperatorRecord>::try_from(record.envelope.envelope)?;
+                let record_id = RecordId::operator_record::<Sha256>(&envelope);
+                records.insert(record_id, envelope.into());
+                operator = Some(record.fetch_token);
+            }
+
+            for (log_id, published) in response.packages {
+                for record in published {
+                    let envelope = ProtoEnvelope::<package::PackageRecord>::try_from(
+                        record.envelope.envelope,
+                    )?;
+                    let record_id = RecordId::package_record::<Sha256>(&envelope);
+                    records.insert(record_id, envelope.into());
+                    packages.insert(log_id.clone(), Some(record.fetch_token));
+                }
+            }
+
+            if !response.more {
+                return Ok(records);
+            }
+        }
+    }
+
+    // Fetches the names of packages not yet known to the mirror
+    async fn fetch_package_names(
+        &self,
+        entries: &[LogLeaf],
+    ) -> Result<IndexMap<LogId, PackageName>> {
+        let operator_log_id = LogId::operator_log::<Sha256>();
+        let log_ids = entries
+            .iter()
+            .map(|entry| &entry.log_id)
+            .filter(|&log_id| log_id != &operator_log_id && !self.fetch_tokens.contains_key(log_id))
+            .cloned()
+            .collect::<IndexSet<_>>();
+
+        let mut names = IndexMap::with_capacity(log_ids.len());
+        let mut remaining = log_ids.into_iter().collect::<Vec<_>>();
+        while !remaining.is_empty() {
+            let response: FetchPackageNamesResponse = self
+                .post(
+                    paths::fetch_package_names(),
+                    &FetchPackageNamesRequest {
+                        packages: std::borrow::Cow::Borrowed(&remaining),
+                    },
+                )
+                .await?;
+
+            let mut progress = false;
+            for (log_id, name) in response.packages {
+                let Some(name) = name else {
+                    continue;
+                };
+                ensure!(
+                    LogId::package_log::<Sha256>(&name) == log_id,
+                    "upstream package name `{name}` does not match log `{log_id}`"
+                );
+                names.insert(log_id, name);
+                progress = true;
+            }
+
+            remaining.retain(|log_id| !names.contains_key(log_id));
+            if !progress && !remaining.is_empty() {
+                bail!(
+                    "upstream did not provide a name for log `{log_id}`",
+                    log_id = remaining[0]
+                );
+            }
+        }
+
+        // Names of logs already mirrored are known locally
+        let known = entries
+            .iter()
+            .map(|entry| entry.log_id.clone())
+            .filter(|log_id| log_id != &operator_log_id && !names.contains_key(log_id))
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        if !known.is_empty() {
+            for (log_id, name) in self.core.store().get_package_names(&known).await? {
+                if let Some(name) = name {
+                    names.insert(log_id, name);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.upstream
+            .join(path)
+            .with_context(|| format!("invalid upstream path `{path}`"))
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = self.url(path)?;
+        self.client
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to get `{url}`"))?
+            .json()
+            .await
+            .with_context(|| format!("invalid response from `{url}`"))
+    }
+
+    async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        let url = self.url(path)?;
+        self.client
+            .post(url.clone())
+            .json(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to post to `{url}`"))?
+            .json()
+            .await
+            .with_context(|| format!("invalid response from `{url}`"))
+    }
+}
+
+// Validates the mirrored operator log
+async fn operator_state(core: &CoreService) -> Result<operator::LogState> {
+    let mut state = operator::LogState::default();
+    let log_length = core.checkpoint_length();
+    if log_length == 0 {
+        return Ok(state);
+    }
+
+    let log_id = LogId::operator_log::<Sha256>();
+    let mut since = None;
+    loop {
+        let records = core
+            .store()
+            .get_operator_records(&log_id, log_length, since.as_ref(), MAX_RECORDS_LIMIT)
+            .await?;
+        let more = records.len() == MAX_RECORDS_LIMIT as usize;
+        for record in records {
+            state = state.validate(&record.envelope)?;
+            since = Some(RecordId::operator_record::<Sha256>(&record.envelope));
+        }
+        if !more {
+            return Ok(state);
+        }
+    }
+}
+
+// Validates a mirrored package log
+async fn package_state(core: &CoreService, log_id: &LogId) -> Result<package::LogState> {
+    let mut state = package::LogState::default();
+    let log_length = core.checkpoint_length();
+    if log_length == 0 {
+        return Ok(state);
+    }
+
+    let mut since = None;
+    loop {
+        let records = match core
+            .store()
+            .get_package_records(log_id, log_length, since.as_ref(), MAX_RECORDS_LIMIT)
+            .await
+        {
+            Ok(records) => records,
+            Err(DataStoreError::LogNotFound(_)) => return Ok(state),
+            Err(e) => return Err(e.into()),
+        };
+        let more = records.len() == MAX_RECORDS_LIMIT as usize;
+        for record in records {
+            state = state.validate(&record.envelope)?;
+            since = Some(RecordId::package_record::<Sha256>(&record.envelope));
+        }
+        if !more {
+            return Ok(state);
+        }
+    }
+}
+
+// Verifies that a checkpoint is signed by a key of the given operator log
+// with permission to sign checkpoints
+fn verify_checkpoint_signature(
+    operator: &operator::LogState,
+    ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+) -> Result<()> {
+    let key_id = ts_checkpoint.key_id();
+    let key = operator
+        .public_key(key_id)
+        .ok_or_else(|| anyhow!("upstream checkpoint is signed by unknown key `{key_id}`"))?;
+    ensure!(
+        operator.key_has_permission_to_sign_checkpoints(key_id),
+        "upstream checkpoint is signed by key `{key_id}` without permission to sign checkpoints"
+    );
+    TimestampedCheckpoint::verify(
+        key,
+        &ts_checkpoint.as_ref().encode(),
+        ts_checkpoint.signature(),
+    )
+    .context("invalid upstream checkpoint signature")
+}
@@ -1,3 +1,7 @@
 mod core;
+mod mirror;
+mod proxy;
 
 pub use self::core::{CoreService, CoreServiceError};
+pub use self::mirror::MirrorService;
+pub use self::proxy::{ProxyError, ProxyService};
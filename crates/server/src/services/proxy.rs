@@ -0,0 +1,650 @@
+use std::{
+    cmp::Ordering,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use indexmap::{IndexMap, IndexSet};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use thiserror::Error;
+use tokio::{sync::RwLock, time::Instant};
+use url::Url;
+use warg_api::v1::{
+    fetch::{FetchPackageNamesRequest, FetchPackageNamesResponse, FetchPackageResponse},
+    paths,
+    proof::{ConsistencyRequest, ConsistencyResponse},
+};
+use warg_crypto::{
+    hash::{AnyHash, Hash, Sha256},
+    Encode, Signable,
+};
+use warg_protocol::{
+    operator, package,
+    registry::{
+        Checkpoint, LogId, LogLeaf, MapLeaf, PackageName, RecordId, RegistryLen,
+        TimestampedCheckpoint,
+    },
+    PublishedProtoEnvelope, Record as _,
+};
+use warg_transparency::{log::LogProofBundle, map::MapProofBundle};
+
+use super::CoreService;
+use crate::datastore::DataStoreError;
+
+const MAX_RECORDS_LIMIT: u16 = 1000;
+
+// The maximum number of packages and misses kept in the cache
+const MAX_CACHED_PACKAGES: usize = 1024;
+
+/// Serves the packages of namespaces imported from other registries.
+///
+/// The complete logs of an imported package are fetched from the registry
+/// that defines its namespace, verified against that registry's checkpoint,
+/// and cached; they are served as-is so that their proofs remain rooted in
+/// the upstream checkpoint.
+///
+/// The operator log and checkpoint of an upstream registry are pinned on
+/// first contact; later responses must extend the pinned operator log and
+/// come with a checkpoint that is proven consistent with the pinned one.
+/// The pins are persisted so that they survive a restart of the server.
+#[derive(Clone)]
+pub struct ProxyService {
+    core: CoreService,
+    client: reqwest::Client,
+    refresh_interval: Duration,
+    pins_path: Arc<PathBuf>,
+    cache: Arc<RwLock<ProxyCache>>,
+}
+
+#[derive(Default)]
+struct ProxyCache {
+    // Verified packages, least recently fetched first
+    packages: IndexMap<LogId, ProxiedPackage>,
+    // Packages that no upstream registry had, least recently looked up first
+    misses: IndexMap<LogId, Instant>,
+    // The local operator log state at the log length of a checkpoint
+    operator: Option<(RegistryLen, operator::LogState)>,
+    // The pinned operator logs and checkpoints of upstream registries
+    upstreams: IndexMap<String, UpstreamPin>,
+}
+
+impl ProxyCache {
+    fn insert_package(&mut self, log_id: LogId, package: ProxiedPackage) {
+        self.misses.shift_remove(&log_id);
+        self.packages.shift_remove(&log_id);
+        self.packages.insert(log_id, package);
+        if self.packages.len() > MAX_CACHED_PACKAGES {
+            self.packages.shift_remove_index(0);
+        }
+    }
+
+    fn insert_miss(&mut self, log_id: LogId) {
+        self.misses.shift_remove(&log_id);
+        self.misses.insert(log_id, Instant::now());
+        if self.misses.len() > MAX_CACHED_PACKAGES {
+            self.misses.shift_remove_index(0);
+        }
+    }
+}
+
+// A verified package fetched from an upstream registry
+struct ProxiedPackage {
+    registry: String,
+    upstream: Url,
+    response: FetchPackageResponse,
+    contents: IndexSet<AnyHash>,
+    fetched: Instant,
+}
+
+// The operator log and checkpoint of an upstream registry as last verified
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpstreamPin {
+    operator_length: usize,
+    operator_head: RecordId,
+    checkpoint: Checkpoint,
+}
+
+impl ProxyService {
+    /// Creates a new proxy service.
+    ///
+    /// Cached packages are fetched again once they are older than the given
+    /// refresh interval.
+    ///
+    /// The pinned upstream registries are loaded from and stored to the
+    /// given path.
+    pub fn new(
+        core: CoreService,
+        refresh_interval: Duration,
+        pins_path: impl Into<PathBuf>,
+    ) -> Result<Self> {
+        let pins_path = pins_path.into();
+        let upstreams = read_pins(&pins_path).with_context(|| {
+            format!(
+                "failed to read upstream pins `{path}`",
+                path = pins_path.display()
+            )
+        })?;
+
+        Ok(Self {
+            core,
+            client: reqwest::Client::new(),
+            refresh_interval,
+            pins_path: Arc::new(pins_path),
+            cache: Arc::new(RwLock::new(ProxyCache {
+                upstreams,
+                ..Default::default()
+            })),
+        })
+    }
+
+    /// Gets the HTTP client used to contact upstream registries.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Fetches the logs of an imported package along with the proofs needed
+    /// to verify them against the latest checkpoint of its upstream registry.
+    ///
+    /// If a registry is given, the package must be imported from it.
+    ///
+    /// Returns `None` if the package is not in an imported namespace or the
+    /// upstream registry does not have it.
+    pub async fn fetch_package(
+        &self,
+        registry: Option<&str>,
+        log_id: &LogId,
+    ) -> Result<Option<FetchPackageResponse>, ProxyError> {
+        let (cached, missed) = {
+            let cache = self.cache.read().await;
+            let cached = cache.packages.get(log_id).map(|package| {
+                (
+                    package.registry.clone(),
+                    package.fetched.elapsed() < self.refresh_interval,
+                )
+            });
+            let missed = cache
+                .misses
+                .get(log_id)
+                .map(|looked_up| looked_up.elapsed() < self.refresh_interval)
+                .unwrap_or(false);
+            (cached, missed)
+        };
+        if let Some((cached_registry, fresh)) = &cached {
+            let matches = match registry {
+                Some(registry) => registry_matches(cached_registry, registry),
+                None => true,
+            };
+            if *fresh && matches {
+                return Ok(self.cached_response(log_id).await);
+            }
+        }
+
+        let state = self.operator_state().await?;
+        let mut registries = IndexSet::new();
+        for (_, namespace_state) in state.namespaces() {
+            if let operator::NamespaceState::Imported { registry: imported } = namespace_state {
+                let matches = match registry {
+                    Some(registry) => registry_matches(imported, registry),
+                    None => true,
+                };
+                if matches {
+                    registries.insert(imported.clone());
+                }
+            }
+        }
+
+        if let Some(registry) = registry {
+            if registries.is_empty() {
+                return Err(ProxyError::RegistryNotImported(registry.to_string()));
+            }
+        }
+
+        // Avoid contacting the upstream registries again for a recent miss
+        if missed && cached.is_none() {
+            return Ok(None);
+        }
+
+        let mut error = None;
+        for registry in registries {
+            match self.fetch_upstream(&state, &registry, log_id).await {
+                Ok(Some(package)) => {
+                    let response = package.response.clone();
+                    self.cache
+                        .write()
+                        .await
+                        .insert_package(log_id.clone(), package);
+                    return Ok(Some(response));
+                }
+                Ok(None) => continue,
+                Err(e) => error = Some(ProxyError::Upstream { registry, error: e }),
+            }
+        }
+
+        if error.is_none() && cached.is_none() {
+            self.cache.write().await.insert_miss(log_id.clone());
+        }
+
+        match error {
+            // Serve a stale package rather than failing when the upstream is unavailable
+            Some(e) if cached.is_some() => {
+                tracing::warn!("serving cached package log `{log_id}`: {e:?}");
+                Ok(self.cached_response(log_id).await)
+            }
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the URL of the upstream registry serving the given content.
+    ///
+    /// Only content referenced by a cached package is served.
+    pub async fn content_upstream(&self, digest: &AnyHash) -> Option<Url> {
+        self.cache
+            .read()
+            .await
+            .packages
+            .values()
+            .find(|package| package.contents.contains(digest))
+            .map(|package| package.upstream.clone())
+    }
+
+    async fn cached_response(&self, log_id: &LogId) -> Option<FetchPackageResponse> {
+        self.cache
+            .read()
+            .await
+            .packages
+            .get(log_id)
+            .map(|package| package.response.clone())
+    }
+
+    // Validates the local operator log to determine the imported namespaces;
+    // the state is validated once per checkpoint
+    async fn operator_state(&self) -> Result<operator::LogState, ProxyError> {
+        let store = self.core.store();
+        let log_length = store
+            .get_latest_checkpoint()
+            .await?
+            .as_ref()
+            .checkpoint
+            .log_length;
+        if let Some((cached_length, state)) = &self.cache.read().await.operator {
+            if *cached_length == log_length {
+                return Ok(state.clone());
+            }
+        }
+
+        let state = self.validate_operator_log(log_length).await?;
+        self.cache.write().await.operator = Some((log_length, state.clone()));
+        Ok(state)
+    }
+
+    async fn validate_operator_log(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<operator::LogState, ProxyError> {
+        let store = self.core.store();
+        let log_id = LogId::operator_log::<Sha256>();
+
+        let mut state = operator::LogState::default();
+        let mut since = None;
+        loop {
+            let records = store
+                .get_operator_records(&log_id, log_length, since.as_ref(), MAX_RECORDS_LIMIT)
+                .await?;
+            let more = records.len() == MAX_RECORDS_LIMIT as usize;
+            for record in records {
+                state = state
+                    .validate(&record.envelope)
+                    .map_err(|e| ProxyError::InvalidOperatorLog(e.into()))?;
+                since = Some(RecordId::operator_record::<Sha256>(&record.envelope));
+            }
+            if !more {
+                return Ok(state);
+            }
+        }
+    }
+
+    // Fetches and verifies a package from the registry defining its namespace,
+    // returning `None` if the package is not imported from that registry
+    async fn fetch_upstream(
+        &self,
+        state: &operator::LogState,
+        registry: &str,
+        log_id: &LogId,
+    ) -> Result<Option<ProxiedPackage>> {
+        let upstream = registry_url(registry)?;
+
+        let mut names: FetchPackageNamesResponse = self
+            .client
+            .post(upstream.join(paths::fetch_package_names())?)
+            .json(&FetchPackageNamesRequest {
+                packages: std::borrow::Cow::Owned(vec![log_id.clone()]),
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let Some(name) = names.packages.swap_remove(log_id).flatten() else {
+            return Ok(None);
+        };
+        ensure!(
+            &LogId::package_log::<Sha256>(&name) == log_id,
+            "upstream package name `{name}` does not match log `{log_id}`"
+        );
+        match state.namespace_state(name.namespace()) {
+            Some(operator::NamespaceState::Imported { registry: imported })
+                if imported == registry => {}
+            _ => return Ok(None),
+        }
+
+        let response = self
+            .client
+            .get(upstream.join(&paths::fetch_package(log_id))?)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response: FetchPackageResponse = response.error_for_status()?.json().await?;
+
+        let (operator, contents) = verify_package(&name, log_id, &response)
+            .with_context(|| format!("failed to verify package `{name}`"))?;
+        let checkpoint = &response.checkpoint.as_ref().checkpoint;
+        self.verify_pin(registry, &upstream, &operator, checkpoint)
+            .await?;
+
+        let log_length = checkpoint.log_length;
+        tracing::debug!("fetched package `{name}` from `{upstream}` at log length {log_length}");
+        Ok(Some(ProxiedPackage {
+            registry: registry.to_string(),
+            upstream,
+            response,
+            contents,
+            fetched: Instant::now(),
+        }))
+    }
+
+    // Verifies an upstream response against the operator log and checkpoint
+    // pinned for the registry, pinning them on first contact
+    async fn verify_pin(
+        &self,
+        registry: &str,
+        upstream: &Url,
+        operator: &[RecordId],
+        checkpoint: &Checkpoint,
+    ) -> Result<()> {
+        let pinned = self.cache.read().await.upstreams.get(registry).cloned();
+        if let Some(pinned) = &pinned {
+            // Operator records link to their predecessor, so a matching record
+            // at the pinned length implies the pinned log is a prefix
+            ensure!(
+                operator.get(pinned.operator_length - 1) == Some(&pinned.operator_head),
+                "upstream operator log does not extend the pinned operator log"
+            );
+
+            let from = &pinned.checkpoint;
+            match from.log_length.cmp(&checkpoint.log_length) {
+                Ordering::Greater => bail!(
+                    "upstream checkpoint log length {to} is behind the pinned log length {from}",
+                    to = checkpoint.log_length,
+                    from = from.log_length
+                ),
+                Ordering::Equal => ensure!(
+                    from == checkpoint,
+                    "upstream checkpoint differs from the pinned checkpoint at log length {length}",
+                    length = from.log_length
+                ),
+                Ordering::Less => {
+                    self.verify_consistency(upstream, from, checkpoint)
+                        .await
+                        .context(
+                            "upstream checkpoint is not consistent with the pinned checkpoint",
+                        )?;
+                }
+            }
+        }
+
+        let mut cache = self.cache.write().await;
+        let current = cache
+            .upstreams
+            .get(registry)
+            .map(|pin| pin.checkpoint.log_length);
+        if current
+            .map(|length| length < checkpoint.log_length)
+            .unwrap_or(true)
+        {
+            if pinned.is_none() {
+                tracing::info!(
+                    "pinning operator log of upstream registry `{registry}` at log length {log_length}",
+                    log_length = checkpoint.log_length
+                );
+            }
+            cache.upstreams.insert(
+                registry.to_string(),
+                UpstreamPin {
+                    operator_length: operator.len(),
+                    operator_head: operator
+                        .last()
+                        .cloned()
+                        .context("upstream operator log is empty")?,
+                    checkpoint: checkpoint.clone(),
+                },
+            );
+            write_pins(&self.pins_path, &cache.upstreams).with_context(|| {
+                format!(
+                    "failed to write upstream pins `{path}`",
+                    path = self.pins_path.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    // Requests a consistency proof between two upstream checkpoints and verifies it
+    async fn verify_consistency(
+        &self,
+        upstream: &Url,
+        from: &Checkpoint,
+        to: &Checkpoint,
+    ) -> Result<()> {
+        let response: ConsistencyResponse = self
+            .client
+            .post(upstream.join(paths::prove_consistency())?)
+            .json(&ConsistencyRequest {
+                from: from.log_length,
+                to: to.log_length,
+            })
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let bundle: LogProofBundle<Sha256, LogLeaf> = LogProofBundle::decode(&response.proof)?;
+        let (log_data, consistencies, inclusions) = bundle.unbundle();
+        ensure!(
+            inclusions.is_empty() && consistencies.len() == 1,
+            "expected exactly one consistency proof"
+        );
+        let (from_root, to_root) = consistencies[0]
+            .evaluate(&log_data)
+            .map_err(|e| anyhow!(e.to_string()))?;
+        ensure!(
+            AnyHash::from(from_root) == from.log_root && AnyHash::from(to_root) == to.log_root,
+            "consistency proof does not match the checkpoint log roots"
+        );
+        Ok(())
+    }
+}
+
+// Validates the operator and package logs of an upstream response and proves
+// their heads against the upstream checkpoint, returning the operator record
+// identifiers and the referenced content
+fn verify_package(
+    name: &PackageName,
+    log_id: &LogId,
+    response: &FetchPackageResponse,
+) -> Result<(Vec<RecordId>, IndexSet<AnyHash>)> {
+    let mut operator = operator::LogState::default();
+    let mut operator_records = Vec::with_capacity(response.operator.len());
+    for record in &response.operator {
+        let record: PublishedProtoEnvelope<operator::OperatorRecord> =
+            record.envelope.clone().try_into()?;
+        operator = operator.validate(&record.envelope)?;
+        operator_records.push(RecordId::operator_record::<Sha256>(&record.envelope));
+    }
+    let operator_head = operator
+        .head()
+        .as_ref()
+        .ok_or_else(|| anyhow!("upstream operator log is empty"))?
+        .digest
+        .clone();
+
+    let mut state = package::LogState::default();
+    let mut contents = IndexSet::new();
+    for record in &response.records {
+        let record: PublishedProtoEnvelope<package::PackageRecord> =
+            record.envelope.clone().try_into()?;
+        state = state.validate(&record.envelope)?;
+        contents.extend(record.envelope.as_ref().contents().into_iter().cloned());
+    }
+    let package_head = state
+        .head()
+        .as_ref()
+        .ok_or_else(|| anyhow!("upstream log of package `{name}` is empty"))?
+        .digest
+        .clone();
+
+    let ts_checkpoint = &response.checkpoint;
+    let key_id = ts_checkpoint.key_id();
+    let key = operator
+        .public_key(key_id)
+        .ok_or_else(|| anyhow!("upstream checkpoint is signed by unknown key `{key_id}`"))?;
+    ensure!(
+        operator.key_has_permission_to_sign_checkpoints(key_id),
+        "upstream checkpoint is signed by key `{key_id}` without permission to sign checkpoints"
+    );
+    TimestampedCheckpoint::verify(
+        key,
+        &ts_checkpoint.as_ref().encode(),
+        ts_checkpoint.signature(),
+    )
+    .context("invalid upstream checkpoint signature")?;
+
+    let leafs = [
+        (
+            LogId::operator_log::<Sha256>(),
+            MapLeaf {
+                record_id: operator_head,
+            },
+        ),
+        (
+            log_id.clone(),
+            MapLeaf {
+                record_id: package_head,
+            },
+        ),
+    ];
+    let bundle: MapProofBundle<Sha256, LogId, MapLeaf> = MapProofBundle::decode(&response.map)?;
+    let root: Hash<Sha256> = ts_checkpoint
+        .as_ref()
+        .checkpoint
+        .map_root
+        .clone()
+        .try_into()?;
+    if let Some(proof) = bundle.multi_proof() {
+        let found = proof.evaluate(&leafs).map_err(|e| anyhow!(e.to_string()))?;
+        ensure!(
+            found == root,
+            "map proof does not match the upstream checkpoint"
+        );
+        return Ok((operator_records, contents));
+    }
+
+    let proofs = bundle.unbundle();
+    ensure!(
+        proofs.len() == leafs.len(),
+        "expected {expected} map inclusion proofs but found {found}",
+        expected = leafs.len(),
+        found = proofs.len()
+    );
+    for ((log_id, leaf), proof) in leafs.iter().zip(&proofs) {
+        ensure!(
+            proof.evaluate(log_id, leaf) == root,
+            "map inclusion proof of log `{log_id}` does not match the upstream checkpoint"
+        );
+    }
+
+    Ok((operator_records, contents))
+}
+
+/// Parses the URL of an imported registry, defaulting to HTTPS.
+fn registry_url(registry: &str) -> Result<Url> {
+    let mut url: Url = if registry.contains("://") {
+        registry.parse()
+    } else {
+        format!("https://{registry}").parse()
+    }
+    .with_context(|| format!("invalid registry `{registry}`"))?;
+
+    // Ensure API paths are joined to the registry URL rather than replacing its last segment
+    if !url.path().ends_with('/') {
+        url.set_path(&format!("{path}/", path = url.path()));
+    }
+    Ok(url)
+}
+
+/// Determines if a `Warg-Registry` header names the given imported registry,
+/// either as written in the operator log or by its host and port.
+fn registry_matches(imported: &str, header: &str) -> bool {
+    if imported == header {
+        return true;
+    }
+
+    match registry_url(imported) {
+        Ok(url) => match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => header == format!("{host}:{port}"),
+            (Some(host), None) => header == host,
+            _ => false,
+        },
+        Err(_) => false,
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ProxyError {
+    #[error("registry `{0}` is not a registry from which namespaces are imported")]
+    RegistryNotImported(String),
+    #[error("failed to fetch from registry `{registry}`: {error:?}")]
+    Upstream {
+        registry: String,
+        error: anyhow::Error,
+    },
+    #[error("invalid operator log: {0}")]
+    InvalidOperatorLog(anyhow::Error),
+    #[error("data store error: {0}")]
+    DataStore(#[from] DataStoreError),
+}
+
+fn read_pins(path: &Path) -> Result<IndexMap<String, UpstreamPin>> {
+    if !path.is_file() {
+        return Ok(Default::default());
+    }
+
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+fn write_pins(path: &Path, pins: &IndexMap<String, UpstreamPin>) -> Result<()> {
+    let dir = path.parent().context("upstream pins path has no parent")?;
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(&serde_json::to_vec(pins)?)?;
+    file.persist(path)?;
+    Ok(())
+}
@@ -0,0 +1,625 @@
+use core::fmt::{self, Debug};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use alloc::vec::Vec;
+use warg_crypto::hash::{Digest, Hash, SupportedDigest};
+use warg_crypto::VisitBytes;
+
+use super::node::Node;
+use super::tile::{encode_tile, level_position, TILE_WIDTH};
+use super::{hash_branch, hash_empty, hash_leaf, Checkpoint, LogBuilder, LogData};
+
+/// The maximum number of tiles kept in the read cache.
+const MAX_CACHED_TILES: usize = 64;
+
+/// Tiles read from disk, by level and tile index.
+type TileCache<D> = Mutex<HashMap<(usize, usize), Arc<Vec<Hash<D>>>>>;
+
+/// A verifiable log where the node hashes are stored on disk.
+///
+/// Node hashes are stored in append-only files, one per tile as described
+/// by [`TileId`](super::TileId).
+///
+/// Pushed entries are kept in memory until [`FileLog::flush`] writes them
+/// to disk. Opening a log repairs any hashes left missing by an interrupted
+/// flush, so a log can always be resumed from its directory.
+pub struct FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    /// The directory of the tiles
+    dir: PathBuf,
+    /// The number of entries
+    length: usize,
+    /// The balanced roots of the tree, with the highest first
+    roots: Vec<(Node, Hash<D>)>,
+    /// The number of hashes written to disk, by level
+    persisted: Vec<usize>,
+    /// The hashes not yet written to disk, by level
+    pending: Vec<Vec<Hash<D>>>,
+    /// Tiles read from disk
+    cache: TileCache<D>,
+    /// The first error reading a hash through [`LogData::hash_for`]
+    read_error: Mutex<Option<io::Error>>,
+    /// Marker for value type
+    _value: PhantomData<V>,
+}
+
+impl<D, V> FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    /// Opens the log stored in the given directory.
+    ///
+    /// The directory is created if it does not exist.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut persisted = Vec::new();
+        loop {
+            let level_dir = Self::level_dir(&dir, persisted.len());
+            if !level_dir.is_dir() {
+                break;
+            }
+            persisted.push(Self::scan_level(&level_dir)?);
+        }
+
+        let mut log = Self {
+            dir,
+            length: persisted.first().copied().unwrap_or(0),
+            roots: Vec::new(),
+            pending: vec![Vec::new(); persisted.len()],
+            persisted,
+            cache: Mutex::new(HashMap::new()),
+            read_error: Mutex::new(None),
+            _value: PhantomData,
+        };
+        log.repair()?;
+        Ok(log)
+    }
+
+    /// Returns the number of entries in the log.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Checks if the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Gets the root of the log when it was at some length.
+    pub fn root_at(&self, length: usize) -> Option<Hash<D>> {
+        if length > self.length {
+            return None;
+        }
+
+        let mut root = None;
+        for node in Node::broots_for_len(length).into_iter().rev() {
+            let hash = self.hash_for(node)?;
+            root = Some(match root {
+                None => hash,
+                Some(right) => hash_branch::<D>(hash, right),
+            });
+        }
+
+        Some(root.unwrap_or_else(hash_empty::<D>))
+    }
+
+    /// Takes the first error that occurred reading a hash from disk through
+    /// [`LogData::hash_for`], which reports such hashes as not present.
+    ///
+    /// Callers of [`LogData`] methods should check for an error afterwards so
+    /// that a failure to read the log is not mistaken for a missing hash.
+    pub fn take_read_error(&self) -> io::Result<()> {
+        match self.read_error.lock().unwrap().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the hashes of pushed entries to disk.
+    ///
+    /// Fails with any error that occurred reading a hash from disk since it
+    /// was last taken.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.take_read_error()?;
+
+        for (level, pending) in self.pending.iter_mut().enumerate() {
+            let mut position = self.persisted[level];
+            for chunk in pending.chunk_by_tile(position) {
+                let tile = position / TILE_WIDTH;
+                let path = Self::tile_path(&self.dir, level, tile);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+                file.write_all(&encode_tile(chunk))?;
+                file.sync_data()?;
+                position += chunk.len();
+            }
+
+            self.persisted[level] = position;
+            pending.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Truncates the log to the given length, discarding later entries.
+    ///
+    /// Pending entries are written to disk first.
+    pub fn truncate(&mut self, length: usize) -> io::Result<()> {
+        if length >= self.length {
+            return Ok(());
+        }
+
+        self.flush()?;
+
+        let hash_len = <D as Digest>::output_size() as u64;
+        for level in (0..self.persisted.len()).rev() {
+            let keep = length >> level;
+            if keep == 0 {
+                fs::remove_dir_all(Self::level_dir(&self.dir, level))?;
+                self.persisted.pop();
+                self.pending.pop();
+                continue;
+            }
+
+            let last = (keep - 1) / TILE_WIDTH;
+            let stored = self.persisted[level].div_ceil(TILE_WIDTH);
+            for tile in last + 1..stored {
+                fs::remove_file(Self::tile_path(&self.dir, level, tile))?;
+            }
+
+            let file = OpenOptions::new()
+                .write(true)
+                .open(Self::tile_path(&self.dir, level, last))?;
+            file.set_len((keep - last * TILE_WIDTH) as u64 * hash_len)?;
+            file.sync_data()?;
+            self.persisted[level] = keep;
+        }
+
+        self.length = length;
+        self.clear_cache();
+        self.roots = self.load_roots()?;
+        Ok(())
+    }
+
+    /// Recomputes any hashes missing from disk and loads the balanced roots.
+    fn repair(&mut self) -> io::Result<()> {
+        let mut level = 1;
+        while self.length >> level > 0 {
+            if level == self.persisted.len() {
+                self.persisted.push(0);
+                self.pending.push(Vec::new());
+            }
+
+            let expected = self.length >> level;
+            let found = self.persisted[level];
+            if found > expected {
+                return Err(invalid_data(format!(
+                    "level {level} of the log has {found} hashes but at most {expected} were expected"
+                )));
+            }
+
+            for position in found..expected {
+                let left = self.read_hash(level - 1, position * 2)?;
+                let right = self.read_hash(level - 1, position * 2 + 1)?;
+                self.pending[level].push(hash_branch::<D>(left, right));
+            }
+
+            level += 1;
+        }
+
+        if let Some(found) = self.persisted.get(level).copied().filter(|&n| n > 0) {
+            return Err(invalid_data(format!(
+                "level {level} of the log has {found} hashes but none were expected"
+            )));
+        }
+
+        self.flush()?;
+        self.roots = self.load_roots()?;
+        Ok(())
+    }
+
+    fn load_roots(&self) -> io::Result<Vec<(Node, Hash<D>)>> {
+        Node::broots_for_len(self.length)
+            .into_iter()
+            .map(|node| {
+                let (level, position) = level_position(node);
+                Ok((node, self.read_hash(level, position)?))
+            })
+            .collect()
+    }
+
+    fn read_hash(&self, level: usize, position: usize) -> io::Result<Hash<D>> {
+        let persisted = self.persisted.get(level).copied().unwrap_or(0);
+        if position >= persisted {
+            return self
+                .pending
+                .get(level)
+                .and_then(|pending| pending.get(position - persisted))
+                .cloned()
+                .ok_or_else(|| {
+                    invalid_data(format!(
+                        "hash {position} of level {level} of the log was not found"
+                    ))
+                });
+        }
+
+        let tile = position / TILE_WIDTH;
+        let offset = position % TILE_WIDTH;
+        if let Some(hashes) = self.cache.lock().unwrap().get(&(level, tile)) {
+            if let Some(hash) = hashes.get(offset) {
+                return Ok(hash.clone());
+            }
+        }
+
+        let hashes = Arc::new(self.read_tile(level, tile)?);
+        let hash = hashes.get(offset).cloned().ok_or_else(|| {
+            invalid_data(format!(
+                "hash {position} of level {level} of the log was not found"
+            ))
+        })?;
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_TILES {
+            cache.clear();
+        }
+        cache.insert((level, tile), hashes);
+        Ok(hash)
+    }
+
+    fn read_tile(&self, level: usize, tile: usize) -> io::Result<Vec<Hash<D>>> {
+        let hash_len = <D as Digest>::output_size();
+        let mut bytes = Vec::new();
+        File::open(Self::tile_path(&self.dir, level, tile))?.read_to_end(&mut bytes)?;
+        bytes
+            .chunks_exact(hash_len)
+            .map(|chunk| Hash::try_from(chunk.to_vec()).map_err(|e| invalid_data(e.to_string())))
+            .collect()
+    }
+
+    fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Counts the hashes stored in a level directory.
+    ///
+    /// A partially-written hash at the end of the last tile is discarded.
+    fn scan_level(level_dir: &Path) -> io::Result<usize> {
+        let hash_len = <D as Digest>::output_size() as u64;
+        let mut tiles = Vec::new();
+        for entry in fs::read_dir(level_dir)? {
+            let entry = entry?;
+            let tile = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<usize>().ok())
+                .ok_or_else(|| {
+                    invalid_data(format!(
+                        "unexpected file `{path}` in log directory",
+                        path = entry.path().display()
+                    ))
+                })?;
+            tiles.push((tile, entry.path()));
+        }
+        tiles.sort();
+
+        let mut count = 0;
+        for (i, (tile, path)) in tiles.iter().enumerate() {
+            if *tile != i {
+                return Err(invalid_data(format!(
+                    "log tile `{path}` is missing",
+                    path = level_dir.join(i.to_string()).display()
+                )));
+            }
+
+            let len = fs::metadata(path)?.len();
+            let hashes = (len / hash_len) as usize;
+            let last = i + 1 == tiles.len();
+            if !last && hashes != TILE_WIDTH || hashes > TILE_WIDTH {
+                return Err(invalid_data(format!(
+                    "log tile `{path}` has an invalid length",
+                    path = path.display()
+                )));
+            }
+
+            if last && len % hash_len != 0 {
+                let file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(hashes as u64 * hash_len)?;
+                file.sync_data()?;
+            }
+
+            count += hashes;
+        }
+
+        Ok(count)
+    }
+
+    fn level_dir(dir: &Path, level: usize) -> PathBuf {
+        dir.join(level.to_string())
+    }
+
+    fn tile_path(dir: &Path, level: usize, tile: usize) -> PathBuf {
+        Self::level_dir(dir, level).join(tile.to_string())
+    }
+
+    fn push_hash(&mut self, level: usize, hash: Hash<D>) {
+        if level == self.pending.len() {
+            self.persisted.push(0);
+            self.pending.push(Vec::new());
+        }
+        self.pending[level].push(hash);
+    }
+}
+
+impl<D, V> Debug for FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileLog")
+            .field("dir", &self.dir)
+            .field("length", &self.length)
+            .finish()
+    }
+}
+
+impl<D, V> LogBuilder<D, V> for FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn checkpoint(&self) -> Checkpoint<D> {
+        let root = self
+            .roots
+            .iter()
+            .rev()
+            .map(|(_n, hash)| hash.clone())
+            .reduce(|new, old| hash_branch::<D>(old, new))
+            .unwrap_or_else(hash_empty::<D>);
+
+        Checkpoint {
+            root,
+            length: self.length,
+        }
+    }
+
+    fn push(&mut self, entry: &V) -> Node {
+        let leaf_node = Node(self.length * 2);
+        let leaf_digest = hash_leaf::<D>(entry);
+
+        self.length += 1;
+        self.push_hash(0, leaf_digest.clone());
+        self.roots.push((leaf_node, leaf_digest));
+
+        // Merge balanced roots of equal height, recording each new parent
+        while self.roots.len() >= 2 {
+            let (right_node, right_hash) = &self.roots[self.roots.len() - 1];
+            let (left_node, left_hash) = &self.roots[self.roots.len() - 2];
+            if right_node.height() != left_node.height() {
+                break;
+            }
+
+            let parent = right_node.parent();
+            let parent_hash = hash_branch::<D>(left_hash.clone(), right_hash.clone());
+            self.roots.truncate(self.roots.len() - 2);
+            self.roots.push((parent, parent_hash.clone()));
+            self.push_hash(parent.height() as usize, parent_hash);
+        }
+
+        leaf_node
+    }
+}
+
+impl<D, V> LogData<D, V> for FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn hash_for(&self, node: Node) -> Option<Hash<D>> {
+        if !self.has_hash(node) {
+            return None;
+        }
+
+        let (level, position) = level_position(node);
+        match self.read_hash(level, position) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                self.read_error.lock().unwrap().get_or_insert(e);
+                None
+            }
+        }
+    }
+
+    fn has_hash(&self, node: Node) -> bool {
+        let (level, position) = level_position(node);
+        self.persisted
+            .get(level)
+            .zip(self.pending.get(level))
+            .is_some_and(|(persisted, pending)| position < persisted + pending.len())
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Splits pending hashes at tile boundaries.
+trait ChunkByTile<T> {
+    fn chunk_by_tile(&self, position: usize) -> Vec<&[T]>;
+}
+
+impl<T> ChunkByTile<T> for Vec<T> {
+    fn chunk_by_tile(&self, mut position: usize) -> Vec<&[T]> {
+        let mut chunks = Vec::new();
+        let mut rest = self.as_slice();
+        while !rest.is_empty() {
+            let len = (TILE_WIDTH - position % TILE_WIDTH).min(rest.len());
+            let (chunk, tail) = rest.split_at(len);
+            chunks.push(chunk);
+            rest = tail;
+            position += len;
+        }
+        chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use warg_crypto::hash::Sha256;
+
+    use super::super::VecLog;
+    use super::*;
+
+    type Value = (u8, u8);
+
+    fn value(i: usize) -> Value {
+        ((i >> 8) as u8, i as u8)
+    }
+
+    fn vec_log(length: usize) -> VecLog<Sha256, Value> {
+        let mut log = VecLog::default();
+        for i in 0..length {
+            log.push(&value(i));
+        }
+        log
+    }
+
+    #[test]
+    fn test_matches_vec_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        let mut vec_log: VecLog<Sha256, Value> = VecLog::default();
+
+        for i in 0..(TILE_WIDTH * 3 + 17) {
+            assert_eq!(file_log.push(&value(i)), vec_log.push(&value(i)));
+            assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+            if i % 100 == 0 {
+                file_log.flush().unwrap();
+            }
+        }
+
+        let length = vec_log.length();
+        for len in (1..=length).step_by(13) {
+            assert_eq!(file_log.root_at(len), vec_log.root_at(len));
+            for leaf in (0..len).step_by(7) {
+                let node = Node(leaf * 2);
+                let proof = file_log.prove_inclusion(node, len);
+                assert_eq!(
+                    proof.evaluate_value(&file_log, &value(leaf)).unwrap(),
+                    vec_log.root_at(len).unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        for i in 0..1000 {
+            file_log.push(&value(i));
+        }
+        file_log.flush().unwrap();
+        drop(file_log);
+
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        assert_eq!(file_log.length(), 1000);
+        assert_eq!(file_log.checkpoint(), vec_log(1000).checkpoint());
+
+        for i in 1000..1100 {
+            file_log.push(&value(i));
+        }
+        assert_eq!(file_log.checkpoint(), vec_log(1100).checkpoint());
+
+        // Unflushed entries are lost
+        drop(file_log);
+        let file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        assert_eq!(file_log.checkpoint(), vec_log(1000).checkpoint());
+    }
+
+    #[test]
+    fn test_repair() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        for i in 0..1000 {
+            file_log.push(&value(i));
+        }
+        file_log.flush().unwrap();
+        drop(file_log);
+
+        // Simulate a flush interrupted after writing the leaves
+        fs::remove_dir_all(dir.path().join("3")).unwrap();
+        let level_one = dir.path().join("1").join("1");
+        let len = fs::metadata(&level_one).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&level_one)
+            .unwrap()
+            .set_len(len - 40)
+            .unwrap();
+
+        let file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        assert_eq!(file_log.checkpoint(), vec_log(1000).checkpoint());
+        assert_eq!(file_log.root_at(777), vec_log(1000).root_at(777));
+    }
+
+    #[test]
+    fn test_read_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        for i in 0..10 {
+            file_log.push(&value(i));
+        }
+        file_log.flush().unwrap();
+        drop(file_log);
+
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        fs::remove_file(dir.path().join("0").join("0")).unwrap();
+
+        // The hash is reported missing and the read error is surfaced
+        assert!(file_log.has_hash(Node(0)));
+        assert!(file_log.hash_for(Node(0)).is_none());
+        assert!(file_log.flush().is_err());
+        file_log.take_read_error().unwrap();
+    }
+
+    #[test]
+    fn test_truncate() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        for i in 0..1000 {
+            file_log.push(&value(i));
+        }
+
+        file_log.truncate(600).unwrap();
+        assert_eq!(file_log.checkpoint(), vec_log(600).checkpoint());
+        drop(file_log);
+
+        let mut file_log: FileLog<Sha256, Value> = FileLog::open(dir.path()).unwrap();
+        assert_eq!(file_log.checkpoint(), vec_log(600).checkpoint());
+        for i in 600..1000 {
+            file_log.push(&value(i));
+        }
+        assert_eq!(file_log.checkpoint(), vec_log(1000).checkpoint());
+
+        file_log.truncate(0).unwrap();
+        assert!(file_log.is_empty());
+        assert_eq!(file_log.checkpoint(), vec_log(0).checkpoint());
+    }
+}
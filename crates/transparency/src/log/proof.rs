@@ -180,18 +180,30 @@ where
             return Err(InclusionProofError::HashNotKnown);
         }
 
+        // A known hash may still fail to load from storage
+        let hash_for = |node: &Node| {
+            hashes
+                .hash_for(*node)
+                .map(|hash| (*node, hash))
+                .ok_or(InclusionProofError::HashNotKnown)
+        };
+
         // Perform initial walk up to the ancestor broot
         let current = walk
             .initial_walk()
             .iter()
-            .map(|node| (*node, hashes.hash_for(*node).unwrap()))
+            .map(hash_for)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .fold(leaf, combine);
 
         // Summarize all of the smaller broots
         let lower_broot = walk
             .lower_broot_walk()
             .iter()
-            .map(|node| (*node, hashes.hash_for(*node).unwrap()))
+            .map(hash_for)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .reduce(combine);
 
         // Combine broot with summary of smaller roots
@@ -204,7 +216,9 @@ where
         let current = walk
             .upper_broot_walk()
             .iter()
-            .map(|node| (*node, hashes.hash_for(*node).unwrap()))
+            .map(hash_for)
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .fold(current, combine);
 
         Ok(current.1)
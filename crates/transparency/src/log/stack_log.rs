@@ -9,7 +9,7 @@ use warg_crypto::{
 };
 use warg_protobuf::internal as protobuf;
 
-use super::{hash_branch, hash_empty, hash_leaf, node::Node, Checkpoint, LogBuilder};
+use super::{hash_branch, hash_empty, hash_leaf, node::Node, Checkpoint, LogBuilder, LogData};
 
 /// A log builder which maintains a stack of balanced roots
 #[derive(Clone, Debug)]
@@ -39,6 +39,24 @@ where
         self.length == 0
     }
 
+    /// Continue a log of the given length from the hashes of its balanced roots.
+    ///
+    /// This allows the root of an extended log to be computed without
+    /// modifying the original log.
+    ///
+    /// Returns `None` if the log data is missing a balanced root.
+    pub fn resume(data: &impl LogData<D, V>, length: usize) -> Option<Self> {
+        let stack = Node::broots_for_len(length)
+            .into_iter()
+            .map(|node| Some((node, data.hash_for(node)?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            stack,
+            length,
+            _value: PhantomData,
+        })
+    }
+
     /// Turn a StackLog into bytes using protobuf
     pub fn to_protobuf(self) -> Vec<u8> {
         let proto: protobuf::StackLog = self.into();
@@ -190,4 +208,27 @@ mod tests {
             assert_eq!(vec_log.checkpoint(), stack_log.checkpoint());
         }
     }
+
+    #[test]
+    fn test_resume() {
+        let mut vec_log: VecLog<Sha256, &str> = VecLog::default();
+        let data = [
+            "93", "67", "30", "37", "23", "75", "57", "89", "76", "42", "9",
+        ];
+
+        for (i, leaf) in data.iter().enumerate() {
+            let mut stack_log = StackLog::resume(&vec_log, i).unwrap();
+            for leaf in &data[i..] {
+                stack_log.push(leaf);
+            }
+
+            let mut expected = vec_log.clone();
+            for leaf in &data[i..] {
+                expected.push(leaf);
+            }
+            assert_eq!(expected.checkpoint(), stack_log.checkpoint());
+
+            vec_log.push(leaf);
+        }
+    }
 }
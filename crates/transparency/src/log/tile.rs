@@ -0,0 +1,369 @@
+use core::fmt;
+use std::{collections::HashMap, marker::PhantomData};
+
+use alloc::vec::Vec;
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Digest, Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{ConsistencyProofError, InclusionProofError, LogData, Node};
+
+/// The number of hashes in a full tile.
+pub const TILE_WIDTH: usize = 256;
+
+/// Identifies a tile of log hashes.
+///
+/// The hashes of a log are grouped by level (the height of their node) and
+/// split into tiles of [`TILE_WIDTH`] hashes, in the style of the
+/// [tlog tile scheme][0]. Hash `n` of level `l` is the root of the subtree
+/// covering leaves `n * 2^l` through `(n + 1) * 2^l - 1`, so a tile only
+/// ever grows as the log grows and never changes once full.
+///
+/// A tile that is not yet full is identified by its width, which makes the
+/// contents of every tile of a given width immutable.
+///
+/// [0]: https://research.swtch.com/tlog#tiling_a_log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileId {
+    /// The level of the hashes in the tile.
+    pub level: usize,
+    /// The index of the tile within its level.
+    pub index: usize,
+}
+
+impl TileId {
+    /// Gets the tile containing the hash of a node, along with the offset
+    /// of the hash within the tile.
+    pub fn for_node(node: Node) -> (Self, usize) {
+        let (level, position) = level_position(node);
+        (
+            Self {
+                level,
+                index: position / TILE_WIDTH,
+            },
+            position % TILE_WIDTH,
+        )
+    }
+
+    /// Gets the number of hashes in the tile for a log of the given length.
+    ///
+    /// Returns zero if the log has no hashes in the tile.
+    pub fn width_at(&self, log_length: usize) -> usize {
+        // Levels above the height of any log have no hashes
+        let Some(level_length) = u32::try_from(self.level)
+            .ok()
+            .and_then(|level| log_length.checked_shr(level))
+        else {
+            return 0;
+        };
+        let hashes = level_length.saturating_sub(self.index.saturating_mul(TILE_WIDTH));
+        hashes.min(TILE_WIDTH)
+    }
+
+    /// Reads the hashes of the tile from log data.
+    ///
+    /// Returns `None` if the data does not contain `width` hashes of the tile.
+    pub fn read<D, V>(&self, data: &impl LogData<D, V>, width: usize) -> Option<Vec<Hash<D>>>
+    where
+        D: SupportedDigest,
+        V: VisitBytes,
+    {
+        if width > TILE_WIDTH {
+            return None;
+        }
+
+        (0..width)
+            .map(|offset| data.hash_for(self.node(offset)?))
+            .collect()
+    }
+
+    /// Gets the node of the hash at the given offset in the tile.
+    ///
+    /// Returns `None` if the index of the node does not fit in a `usize`.
+    fn node(&self, offset: usize) -> Option<Node> {
+        let position = self.index.checked_mul(TILE_WIDTH)?.checked_add(offset)?;
+        let span = 1usize.checked_shl(u32::try_from(self.level).ok()?.checked_add(1)?)?;
+        Some(Node(position.checked_mul(span)? | (span / 2 - 1)))
+    }
+}
+
+impl fmt::Display for TileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{level}/{index}", level = self.level, index = self.index)
+    }
+}
+
+/// Gets the level and the position within the level of a node.
+pub(crate) fn level_position(node: Node) -> (usize, usize) {
+    let level = node.height() as usize;
+    (level, node.index() >> (level + 1))
+}
+
+/// Encodes tile hashes as their concatenated bytes.
+pub fn encode_tile<D: SupportedDigest>(hashes: &[Hash<D>]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(hashes.len() * <D as Digest>::output_size());
+    for hash in hashes {
+        bytes.extend_from_slice(hash.bytes());
+    }
+    bytes
+}
+
+/// An error occurring when adding a tile to a [`TileLog`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TileError {
+    /// The tile has no hashes for the length of the log.
+    #[error("tile `{0}` is not part of the log")]
+    NotInLog(TileId),
+    /// The tile did not contain the expected number of hashes.
+    #[error("tile `{tile}` has {found} bytes but {expected} bytes were expected")]
+    InvalidLength {
+        /// The tile.
+        tile: TileId,
+        /// The expected number of bytes.
+        expected: usize,
+        /// The number of bytes found.
+        found: usize,
+    },
+}
+
+/// Log data assembled from tiles of a log of a given length.
+///
+/// Proofs are computed locally from the tiles, which are typically fetched
+/// from a registry or a cache in front of it. The tiles are not trusted;
+/// evaluating a proof against a known root validates the hashes used.
+pub struct TileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    length: usize,
+    tiles: HashMap<TileId, Vec<Hash<D>>>,
+    _value: PhantomData<V>,
+}
+
+impl<D, V> TileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    /// Creates empty tile data for a log of the given length.
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            tiles: HashMap::new(),
+            _value: PhantomData,
+        }
+    }
+
+    /// Gets the length of the log.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Gets the tiles not yet added that are needed to evaluate an
+    /// inclusion proof of a leaf in the log.
+    pub fn tiles_for_inclusion(&self, leaf: Node) -> Result<Vec<TileId>, InclusionProofError> {
+        let walk = self.prove_inclusion(leaf, self.length).walk()?;
+        Ok(self.missing_tiles(walk.nodes))
+    }
+
+    /// Gets the tiles not yet added that are needed to evaluate a
+    /// consistency proof from an older length of the log.
+    pub fn tiles_for_consistency(
+        &self,
+        old_length: usize,
+    ) -> Result<Vec<TileId>, ConsistencyProofError> {
+        let mut nodes = Vec::new();
+        for proof in self
+            .prove_consistency(old_length, self.length)
+            .inclusions()?
+        {
+            nodes.push(proof.leaf());
+            nodes.extend(proof.walk()?.nodes);
+        }
+        Ok(self.missing_tiles(nodes))
+    }
+
+    /// Adds the encoded hashes of a tile.
+    pub fn add_tile(&mut self, tile: TileId, bytes: &[u8]) -> Result<(), TileError> {
+        let width = tile.width_at(self.length);
+        if width == 0 {
+            return Err(TileError::NotInLog(tile));
+        }
+
+        let hash_len = <D as Digest>::output_size();
+        if bytes.len() != width * hash_len {
+            return Err(TileError::InvalidLength {
+                tile,
+                expected: width * hash_len,
+                found: bytes.len(),
+            });
+        }
+
+        let hashes = bytes
+            .chunks_exact(hash_len)
+            .map(|chunk| Hash::try_from(chunk.to_vec()))
+            .collect::<Result<_, _>>()
+            .map_err(|_| TileError::InvalidLength {
+                tile,
+                expected: width * hash_len,
+                found: bytes.len(),
+            })?;
+        self.tiles.insert(tile, hashes);
+        Ok(())
+    }
+
+    fn missing_tiles(&self, nodes: impl IntoIterator<Item = Node>) -> Vec<TileId> {
+        let mut tiles: Vec<_> = nodes
+            .into_iter()
+            .map(|node| TileId::for_node(node).0)
+            .filter(|tile| !self.tiles.contains_key(tile))
+            .collect();
+        tiles.sort();
+        tiles.dedup();
+        tiles
+    }
+}
+
+impl<D, V> fmt::Debug for TileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TileLog")
+            .field("length", &self.length)
+            .field("tiles", &self.tiles.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<D, V> LogData<D, V> for TileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn has_hash(&self, node: Node) -> bool {
+        let (tile, offset) = TileId::for_node(node);
+        self.tiles
+            .get(&tile)
+            .is_some_and(|hashes| offset < hashes.len())
+    }
+
+    fn hash_for(&self, node: Node) -> Option<Hash<D>> {
+        let (tile, offset) = TileId::for_node(node);
+        self.tiles.get(&tile)?.get(offset).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use warg_crypto::hash::Sha256;
+
+    use super::super::{LogBuilder, VecLog};
+    use super::*;
+
+    type Value = (u8, u8);
+
+    fn value(i: usize) -> Value {
+        ((i >> 8) as u8, i as u8)
+    }
+
+    fn fetch(log: &VecLog<Sha256, Value>, tiles: &mut TileLog<Sha256, Value>, ids: Vec<TileId>) {
+        for id in ids {
+            let hashes = id.read(log, id.width_at(tiles.length())).unwrap();
+            tiles.add_tile(id, &encode_tile(&hashes)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tile_nodes() {
+        for index in (0..2048).step_by(3) {
+            let node = Node(index);
+            let (tile, offset) = TileId::for_node(node);
+            assert_eq!(tile.node(offset), Some(node));
+        }
+    }
+
+    #[test]
+    fn test_inclusion_from_tiles() {
+        let mut log: VecLog<Sha256, Value> = VecLog::default();
+        for i in 0..(TILE_WIDTH * 2 + 77) {
+            log.push(&value(i));
+        }
+
+        for length in [1, 2, 255, 256, 257, 300, log.length()] {
+            let root = log.root_at(length).unwrap();
+            let mut tiles = TileLog::new(length);
+            for leaf in (0..length).step_by(11) {
+                let node = Node(leaf * 2);
+                let ids = tiles.tiles_for_inclusion(node).unwrap();
+                fetch(&log, &mut tiles, ids);
+                let proof = tiles.prove_inclusion(node, length);
+                assert_eq!(proof.evaluate_value(&tiles, &value(leaf)).unwrap(), root);
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_from_tiles() {
+        let mut log: VecLog<Sha256, Value> = VecLog::default();
+        for i in 0..(TILE_WIDTH * 2 + 77) {
+            log.push(&value(i));
+        }
+
+        let new_length = log.length();
+        let new_root = log.root_at(new_length).unwrap();
+        for old_length in (1..new_length).step_by(17) {
+            let mut tiles = TileLog::new(new_length);
+            let ids = tiles.tiles_for_consistency(old_length).unwrap();
+            fetch(&log, &mut tiles, ids);
+            let proof = tiles.prove_consistency(old_length, new_length);
+            assert_eq!(
+                proof.evaluate(&tiles).unwrap(),
+                (log.root_at(old_length).unwrap(), new_root.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_tiles() {
+        let mut tiles: TileLog<Sha256, Value> = TileLog::new(300);
+        let tile = TileId { level: 0, index: 1 };
+        assert_eq!(tile.width_at(300), 44);
+        assert_eq!(
+            tiles.add_tile(tile, &[0; 32]),
+            Err(TileError::InvalidLength {
+                tile,
+                expected: 44 * 32,
+                found: 32
+            })
+        );
+
+        let tile = TileId { level: 9, index: 0 };
+        assert_eq!(tiles.add_tile(tile, &[]), Err(TileError::NotInLog(tile)));
+    }
+
+    #[test]
+    fn test_out_of_range_tiles() {
+        let mut log: VecLog<Sha256, Value> = VecLog::default();
+        for i in 0..300 {
+            log.push(&value(i));
+        }
+
+        for level in [63, 64, 65, usize::MAX] {
+            let tile = TileId { level, index: 0 };
+            assert_eq!(tile.width_at(log.length()), 0);
+            assert_eq!(tile.read(&log, 1), None);
+        }
+
+        let tile = TileId {
+            level: 0,
+            index: usize::MAX,
+        };
+        assert_eq!(tile.width_at(usize::MAX), 0);
+        assert_eq!(tile.read(&log, 1), None);
+    }
+}
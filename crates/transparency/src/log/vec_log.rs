@@ -51,7 +51,7 @@ where
     }
 
     /// Get the root of the log when it was at some length
-    fn root_at(&self, length: usize) -> Option<Hash<D>> {
+    pub fn root_at(&self, length: usize) -> Option<Hash<D>> {
         if length > self.length {
             return None;
         }
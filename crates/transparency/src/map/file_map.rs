@@ -0,0 +1,808 @@
+use core::fmt::{self, Debug};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    path::{Path as FsPath, PathBuf},
+    sync::Mutex,
+};
+
+use alloc::vec::Vec;
+use warg_crypto::hash::{Digest, Hash, SupportedDigest};
+use warg_crypto::VisitBytes;
+
+use super::map::{hash_branch, hash_leaf};
+use super::path::{Path, Side};
+use super::proof::{AbsenceProof, Proof};
+use super::singleton::Singleton;
+
+const NODES_FILE: &str = "nodes";
+const ROOTS_FILE: &str = "roots";
+
+const FORK_TAG: u8 = 1;
+const SINGLETON_TAG: u8 = 2;
+
+/// A map node as stored on disk.
+///
+/// Empty subtrees are never stored; a child whose hash is the empty tree
+/// hash for its height is empty.
+enum StoredNode<D: SupportedDigest> {
+    /// A branch with the hashes of its children.
+    Fork(Hash<D>, Hash<D>),
+    /// A subtree containing a single key, with the leaf hash of its value.
+    Singleton(Hash<D>, Hash<D>),
+}
+
+impl<D: SupportedDigest> Clone for StoredNode<D> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Fork(left, right) => Self::Fork(left.clone(), right.clone()),
+            Self::Singleton(key, value) => Self::Singleton(key.clone(), value.clone()),
+        }
+    }
+}
+
+/// A retained version of the map.
+struct Snapshot<D: SupportedDigest> {
+    version: usize,
+    root: Hash<D>,
+    len: usize,
+}
+
+/// A verifiable map where the tree nodes are stored on disk.
+///
+/// The map has the same contents, root hashes and proofs as a [`Map`] with
+/// the same items, but only an index of node locations is kept in memory.
+///
+/// Note that the index has an entry (a node hash and a file offset) for
+/// every stored node, so memory use still grows linearly with the number of
+/// stored nodes; only the contents of the nodes are left on disk.
+///
+/// Each insert writes the nodes along the path of the key, so the nodes of
+/// unchanged subtrees are shared between versions. Calling
+/// [`FileMap::snapshot`] writes pending nodes to disk and retains the
+/// current root as a version of the map that proofs can be generated for.
+/// Versions no longer needed are removed with [`FileMap::prune`], which
+/// also reclaims the space of nodes only they referenced.
+///
+/// [`Map`]: super::Map
+pub struct FileMap<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes + Clone,
+    V: VisitBytes + Clone,
+{
+    /// The directory of the map files
+    dir: PathBuf,
+    /// The current root of the map
+    root: Hash<D>,
+    /// The number of items in the map
+    len: usize,
+    /// The retained versions of the map, in ascending order
+    snapshots: Vec<Snapshot<D>>,
+    /// The offsets of nodes in the nodes file
+    index: HashMap<Hash<D>, u64>,
+    /// The nodes not yet written to disk
+    pending: HashMap<Hash<D>, StoredNode<D>>,
+    /// The length of the nodes file
+    end: u64,
+    /// The nodes file, used for reading
+    file: Mutex<File>,
+    /// Marker for key and value types
+    _kv: PhantomData<(K, V)>,
+}
+
+impl<D, K, V> FileMap<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes + Clone,
+    V: VisitBytes + Clone,
+{
+    /// Opens the map stored in the given directory.
+    ///
+    /// The directory is created if it does not exist. The map starts at its
+    /// latest version; changes made after the latest snapshot are discarded.
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let nodes_path = dir.join(NODES_FILE);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&nodes_path)?;
+        let (index, end) = Self::scan_nodes(&file)?;
+        let snapshots = Self::read_snapshots(&dir.join(ROOTS_FILE))?;
+
+        let mut map = Self {
+            dir,
+            root: D::empty_tree_hash(256).clone(),
+            len: 0,
+            snapshots,
+            index,
+            pending: HashMap::new(),
+            end,
+            file: Mutex::new(file),
+            _kv: PhantomData,
+        };
+        map.reset();
+        Ok(map)
+    }
+
+    /// The hash of the root of the current version of the map.
+    pub fn root(&self) -> &Hash<D> {
+        &self.root
+    }
+
+    /// The number of items in the current version of the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether or not the current version of the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the latest retained version of the map, if any.
+    pub fn latest_version(&self) -> Option<usize> {
+        self.snapshots.last().map(|s| s.version)
+    }
+
+    /// Gets the versions of the map that are retained.
+    pub fn versions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.snapshots.iter().map(|s| s.version)
+    }
+
+    /// Gets the root hash of a retained version of the map.
+    pub fn root_at(&self, version: usize) -> Option<&Hash<D>> {
+        self.retained(version).map(|s| &s.root)
+    }
+
+    /// Insert a value into the current version of the map.
+    ///
+    /// This replaces any existing item with the same key.
+    pub fn insert(&mut self, key: K, val: V) -> io::Result<()> {
+        let key = Hash::<D>::of(&key);
+        let (root, new) = self.insert_at(self.root.clone(), 256, &key, hash_leaf(val))?;
+        self.root = root;
+        self.len += usize::from(new);
+        Ok(())
+    }
+
+    /// Inserts all key/value pairs into the current version of the map.
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = (K, V)>) -> io::Result<()> {
+        for (key, val) in iter {
+            self.insert(key, val)?;
+        }
+        Ok(())
+    }
+
+    /// Retains the current version of the map with the given version number.
+    ///
+    /// Versions must be snapshot in ascending order.
+    pub fn snapshot(&mut self, version: usize) -> io::Result<()> {
+        if let Some(latest) = self.snapshots.last() {
+            if version < latest.version || (version == latest.version && latest.root != self.root) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "version {version} of the map is not after the latest version {latest}",
+                        latest = latest.version
+                    ),
+                ));
+            }
+
+            if version == latest.version {
+                return Ok(());
+            }
+        }
+
+        self.flush()?;
+
+        let snapshot = Snapshot {
+            version,
+            root: self.root.clone(),
+            len: self.len,
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(ROOTS_FILE))?;
+        file.write_all(&encode_snapshot(&snapshot))?;
+        file.sync_data()?;
+        self.snapshots.push(snapshot);
+        Ok(())
+    }
+
+    /// Discards the versions of the map after the given version.
+    ///
+    /// The current version of the map becomes the latest version retained.
+    pub fn truncate(&mut self, version: usize) -> io::Result<()> {
+        let keep = self.snapshots.partition_point(|s| s.version <= version);
+        if keep < self.snapshots.len() {
+            self.snapshots.truncate(keep);
+            self.write_snapshots()?;
+        }
+
+        self.reset();
+        Ok(())
+    }
+
+    /// Removes the versions of the map before the given version and
+    /// reclaims the space of the nodes only they referenced.
+    pub fn prune(&mut self, before: usize) -> io::Result<()> {
+        let keep = self.snapshots.partition_point(|s| s.version < before);
+        if keep == 0 {
+            return Ok(());
+        }
+
+        self.snapshots.drain(..keep);
+        self.write_snapshots()?;
+        self.compact()
+    }
+
+    /// Gets a proof of the value of a key at a retained version of the map.
+    ///
+    /// Returns `Ok(None)` if the version is not retained or the key is not
+    /// present in that version.
+    pub fn prove(&self, version: usize, key: K) -> io::Result<Option<Proof<D, K, V>>> {
+        let root = match self.retained(version) {
+            Some(snapshot) => snapshot.root.clone(),
+            None => return Ok(None),
+        };
+
+        let key = Hash::<D>::of(&key);
+        let (peers, node) = self.walk(root, &key)?;
+        match node {
+            Some(StoredNode::Singleton(k, _)) if k == key => Ok(Some(Proof::new(peers))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gets a proof that no value is stored for a key at a retained version
+    /// of the map.
+    ///
+    /// Returns `Ok(None)` if the version is not retained or the key is
+    /// present in that version.
+    pub fn prove_absence(&self, version: usize, key: K) -> io::Result<Option<AbsenceProof<D, K>>> {
+        let root = match self.retained(version) {
+            Some(snapshot) => snapshot.root.clone(),
+            None => return Ok(None),
+        };
+
+        let key = Hash::<D>::of(&key);
+        let (peers, node) = self.walk(root, &key)?;
+        match node {
+            None => Ok(Some(AbsenceProof::new(peers))),
+            Some(StoredNode::Singleton(k, value)) => {
+                let height = 256 - peers.len();
+                let mut proof = match Singleton::new(k, value, height).prove_absence::<K>(&key) {
+                    Some(proof) => proof,
+                    None => return Ok(None),
+                };
+                for peer in peers {
+                    proof.push(peer);
+                }
+                Ok(Some(proof))
+            }
+            Some(StoredNode::Fork(..)) => Err(invalid_data("map walk ended at a branch".into())),
+        }
+    }
+
+    /// Walks the path of a key from a root.
+    ///
+    /// Returns the peers of the branches walked, from the bottom up, and
+    /// the singleton reached, or `None` if an empty subtree was reached.
+    #[allow(clippy::type_complexity)]
+    fn walk(
+        &self,
+        root: Hash<D>,
+        key: &Hash<D>,
+    ) -> io::Result<(Vec<Option<Hash<D>>>, Option<StoredNode<D>>)> {
+        let path = Path::new(key);
+        let mut peers = Vec::new();
+        let mut hash = root;
+        let mut height = 256;
+        loop {
+            match self.load(&hash, height)? {
+                Some(StoredNode::Fork(left, right)) => {
+                    let (next, peer) = match path.get(256 - height) {
+                        Side::Left => (left, right),
+                        Side::Right => (right, left),
+                    };
+                    peers.push(Some(peer));
+                    hash = next;
+                    height -= 1;
+                }
+                node => {
+                    peers.reverse();
+                    return Ok((peers, node));
+                }
+            }
+        }
+    }
+
+    /// Inserts a leaf into the subtree with the given root and height.
+    ///
+    /// Returns the new root of the subtree and whether the key is new.
+    fn insert_at(
+        &mut self,
+        hash: Hash<D>,
+        height: usize,
+        key: &Hash<D>,
+        value: Hash<D>,
+    ) -> io::Result<(Hash<D>, bool)> {
+        match self.load(&hash, height)? {
+            None => Ok((self.store_singleton(key.clone(), value, height), true)),
+            Some(StoredNode::Singleton(k, _)) if &k == key => {
+                Ok((self.store_singleton(k, value, height), false))
+            }
+            Some(StoredNode::Singleton(k, v)) => Ok((self.split(k, v, key, value, height), true)),
+            Some(StoredNode::Fork(left, right)) => {
+                let (left, right, new) = match Path::new(key).get(256 - height) {
+                    Side::Left => {
+                        let (left, new) = self.insert_at(left, height - 1, key, value)?;
+                        (left, right, new)
+                    }
+                    Side::Right => {
+                        let (right, new) = self.insert_at(right, height - 1, key, value)?;
+                        (left, right, new)
+                    }
+                };
+                Ok((self.store_fork(left, right), new))
+            }
+        }
+    }
+
+    /// Stores a subtree of the given height containing two distinct keys.
+    fn split(
+        &mut self,
+        key_a: Hash<D>,
+        value_a: Hash<D>,
+        key_b: &Hash<D>,
+        value_b: Hash<D>,
+        height: usize,
+    ) -> Hash<D> {
+        let depth = 256 - height;
+        let side_a = Path::new(&key_a).get(depth);
+        let side_b = Path::new(key_b).get(depth);
+        let (left, right) = if side_a == side_b {
+            let child = self.split(key_a, value_a, key_b, value_b, height - 1);
+            let empty = D::empty_tree_hash(height - 1).clone();
+            match side_a {
+                Side::Left => (child, empty),
+                Side::Right => (empty, child),
+            }
+        } else {
+            let a = self.store_singleton(key_a, value_a, height - 1);
+            let b = self.store_singleton(key_b.clone(), value_b, height - 1);
+            match side_a {
+                Side::Left => (a, b),
+                Side::Right => (b, a),
+            }
+        };
+        self.store_fork(left, right)
+    }
+
+    fn store_fork(&mut self, left: Hash<D>, right: Hash<D>) -> Hash<D> {
+        let hash = hash_branch(&left, &right);
+        self.store(hash.clone(), StoredNode::Fork(left, right));
+        hash
+    }
+
+    fn store_singleton(&mut self, key: Hash<D>, value: Hash<D>, height: usize) -> Hash<D> {
+        let hash = Singleton::new(key.clone(), value.clone(), height).hash();
+        self.store(hash.clone(), StoredNode::Singleton(key, value));
+        hash
+    }
+
+    fn store(&mut self, hash: Hash<D>, node: StoredNode<D>) {
+        if !self.index.contains_key(&hash) {
+            self.pending.insert(hash, node);
+        }
+    }
+
+    /// Loads the node with the given hash at the given height.
+    ///
+    /// Returns `None` for an empty subtree.
+    fn load(&self, hash: &Hash<D>, height: usize) -> io::Result<Option<StoredNode<D>>> {
+        if hash == D::empty_tree_hash(height) {
+            return Ok(None);
+        }
+
+        if let Some(node) = self.pending.get(hash) {
+            return Ok(Some(node.clone()));
+        }
+
+        let offset = *self
+            .index
+            .get(hash)
+            .ok_or_else(|| invalid_data(format!("map node `{hash}` was not found")))?;
+
+        let hash_len = <D as Digest>::output_size();
+        let mut file = self.file.lock().unwrap();
+        file.seek(SeekFrom::Start(offset + hash_len as u64))?;
+        let (node, _) = read_node::<D>(&mut *file)?
+            .ok_or_else(|| invalid_data(format!("map node `{hash}` is truncated")))?;
+        Ok(Some(node))
+    }
+
+    /// Writes pending nodes to disk.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(self.dir.join(NODES_FILE))?;
+        let mut bytes = Vec::new();
+        let mut offsets = Vec::with_capacity(self.pending.len());
+        for (hash, node) in self.pending.drain() {
+            offsets.push((hash.clone(), self.end + bytes.len() as u64));
+            encode_node(&hash, &node, &mut bytes);
+        }
+        file.write_all(&bytes)?;
+        file.sync_data()?;
+
+        self.end += bytes.len() as u64;
+        self.index.extend(offsets);
+        Ok(())
+    }
+
+    /// Rewrites the nodes file with only the nodes reachable from the
+    /// retained versions.
+    fn compact(&mut self) -> io::Result<()> {
+        self.flush()?;
+
+        let mut live = HashSet::new();
+        let mut stack: Vec<_> = self
+            .snapshots
+            .iter()
+            .map(|s| (s.root.clone(), 256))
+            .chain([(self.root.clone(), 256)])
+            .collect();
+        let mut bytes = Vec::new();
+        let mut index = HashMap::new();
+        while let Some((hash, height)) = stack.pop() {
+            if live.contains(&hash) {
+                continue;
+            }
+
+            let node = match self.load(&hash, height)? {
+                Some(node) => node,
+                None => continue,
+            };
+            if let StoredNode::Fork(left, right) = &node {
+                stack.push((left.clone(), height - 1));
+                stack.push((right.clone(), height - 1));
+            }
+
+            index.insert(hash.clone(), bytes.len() as u64);
+            encode_node(&hash, &node, &mut bytes);
+            live.insert(hash);
+        }
+
+        let temp_path = self.dir.join(format!("{NODES_FILE}.tmp"));
+        let mut temp = File::create(&temp_path)?;
+        temp.write_all(&bytes)?;
+        temp.sync_all()?;
+        drop(temp);
+        fs::rename(&temp_path, self.dir.join(NODES_FILE))?;
+
+        *self.file.lock().unwrap() = OpenOptions::new()
+            .read(true)
+            .open(self.dir.join(NODES_FILE))?;
+        self.index = index;
+        self.end = bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Resets the current version to the latest retained version.
+    fn reset(&mut self) {
+        self.pending.clear();
+        match self.snapshots.last() {
+            Some(snapshot) => {
+                self.root = snapshot.root.clone();
+                self.len = snapshot.len;
+            }
+            None => {
+                self.root = D::empty_tree_hash(256).clone();
+                self.len = 0;
+            }
+        }
+    }
+
+    fn retained(&self, version: usize) -> Option<&Snapshot<D>> {
+        self.snapshots
+            .binary_search_by_key(&version, |s| s.version)
+            .ok()
+            .map(|i| &self.snapshots[i])
+    }
+
+    fn write_snapshots(&self) -> io::Result<()> {
+        let mut bytes = Vec::new();
+        for snapshot in &self.snapshots {
+            bytes.extend(encode_snapshot(snapshot));
+        }
+
+        let temp_path = self.dir.join(format!("{ROOTS_FILE}.tmp"));
+        let mut temp = File::create(&temp_path)?;
+        temp.write_all(&bytes)?;
+        temp.sync_all()?;
+        drop(temp);
+        fs::rename(&temp_path, self.dir.join(ROOTS_FILE))
+    }
+
+    /// Builds the index of the nodes file.
+    ///
+    /// Every node in the file has an entry in the index, which is kept in
+    /// memory. A partially-written node at the end of the file is discarded.
+    fn scan_nodes(file: &File) -> io::Result<(HashMap<Hash<D>, u64>, u64)> {
+        let hash_len = <D as Digest>::output_size();
+        let mut reader = BufReader::new(file);
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut index = HashMap::new();
+        let mut offset = 0;
+        loop {
+            let mut hash = vec![0; hash_len];
+            if !read_full(&mut reader, &mut hash)? {
+                break;
+            }
+
+            let (_, len) = match read_node::<D>(&mut reader)? {
+                Some(node) => node,
+                None => break,
+            };
+
+            let hash = Hash::try_from(hash).map_err(|e| invalid_data(e.to_string()))?;
+            index.insert(hash, offset);
+            offset += (hash_len + len) as u64;
+        }
+
+        if file.metadata()?.len() != offset {
+            file.set_len(offset)?;
+            file.sync_data()?;
+        }
+
+        Ok((index, offset))
+    }
+
+    fn read_snapshots(path: &FsPath) -> io::Result<Vec<Snapshot<D>>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        // A partially-written snapshot at the end of the file is ignored
+        let hash_len = <D as Digest>::output_size();
+        bytes
+            .chunks_exact(16 + hash_len)
+            .map(|chunk| {
+                let (version, rest) = chunk.split_at(8);
+                let (len, root) = rest.split_at(8);
+                Ok(Snapshot {
+                    version: decode_usize(version)?,
+                    len: decode_usize(len)?,
+                    root: Hash::try_from(root.to_vec()).map_err(|e| invalid_data(e.to_string()))?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<D, K, V> Debug for FileMap<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes + Clone,
+    V: VisitBytes + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileMap")
+            .field("dir", &self.dir)
+            .field("root", &self.root)
+            .field("len", &self.len)
+            .field("versions", &self.versions().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+fn encode_node<D: SupportedDigest>(hash: &Hash<D>, node: &StoredNode<D>, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(hash.bytes());
+    match node {
+        StoredNode::Fork(left, right) => {
+            bytes.push(FORK_TAG);
+            bytes.extend_from_slice(left.bytes());
+            bytes.extend_from_slice(right.bytes());
+        }
+        StoredNode::Singleton(key, value) => {
+            bytes.push(SINGLETON_TAG);
+            bytes.extend_from_slice(key.bytes());
+            bytes.extend_from_slice(value.bytes());
+        }
+    }
+}
+
+/// Reads a node, returning it and its encoded length.
+///
+/// Returns `None` if the end of the input was reached.
+fn read_node<D: SupportedDigest>(
+    reader: &mut impl Read,
+) -> io::Result<Option<(StoredNode<D>, usize)>> {
+    let hash_len = <D as Digest>::output_size();
+    let mut tag = [0; 1];
+    let mut body = vec![0; hash_len * 2];
+    if !read_full(reader, &mut tag)? || !read_full(reader, &mut body)? {
+        return Ok(None);
+    }
+
+    let right = body.split_off(hash_len);
+    let left = Hash::try_from(body).map_err(|e| invalid_data(e.to_string()))?;
+    let right = Hash::try_from(right).map_err(|e| invalid_data(e.to_string()))?;
+    let node = match tag[0] {
+        FORK_TAG => StoredNode::Fork(left, right),
+        SINGLETON_TAG => StoredNode::Singleton(left, right),
+        tag => return Err(invalid_data(format!("unknown map node tag {tag}"))),
+    };
+    Ok(Some((node, 1 + hash_len * 2)))
+}
+
+/// Fills the buffer, returning `false` if the end of the input was reached.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn encode_snapshot<D: SupportedDigest>(snapshot: &Snapshot<D>) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(16 + snapshot.root.len());
+    bytes.extend_from_slice(&(snapshot.version as u64).to_le_bytes());
+    bytes.extend_from_slice(&(snapshot.len as u64).to_le_bytes());
+    bytes.extend_from_slice(snapshot.root.bytes());
+    bytes
+}
+
+fn decode_usize(bytes: &[u8]) -> io::Result<usize> {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(bytes);
+    usize::try_from(u64::from_le_bytes(buf)).map_err(|e| invalid_data(e.to_string()))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use warg_crypto::hash::Sha256;
+
+    use super::super::Map;
+    use super::*;
+
+    type Key = (u8, u8);
+
+    fn key(i: usize) -> Key {
+        ((i >> 8) as u8, i as u8)
+    }
+
+    #[test]
+    fn test_matches_map() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        let mut map: Map<Sha256, Key, u8> = Map::default();
+        let mut versions = Vec::new();
+
+        for i in 0..300 {
+            // Update some existing keys as well as inserting new ones
+            let k = key(i % 200);
+            file_map.insert(k, i as u8).unwrap();
+            map = map.insert(k, i as u8);
+            assert_eq!(file_map.root(), map.root());
+            assert_eq!(file_map.len(), (i + 1).min(200));
+
+            if i % 50 == 49 {
+                file_map.snapshot(i).unwrap();
+                versions.push((i, map.clone()));
+            }
+        }
+
+        for (version, map) in &versions {
+            for i in (0..250).step_by(7) {
+                let k = key(i);
+                match map.prove(k) {
+                    Some(expected) => {
+                        let proof = file_map.prove(*version, k).unwrap().unwrap();
+                        assert_eq!(proof.peers, expected.peers);
+                        assert!(file_map.prove_absence(*version, k).unwrap().is_none());
+                    }
+                    None => {
+                        assert!(file_map.prove(*version, k).unwrap().is_none());
+                        let proof = file_map.prove_absence(*version, k).unwrap().unwrap();
+                        assert_eq!(&proof.evaluate(&k), map.root());
+                    }
+                }
+            }
+        }
+
+        assert!(file_map.prove(1, key(0)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_resume_and_truncate() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        let mut map: Map<Sha256, Key, u8> = Map::default();
+        for i in 0..100 {
+            file_map.insert(key(i), 1).unwrap();
+            map = map.insert(key(i), 1);
+        }
+        file_map.snapshot(100).unwrap();
+        let first = map.clone();
+
+        for i in 100..150 {
+            file_map.insert(key(i), 1).unwrap();
+            map = map.insert(key(i), 1);
+        }
+        file_map.snapshot(150).unwrap();
+
+        // Changes after the latest snapshot are discarded
+        file_map.insert(key(1000), 1).unwrap();
+        drop(file_map);
+
+        let mut file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        assert_eq!(file_map.latest_version(), Some(150));
+        assert_eq!(file_map.root(), map.root());
+        assert_eq!(file_map.len(), 150);
+
+        file_map.truncate(120).unwrap();
+        assert_eq!(file_map.latest_version(), Some(100));
+        assert_eq!(file_map.root(), first.root());
+        drop(file_map);
+
+        let file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        assert_eq!(file_map.versions().collect::<Vec<_>>(), [100]);
+        assert_eq!(file_map.root(), first.root());
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        let mut map: Map<Sha256, Key, u8> = Map::default();
+        for version in 1..=10 {
+            for i in 0..20 {
+                file_map.insert(key(i), version as u8).unwrap();
+                map = map.insert(key(i), version as u8);
+            }
+            file_map.snapshot(version).unwrap();
+        }
+
+        let size = fs::metadata(dir.path().join(NODES_FILE)).unwrap().len();
+        file_map.prune(10).unwrap();
+        assert!(fs::metadata(dir.path().join(NODES_FILE)).unwrap().len() < size / 5);
+        assert_eq!(file_map.versions().collect::<Vec<_>>(), [10]);
+        assert!(file_map.prove(9, key(0)).unwrap().is_none());
+
+        let proof = file_map.prove(10, key(3)).unwrap().unwrap();
+        assert_eq!(&proof.evaluate(&key(3), &10), map.root());
+
+        // The map can still be updated and reopened after compaction
+        file_map.insert(key(500), 1).unwrap();
+        map = map.insert(key(500), 1);
+        file_map.snapshot(11).unwrap();
+        drop(file_map);
+
+        let file_map: FileMap<Sha256, Key, u8> = FileMap::open(dir.path()).unwrap();
+        assert_eq!(file_map.root(), map.root());
+        let proof = file_map.prove(11, key(500)).unwrap().unwrap();
+        assert_eq!(&proof.evaluate(&key(500), &1), map.root());
+    }
+}
@@ -7,7 +7,7 @@ use warg_crypto::VisitBytes;
 use super::link::Link;
 use super::node::Node;
 use super::path::Path;
-use super::proof::Proof;
+use super::proof::{AbsenceProof, Proof};
 
 /// Immutable Map w/ Inclusion Proofs
 ///
@@ -190,6 +190,13 @@ where {
         self.link.node().prove(Path::new(&Hash::of(key)))
     }
 
+    /// Gets a proof that no value is stored for a given key in this map.
+    ///
+    /// Returns `None` if the key is present in the map.
+    pub fn prove_absence(&self, key: K) -> Option<AbsenceProof<D, K>> {
+        self.link.node().prove_absence(Path::new(&Hash::of(key)))
+    }
+
     /// Insert a value into the map, creating a new map.
     ///
     /// This replaces any existing items with the same key.
@@ -10,6 +10,7 @@
 
 #![allow(clippy::module_inception)]
 
+mod file_map;
 mod fork;
 mod link;
 mod map;
@@ -19,9 +20,10 @@ mod proof;
 mod proof_bundle;
 mod singleton;
 
+pub use file_map::FileMap;
 pub use map::Map;
-pub use proof::Proof;
-pub use proof_bundle::ProofBundle as MapProofBundle;
+pub use proof::{AbsenceProof, Proof};
+pub use proof_bundle::{MultiProof, MultiProofError, ProofBundle as MapProofBundle};
 
 #[cfg(test)]
 mod test {
@@ -30,7 +32,7 @@ mod test {
         VisitBytes,
     };
 
-    use super::Map;
+    use super::{Map, MapProofBundle, MultiProofError};
 
     #[test]
     fn insert() {
@@ -135,4 +137,68 @@ mod test {
         let fourth = third.insert("foo", "qux");
         check(&fourth, "foo", "qux");
     }
+
+    #[test]
+    fn prove_absence() {
+        let keys: Vec<String> = (0..128).map(|k| format!("key-{k}")).collect();
+        let mut tree = Map::<Sha256, &str, &str>::default();
+        for n in 0..64 {
+            for (k, key) in keys.iter().enumerate() {
+                let proof = tree.prove_absence(key.as_str());
+                assert_eq!(proof.is_none(), k < n);
+                if let Some(proof) = proof {
+                    assert_eq!(tree.root().clone(), proof.evaluate(&key.as_str()));
+                }
+            }
+            tree = tree.insert(keys[n].as_str(), "value");
+        }
+    }
+
+    #[test]
+    fn multi_proof() {
+        let keys: Vec<String> = (0..101).map(|k| format!("key-{k}")).collect();
+        let values: Vec<String> = (0..101).map(|k| format!("value-{k}")).collect();
+        let mut tree = Map::<Sha256, &str, &str>::default();
+        for k in 0..100 {
+            tree = tree.insert(keys[k].as_str(), values[k].as_str());
+        }
+
+        for count in [1, 2, 10, 100] {
+            let leaves: Vec<(&str, &str)> = (0..count)
+                .map(|k| (keys[k].as_str(), values[k].as_str()))
+                .collect();
+            let proofs = leaves
+                .iter()
+                .map(|(key, _)| (*key, tree.prove(key).unwrap()))
+                .collect();
+
+            let bundle = MapProofBundle::bundle_multi(proofs);
+            let bundle = MapProofBundle::<Sha256, &str, &str>::decode(&bundle.encode()).unwrap();
+            let proof = bundle.multi_proof().unwrap();
+            assert_eq!(proof.skips.len(), count);
+            assert_eq!(&proof.evaluate(&leaves).unwrap(), tree.root());
+
+            // Leaves may be given in any order
+            let reversed: Vec<_> = leaves.iter().rev().cloned().collect();
+            assert_eq!(&proof.evaluate(&reversed).unwrap(), tree.root());
+
+            // A different value evaluates to a different root
+            let mut changed = leaves.clone();
+            changed[0].1 = "other";
+            assert_ne!(&proof.evaluate(&changed).unwrap(), tree.root());
+
+            // Missing or additional keys do not match the proof
+            assert_eq!(
+                proof.evaluate(&leaves[1..]),
+                Err(if count == 1 {
+                    MultiProofError::NoLeaves
+                } else {
+                    MultiProofError::KeysMismatch
+                })
+            );
+            let mut extra = leaves.clone();
+            extra.push((keys[100].as_str(), values[100].as_str()));
+            assert_eq!(proof.evaluate(&extra), Err(MultiProofError::KeysMismatch));
+        }
+    }
 }
@@ -8,7 +8,7 @@ use warg_crypto::VisitBytes;
 use super::fork::Fork;
 use super::link::Link;
 use super::path::{Path, Side};
-use super::proof::Proof;
+use super::proof::{AbsenceProof, Proof};
 use super::singleton::Singleton;
 
 #[derive(Debug)]
@@ -66,6 +66,24 @@ impl<D: SupportedDigest> Node<D> {
         }
     }
 
+    pub fn prove_absence<K: VisitBytes>(
+        &self,
+        mut path: Path<'_, D>,
+    ) -> Option<AbsenceProof<D, K>> {
+        match (path.next(), self) {
+            (Some(_), Self::Singleton(singleton)) => singleton.prove_absence(path.hash()),
+            (Some(_), Self::Empty(_)) => Some(AbsenceProof::new(Vec::new())),
+            (Some(idx), Self::Fork(fork)) => {
+                let mut proof = fork[idx].as_ref().node().prove_absence(path)?;
+                let peer = fork[idx.opposite()].as_ref().hash();
+                proof.push(Some(peer.clone()));
+                Some(proof)
+            }
+
+            _ => None,
+        }
+    }
+
     /// A recursive function for setting the value in the tree.
     ///
     /// Returns:
@@ -1,15 +1,21 @@
 use alloc::vec::Vec;
 use anyhow::Error;
+use core::marker::PhantomData;
 use prost::Message;
+use thiserror::Error;
 use warg_crypto::{
     hash::{Hash, SupportedDigest},
     VisitBytes,
 };
 use warg_protobuf::transparency as protobuf;
 
-use crate::map::proof::Proof;
+use crate::map::{
+    map::{hash_branch, hash_leaf},
+    path::{Path, Side},
+    proof::{AbsenceProof, Proof},
+};
 
-/// A collection of inclusion proof info
+/// A collection of inclusion and absence proof info
 pub struct ProofBundle<D, K, V>
 where
     D: SupportedDigest,
@@ -17,6 +23,8 @@ where
     V: VisitBytes,
 {
     proofs: Vec<Proof<D, K, V>>,
+    absence_proofs: Vec<AbsenceProof<D, K>>,
+    multi_proof: Option<MultiProof<D, K, V>>,
 }
 
 impl<D, K, V> ProofBundle<D, K, V>
@@ -27,7 +35,34 @@ where
 {
     /// Bundles inclusion proofs together
     pub fn bundle(proofs: Vec<Proof<D, K, V>>) -> Self {
-        ProofBundle { proofs }
+        ProofBundle {
+            proofs,
+            absence_proofs: Vec::new(),
+            multi_proof: None,
+        }
+    }
+
+    /// Bundles absence proofs together
+    pub fn bundle_absence(absence_proofs: Vec<AbsenceProof<D, K>>) -> Self {
+        ProofBundle {
+            proofs: Vec::new(),
+            absence_proofs,
+            multi_proof: None,
+        }
+    }
+
+    /// Bundles inclusion proofs together as a single multi-proof
+    pub fn bundle_multi(proofs: Vec<(K, Proof<D, K, V>)>) -> Self {
+        ProofBundle {
+            proofs: Vec::new(),
+            absence_proofs: Vec::new(),
+            multi_proof: Some(MultiProof::new(proofs)),
+        }
+    }
+
+    /// Gets the multi-proof of the bundle, if it has one
+    pub fn multi_proof(&self) -> Option<&MultiProof<D, K, V>> {
+        self.multi_proof.as_ref()
     }
 
     /// Splits a bundle into its constituent inclusion proofs
@@ -35,6 +70,11 @@ where
         self.proofs
     }
 
+    /// Splits a bundle into its constituent absence proofs
+    pub fn unbundle_absence(self) -> Vec<AbsenceProof<D, K>> {
+        self.absence_proofs
+    }
+
     /// Turn a bundle into bytes using protobuf
     pub fn encode(self) -> Vec<u8> {
         let proto: protobuf::MapProofBundle = self.into();
@@ -57,7 +97,30 @@ where
 {
     fn from(value: ProofBundle<D, K, V>) -> Self {
         let proofs = value.proofs.into_iter().map(|proof| proof.into()).collect();
-        protobuf::MapProofBundle { proofs }
+        let absence_proofs = value
+            .absence_proofs
+            .into_iter()
+            .map(|proof| proof.into())
+            .collect();
+        protobuf::MapProofBundle {
+            proofs,
+            absence_proofs,
+            multi_proof: value.multi_proof.map(|proof| proof.into()),
+        }
+    }
+}
+
+impl<D, K, V> From<MultiProof<D, K, V>> for protobuf::MapMultiProof
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    fn from(value: MultiProof<D, K, V>) -> Self {
+        protobuf::MapMultiProof {
+            skips: value.skips.into_iter().map(|skip| skip as u32).collect(),
+            hashes: value.peers.into_iter().map(|h| h.into()).collect(),
+        }
     }
 }
 
@@ -75,6 +138,19 @@ where
     }
 }
 
+impl<D, K> From<AbsenceProof<D, K>> for protobuf::MapAbsenceProof
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    fn from(value: AbsenceProof<D, K>) -> Self {
+        let peers: Vec<Option<Hash<D>>> = value.into();
+        protobuf::MapAbsenceProof {
+            hashes: peers.into_iter().map(|h| h.into()).collect(),
+        }
+    }
+}
+
 impl<D, K, V> TryFrom<protobuf::MapProofBundle> for ProofBundle<D, K, V>
 where
     D: SupportedDigest,
@@ -88,11 +164,39 @@ where
         for entry in value.proofs {
             proofs.push(entry.try_into()?);
         }
-        let bundle = ProofBundle { proofs };
+        let mut absence_proofs = Vec::new();
+        for entry in value.absence_proofs {
+            absence_proofs.push(entry.try_into()?);
+        }
+        let multi_proof = value.multi_proof.map(|p| p.try_into()).transpose()?;
+        let bundle = ProofBundle {
+            proofs,
+            absence_proofs,
+            multi_proof,
+        };
         Ok(bundle)
     }
 }
 
+impl<D, K, V> TryFrom<protobuf::MapMultiProof> for MultiProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    type Error = Error;
+
+    fn try_from(value: protobuf::MapMultiProof) -> Result<Self, Self::Error> {
+        let peers: Result<Vec<Option<Hash<D>>>, Error> =
+            value.hashes.into_iter().map(|h| h.try_into()).collect();
+        Ok(MultiProof {
+            skips: value.skips.into_iter().map(|skip| skip as usize).collect(),
+            peers: peers?,
+            _kv: PhantomData,
+        })
+    }
+}
+
 impl<D, K, V> TryFrom<protobuf::MapInclusionProof> for Proof<D, K, V>
 where
     D: SupportedDigest,
@@ -108,3 +212,200 @@ where
         Ok(proof)
     }
 }
+
+impl<D, K> TryFrom<protobuf::MapAbsenceProof> for AbsenceProof<D, K>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    type Error = Error;
+
+    fn try_from(value: protobuf::MapAbsenceProof) -> Result<Self, Self::Error> {
+        let peers: Result<Vec<Option<Hash<D>>>, Error> =
+            value.hashes.into_iter().map(|h| h.try_into()).collect();
+        let proof = AbsenceProof::new(peers?);
+        Ok(proof)
+    }
+}
+
+/// An error occurring when evaluating a [`MultiProof`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MultiProofError {
+    /// No leaves were given to evaluate the proof with.
+    #[error("a multi-proof must be evaluated with at least one leaf")]
+    NoLeaves,
+    /// Different values were given for the same key.
+    #[error("conflicting values were given for the same key")]
+    ConflictingValues,
+    /// The proof does not have the peers of the given keys.
+    #[error("the multi-proof does not match the keys it was evaluated with")]
+    KeysMismatch,
+}
+
+/// The hash of a key and all of the peers of its path, from the bottom up
+type KeyPeers<D> = (Hash<D>, Vec<Option<Hash<D>>>);
+
+/// A compressed inclusion proof of many values in a map
+///
+/// The proof contains each peer needed by the paths of the proven keys only
+/// once: the peers shared by several paths are deduplicated and the peers
+/// that are branches on other proven paths are computed when the proof is
+/// evaluated. The empty peers at the bottom of each key's own path are
+/// omitted, as for [`Proof`].
+///
+/// Peers are ordered as they are consumed by a depth-first, left-to-right
+/// walk of the proven paths, so the proof is evaluated in a single pass.
+pub struct MultiProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    /// The number of empty peers omitted from the bottom of each key's own
+    /// path, in the left-to-right order of the keys
+    pub skips: Vec<usize>,
+    /// Sibling node hashes needed to construct the proof, in walk order
+    pub peers: Vec<Option<Hash<D>>>,
+    _kv: PhantomData<(K, V)>,
+}
+
+impl<D, K, V> MultiProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    /// Combines the inclusion proofs of keys against the same map root.
+    pub fn new(proofs: Vec<(K, Proof<D, K, V>)>) -> Self {
+        let mut paths: Vec<KeyPeers<D>> = proofs
+            .into_iter()
+            .map(|(key, proof)| {
+                // Restore the leading empty peers omitted from the proof
+                let mut peers = Vec::with_capacity(256);
+                peers.resize(256 - proof.peers.len(), None);
+                peers.extend(proof.peers);
+                (Hash::of(&key), peers)
+            })
+            .collect();
+        paths.sort_by(|(a, _), (b, _)| a.bytes().cmp(b.bytes()));
+        paths.dedup_by(|(a, _), (b, _)| a == b);
+
+        let mut proof = Self {
+            skips: Vec::new(),
+            peers: Vec::new(),
+            _kv: PhantomData,
+        };
+        if !paths.is_empty() {
+            proof.collect(0, &paths);
+        }
+        proof
+    }
+
+    /// Collects the peers for the subtree at the given depth containing the
+    /// given (non-empty) sorted paths.
+    fn collect(&mut self, depth: usize, paths: &[KeyPeers<D>]) {
+        let height = 256 - depth;
+        if let [(_, peers)] = paths {
+            let peers = &peers[..height];
+            let skip = peers.iter().take_while(|peer| peer.is_none()).count();
+            self.skips.push(skip);
+            self.peers.extend(peers[skip..].iter().cloned());
+            return;
+        }
+
+        let split = paths.partition_point(|(key, _)| Path::new(key).get(depth) == Side::Left);
+        if split == 0 || split == paths.len() {
+            self.collect(depth + 1, paths);
+            self.peers.push(paths[0].1[height - 1].clone());
+        } else {
+            self.collect(depth + 1, &paths[..split]);
+            self.collect(depth + 1, &paths[split..]);
+        }
+    }
+
+    /// Computes the root obtained by evaluating this proof with the given leaves
+    pub fn evaluate(&self, leaves: &[(K, V)]) -> Result<Hash<D>, MultiProofError> {
+        let mut leaves: Vec<(Hash<D>, Hash<D>)> = leaves
+            .iter()
+            .map(|(key, value)| (Hash::of(key), hash_leaf(value)))
+            .collect();
+        if leaves.is_empty() {
+            return Err(MultiProofError::NoLeaves);
+        }
+
+        leaves.sort_by(|(a, _), (b, _)| a.bytes().cmp(b.bytes()));
+        for pair in leaves.windows(2) {
+            if pair[0].0 == pair[1].0 && pair[0].1 != pair[1].1 {
+                return Err(MultiProofError::ConflictingValues);
+            }
+        }
+        leaves.dedup();
+
+        let mut skips = self.skips.iter();
+        let mut peers = self.peers.iter();
+        let root = evaluate_subtree(0, &leaves, &mut skips, &mut peers)?;
+        if skips.next().is_some() || peers.next().is_some() {
+            return Err(MultiProofError::KeysMismatch);
+        }
+        Ok(root)
+    }
+}
+
+/// Computes the hash of the subtree at the given depth containing the given
+/// (non-empty) sorted leaves, consuming the peers it needs.
+fn evaluate_subtree<'a, D: SupportedDigest>(
+    depth: usize,
+    leaves: &[(Hash<D>, Hash<D>)],
+    skips: &mut impl Iterator<Item = &'a usize>,
+    peers: &mut impl Iterator<Item = &'a Option<Hash<D>>>,
+) -> Result<Hash<D>, MultiProofError> {
+    let height = 256 - depth;
+    if let [(key, leaf)] = leaves {
+        let skip = *skips.next().ok_or(MultiProofError::KeysMismatch)?;
+        if skip > height {
+            return Err(MultiProofError::KeysMismatch);
+        }
+
+        let path = Path::new(key);
+        let mut hash = leaf.clone();
+        for h in 0..height {
+            let peer = if h < skip {
+                D::empty_tree_hash(h).clone()
+            } else {
+                next_peer(peers, h)?
+            };
+            hash = match path.get(255 - h) {
+                Side::Left => hash_branch(&hash, &peer),
+                Side::Right => hash_branch(&peer, &hash),
+            };
+        }
+        return Ok(hash);
+    }
+
+    let split = leaves.partition_point(|(key, _)| Path::new(key).get(depth) == Side::Left);
+    if split == 0 || split == leaves.len() {
+        let child = evaluate_subtree(depth + 1, leaves, skips, peers)?;
+        let peer = next_peer(peers, height - 1)?;
+        Ok(if split == 0 {
+            hash_branch(&peer, &child)
+        } else {
+            hash_branch(&child, &peer)
+        })
+    } else {
+        let left = evaluate_subtree(depth + 1, &leaves[..split], skips, peers)?;
+        let right = evaluate_subtree(depth + 1, &leaves[split..], skips, peers)?;
+        Ok(hash_branch(&left, &right))
+    }
+}
+
+/// Gets the next peer of the given height.
+fn next_peer<'a, D: SupportedDigest>(
+    peers: &mut impl Iterator<Item = &'a Option<Hash<D>>>,
+    height: usize,
+) -> Result<Hash<D>, MultiProofError> {
+    match peers.next() {
+        Some(Some(peer)) => Ok(peer.clone()),
+        Some(None) => Ok(D::empty_tree_hash(height).clone()),
+        None => Err(MultiProofError::KeysMismatch),
+    }
+}
@@ -6,8 +6,10 @@ use super::{
     map::hash_branch,
     node::Node,
     path::{Path, ReversePath, Side},
+    proof::AbsenceProof,
 };
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, iter::repeat, sync::Arc};
+use warg_crypto::VisitBytes;
 
 #[derive(Debug)]
 pub struct Singleton<D: SupportedDigest> {
@@ -40,6 +42,27 @@ impl<D: SupportedDigest> Singleton<D> {
         hash
     }
 
+    /// Proves that the given key is not the key of this singleton.
+    ///
+    /// The singleton's subtree is empty except for the path of its own key,
+    /// so the only non-empty peer along the given key's path is the subtree
+    /// below the first bit where the two keys diverge.
+    pub fn prove_absence<K: VisitBytes>(&self, key: &Hash<D>) -> Option<AbsenceProof<D, K>> {
+        if self.key() == key {
+            return None;
+        }
+
+        let ours = Path::new(key);
+        let theirs = Path::new(&self.key);
+        let top = 256 - self.height;
+        let diverge = (top..256).find(|&i| ours.get(i) != theirs.get(i))?;
+
+        let peer = Singleton::new(self.key.clone(), self.value.clone(), 255 - diverge).hash();
+        let mut peers = vec![Some(peer)];
+        peers.extend(repeat(None).take(diverge - top));
+        Some(AbsenceProof::new(peers))
+    }
+
     pub fn insert(
         &self,
         path: &mut Path<'_, D>,
@@ -3,9 +3,10 @@ use clap::Parser;
 use std::process::exit;
 use tracing_subscriber::EnvFilter;
 use warg_cli::commands::{
-    BundleCommand, ClearCommand, ConfigCommand, DependenciesCommand, DownloadCommand, InfoCommand,
-    KeyCommand, LockCommand, LoginCommand, LogoutCommand, PublishCommand, ResetCommand,
-    UpdateCommand,
+    AuditCommand, BundleCommand, CacheCommand, ClearCommand, ConfigCommand, DependenciesCommand,
+    DownloadCommand, GossipCommand, InfoCommand, KeyCommand, LockCommand, LoginCommand,
+    LogoutCommand, NamespaceCommand, PublishCommand, ResetCommand, UpdateCommand, VendorCommand,
+    WatchCommand,
 };
 use warg_client::ClientError;
 
@@ -30,11 +31,17 @@ enum WargCli {
     Bundle(BundleCommand),
     Dependencies(DependenciesCommand),
     Download(DownloadCommand),
+    Gossip(GossipCommand),
+    Audit(AuditCommand),
     Update(UpdateCommand),
+    Vendor(VendorCommand),
+    Watch(WatchCommand),
     #[clap(subcommand)]
     Publish(PublishCommand),
     Reset(ResetCommand),
     Clear(ClearCommand),
+    Cache(CacheCommand),
+    Namespace(NamespaceCommand),
     Login(LoginCommand),
     Logout(LogoutCommand),
 }
@@ -53,10 +60,16 @@ async fn main() -> Result<()> {
         WargCli::Bundle(cmd) => cmd.exec().await,
         WargCli::Dependencies(cmd) => cmd.exec().await,
         WargCli::Download(cmd) => cmd.exec().await,
+        WargCli::Gossip(cmd) => cmd.exec().await,
+        WargCli::Audit(cmd) => cmd.exec().await,
         WargCli::Update(cmd) => cmd.exec().await,
+        WargCli::Vendor(cmd) => cmd.exec().await,
+        WargCli::Watch(cmd) => cmd.exec().await,
         WargCli::Publish(cmd) => cmd.exec().await,
         WargCli::Reset(cmd) => cmd.exec().await,
         WargCli::Clear(cmd) => cmd.exec().await,
+        WargCli::Cache(cmd) => cmd.exec().await,
+        WargCli::Namespace(cmd) => cmd.exec().await,
         WargCli::Login(cmd) => cmd.exec().await,
         WargCli::Logout(cmd) => cmd.exec().await,
     } {
@@ -149,6 +162,26 @@ This may be expected behavior for registries that offer key management."
         ClientError::Unauthorized(reason) => {
             eprintln!("Unauthorized: {reason}")
         }
+        ClientError::CheckpointEquivocation { ours, theirs } => {
+            eprintln!("error: {e}");
+            for (observer, checkpoint) in [("client", ours), ("peer", theirs)] {
+                let ts_checkpoint = checkpoint.as_ref().as_ref();
+                eprintln!(
+                    "{observer} checkpoint: log length {log_length}, log root `{log_root}`, map root `{map_root}`, timestamp {timestamp}, signed by `{key_id}` with signature `{signature}`",
+                    log_length = ts_checkpoint.checkpoint.log_length,
+                    log_root = ts_checkpoint.checkpoint.log_root,
+                    map_root = ts_checkpoint.checkpoint.map_root,
+                    timestamp = ts_checkpoint.timestamp,
+                    key_id = checkpoint.key_id(),
+                    signature = checkpoint.signature(),
+                );
+            }
+        }
+        ClientError::OfflineUnavailable(operation) => {
+            eprintln!(
+                "Unable to {operation} while in offline mode; the registry must be contacted."
+            );
+        }
         _ => {
             eprintln!("error: {e}")
         }
@@ -4,37 +4,49 @@ use anyhow::Result;
 use clap::Args;
 use std::path::PathBuf;
 use warg_client::keyring::Keyring;
+use warg_client::signer::{block_in_place, ExternalSigner, Signer};
 use warg_client::storage::RegistryDomain;
 use warg_client::{ClientError, Config, FileSystemClient, StorageLockResult};
-use warg_crypto::signing::PrivateKey;
 
+mod audit;
 mod bundle;
+mod cache;
 mod clear;
 mod config;
 mod dependencies;
 mod download;
+mod gossip;
 mod info;
 mod key;
 mod lock;
 mod login;
 mod logout;
+mod namespace;
 mod publish;
 mod reset;
 mod update;
+mod vendor;
+mod watch;
 
+pub use self::audit::*;
 pub use self::bundle::*;
+pub use self::cache::*;
 pub use self::clear::*;
 pub use self::config::*;
 pub use self::dependencies::*;
 pub use self::download::*;
+pub use self::gossip::*;
 pub use self::info::*;
 pub use self::key::*;
 pub use self::lock::*;
 pub use self::login::*;
 pub use self::logout::*;
+pub use self::namespace::*;
 pub use self::publish::*;
 pub use self::reset::*;
 pub use self::update::*;
+pub use self::vendor::*;
+pub use self::watch::*;
 
 /// Common options for commands.
 #[derive(Args)]
@@ -42,6 +54,14 @@ pub struct CommonOptions {
     /// The URL of the registry to use.
     #[clap(long, value_name = "URL")]
     pub registry: Option<String>,
+    /// The client configuration options.
+    #[clap(flatten)]
+    pub options: ConfigOptions,
+}
+
+/// Options for the client configuration of commands.
+#[derive(Args, Clone)]
+pub struct ConfigOptions {
     /// The path to the client configuration file to use.
     ///
     /// If not specified, the following locations are searched in order: `./warg-config.json`, `<system-config-dir>/warg/config.json`.
@@ -49,9 +69,14 @@ pub struct CommonOptions {
     /// If no configuration file is found, a default configuration is used.
     #[clap(long, value_name = "CONFIG")]
     pub config: Option<PathBuf>,
+    /// Resolve packages and content only from local storage.
+    ///
+    /// Any operation that requires contacting the registry will fail.
+    #[clap(long)]
+    pub offline: bool,
 }
 
-impl CommonOptions {
+impl ConfigOptions {
     /// Reads the client configuration.
     ///
     /// If a client configuration was not specified, a default configuration is returned.
@@ -64,9 +89,22 @@ impl CommonOptions {
             })?
             .unwrap_or_default())
     }
+}
+
+impl CommonOptions {
+    /// Reads the client configuration.
+    ///
+    /// If a client configuration was not specified, a default configuration is returned.
+    pub fn read_config(&self) -> Result<Config> {
+        self.options.read_config()
+    }
 
     /// Creates the warg client to use.
     pub async fn create_client(&self, config: &Config) -> Result<FileSystemClient, ClientError> {
+        let config = &Config {
+            offline: config.offline || self.options.offline,
+            ..config.clone()
+        };
         let client =
             match FileSystemClient::try_new_with_config(self.registry.as_deref(), config, None)
                 .await?
@@ -84,17 +122,24 @@ impl CommonOptions {
         Ok(client)
     }
 
-    /// Gets the signing key for the given registry URL.
+    /// Gets the signer for the given registry URL.
+    ///
+    /// If a signing program is configured, records are signed by the program;
+    /// otherwise, the signing key is retrieved from the keyring.
     pub async fn signing_key(
         &self,
         registry_domain: Option<&RegistryDomain>,
-    ) -> Result<PrivateKey> {
+    ) -> Result<Box<dyn Signer>> {
         let config = self.read_config()?;
+        if let Some(program) = &config.signing_program {
+            return Ok(Box::new(block_in_place(|| ExternalSigner::new(program))?));
+        }
+
         let key = Keyring::from_config(&config)?.get_signing_key(
             registry_domain.map(|domain| domain.to_string()).as_deref(),
             &config.keys,
             config.home_url.as_deref(),
         )?;
-        Ok(key)
+        Ok(Box::new(key))
     }
 }
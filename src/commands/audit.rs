@@ -0,0 +1,28 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::Args;
+
+/// Re-verify the chain of registry checkpoints accepted by the client.
+#[derive(Args)]
+pub struct AuditCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+}
+
+impl AuditCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+
+        let summary = client.audit(None).await?;
+        println!(
+            "audited {checkpoints} accepted checkpoint(s) up to the latest checkpoint with log length {log_length}",
+            checkpoints = summary.checkpoints,
+            log_length = summary.log_length,
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::time::Duration;
+use warg_client::ContentGcOptions;
+
+/// Manage the local content cache.
+#[derive(Args)]
+pub struct CacheCommand {
+    /// The subcommand to execute.
+    #[clap(subcommand)]
+    pub command: CacheSubcommand,
+}
+
+impl CacheCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        match self.command {
+            CacheSubcommand::Gc(cmd) => cmd.exec().await,
+        }
+    }
+}
+
+/// The subcommand to execute.
+#[derive(Subcommand)]
+pub enum CacheSubcommand {
+    /// Removes content not referenced by any locally tracked package.
+    Gc(CacheGcCommand),
+}
+
+/// Removes content not referenced by any locally tracked package.
+#[derive(Args)]
+pub struct CacheGcCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// Also remove referenced content older than the given number of days.
+    #[clap(long, value_name = "DAYS")]
+    pub max_age_days: Option<u64>,
+    /// Also remove the oldest referenced content until the cache is at most the given number of bytes.
+    #[clap(long, value_name = "BYTES")]
+    pub max_size: Option<u64>,
+    /// Only print the content that would be removed.
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+impl CacheGcCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+
+        println!("collecting unreferenced content...");
+        let summary = client
+            .gc_content_cache(&ContentGcOptions {
+                max_age: self
+                    .max_age_days
+                    .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+                max_size: self.max_size,
+                dry_run: self.dry_run,
+            })
+            .await?;
+
+        for entry in &summary.removed {
+            let kind = if entry.partial {
+                " (partial download)"
+            } else {
+                ""
+            };
+            if self.dry_run {
+                println!("would remove `{digest}`{kind}", digest = entry.digest);
+            } else {
+                println!("removed `{digest}`{kind}", digest = entry.digest);
+            }
+        }
+
+        println!(
+            "{count} content item(s) totaling {removed} bytes {action}; {retained} bytes retained",
+            count = summary.removed.len(),
+            removed = summary.removed.iter().map(|e| e.size).sum::<u64>(),
+            action = if self.dry_run {
+                "would be removed"
+            } else {
+                "removed"
+            },
+            retained = summary.retained_size,
+        );
+
+        Ok(())
+    }
+}
@@ -2,7 +2,12 @@ use super::CommonOptions;
 use anyhow::{bail, Context, Result};
 use clap::Args;
 use std::path::PathBuf;
-use warg_client::{keyring::Keyring, Config, RegistryUrl};
+use warg_client::{
+    keyring::Keyring,
+    storage::{OperatorPin, RegistryDomain},
+    Config, RegistryUrl,
+};
+use warg_crypto::signing::PublicKey;
 
 /// Creates a new warg configuration file.
 #[derive(Args)]
@@ -44,6 +49,34 @@ pub struct ConfigCommand {
     /// The backend to use for keyring access
     #[clap(long, value_name = "KEYRING_BACKEND", value_parser = keyring_backend_parser, long_help = keyring_backend_help())]
     pub keyring_backend: Option<String>,
+
+    /// The public key of a witness trusted to cosign registry checkpoints.
+    ///
+    /// May be specified multiple times; replaces any configured witness keys.
+    #[clap(long = "witness-key", value_name = "PUBLIC_KEY")]
+    pub witness_keys: Vec<PublicKey>,
+
+    /// The number of trusted witness cosignatures required before a
+    /// registry checkpoint is accepted.
+    #[clap(long, value_name = "THRESHOLD")]
+    pub witness_threshold: Option<usize>,
+
+    /// The path to an external program used to sign published records
+    /// instead of a signing key from the keyring.
+    #[clap(long, value_name = "PROGRAM")]
+    pub signing_program: Option<PathBuf>,
+
+    /// Pins the operator log of a registry to an operator key ID and,
+    /// optionally, the record ID of the log's init record.
+    ///
+    /// May be specified multiple times; replaces any configured pin of the
+    /// given registry.
+    #[clap(
+        long = "operator-pin",
+        value_name = "REGISTRY=KEY_ID[,RECORD_ID]",
+        value_parser = operator_pin_parser
+    )]
+    pub operator_pins: Vec<(RegistryDomain, OperatorPin)>,
 }
 
 impl ConfigCommand {
@@ -88,7 +121,13 @@ impl ConfigCommand {
                 ignore_federation_hints: self.ignore_federation_hints.unwrap_or_default(),
                 auto_accept_federation_hints: self.auto_accept_federation_hints.unwrap_or_default(),
                 disable_interactive: false,
+                offline: false,
+                vendor_dir: None,
                 keyring_backend: self.keyring_backend,
+                witness_keys: self.witness_keys,
+                witness_threshold: self.witness_threshold.unwrap_or_default(),
+                signing_program: self.signing_program,
+                operator_pins: self.operator_pins.into_iter().collect(),
             }
         } else {
             let mut config = self.common.read_config()?;
@@ -126,6 +165,16 @@ impl ConfigCommand {
             if self.keyring_backend.is_some() {
                 config.keyring_backend = self.keyring_backend;
             }
+            if !self.witness_keys.is_empty() {
+                config.witness_keys = self.witness_keys;
+            }
+            if let Some(witness_threshold) = self.witness_threshold {
+                config.witness_threshold = witness_threshold;
+            }
+            if self.signing_program.is_some() {
+                config.signing_program = self.signing_program;
+            }
+            config.operator_pins.extend(self.operator_pins);
 
             config
         };
@@ -150,6 +199,13 @@ impl ConfigCommand {
     }
 }
 
+fn operator_pin_parser(s: &str) -> Result<(RegistryDomain, OperatorPin)> {
+    let (registry, pin) = s
+        .split_once('=')
+        .context("expected an operator pin of the form `<REGISTRY>=<KEY_ID>[,<RECORD_ID>]`")?;
+    Ok((RegistryUrl::new(registry)?.registry_domain(), pin.parse()?))
+}
+
 pub(crate) fn keyring_backend_parser(s: &str) -> Result<String, String> {
     if Keyring::SUPPORTED_BACKENDS.contains(&s) {
         Ok(s.to_string())
@@ -22,6 +22,9 @@ pub struct DownloadCommand {
     /// The output path for the file. If not specified, just downloads to local cache.
     #[clap(long, short = 'o')]
     pub output: Option<PathBuf>,
+    /// Verify and download only this package, without syncing its log into local storage.
+    #[clap(long)]
+    pub standalone: bool,
 }
 
 impl DownloadCommand {
@@ -38,10 +41,13 @@ impl DownloadCommand {
             None => VersionReq::STAR,
         };
 
-        let download = client
-            .download(&self.name, &version)
-            .await?
-            .ok_or_else(|| ClientError::PackageVersionRequirementDoesNotExist {
+        let download = if self.standalone {
+            client.download_with_proof(&self.name, &version).await?
+        } else {
+            client.download(&self.name, &version).await?
+        };
+        let download =
+            download.ok_or_else(|| ClientError::PackageVersionRequirementDoesNotExist {
                 name: self.name.clone(),
                 version,
             })?;
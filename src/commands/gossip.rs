@@ -0,0 +1,41 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::Args;
+
+/// Compare the latest registry checkpoint with peers.
+#[derive(Args)]
+pub struct GossipCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The namespace whose registry is gossiped about; defaults to the home registry.
+    #[clap(long, value_name = "NAMESPACE")]
+    pub namespace: Option<String>,
+    /// The URLs of the peers to gossip with.
+    #[clap(value_name = "PEER", required = true)]
+    pub peers: Vec<String>,
+}
+
+impl GossipCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+        let registry_domain = match &self.namespace {
+            Some(namespace) => client.get_warg_registry(namespace).await?,
+            None => None,
+        };
+
+        for peer in &self.peers {
+            let checkpoint = client
+                .gossip_checkpoint(registry_domain.as_ref(), peer)
+                .await?;
+            println!(
+                "peer `{peer}` observed a consistent checkpoint with log length {log_length}",
+                log_length = checkpoint.as_ref().checkpoint.log_length,
+            );
+        }
+
+        Ok(())
+    }
+}
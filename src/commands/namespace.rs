@@ -0,0 +1,194 @@
+use super::CommonOptions;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use indexmap::IndexMap;
+use std::path::PathBuf;
+use warg_client::{
+    storage::{FileSystemNamespaceMapStorage, NamespaceMapStorage},
+    Config, RegistryUrl,
+};
+use warg_protocol::{operator::NamespaceState, registry::PackageName};
+
+/// Manage the mapping of namespaces to registries.
+#[derive(Args)]
+pub struct NamespaceCommand {
+    /// The subcommand to execute.
+    #[clap(subcommand)]
+    pub command: NamespaceSubcommand,
+}
+
+impl NamespaceCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        match self.command {
+            NamespaceSubcommand::List(cmd) => cmd.exec().await,
+            NamespaceSubcommand::Set(cmd) => cmd.exec().await,
+            NamespaceSubcommand::Remove(cmd) => cmd.exec().await,
+            NamespaceSubcommand::Import(cmd) => cmd.exec().await,
+        }
+    }
+}
+
+/// The subcommand to execute.
+#[derive(Subcommand)]
+pub enum NamespaceSubcommand {
+    /// Lists the namespaces mapped to registries.
+    List(NamespaceListCommand),
+    /// Maps a namespace to the registry that defines it.
+    Set(NamespaceSetCommand),
+    /// Removes the mapping of a namespace.
+    Remove(NamespaceRemoveCommand),
+    /// Imports namespace mappings from a file.
+    Import(NamespaceImportCommand),
+}
+
+/// Lists the namespaces mapped to registries.
+#[derive(Args)]
+pub struct NamespaceListCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+}
+
+impl NamespaceListCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let mapping = FileSystemNamespaceMapStorage::new(config.namespace_map_path()?)
+            .load_namespace_map()
+            .await?
+            .unwrap_or_default();
+        if mapping.is_empty() {
+            println!("no namespaces are mapped to registries");
+        }
+
+        for (namespace, registry) in mapping {
+            println!("{namespace} = {registry}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps a namespace to the registry that defines it.
+#[derive(Args)]
+pub struct NamespaceSetCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The namespace to map.
+    #[clap(value_name = "NAMESPACE")]
+    pub namespace: String,
+    /// The registry that defines the namespace.
+    #[clap(value_name = "REGISTRY")]
+    pub target: String,
+}
+
+impl NamespaceSetCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        set_namespace(&self.common, &config, self.namespace, &self.target).await
+    }
+}
+
+/// Removes the mapping of a namespace.
+#[derive(Args)]
+pub struct NamespaceRemoveCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The namespace to remove the mapping of.
+    #[clap(value_name = "NAMESPACE")]
+    pub namespace: String,
+}
+
+impl NamespaceRemoveCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let namespace_map = FileSystemNamespaceMapStorage::new(config.namespace_map_path()?);
+
+        if !namespace_map.remove_namespace(&self.namespace).await? {
+            bail!(
+                "namespace `{namespace}` is not mapped to a registry",
+                namespace = self.namespace
+            );
+        }
+
+        println!(
+            "removed mapping of namespace `{namespace}`",
+            namespace = self.namespace
+        );
+        Ok(())
+    }
+}
+
+/// Imports namespace mappings from a file.
+#[derive(Args)]
+pub struct NamespaceImportCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The path to a JSON file mapping namespaces to registries.
+    #[clap(value_name = "PATH")]
+    pub path: PathBuf,
+}
+
+impl NamespaceImportCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read `{path}`", path = self.path.display()))?;
+        let mapping: IndexMap<String, String> =
+            serde_json::from_str(&contents).with_context(|| {
+                format!(
+                    "failed to deserialize namespace mappings from `{path}`",
+                    path = self.path.display()
+                )
+            })?;
+
+        for (namespace, registry) in mapping {
+            set_namespace(&self.common, &config, namespace, &registry).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps a namespace to a registry after verifying the registry defines it.
+async fn set_namespace(
+    common: &CommonOptions,
+    config: &Config,
+    namespace: String,
+    registry: &str,
+) -> Result<()> {
+    if !PackageName::is_valid_namespace(&namespace) {
+        bail!("invalid namespace `{namespace}`: expected a lowercased kebab-case string");
+    }
+    let registry_domain = RegistryUrl::new(registry)?.registry_domain();
+
+    // The namespace map is shared by all registries, so the client for the
+    // target registry can store the mapping once the namespace is verified
+    let target = CommonOptions {
+        registry: Some(registry.to_string()),
+        options: common.options.clone(),
+    };
+    let client = target.create_client(config).await?;
+
+    match client.fetch_namespace_state(&namespace).await? {
+        Some(NamespaceState::Defined) => {}
+        Some(NamespaceState::Imported { registry: source }) => bail!(
+            "namespace `{namespace}` is imported by registry `{registry}` from registry `{source}`"
+        ),
+        None => bail!("namespace `{namespace}` is not defined by registry `{registry}`"),
+    }
+
+    client
+        .store_namespace(namespace.clone(), registry_domain.clone())
+        .await?;
+
+    println!("mapped namespace `{namespace}` to registry `{registry_domain}`");
+    Ok(())
+}
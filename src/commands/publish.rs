@@ -1,4 +1,4 @@
-use super::CommonOptions;
+use super::{CommonOptions, ConfigOptions};
 use anyhow::{anyhow, bail, Context, Result};
 use clap::{Args, Subcommand};
 use dialoguer::{theme::ColorfulTheme, Confirm};
@@ -8,15 +8,17 @@ use std::{future::Future, path::PathBuf, time::Duration};
 use tokio::io::BufReader;
 use tokio_util::io::ReaderStream;
 use warg_client::{
+    identity::GitHubActionsTokenSource,
+    signer::Signer,
     storage::{ContentStorage as _, PublishEntry, PublishInfo, RegistryStorage as _},
-    FileSystemClient,
+    FileSystemClient, RegistryUrl,
 };
 use warg_crypto::{
     hash::AnyHash,
     signing::{KeyID, PublicKey},
 };
 use warg_protocol::{
-    package::Permission,
+    package::{IdentityPattern, Permission},
     registry::{PackageName, RecordId},
     Version,
 };
@@ -57,6 +59,15 @@ where
     }
 }
 
+/// Gets the identity token source for keyless publishing from the environment.
+fn identity_token_source() -> Result<GitHubActionsTokenSource> {
+    GitHubActionsTokenSource::from_env().ok_or_else(|| {
+        anyhow!(
+            "keyless publishing requires a GitHub Actions workflow with the `id-token: write` permission"
+        )
+    })
+}
+
 /// Publish a package to a warg registry.
 #[derive(Subcommand)]
 pub enum PublishCommand {
@@ -70,6 +81,10 @@ pub enum PublishCommand {
     Grant(PublishGrantCommand),
     /// Revoke permissions for the package.
     Revoke(PublishRevokeCommand),
+    /// Grant permissions for the package to publisher identities.
+    GrantIdentity(PublishGrantIdentityCommand),
+    /// Revoke permissions for the package from publisher identities.
+    RevokeIdentity(PublishRevokeIdentityCommand),
     /// Start a new pending publish.
     Start(PublishStartCommand),
     /// List the records in a pending publish.
@@ -91,6 +106,8 @@ impl PublishCommand {
             Self::Yank(cmd) => cmd.exec().await,
             Self::Grant(cmd) => cmd.exec().await,
             Self::Revoke(cmd) => cmd.exec().await,
+            Self::GrantIdentity(cmd) => cmd.exec().await,
+            Self::RevokeIdentity(cmd) => cmd.exec().await,
             Self::Start(cmd) => cmd.exec().await,
             Self::List(cmd) => cmd.exec().await,
             Self::Abort(cmd) => cmd.exec().await,
@@ -169,9 +186,16 @@ impl PublishInitCommand {
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
 pub struct PublishReleaseCommand {
-    /// The common command options.
+    /// The URL of the registry to publish to.
+    ///
+    /// May be specified multiple times to publish the release to each of the
+    /// registries; the package is initialized in any registry where it does
+    /// not yet exist.
+    #[clap(long = "registry", value_name = "URL")]
+    pub registries: Vec<String>,
+    /// The client configuration options.
     #[clap(flatten)]
-    pub common: CommonOptions,
+    pub options: ConfigOptions,
     /// The package name being published.
     #[clap(long, short, value_name = "PACKAGE")]
     pub name: PackageName,
@@ -184,49 +208,131 @@ pub struct PublishReleaseCommand {
     /// Whether to wait for the publish to complete.
     #[clap(long)]
     pub no_wait: bool,
+    /// Sign the release with an ephemeral key bound to the publisher identity
+    /// of the current GitHub Actions workflow instead of a signing key.
+    #[clap(long)]
+    pub keyless: bool,
+    /// When publishing to multiple registries, yank the release from the
+    /// registries that published it if another registry fails to publish it.
+    #[clap(long)]
+    pub yank_on_failure: bool,
+}
+
+/// The status of a release published to one of several registries.
+enum ReleaseStatus {
+    /// The release was published.
+    Published(RecordId),
+    /// The release failed to publish.
+    Failed(anyhow::Error),
+    /// The release was not attempted because another registry failed.
+    Skipped,
+    /// The release was published and then yanked because another registry failed.
+    Yanked,
+    /// The release was published but yanking it failed.
+    YankFailed(anyhow::Error),
 }
 
 impl PublishReleaseCommand {
+    /// Gets the common command options for the given registry.
+    fn common(&self, registry: Option<&str>) -> CommonOptions {
+        CommonOptions {
+            registry: registry.map(ToOwned::to_owned),
+            options: self.options.clone(),
+        }
+    }
+
+    /// Stores the content of the release in client content storage.
+    async fn store_content(&self, client: &FileSystemClient) -> Result<AnyHash> {
+        let path = &self.path;
+        client
+            .content()
+            .store_content(
+                Box::pin(
+                    ReaderStream::new(BufReader::new(
+                        tokio::fs::File::open(path).await.with_context(|| {
+                            format!("failed to open `{path}`", path = path.display())
+                        })?,
+                    ))
+                    .map_err(|e| anyhow!(e)),
+                ),
+                None,
+            )
+            .await
+    }
+
+    /// Publishes the given information and waits for it to complete.
+    async fn publish(
+        &self,
+        client: &FileSystemClient,
+        signing_key: Option<&dyn Signer>,
+        info: PublishInfo,
+    ) -> Result<RecordId> {
+        let record_id = match signing_key {
+            Some(signing_key) => client.publish_with_info(signing_key, info).await?,
+            None => {
+                client
+                    .publish_with_identity(&identity_token_source()?, info)
+                    .await?
+            }
+        };
+
+        client
+            .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+            .await?;
+        Ok(record_id)
+    }
+
     /// Executes the command.
     pub async fn exec(self) -> Result<()> {
-        let config = self.common.read_config()?;
-        let client = self.common.create_client(&config).await?;
+        let domains = self
+            .registries
+            .iter()
+            .map(|registry| Ok(RegistryUrl::new(registry)?.registry_domain()))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(domain) = domains.iter().duplicates().next() {
+            bail!("registry `{domain}` was specified more than once");
+        }
+
+        if self.registries.len() > 1 {
+            return self.exec_multiple().await;
+        }
+
+        let common = self.common(self.registries.first().map(String::as_str));
+        let config = common.read_config()?;
+        let client = common.create_client(&config).await?;
         let registry_domain = client.get_warg_registry(self.name.namespace()).await?;
-        let signing_key = self.common.signing_key(registry_domain.as_ref()).await?;
+        let signing_key = if self.keyless {
+            if client.registry().load_publish().await?.is_some() {
+                bail!("keyless publishing cannot be used with a pending publish");
+            }
 
-        let path = self.path.clone();
+            None
+        } else {
+            Some(common.signing_key(registry_domain.as_ref()).await?)
+        };
+
+        let command = &self;
         let version = self.version.clone();
         match enqueue(&client, &self.name, move |c| async move {
-            let content = c
-                .content()
-                .store_content(
-                    Box::pin(
-                        ReaderStream::new(BufReader::new(
-                            tokio::fs::File::open(&path).await.with_context(|| {
-                                format!("failed to open `{path}`", path = path.display())
-                            })?,
-                        ))
-                        .map_err(|e| anyhow!(e)),
-                    ),
-                    None,
-                )
-                .await?;
-
+            let content = command.store_content(c).await?;
             Ok(PublishEntry::Release { version, content })
         })
         .await?
         {
             Some(entry) => {
-                let record_id = client
-                    .publish_with_info(
-                        &signing_key,
-                        PublishInfo {
-                            name: self.name.clone(),
-                            head: None,
-                            entries: vec![entry],
-                        },
-                    )
-                    .await?;
+                let info = PublishInfo {
+                    name: self.name.clone(),
+                    head: None,
+                    entries: vec![entry],
+                };
+                let record_id = match &signing_key {
+                    Some(signing_key) => client.publish_with_info(signing_key, info).await?,
+                    None => {
+                        client
+                            .publish_with_identity(&identity_token_source()?, info)
+                            .await?
+                    }
+                };
 
                 if self.no_wait {
                     println!("submitted record `{record_id}` for publishing");
@@ -253,6 +359,152 @@ impl PublishReleaseCommand {
 
         Ok(())
     }
+
+    /// Publishes the release to each of the registries.
+    ///
+    /// Records in a registry cannot be removed once published, so the release
+    /// is checked against every registry before anything is signed. If a
+    /// registry still fails to publish its record, the remaining registries
+    /// are skipped and the release is optionally yanked from the registries
+    /// that published it.
+    async fn exec_multiple(self) -> Result<()> {
+        if self.no_wait {
+            bail!("`--no-wait` cannot be used when publishing to multiple registries");
+        }
+
+        let config = self.common(None).read_config()?;
+
+        let mut content: Option<AnyHash> = None;
+        let mut plans = Vec::with_capacity(self.registries.len());
+        for registry in &self.registries {
+            println!("checking registry `{registry}`...");
+            let common = self.common(Some(registry));
+            let client = common.create_client(&config).await?;
+            if client.registry().load_publish().await?.is_some() {
+                bail!("registry `{registry}` has a pending publish; submit or abort it first");
+            }
+
+            // Content storage is shared by all registries, so it is stored once
+            let digest = match &content {
+                Some(digest) => digest.clone(),
+                None => content.insert(self.store_content(&client).await?).clone(),
+            };
+
+            let registry_domain = client.get_warg_registry(self.name.namespace()).await?;
+            let signing_key = if self.keyless {
+                None
+            } else {
+                Some(common.signing_key(registry_domain.as_ref()).await?)
+            };
+
+            let info = client
+                .prepare_release(
+                    &self.name,
+                    self.version.clone(),
+                    digest,
+                    signing_key
+                        .as_ref()
+                        .map(|key| key.public_key().fingerprint())
+                        .as_ref(),
+                )
+                .await?;
+
+            plans.push((registry, common, signing_key, info));
+        }
+
+        let mut statuses = Vec::with_capacity(plans.len());
+        for (registry, common, signing_key, info) in &plans {
+            if statuses
+                .iter()
+                .any(|(_, status)| matches!(status, ReleaseStatus::Failed(_)))
+            {
+                statuses.push((registry, ReleaseStatus::Skipped));
+                continue;
+            }
+
+            println!("publishing to registry `{registry}`...");
+            let status = match common.create_client(&config).await {
+                Ok(client) => match self
+                    .publish(&client, signing_key.as_deref(), info.clone())
+                    .await
+                {
+                    Ok(record_id) => ReleaseStatus::Published(record_id),
+                    Err(e) => ReleaseStatus::Failed(e),
+                },
+                Err(e) => ReleaseStatus::Failed(e.into()),
+            };
+            statuses.push((registry, status));
+        }
+
+        let failed = statuses
+            .iter()
+            .any(|(_, status)| matches!(status, ReleaseStatus::Failed(_)));
+        if failed && self.yank_on_failure {
+            for ((registry, status), (_, common, signing_key, _)) in statuses.iter_mut().zip(&plans)
+            {
+                if !matches!(status, ReleaseStatus::Published(_)) {
+                    continue;
+                }
+
+                println!("yanking from registry `{registry}`...");
+                let info = PublishInfo {
+                    name: self.name.clone(),
+                    head: None,
+                    entries: vec![PublishEntry::Yank {
+                        version: self.version.clone(),
+                    }],
+                };
+                let yanked = match common.create_client(&config).await {
+                    Ok(client) => self.publish(&client, signing_key.as_deref(), info).await,
+                    Err(e) => Err(e.into()),
+                };
+                *status = match yanked {
+                    Ok(_) => ReleaseStatus::Yanked,
+                    Err(e) => ReleaseStatus::YankFailed(e),
+                };
+            }
+        }
+
+        for (registry, status) in &statuses {
+            match status {
+                ReleaseStatus::Published(record_id) => {
+                    println!("`{registry}`: published record `{record_id}`")
+                }
+                ReleaseStatus::Failed(e) => println!("`{registry}`: failed: {e:#}"),
+                ReleaseStatus::Skipped => println!("`{registry}`: skipped"),
+                ReleaseStatus::Yanked => println!("`{registry}`: published and yanked"),
+                ReleaseStatus::YankFailed(e) => {
+                    println!("`{registry}`: published but failed to yank: {e:#}")
+                }
+            }
+        }
+
+        if failed {
+            if !self.yank_on_failure
+                && statuses
+                    .iter()
+                    .any(|(_, status)| matches!(status, ReleaseStatus::Published(_)))
+            {
+                println!(
+                    "the release remains published to the registries above; yank it with `warg publish yank` or retry the failed registries"
+                );
+            }
+
+            bail!(
+                "failed to publish version {version} of package `{name}` to every registry",
+                version = self.version,
+                name = self.name
+            );
+        }
+
+        println!(
+            "published version {version} of package `{name}` to {count} registries",
+            version = self.version,
+            name = self.name,
+            count = statuses.len()
+        );
+        Ok(())
+    }
 }
 
 /// Yank a package release from a warg registry.
@@ -504,6 +756,164 @@ impl PublishRevokeCommand {
     }
 }
 
+/// Grant permissions for the package to publisher identities.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishGrantIdentityCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The identity pattern to grant permissions to, of the form `<issuer>#<subject>`.
+    ///
+    /// The subject may contain `*` wildcards.
+    #[clap(value_name = "IDENTITY")]
+    pub identity: IdentityPattern,
+    /// The permission(s) to grant.
+    #[clap(long = "permission", value_delimiter = ',', default_value = "release")]
+    pub permissions: Vec<Permission>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishGrantIdentityCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+        let registry_domain = client.get_warg_registry(self.name.namespace()).await?;
+        let signing_key = self.common.signing_key(registry_domain.as_ref()).await?;
+
+        match enqueue(&client, &self.name, |_| async {
+            Ok(PublishEntry::GrantIdentity {
+                identity: self.identity.clone(),
+                permissions: self.permissions.clone(),
+            })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let record_id = client
+                    .publish_with_info(
+                        &signing_key,
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "granted ({permissions_str}) to identity `{identity}` for package `{name}`",
+                        permissions_str = self.permissions.iter().join(","),
+                        identity = self.identity,
+                        name = self.name
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added grant of ({permissions_str}) to identity `{identity}` for package `{name}` to pending publish",
+                    permissions_str = self.permissions.iter().join(","),
+                    identity = self.identity,
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Revoke permissions for the package from publisher identities.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishRevokeIdentityCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The identity pattern to revoke permissions from, of the form `<issuer>#<subject>`.
+    #[clap(value_name = "IDENTITY")]
+    pub identity: IdentityPattern,
+    /// The permission(s) to revoke.
+    #[clap(long = "permission", value_delimiter = ',', default_value = "release")]
+    pub permissions: Vec<Permission>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishRevokeIdentityCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+        let registry_domain = client.get_warg_registry(self.name.namespace()).await?;
+        let signing_key = self.common.signing_key(registry_domain.as_ref()).await?;
+
+        match enqueue(&client, &self.name, |_| async {
+            Ok(PublishEntry::RevokeIdentity {
+                identity: self.identity.clone(),
+                permissions: self.permissions.clone(),
+            })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let record_id = client
+                    .publish_with_info(
+                        &signing_key,
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "revoked ({permissions_str}) from identity `{identity}` for package `{name}`",
+                        permissions_str = self.permissions.iter().join(","),
+                        identity = self.identity,
+                        name = self.name
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added revoke of ({permissions_str}) from identity `{identity}` for package `{name}` to pending publish",
+                    permissions_str = self.permissions.iter().join(","),
+                    identity = self.identity,
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Start a new pending publish.
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
@@ -588,6 +998,23 @@ impl PublishListCommand {
                             "revoke ({permissions_str}) from `{key_id}`",
                             permissions_str = permissions.iter().join(","),
                         ),
+                        PublishEntry::GrantIdentity {
+                            identity,
+                            permissions,
+                        } => println!(
+                            "grant ({permissions_str}) to identity `{identity}`",
+                            permissions_str = permissions.iter().join(","),
+                        ),
+                        PublishEntry::RevokeIdentity {
+                            identity,
+                            permissions,
+                        } => println!(
+                            "revoke ({permissions_str}) from identity `{identity}`",
+                            permissions_str = permissions.iter().join(","),
+                        ),
+                        PublishEntry::Identity { issuer, subject } => {
+                            println!("bind to identity `{subject}` issued by `{issuer}`")
+                        }
                     }
                 }
             }
@@ -689,6 +1116,21 @@ impl PublishSubmitCommand {
                                 "revoked ({permissions_str}) from `{key_id}`",
                                 permissions_str = permissions.iter().join(","),
                             ),
+                            PublishEntry::GrantIdentity {
+                                identity,
+                                permissions,
+                            } => println!(
+                                "granted ({permissions_str}) to identity `{identity}`",
+                                permissions_str = permissions.iter().join(","),
+                            ),
+                            PublishEntry::RevokeIdentity {
+                                identity,
+                                permissions,
+                            } => println!(
+                                "revoked ({permissions_str}) from identity `{identity}`",
+                                permissions_str = permissions.iter().join(","),
+                            ),
+                            PublishEntry::Identity { .. } => {}
                         }
                     }
                 }
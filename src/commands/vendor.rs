@@ -0,0 +1,49 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::Args;
+use std::path::PathBuf;
+use warg_protocol::registry::PackageName;
+
+/// Vendor packages, their dependencies, and verification proofs into a directory.
+///
+/// The vendor directory can be used by setting `vendorDir` in the client
+/// configuration; the client then resolves packages from the directory
+/// without contacting the registry.
+#[derive(Args)]
+pub struct VendorCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The directory to vendor packages into.
+    #[clap(value_name = "DIR")]
+    pub dir: PathBuf,
+    /// The packages to vendor; defaults to all packages in local storage.
+    #[clap(value_name = "PACKAGE")]
+    pub packages: Vec<PackageName>,
+}
+
+impl VendorCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+
+        println!(
+            "vendoring packages into `{dir}`...",
+            dir = self.dir.display()
+        );
+
+        let manifest = client.vendor(&self.dir, &self.packages).await?;
+        for registry in &manifest.registries {
+            for package in &registry.packages {
+                println!(
+                    "vendored `{name}` at registry log length {log_length}",
+                    name = package.name,
+                    log_length = registry.checkpoint.as_ref().checkpoint.log_length,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
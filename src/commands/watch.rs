@@ -0,0 +1,37 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::Args;
+use warg_protocol::registry::PackageName;
+
+/// Watch package logs for new records.
+#[derive(Args)]
+pub struct WatchCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The packages to watch.
+    #[clap(value_name = "PACKAGE", required = true)]
+    pub packages: Vec<PackageName>,
+}
+
+impl WatchCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config).await?;
+
+        println!("watching for new records...");
+        loop {
+            for info in client.watch(&self.packages).await? {
+                match info.state.releases().last() {
+                    Some(release) => println!(
+                        "package `{name}` updated; latest release is {version}",
+                        name = info.name,
+                        version = release.version,
+                    ),
+                    None => println!("package `{name}` updated", name = info.name),
+                }
+            }
+        }
+    }
+}
@@ -1,11 +1,26 @@
 use self::support::*;
 use anyhow::{bail, Context, Result};
-use std::{fs, time::Duration};
+use std::{fs, str::FromStr, time::Duration};
+use warg_cli::commands::{
+    CommonOptions, ConfigOptions, NamespaceSetCommand, PublishReleaseCommand,
+};
 use warg_client::{
-    storage::{ContentStorage, PublishEntry, PublishInfo, RegistryStorage},
-    Config, FileSystemClient, StorageLockResult,
+    keyring::Keyring,
+    storage::{
+        ContentStorage, FileSystemNamespaceMapStorage, FileSystemRegistryStorage,
+        NamespaceMapStorage, OperatorPin, PublishEntry, PublishInfo, RegistryDomain,
+        RegistryStorage,
+    },
+    vendor::VendorManifest,
+    ClientError, Config, ContentGcOptions, FileSystemClient, RegistryUrl, StorageLockResult,
+};
+use warg_crypto::{
+    hash::{AnyHash, Hash, Sha256},
+    signing::KeyID,
+};
+use warg_protocol::{
+    operator::NamespaceState, package::Permission, registry::PackageName, SerdeEnvelope,
 };
-use warg_protocol::registry::PackageName;
 
 pub mod support;
 
@@ -115,3 +130,1040 @@ async fn client_incrementally_fetches() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_resolves_offline() -> Result<()> {
+    const PACKAGE_NAME: &str = "test:offline";
+    const MISSING_PACKAGE_NAME: &str = "test:missing";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let digest = publish_component(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // Populate the local storage with the package log and content
+    client
+        .download(&name, &PACKAGE_VERSION.parse()?)
+        .await?
+        .context("failed to resolve package")?;
+
+    drop(client);
+
+    let offline_config = Config {
+        offline: true,
+        ..config.clone()
+    };
+    let client = create_client(&offline_config).await?;
+    assert!(client.is_offline());
+
+    // The package and its content resolve from local storage
+    let download = client
+        .download(&name, &PACKAGE_VERSION.parse()?)
+        .await?
+        .context("failed to resolve package offline")?;
+    assert_eq!(download.digest, digest);
+
+    // Anything requiring the registry fails
+    match client.update().await {
+        Err(ClientError::OfflineUnavailable(_)) => {}
+        res => bail!("expected offline error from update but got {res:?}"),
+    }
+
+    match client
+        .download(&PackageName::new(MISSING_PACKAGE_NAME)?, &"*".parse()?)
+        .await
+    {
+        Err(ClientError::OfflineUnavailable(_)) => {}
+        res => bail!("expected offline error for a package not in storage but got {res:?}"),
+    }
+
+    // Content missing from local storage cannot be downloaded
+    client.clear_content_cache().await?;
+    match client.download(&name, &PACKAGE_VERSION.parse()?).await {
+        Err(ClientError::OfflineUnavailable(_)) => {}
+        res => bail!("expected offline error for missing content but got {res:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_resolves_vendored_packages() -> Result<()> {
+    const PACKAGE_NAME: &str = "test:vendored";
+
+    let root = root().await?;
+    let vendor_dir = root.join("vendor");
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let previous =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    let manifest = client
+        .vendor(&vendor_dir, std::slice::from_ref(&name))
+        .await?;
+    let previous_path = manifest
+        .storage_paths(&vendor_dir)?
+        .content_dir
+        .join(previous.to_string().replace(':', "/"));
+    assert!(previous_path.is_file());
+    assert_eq!(manifest.registries.len(), 1);
+    assert_eq!(manifest.registries[0].packages.len(), 1);
+    assert!(manifest.registries[0].consistency.is_none());
+
+    // Vendoring again after another release proves consistency with the previous checkpoint
+    let digest = publish_component(
+        &client,
+        &name,
+        "0.2.0",
+        "(component (core module))",
+        false,
+        &signing_key,
+    )
+    .await?;
+    let manifest = client
+        .vendor(&vendor_dir, std::slice::from_ref(&name))
+        .await?;
+    assert!(manifest.registries[0].consistency.is_some());
+
+    // Content that is no longer vendored is removed
+    assert!(!previous_path.exists());
+
+    drop(client);
+
+    // The vendored client resolves packages without the registry
+    let vendored_config = Config {
+        home_url: Some("https://localhost:1".to_string()),
+        vendor_dir: Some(vendor_dir.clone()),
+        ..config.clone()
+    };
+    let client = create_client(&vendored_config).await?;
+    assert!(client.is_offline());
+
+    let download = client
+        .download(&name, &"*".parse()?)
+        .await?
+        .context("failed to resolve vendored package")?;
+    assert_eq!(download.digest, digest);
+
+    match client.update().await {
+        Err(ClientError::OfflineUnavailable(_)) => {}
+        res => bail!("expected offline error from update but got {res:?}"),
+    }
+
+    drop(client);
+
+    // Tampering with a vendored package log state fails verification, even
+    // when the log head is left unchanged
+    let manifest = VendorManifest::from_dir(&vendor_dir)?;
+    let paths = manifest.storage_paths(&vendor_dir)?;
+    let registry = FileSystemRegistryStorage::lock(&paths.registries_dir)?;
+    let original = registry
+        .load_package(None, &name)
+        .await?
+        .context("expected a vendored package log")?;
+    let mut tampered = serde_json::to_value(&original)?;
+    tampered["state"]["releases"]
+        .as_object_mut()
+        .context("expected vendored releases")?
+        .remove("0.2.0");
+    registry
+        .store_package(None, &serde_json::from_value(tampered)?)
+        .await?;
+    drop(registry);
+
+    match FileSystemClient::try_new_with_config(None, &vendored_config, None).await {
+        Err(ClientError::InvalidVendorDirectory(_)) => {}
+        Err(e) => bail!("expected invalid vendor directory error but got {e:?}"),
+        Ok(_) => bail!("expected vendored client creation to fail"),
+    }
+
+    let registry = FileSystemRegistryStorage::lock(&paths.registries_dir)?;
+    registry.store_package(None, &original).await?;
+    drop(registry);
+
+    // Tampering with the vendored content fails verification
+    fs::write(&download.path, b"tampered")?;
+    match FileSystemClient::try_new_with_config(None, &vendored_config, None).await {
+        Err(ClientError::IncorrectContent { .. }) => {}
+        Err(e) => bail!("expected incorrect content error but got {e:?}"),
+        Ok(_) => bail!("expected vendored client creation to fail"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_downloads_concurrently_and_resumes() -> Result<()> {
+    const PACKAGE_NAMES: [&str; 3] = ["test:first", "test:second", "test:third"];
+    const PARTIAL_LEN: usize = 4;
+
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+
+    let mut packages = Vec::new();
+    for (i, name) in PACKAGE_NAMES.into_iter().enumerate() {
+        let name = PackageName::new(name)?;
+        let digest = publish_component(
+            &client,
+            &name,
+            "0.1.0",
+            &format!("(component (core module (@custom \"id\" \"{i}\")))"),
+            true,
+            &signing_key,
+        )
+        .await?;
+        packages.push((name, "*".parse()?, digest));
+    }
+
+    let downloads = client
+        .download_many(packages.iter().map(|(name, req, _)| (name, req)))
+        .await?;
+    assert_eq!(downloads.len(), packages.len());
+    for (download, (_, _, digest)) in downloads.into_iter().zip(&packages) {
+        let download = download.context("failed to resolve package")?;
+        assert_eq!(&download.digest, digest);
+        assert!(download.path.is_file());
+    }
+
+    // Simulate a download that failed part way
+    let (name, req, digest) = &packages[0];
+    let path = client
+        .content()
+        .content_location(digest)
+        .context("content missing from storage")?;
+    let bytes = fs::read(&path)?;
+    client.clear_content_cache().await?;
+
+    let partial = bytes::Bytes::copy_from_slice(&bytes[..PARTIAL_LEN]);
+    assert!(client
+        .content()
+        .store_content_at(
+            Box::pin(futures::stream::iter([
+                Ok(partial),
+                Err(anyhow::anyhow!("connection reset")),
+            ])),
+            digest,
+            0,
+        )
+        .await
+        .is_err());
+    assert_eq!(
+        client.content().partial_content_len(digest),
+        PARTIAL_LEN as u64
+    );
+
+    // The download resumes from the partial content
+    let download = client
+        .download(name, req)
+        .await?
+        .context("failed to resolve package")?;
+    assert_eq!(&download.digest, digest);
+    assert_eq!(fs::read(&download.path)?, bytes);
+    assert_eq!(client.content().partial_content_len(digest), 0);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_collects_unreferenced_content() -> Result<()> {
+    const PACKAGE_NAME: &str = "test:gc";
+
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let referenced =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    client
+        .download(&name, &"*".parse()?)
+        .await?
+        .context("failed to resolve package")?;
+
+    let unreferenced = client
+        .content()
+        .store_content(
+            Box::pin(futures::stream::once(async {
+                Ok(bytes::Bytes::from_static(b"unreferenced"))
+            })),
+            None,
+        )
+        .await?;
+
+    // An abandoned download leaves partial content behind
+    let abandoned = AnyHash::from(Hash::<Sha256>::of(b"abandoned".as_slice()));
+    assert!(client
+        .content()
+        .store_content_at(
+            Box::pin(futures::stream::iter([
+                Ok(bytes::Bytes::from_static(b"aband")),
+                Err(anyhow::anyhow!("connection reset")),
+            ])),
+            &abandoned,
+            0,
+        )
+        .await
+        .is_err());
+    assert_eq!(client.content().partial_content_len(&abandoned), 5);
+
+    // A dry run reports but does not remove anything
+    let summary = client
+        .gc_content_cache(&ContentGcOptions {
+            dry_run: true,
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(summary.removed.len(), 2);
+    assert!(summary
+        .removed
+        .iter()
+        .any(|e| e.digest == unreferenced && !e.partial));
+    assert!(summary
+        .removed
+        .iter()
+        .any(|e| e.digest == abandoned && e.partial));
+    assert!(client.content().content_location(&unreferenced).is_some());
+    assert_eq!(client.content().partial_content_len(&abandoned), 5);
+
+    let summary = client
+        .gc_content_cache(&ContentGcOptions::default())
+        .await?;
+    assert_eq!(summary.removed.len(), 2);
+    assert!(client.content().content_location(&unreferenced).is_none());
+    assert_eq!(client.content().partial_content_len(&abandoned), 0);
+    assert!(client.content().content_location(&referenced).is_some());
+
+    // A size limit also removes referenced content
+    let summary = client
+        .gc_content_cache(&ContentGcOptions {
+            max_size: Some(0),
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(summary.removed.len(), 1);
+    assert_eq!(summary.retained_size, 0);
+    assert!(client.content().content_location(&referenced).is_none());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_watches_package_logs() -> Result<()> {
+    const PACKAGE_NAME: &str = "test:watched";
+
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new(PACKAGE_NAME)?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // Publish from a client with separate storage while the first client watches
+    let publisher = create_client(&Config {
+        registries_dir: Some(root.join("publisher/registries")),
+        content_dir: Some(root.join("publisher/content")),
+        namespace_map_path: Some(root.join("publisher/namespaces")),
+        ..config.clone()
+    })
+    .await?;
+
+    let (updated, published) = tokio::time::timeout(Duration::from_secs(20), async {
+        tokio::join!(client.watch(std::slice::from_ref(&name)), async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            publish_component(
+                &publisher,
+                &name,
+                "0.2.0",
+                "(component (core module))",
+                false,
+                &signing_key,
+            )
+            .await
+        })
+    })
+    .await?;
+    published?;
+
+    let updated = updated?;
+    assert_eq!(updated.len(), 1);
+    assert_eq!(updated[0].name, name);
+    assert_eq!(
+        updated[0]
+            .state
+            .releases()
+            .last()
+            .context("expected a release")?
+            .version,
+        "0.2.0".parse()?
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_verifies_missing_packages() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:present")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // The registry must prove the package is absent from its map
+    let missing = PackageName::new("test:missing")?;
+    match client.fetch_package(&missing).await {
+        Err(ClientError::PackageDoesNotExist { name, .. }) => assert_eq!(name, missing),
+        Err(e) => bail!("expected package to not exist, but got error: {e}"),
+        Ok(_) => bail!("expected package to not exist"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_gossips_checkpoints() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    let peer = config.home_url.clone().unwrap();
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:gossiped")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+
+    // The peer's checkpoint is consistent with the client's view
+    let ours = client
+        .registry()
+        .load_checkpoint(None)
+        .await?
+        .context("expected a stored checkpoint")?;
+    let theirs = client.gossip_checkpoint(None, &peer).await?;
+    assert!(theirs.as_ref().checkpoint.log_length >= ours.as_ref().checkpoint.log_length);
+
+    // A forked checkpoint signed by the operator conflicts with the peer's
+    // checkpoint at the same log length and is reported as an equivocation
+    let mut forked = ours.as_ref().clone();
+    forked.checkpoint.map_root = forked.checkpoint.log_root.clone();
+    let forked = SerdeEnvelope::signed_contents(&test_operator_key(), forked)?;
+    client.registry().store_checkpoint(None, &forked).await?;
+
+    match client.gossip_checkpoint(None, &peer).await {
+        Err(ClientError::CheckpointEquivocation { ours: o, theirs }) => {
+            assert_eq!(o.as_ref().as_ref(), forked.as_ref());
+            assert_eq!(
+                theirs.as_ref().as_ref().checkpoint,
+                ours.as_ref().checkpoint
+            );
+        }
+        Err(e) => bail!("expected an equivocation, but got error: {e}"),
+        Ok(_) => bail!("expected an equivocation"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_audits_checkpoints() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:audited")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+
+    // Every accepted checkpoint is recorded in the audit trail
+    let trail = client.registry().load_audit_trail(None).await?;
+    assert!(trail.len() > 1);
+    assert!(trail.windows(2).all(
+        |pair| pair[0].as_ref().checkpoint.log_length < pair[1].as_ref().checkpoint.log_length
+    ));
+
+    let summary = client.audit(None).await?;
+    assert_eq!(summary.checkpoints, trail.len());
+    assert!(summary.log_length >= trail.last().unwrap().as_ref().checkpoint.log_length);
+
+    // A checkpoint that differs from the registry's history fails the audit
+    let mut forked = trail.last().unwrap().as_ref().clone();
+    forked.checkpoint.map_root = forked.checkpoint.log_root.clone();
+    let forked = SerdeEnvelope::signed_contents(&test_operator_key(), forked)?;
+    client
+        .registry()
+        .append_audit_checkpoint(None, &forked)
+        .await?;
+
+    match client.audit(None).await {
+        Err(ClientError::CheckpointHistoryMismatch { log_length }) => {
+            assert_eq!(log_length, forked.as_ref().checkpoint.log_length)
+        }
+        Err(e) => bail!("expected a checkpoint history mismatch, but got error: {e}"),
+        Ok(_) => bail!("expected a checkpoint history mismatch"),
+    }
+
+    // A checkpoint missing from the registry's history fails the audit
+    client.reset_registry().await?;
+    client.fetch_package(&name).await?;
+    let mut omitted = client
+        .registry()
+        .load_audit_trail(None)
+        .await?
+        .last()
+        .context("expected an accepted checkpoint")?
+        .as_ref()
+        .clone();
+    omitted.checkpoint.log_length += 1000;
+    let omitted = SerdeEnvelope::signed_contents(&test_operator_key(), omitted)?;
+    client
+        .registry()
+        .append_audit_checkpoint(None, &omitted)
+        .await?;
+
+    match client.audit(None).await {
+        Err(ClientError::CheckpointHistoryOmitted { log_length }) => {
+            assert_eq!(log_length, omitted.as_ref().checkpoint.log_length)
+        }
+        Err(e) => bail!("expected an omitted checkpoint, but got error: {e}"),
+        Ok(_) => bail!("expected an omitted checkpoint"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_prepares_releases() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let key_id = signing_key.public_key().fingerprint();
+    let name = PackageName::new("test:prepared")?;
+    let digest =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // A release of an existing package is not initialized
+    let info = client
+        .prepare_release(&name, "0.2.0".parse()?, digest.clone(), Some(&key_id))
+        .await?;
+    assert!(matches!(
+        info.entries.as_slice(),
+        [PublishEntry::Release { version, .. }] if version == &"0.2.0".parse()?
+    ));
+
+    // A release of a missing package initializes it
+    let missing = PackageName::new("test:unpublished")?;
+    let info = client
+        .prepare_release(&missing, "0.1.0".parse()?, digest.clone(), Some(&key_id))
+        .await?;
+    assert!(matches!(
+        info.entries.as_slice(),
+        [PublishEntry::Init, PublishEntry::Release { .. }]
+    ));
+    match client
+        .prepare_release(&missing, "0.1.0".parse()?, digest.clone(), None)
+        .await
+    {
+        Err(ClientError::CannotInitializeWithIdentity { name }) => assert_eq!(name, missing),
+        Err(e) => bail!("expected an identity initialization error, but got error: {e}"),
+        Ok(_) => bail!("expected an identity initialization error"),
+    }
+
+    match client
+        .prepare_release(&name, "0.1.0".parse()?, digest.clone(), Some(&key_id))
+        .await
+    {
+        Err(ClientError::PackageVersionAlreadyReleased { version, .. }) => {
+            assert_eq!(version, "0.1.0".parse()?)
+        }
+        Err(e) => bail!("expected an already released error, but got error: {e}"),
+        Ok(_) => bail!("expected an already released error"),
+    }
+
+    let other = test_operator_key().public_key().fingerprint();
+    match client
+        .prepare_release(&name, "0.3.0".parse()?, digest, Some(&other))
+        .await
+    {
+        Err(ClientError::ReleaseNotPermitted { key_id, .. }) => assert_eq!(key_id, other),
+        Err(e) => bail!("expected a release permission error, but got error: {e}"),
+        Ok(_) => bail!("expected a release permission error"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_manages_namespace_mappings() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+
+    // Only namespaces defined in the operator log are reported as defined
+    assert_eq!(
+        client.fetch_namespace_state("test").await?,
+        Some(NamespaceState::Defined)
+    );
+    assert_eq!(client.fetch_namespace_state("undefined").await?, None);
+
+    let registry = RegistryDomain::new("registry.example.com".to_string());
+    client
+        .store_namespace("test".to_string(), registry.clone())
+        .await?;
+    let mapping = client.namespace_map().load_namespace_map().await?;
+    assert_eq!(
+        mapping.as_ref().and_then(|m| m.get("test")),
+        Some(&registry.to_string())
+    );
+
+    assert!(client.remove_namespace("test").await?);
+    assert!(!client.remove_namespace("test").await?);
+    let mapping = client.namespace_map().load_namespace_map().await?;
+    assert!(mapping.unwrap_or_default().is_empty());
+    drop(client);
+
+    // Setting a namespace with a registry URL maps it to the registry's domain
+    let config_path = root.join("warg-config.json");
+    config.write_to_file(&config_path)?;
+    let home_url = config.home_url.clone().unwrap();
+    NamespaceSetCommand {
+        common: CommonOptions {
+            registry: None,
+            options: ConfigOptions {
+                config: Some(config_path),
+                offline: false,
+            },
+        },
+        namespace: "test".to_string(),
+        target: home_url.clone(),
+    }
+    .exec()
+    .await?;
+
+    let mapping = FileSystemNamespaceMapStorage::new(config.namespace_map_path()?)
+        .load_namespace_map()
+        .await?;
+    let mapped = mapping
+        .as_ref()
+        .and_then(|m| m.get("test"))
+        .context("expected the namespace to be mapped")?;
+    assert_eq!(
+        mapped,
+        &RegistryUrl::new(&home_url)?.registry_domain().to_string()
+    );
+    assert!(
+        !mapped.contains("://"),
+        "expected a registry domain: {mapped}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_pins_operator_keys() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:pinned")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // The operator log is pinned on first contact
+    let registry = RegistryUrl::new(config.home_url.as_ref().unwrap())?.registry_domain();
+    let pin = client
+        .registry()
+        .load_operator_pin(&registry)
+        .await?
+        .context("expected a recorded operator pin")?;
+    assert_eq!(pin.key_id, test_operator_key().public_key().fingerprint());
+    assert!(pin.root_record.is_some());
+
+    // The pin survives a reset of the registry storage
+    client.reset_registry().await?;
+    assert_eq!(
+        client
+            .registry()
+            .load_operator_pin(&registry)
+            .await?
+            .as_ref(),
+        Some(&pin)
+    );
+    client.fetch_package(&name).await?;
+    drop(client);
+
+    // A configured pin is checked on first contact with fresh storage
+    let pinned = |dir: &str, key_id: KeyID| Config {
+        registries_dir: Some(root.join(dir).join("registries")),
+        operator_pins: [(
+            registry.clone(),
+            OperatorPin {
+                key_id,
+                root_record: None,
+            },
+        )]
+        .into_iter()
+        .collect(),
+        ..config.clone()
+    };
+
+    let client = create_client(&pinned("pinned-registries", pin.key_id.clone())).await?;
+    client.fetch_package(&name).await?;
+    assert_eq!(
+        client
+            .registry()
+            .load_operator_pin(&registry)
+            .await?
+            .as_ref(),
+        None,
+        "a configured pin should not be recorded"
+    );
+    drop(client);
+
+    let client = create_client(&pinned(
+        "mismatched-registries",
+        signing_key.public_key().fingerprint(),
+    ))
+    .await?;
+    match client.fetch_package(&name).await {
+        Err(ClientError::OperatorPinMismatch {
+            registry: found_registry,
+            found,
+            ..
+        }) => {
+            assert_eq!(found_registry, registry);
+            assert_eq!(found, pin);
+        }
+        Err(e) => bail!("expected an operator pin mismatch, but got error: {e}"),
+        Ok(_) => bail!("expected an operator pin mismatch"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_downloads_with_proof() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:standalone")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    let digest =
+        publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+
+    // Use a client with fresh storage so nothing is known about the registry
+    let config = Config {
+        registries_dir: Some(root.join("standalone-registries")),
+        content_dir: Some(root.join("standalone-content")),
+        ..config
+    };
+    let client = create_client(&config).await?;
+
+    let download = client
+        .download_with_proof(&name, &"0.1".parse()?)
+        .await?
+        .context("failed to resolve package")?;
+    assert_eq!(download.version, "0.1.0".parse()?);
+
+    let download = client
+        .download_with_proof(&name, &"*".parse()?)
+        .await?
+        .context("failed to resolve package")?;
+    assert_eq!(download.version, "0.2.0".parse()?);
+    assert_eq!(download.digest, digest);
+    assert_eq!(
+        fs::read(&download.path)?,
+        wat::parse_str("(component)")?,
+        "downloaded content does not match"
+    );
+
+    // Neither the package log nor the checkpoint were stored
+    assert!(client.registry().load_package(None, &name).await?.is_none());
+    assert!(client.registry().load_checkpoint(None).await?.is_none());
+
+    match client
+        .download_with_proof(&PackageName::new("test:missing")?, &"*".parse()?)
+        .await
+    {
+        Err(ClientError::PackageDoesNotExist { name, .. }) => {
+            assert_eq!(name.as_ref(), "test:missing")
+        }
+        Err(e) => bail!("expected package to not exist, but got error: {e}"),
+        Ok(_) => bail!("expected package to not exist"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_publishes_with_identity() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+
+    let client = create_client(&config).await?;
+    let signing_key = support::test_signing_key();
+    let name = PackageName::new("test:keyless")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // Grant release to the workflows of the `test` organization
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::GrantIdentity {
+                    identity: format!("{TEST_IDENTITY_ISSUER}#repo:test/*")
+                        .parse()
+                        .unwrap(),
+                    permissions: vec![Permission::Release],
+                }],
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    let digest = client
+        .content()
+        .store_content(
+            Box::pin(futures::stream::once(async {
+                Ok(wat::parse_str("(component)")?.into())
+            })),
+            None,
+        )
+        .await?;
+    let release = |version: &str| PublishInfo {
+        name: name.clone(),
+        head: None,
+        entries: vec![PublishEntry::Release {
+            version: version.parse().unwrap(),
+            content: digest.clone(),
+        }],
+    };
+
+    // A matching identity can release with an ephemeral key
+    let record_id = client
+        .publish_with_identity(&TestIdentityIssuer::new("repo:test/app"), release("0.2.0"))
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    let package = client.package(&name).await?;
+    let release = package
+        .state
+        .release(&"0.2.0".parse()?)
+        .context("expected release 0.2.0")?;
+    let identity = package
+        .state
+        .identity(&release.by)
+        .context("expected the release to be bound to an identity")?;
+    assert_eq!(identity.issuer, TEST_IDENTITY_ISSUER);
+    assert_eq!(identity.subject, "repo:test/app");
+
+    // An identity that was not granted permission is rejected
+    let record_id = client
+        .publish_with_identity(
+            &TestIdentityIssuer::new("repo:other/app"),
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::Yank {
+                    version: "0.2.0".parse()?,
+                }],
+            },
+        )
+        .await?;
+    match client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await
+    {
+        Err(ClientError::PublishRejected { .. }) => {}
+        Err(e) => bail!("expected the publish to be rejected, but got error: {e}"),
+        Ok(_) => bail!("expected the publish to be rejected"),
+    }
+
+    // A token issued for a different key is not accepted
+    let issuer = TestIdentityIssuer {
+        audience: Some("sha256:other".to_string()),
+        ..TestIdentityIssuer::new("repo:test/app")
+    };
+    match client
+        .publish_with_identity(
+            &issuer,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::Yank {
+                    version: "0.2.0".parse()?,
+                }],
+            },
+        )
+        .await
+    {
+        Err(ClientError::Unauthorized(reason)) => assert!(reason.contains("audience")),
+        Err(e) => bail!("expected the publish to be unauthorized, but got error: {e}"),
+        Ok(_) => bail!("expected the publish to be unauthorized"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_tolerates_unusable_discovery_documents() -> Result<()> {
+    use axum::{
+        http::{HeaderMap, StatusCode},
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    };
+    use warg_api::{v1::REGISTRY_HEADER_NAME, DISCOVERY_PATH};
+
+    // Serves a discovery document that depends on the `Warg-Registry` header
+    async fn discovery(headers: HeaderMap) -> Response {
+        match headers
+            .get(REGISTRY_HEADER_NAME)
+            .and_then(|v| v.to_str().ok())
+        {
+            None => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+            Some("garbage.example.com") => "not a discovery document".into_response(),
+            Some(_) => axum::Json(serde_json::json!({
+                "apiVersions": ["v2"],
+                "hashAlgorithms": ["sha256"],
+                "signatureAlgorithms": ["ecdsa-p256"],
+            }))
+            .into_response(),
+        }
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{addr}", addr = listener.local_addr()?);
+    let router = Router::new().route(&format!("/{DISCOVERY_PATH}"), get(discovery));
+    let _task = tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+
+    let root = root().await?;
+    let client = create_client(&Config {
+        home_url: Some(url),
+        registries_dir: Some(root.join("registries")),
+        content_dir: Some(root.join("content")),
+        namespace_map_path: Some(root.join("namespaces")),
+        ..Default::default()
+    })
+    .await?;
+
+    // An error status or an unparseable document leaves the capabilities unknown
+    assert!(client.discovery().await?.is_none());
+    let garbage = RegistryDomain::from_str("garbage.example.com")?;
+    assert!(client.registry_discovery(Some(&garbage)).await?.is_none());
+
+    // An explicit incompatibility of a namespace-mapped registry is an error
+    let incompatible = RegistryDomain::from_str("incompatible.example.com")?;
+    match client.registry_discovery(Some(&incompatible)).await {
+        Err(ClientError::IncompatibleRegistry { registry, .. }) => {
+            assert_eq!(registry, incompatible)
+        }
+        other => panic!("expected an incompatible registry error: {other:?}"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn client_publishes_to_multiple_registries() -> Result<()> {
+    let root = root().await?;
+    let (_accepting, config) = spawn_server(&root.join("accepting"), None, None, None).await?;
+    let (_rejecting, rejecting_config) =
+        spawn_configured_server(&root.join("rejecting"), None, None, None, |config| {
+            config.with_max_content_size(4)
+        })
+        .await?;
+    let accepting_url = config.home_url.clone().unwrap();
+    let rejecting_url = rejecting_config.home_url.clone().unwrap();
+
+    // Sign with a key from a flat-file keyring kept under the test root
+    std::env::set_var("XDG_CONFIG_HOME", root.join("xdg"));
+    let mut config = Config {
+        keyring_backend: Some("flat-file".to_string()),
+        ..config
+    };
+    let mut keys = config.keys.clone();
+    keys.insert("default".to_string());
+    Keyring::from_config(&config)?.set_signing_key(
+        None,
+        &test_signing_key(),
+        &mut keys,
+        config.home_url.as_deref(),
+    )?;
+    config.keys = keys;
+    let config_path = root.join("warg-config.json");
+    config.write_to_file(&config_path)?;
+
+    let path = root.join("component.wasm");
+    fs::write(&path, wat::parse_str("(component)")?)?;
+    let name = PackageName::new("test:multi")?;
+    let command = |registries: Vec<String>| PublishReleaseCommand {
+        registries,
+        options: ConfigOptions {
+            config: Some(config_path.clone()),
+            offline: false,
+        },
+        name: name.clone(),
+        version: "0.1.0".parse().unwrap(),
+        path: path.clone(),
+        no_wait: false,
+        keyless: false,
+        yank_on_failure: true,
+    };
+
+    // The same registry cannot be named twice
+    let err = command(vec![accepting_url.clone(), accepting_url.clone()])
+        .exec()
+        .await
+        .expect_err("expected duplicate registries to be rejected");
+    assert!(err.to_string().contains("more than once"), "{err:#}");
+
+    // The rejecting registry only accepts content of up to four bytes
+    command(vec![accepting_url, rejecting_url])
+        .exec()
+        .await
+        .expect_err("expected the publish to fail");
+
+    // The accepting registry published the release and then yanked it
+    let client = create_client(&config).await?;
+    let package = client.fetch_package(&name).await?;
+    let release = package
+        .state
+        .release(&"0.1.0".parse()?)
+        .context("expected the release to be published")?;
+    assert!(release.yanked());
+
+    // The rejecting registry never published the package
+    let client = create_client(&rejecting_config).await?;
+    assert!(matches!(
+        client.fetch_package(&name).await,
+        Err(ClientError::PackageDoesNotExist { .. })
+    ));
+
+    Ok(())
+}
@@ -69,6 +69,26 @@ async fn it_rejects_non_wasm_content() -> Result<()> {
     test_wasm_content_policy(&config).await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_serves_a_discovery_document() -> Result<()> {
+    let (_server, config) = spawn_configured_server(&root().await?, None, None, None, |config| {
+        config
+            .with_max_content_size(4)
+            .with_witness_keys([test_signing_key().public_key()])
+    })
+    .await?;
+    test_discovery(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_rejects_content_larger_than_the_maximum_size() -> Result<()> {
+    let (_server, config) = spawn_configured_server(&root().await?, None, None, None, |config| {
+        config.with_max_content_size(4)
+    })
+    .await?;
+    test_max_content_size(&config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_rejects_unauthorized_signing_key() -> Result<()> {
     let (_server, config) = spawn_server(
@@ -97,6 +117,37 @@ async fn it_rejects_invalid_signature() -> Result<()> {
     test_invalid_signature(&config).await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_requires_identity_tokens() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_identity_token_required(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_mirrors_a_registry() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+    test_mirror(&root, &config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_resumes_from_persisted_state() -> Result<()> {
+    test_restart(&root().await?).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_rejects_forged_mirrored_checkpoints() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_mirror_forged_checkpoint(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_proxies_imported_namespaces() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+    test_federation_proxy(&root, &config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_formats_custom_content_urls() -> Result<()> {
     let (_server, config) = spawn_server(
@@ -114,3 +165,37 @@ async fn it_get_ledger() -> Result<()> {
     let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
     test_get_ledger(&config).await
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_serves_log_tiles() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_component_publishing(&config).await?;
+    test_log_tiles(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_rejects_untrusted_witnesses() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_untrusted_witness(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_detects_conflicting_gossip() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_component_publishing(&config).await?;
+    test_conflicting_gossip(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_serves_map_multi_proofs() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_component_publishing(&config).await?;
+    test_map_multi_proofs(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_fetches_checkpoints() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_component_publishing(&config).await?;
+    test_fetch_checkpoints(&config).await
+}
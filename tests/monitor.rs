@@ -0,0 +1,143 @@
+use self::support::*;
+use anyhow::{bail, Context, Result};
+use std::{fs, time::Duration};
+use warg_client::ClientError;
+use warg_monitor::{Monitor, MonitorError, MonitorState};
+use warg_protocol::registry::PackageName;
+
+pub mod support;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn monitor_replays_registry_log() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+    let url = config.home_url.clone().unwrap();
+    let path = root.join("monitor.json");
+
+    let mut monitor = Monitor::new(&url, &path)?;
+    let initial = monitor.check().await?.expect("expected the log to grow");
+    assert!(monitor.state().packages.is_empty());
+
+    let client = create_client(&config).await?;
+    let signing_key = test_signing_key();
+    let name = PackageName::new("test:monitored")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+
+    let log_length = monitor.check().await?.expect("expected the log to grow");
+    assert!(log_length > initial);
+    assert_eq!(monitor.state().log_length, log_length);
+    assert_eq!(monitor.state().packages.len(), 1);
+    assert_eq!(monitor.state().checkpoints.len(), 2);
+
+    // The leafs are persisted one per line next to the state
+    let leafs_path = path.with_extension("leafs");
+    assert_eq!(fs::read_to_string(&leafs_path)?.lines().count(), log_length);
+
+    // The persisted view is resumed, discarding leafs of an incomplete check
+    let mut leafs = fs::read_to_string(&leafs_path)?;
+    let partial = leafs.len();
+    leafs.push_str("{\"incomplete\"");
+    fs::write(&leafs_path, leafs)?;
+
+    let mut monitor = Monitor::new(&url, &path)?;
+    assert_eq!(monitor.state().log_length, log_length);
+    assert_eq!(fs::metadata(&leafs_path)?.len(), partial as u64);
+    assert!(monitor.check().await?.is_none());
+
+    // A conflicting checkpoint for the same log length is an equivocation
+    let mut state: MonitorState = serde_json::from_slice(&fs::read(&path)?)?;
+    let mut tampered = state.checkpoints[&log_length].as_ref().clone();
+    tampered.checkpoint.map_root = tampered.checkpoint.log_root.clone();
+    let envelope = warg_protocol::SerdeEnvelope::signed_contents(&test_operator_key(), tampered)?;
+    state.checkpoints.insert(log_length, envelope);
+    fs::write(&path, serde_json::to_vec(&state)?)?;
+
+    let mut monitor = Monitor::new(&url, &path)?;
+    match monitor.check().await {
+        Err(e @ MonitorError::Equivocation { .. }) => assert!(e.is_violation()),
+        Err(e) => bail!("expected an equivocation, but got error: {e}"),
+        Ok(_) => bail!("expected an equivocation"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn monitor_cosigns_checkpoints_as_witness() -> Result<()> {
+    let root = root().await?;
+    let witness_key = test_signing_key();
+    let witness_public_key = witness_key.public_key();
+    let (_server, config) = spawn_configured_server(&root, None, None, None, |config| {
+        config.with_witness_keys([witness_public_key])
+    })
+    .await?;
+    let url = config.home_url.clone().unwrap();
+
+    let client = create_client(&config).await?;
+    let signing_key = test_signing_key();
+    let name = PackageName::new("test:witnessed")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    drop(client);
+
+    let witnessed_config = warg_client::Config {
+        registries_dir: Some(root.join("witnessed-registries")),
+        content_dir: Some(root.join("witnessed-content")),
+        witness_keys: vec![witness_key.public_key()],
+        witness_threshold: 1,
+        ..config.clone()
+    };
+
+    // The checkpoint is rejected until a trusted witness cosigns it
+    let client = create_client(&witnessed_config).await?;
+    match client.download(&name, &"0.1.0".parse()?).await {
+        Err(ClientError::InsufficientWitnessCosignatures {
+            found: 0,
+            required: 1,
+            ..
+        }) => {}
+        Err(e) => bail!("expected insufficient cosignatures, but got error: {e}"),
+        Ok(_) => bail!("expected insufficient cosignatures"),
+    }
+
+    // Wait for the published record to be checkpointed before witnessing it
+    let api = warg_client::api::Client::new(&url, None)?;
+    for _ in 0..50 {
+        if api
+            .latest_checkpoint(None)
+            .await?
+            .as_ref()
+            .checkpoint
+            .log_length
+            > 1
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let mut monitor = Monitor::new(&url, root.join("witness.json"))?.with_witness_key(witness_key);
+    let log_length = monitor.check().await?.expect("expected the log to grow");
+    assert_eq!(monitor.state().cosigned, Some(log_length));
+
+    client
+        .download(&name, &"0.1.0".parse()?)
+        .await?
+        .context("failed to resolve witnessed package")?;
+    drop(client);
+
+    // A cosignature by an untrusted witness does not count
+    let untrusted_config = warg_client::Config {
+        registries_dir: Some(root.join("untrusted-registries")),
+        content_dir: Some(root.join("untrusted-content")),
+        witness_keys: vec![test_operator_key().public_key()],
+        ..witnessed_config
+    };
+    let client = create_client(&untrusted_config).await?;
+    assert!(matches!(
+        client.download(&name, &"0.1.0".parse()?).await,
+        Err(ClientError::InsufficientWitnessCosignatures { found: 0, .. })
+    ));
+
+    Ok(())
+}
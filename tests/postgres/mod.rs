@@ -55,6 +55,9 @@ async fn it_works_with_postgres() -> TestResult {
     test_invalid_signature(&config).await?;
     test_fetch_package_names(&config).await?;
     test_get_ledger(&config).await?;
+    test_log_tiles(&config).await?;
+    test_map_multi_proofs(&config).await?;
+    test_fetch_checkpoints(&config).await?;
 
     let mut packages = vec![
         PackageName::new("test:component")?,
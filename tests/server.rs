@@ -1,22 +1,32 @@
 use self::support::*;
 use anyhow::{Context, Result};
+use futures::StreamExt;
+use indexmap::IndexMap;
 use rand_core::OsRng;
 use reqwest::StatusCode;
 use std::{
     borrow::Cow,
     fs,
+    path::Path,
     time::{Duration, SystemTime},
 };
 use url::Url;
 use warg_api::v1::{
     content::{ContentSource, ContentSourcesResponse},
-    fetch::{FetchPackageNamesRequest, FetchPackageNamesResponse},
+    fetch::{
+        FetchCheckpointsQuery, FetchError, FetchPackageNamesRequest, FetchPackageNamesResponse,
+    },
+    gossip::{GossipError, GossipRequest},
     ledger::{LedgerSource, LedgerSourceContentType, LedgerSourcesResponse},
-    package::PublishRecordRequest,
+    package::{PackageError, PublishRecordRequest, UploadEndpoint},
     paths,
+    proof::InclusionRequest,
+    tile::{TileError, TILE_CONTENT_TYPE},
+    witness::{CosignRequest, WitnessError},
 };
 use warg_client::{
     api,
+    identity::IdentityTokenSource,
     storage::{PublishEntry, PublishInfo},
     ClientError, Config,
 };
@@ -26,10 +36,16 @@ use warg_crypto::{
     Encode, Signable,
 };
 use warg_protocol::{
+    operator::OperatorRecord,
     package::{PackageEntry, PackageRecord, PACKAGE_RECORD_VERSION},
-    registry::{LogId, PackageName},
-    ProtoEnvelope, ProtoEnvelopeBody, Version,
+    registry::{CheckpointCosignature, LogId, PackageName, RecordId},
+    ProtoEnvelope, ProtoEnvelopeBody, SerdeEnvelope, Version,
 };
+use warg_server::{
+    datastore::MemoryDataStore,
+    services::{CoreService, MirrorService},
+};
+use warg_transparency::log::{TileId, TILE_WIDTH};
 use wit_component::DecodedWasm;
 
 mod support;
@@ -257,6 +273,115 @@ async fn test_wasm_content_policy(config: &Config) -> Result<()> {
     Ok(())
 }
 
+async fn test_discovery(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let discovery = client
+        .discovery(None)
+        .await?
+        .context("expected a discovery document")?;
+
+    assert_eq!(discovery.api_versions, ["v1"]);
+    assert_eq!(discovery.hash_algorithms, ["sha256"]);
+    assert_eq!(discovery.signature_algorithms, ["ecdsa-p256"]);
+    assert_eq!(
+        discovery.operator_key_id,
+        Some(test_operator_key().public_key().fingerprint())
+    );
+    assert_eq!(
+        discovery.ledger_url.as_deref(),
+        Some(paths::ledger_sources())
+    );
+    assert_eq!(
+        discovery.witness_url.as_deref(),
+        Some(paths::witness_cosign())
+    );
+    assert_eq!(
+        discovery.witness_key_ids,
+        Some(vec![test_signing_key().public_key().fingerprint()])
+    );
+    assert_eq!(discovery.max_content_size, Some(4));
+
+    Ok(())
+}
+
+async fn test_max_content_size(config: &Config) -> Result<()> {
+    let name = PackageName::new("test:too-large")?;
+    let client = create_client(config).await?;
+    let signing_key = test_signing_key();
+
+    // The client fails before publishing content larger than the registry accepts
+    match publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key)
+        .await
+        .expect_err("expected publish to fail")
+        .downcast::<ClientError>()
+    {
+        Ok(ClientError::ContentTooLarge { size, max_size, .. }) => {
+            assert_eq!(size, 8);
+            assert_eq!(max_size, 4);
+        }
+        other => panic!("expected a content too large error: {other:?}"),
+    }
+
+    // The registry rejects the content if it is uploaded anyway
+    let content = wat::parse_str("(component)")?;
+    let mut hasher = HashAlgorithm::Sha256.hasher();
+    hasher.update(&content);
+    let digest = hasher.finalize();
+    let record = ProtoEnvelope::signed_contents(
+        &signing_key,
+        PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![
+                PackageEntry::Init {
+                    hash_algorithm: warg_crypto::hash::HashAlgorithm::Sha256,
+                    key: signing_key.public_key(),
+                },
+                PackageEntry::Release {
+                    version: "0.1.0".parse()?,
+                    content: digest.clone(),
+                },
+            ],
+        },
+    )?;
+
+    let api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let log_id = LogId::package_log::<Sha256>(&name);
+    let record = api
+        .publish_package_record(
+            None,
+            &log_id,
+            PublishRecordRequest {
+                package_name: Cow::Borrowed(&name),
+                record: Cow::Owned(ProtoEnvelopeBody::from(record)),
+                content_sources: Default::default(),
+                identity_token: None,
+            },
+        )
+        .await?;
+    let (_, missing) = record
+        .missing_content()
+        .next()
+        .context("expected missing content")?;
+    let Some(UploadEndpoint::Http {
+        method,
+        url,
+        headers,
+    }) = missing.upload.first()
+    else {
+        panic!("expected an upload endpoint");
+    };
+    match api.upload_content(method, url, headers, content).await {
+        Err(api::ClientError::Package(PackageError::Rejection(reason))) => {
+            assert_eq!(reason, "content exceeds the maximum size of 4 bytes");
+        }
+        other => panic!("expected a content rejection: {other:?}"),
+    }
+
+    Ok(())
+}
+
 async fn test_unauthorized_signing_key(config: &Config) -> Result<()> {
     const PACKAGE_NAME: &str = "test:unauthorized-key";
     const PACKAGE_VERSION: &str = "0.1.0";
@@ -358,6 +483,7 @@ async fn test_invalid_signature(config: &Config) -> Result<()> {
         package_name: Cow::Borrowed(&name),
         record: Cow::Owned(ProtoEnvelopeBody::from(record)),
         content_sources: Default::default(),
+        identity_token: None,
     };
 
     // Update the signature to one that does not match the contents
@@ -386,6 +512,82 @@ async fn test_invalid_signature(config: &Config) -> Result<()> {
     Ok(())
 }
 
+async fn test_identity_token_required(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:identity-token";
+
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config).await?;
+    publish_component(
+        &client,
+        &name,
+        "0.1.0",
+        "(component)",
+        true,
+        &test_signing_key(),
+    )
+    .await?;
+    let head = client
+        .package(&name)
+        .await?
+        .state
+        .head()
+        .as_ref()
+        .map(|h| h.digest.clone());
+
+    // Use a reqwest client directly here as the client always sends a token
+    let log_id = LogId::package_log::<Sha256>(&name);
+    let url = Url::parse(config.home_url.as_ref().unwrap())?
+        .join(&paths::publish_package_record(&log_id))
+        .unwrap();
+
+    let (ephemeral_pub, ephemeral_priv) = warg_crypto::signing::generate_p256_pair();
+    let record = ProtoEnvelope::signed_contents(
+        &ephemeral_priv,
+        PackageRecord {
+            prev: head,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![PackageEntry::Identity {
+                issuer: TEST_IDENTITY_ISSUER.to_string(),
+                subject: "repo:test/app".to_string(),
+                key: ephemeral_pub.clone(),
+            }],
+        },
+    )?;
+
+    let other_subject = TestIdentityIssuer::new("repo:test/other")
+        .token(&ephemeral_pub.fingerprint().to_string())
+        .await?;
+
+    for (token, expected) in [
+        (None, "identity token is required"),
+        (Some(other_subject), "do not match"),
+    ] {
+        let body = PublishRecordRequest {
+            package_name: Cow::Borrowed(&name),
+            record: Cow::Owned(ProtoEnvelopeBody::from(record.clone())),
+            content_sources: Default::default(),
+            identity_token: token.map(Cow::Owned),
+        };
+
+        let response = reqwest::Client::new()
+            .post(url.clone())
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        assert_eq!(
+            status,
+            StatusCode::UNAUTHORIZED,
+            "unexpected response from server: {status}\n{body}",
+        );
+        assert!(body.contains(expected), "unexpected response body: {body}");
+    }
+
+    Ok(())
+}
+
 async fn test_custom_content_url(config: &Config) -> Result<()> {
     const PACKAGE_NAME: &str = "test:custom-content-url";
     const PACKAGE_VERSION: &str = "0.1.0";
@@ -471,6 +673,18 @@ async fn test_fetch_package_names(config: &Config) -> Result<()> {
 }
 
 async fn test_get_ledger(config: &Config) -> Result<()> {
+    let name = PackageName::new("test:ledger")?;
+    let client = create_client(config).await?;
+    publish_component(
+        &client,
+        &name,
+        "0.1.0",
+        "(component)",
+        true,
+        &test_signing_key(),
+    )
+    .await?;
+
     let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
 
     let ts_checkpoint = client.latest_checkpoint(None).await?;
@@ -547,5 +761,525 @@ async fn test_get_ledger(config: &Config) -> Result<()> {
         "unexpected response body length for ledger source from server: {bytes_len}",
     );
 
+    // The ledger is also available with the record envelopes
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let packed_sources = client.ledger_sources(None).await?;
+    let leafs = client
+        .ledger_records(None, hash_algorithm, &packed_sources.sources[0])
+        .await?;
+
+    let envelope_sources = client.ledger_envelope_sources(None).await?;
+    assert_eq!(envelope_sources.sources.len(), 1);
+    let source = &envelope_sources.sources[0];
+    assert_eq!(source.content_type, LedgerSourceContentType::Envelopes);
+    assert_eq!(source.first_registry_index, 0);
+    assert_eq!(source.last_registry_index, checkpoint.log_length - 1);
+
+    let records = client
+        .ledger_envelopes(None, envelope_sources.hash_algorithm, source)
+        .await?;
+    assert_eq!(records.len(), checkpoint.log_length);
+    for (index, (record, leaf)) in records.into_iter().zip(leafs).enumerate() {
+        assert_eq!(record.leaf, leaf);
+        assert_eq!(record.envelope.registry_index, index);
+
+        // The envelope is the signed record identified by the leaf
+        let record_id = if leaf.log_id == LogId::operator_log::<Sha256>() {
+            RecordId::operator_record::<Sha256>(&ProtoEnvelope::<OperatorRecord>::try_from(
+                record.envelope.envelope,
+            )?)
+        } else {
+            RecordId::package_record::<Sha256>(&ProtoEnvelope::<PackageRecord>::try_from(
+                record.envelope.envelope,
+            )?)
+        };
+        assert_eq!(record_id, leaf.record_id);
+    }
+
+    Ok(())
+}
+
+async fn test_log_tiles(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+
+    let ts_checkpoint = client.latest_checkpoint(None).await?;
+    let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+
+    // Prove inclusion of every leaf in the ledger from the tiles
+    let sources = client.ledger_sources(None).await?;
+    let mut leafs = Vec::new();
+    for source in &sources.sources {
+        let records = client
+            .ledger_records(None, sources.hash_algorithm, source)
+            .await?;
+        leafs.extend(
+            records
+                .into_iter()
+                .enumerate()
+                .map(|(i, leaf)| (source.first_registry_index + i, leaf)),
+        );
+    }
+    assert_eq!(leafs.len(), checkpoint.log_length);
+
+    client
+        .prove_log_inclusion_from_tiles(None, checkpoint, &leafs)
+        .await?;
+
+    // Prove consistency with the initial checkpoint from the tiles
+    let initial = client.cosigned_checkpoint(None, 1).await?;
+    let initial = &initial.checkpoint.as_ref().checkpoint;
+    client
+        .prove_log_consistency_from_tiles(None, initial, checkpoint)
+        .await?;
+
+    // Tiles are immutable and may be cached
+    let url = Url::parse(config.home_url.as_ref().unwrap())?
+        .join(&paths::partial_log_tile(0, 0, checkpoint.log_length))
+        .unwrap();
+    let response = reqwest::get(url).await?;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[reqwest::header::CONTENT_TYPE],
+        TILE_CONTENT_TYPE
+    );
+    assert!(response.headers()[reqwest::header::CACHE_CONTROL]
+        .to_str()?
+        .contains("immutable"));
+    assert_eq!(response.bytes().await?.len(), checkpoint.log_length * 32);
+
+    // Tiles beyond the end of the log are not found
+    match client
+        .log_tile(
+            None,
+            TileId { level: 0, index: 0 },
+            checkpoint.log_length + 1,
+        )
+        .await
+    {
+        Err(api::ClientError::Tile(TileError::TileNotFound { width, .. })) => {
+            assert_eq!(width, checkpoint.log_length + 1)
+        }
+        res => panic!("expected a tile not found error, but got {res:?}"),
+    }
+
+    // Tiles above the height of the log are not found
+    for level in [63, 64, 1000] {
+        match client
+            .log_tile(None, TileId { level, index: 0 }, TILE_WIDTH)
+            .await
+        {
+            Err(api::ClientError::Tile(TileError::TileNotFound { .. })) => {}
+            res => panic!("expected a tile not found error, but got {res:?}"),
+        }
+    }
+
+    Ok(())
+}
+
+async fn test_untrusted_witness(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let ts_checkpoint = client.latest_checkpoint(None).await?;
+
+    // Cosignatures are not accepted unless witness keys are configured
+    let witness_key = test_signing_key();
+    let request = CosignRequest {
+        log_length: ts_checkpoint.as_ref().checkpoint.log_length,
+        public_key: witness_key.public_key(),
+        cosignature: CheckpointCosignature::sign(&witness_key, ts_checkpoint.as_ref())?,
+    };
+    match client.cosign_checkpoint(None, request).await {
+        Err(api::ClientError::Witness(WitnessError::WitnessNotTrusted(key_id))) => {
+            assert_eq!(key_id, witness_key.public_key().fingerprint())
+        }
+        res => panic!("expected an untrusted witness error, but got {res:?}"),
+    }
+
+    Ok(())
+}
+
+async fn test_conflicting_gossip(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let ts_checkpoint = client.latest_checkpoint(None).await?;
+
+    // The registry's own checkpoint is accepted
+    let response = client
+        .gossip_checkpoint(
+            None,
+            GossipRequest {
+                checkpoint: ts_checkpoint.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(response.checkpoint.as_ref(), ts_checkpoint.as_ref());
+
+    // A forked checkpoint at the same log length conflicts with the registry's
+    let mut forked = ts_checkpoint.as_ref().clone();
+    forked.checkpoint.map_root = forked.checkpoint.log_root.clone();
+    let forked = SerdeEnvelope::signed_contents(&test_operator_key(), forked)?;
+    match client
+        .gossip_checkpoint(None, GossipRequest { checkpoint: forked })
+        .await
+    {
+        Err(api::ClientError::Gossip(GossipError::ConflictingCheckpoint {
+            log_length,
+            checkpoint: Some(checkpoint),
+        })) => {
+            assert_eq!(log_length, ts_checkpoint.as_ref().checkpoint.log_length);
+            assert_eq!(checkpoint.as_ref().as_ref(), ts_checkpoint.as_ref());
+        }
+        Err(e) => panic!("expected a conflicting checkpoint error, but got {e:?}"),
+        Ok(_) => panic!("expected a conflicting checkpoint error"),
+    }
+
+    Ok(())
+}
+
+async fn test_map_multi_proofs(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+
+    let ts_checkpoint = client.latest_checkpoint(None).await?;
+    let checkpoint = &ts_checkpoint.as_ref().checkpoint;
+
+    // The map contains the latest leaf of every log in the ledger
+    let sources = client.ledger_sources(None).await?;
+    let mut latest = IndexMap::new();
+    for source in &sources.sources {
+        let records = client
+            .ledger_records(None, sources.hash_algorithm, source)
+            .await?;
+        for (i, leaf) in records.into_iter().enumerate() {
+            latest.insert(leaf.log_id.clone(), (source.first_registry_index + i, leaf));
+        }
+    }
+    let (indexes, leafs): (Vec<_>, Vec<_>) = latest.into_values().unzip();
+    assert!(leafs.len() > 1);
+
+    // Both the multi-proof and the individual proofs are validated
+    let mut sizes = Vec::new();
+    for map_multi_proof in [true, false] {
+        let response = client
+            .prove_inclusion(
+                None,
+                InclusionRequest {
+                    log_length: checkpoint.log_length,
+                    leafs: indexes.clone(),
+                    map_multi_proof,
+                },
+                checkpoint,
+                &leafs,
+            )
+            .await?;
+        sizes.push(response.map.len());
+    }
+    assert!(sizes[0] < sizes[1]);
+
+    Ok(())
+}
+
+async fn test_fetch_checkpoints(config: &Config) -> Result<()> {
+    let client = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let latest = client.latest_checkpoint(None).await?;
+
+    // Page through the history one checkpoint at a time
+    let mut history = Vec::new();
+    let mut since = None;
+    loop {
+        let page = client
+            .fetch_checkpoints(
+                None,
+                FetchCheckpointsQuery {
+                    since,
+                    limit: Some(1),
+                },
+            )
+            .await?;
+        assert!(page.checkpoints.len() <= 1);
+        history.extend(page.checkpoints);
+        since = history.last().map(|c| c.as_ref().checkpoint.log_length);
+        if !page.more {
+            break;
+        }
+    }
+
+    assert!(history.len() > 1);
+    assert!(history.windows(2).all(
+        |pair| pair[0].as_ref().checkpoint.log_length < pair[1].as_ref().checkpoint.log_length
+    ));
+    assert_eq!(history.last().unwrap().as_ref(), latest.as_ref());
+
+    // The limit must be within bounds
+    match client
+        .fetch_checkpoints(
+            None,
+            FetchCheckpointsQuery {
+                since: None,
+                limit: Some(0),
+            },
+        )
+        .await
+    {
+        Err(api::ClientError::Fetch(FetchError::Message { status, .. })) => {
+            assert_eq!(status, StatusCode::BAD_REQUEST.as_u16())
+        }
+        res => panic!("expected a bad request error, but got {res:?}"),
+    }
+
+    Ok(())
+}
+
+async fn test_mirror(root: &Path, config: &Config) -> Result<()> {
+    let name = PackageName::new("test:mirrored")?;
+    let client = create_client(config).await?;
+    let signing_key = test_signing_key();
+    let digest =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    let (_mirror, mirror_config) = spawn_mirror(root, config).await?;
+    let mirror_client = create_client(&mirror_config).await?;
+
+    // The mirror serves the upstream checkpoint and caches the content
+    let upstream_api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let mirror_api = api::Client::new(mirror_config.home_url.as_ref().unwrap(), None)?;
+    assert_eq!(
+        mirror_api
+            .latest_checkpoint(None)
+            .await?
+            .as_ref()
+            .checkpoint,
+        upstream_api
+            .latest_checkpoint(None)
+            .await?
+            .as_ref()
+            .checkpoint
+    );
+    let download = mirror_client
+        .download(&name, &"0.1.0".parse()?)
+        .await?
+        .context("failed to resolve package from mirror")?;
+    assert_eq!(download.digest, digest);
+
+    // The mirror follows new upstream checkpoints
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    let mut download = None;
+    for _ in 0..50 {
+        mirror_client.update().await?;
+        download = mirror_client.download(&name, &"0.2.0".parse()?).await?;
+        if download.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(
+        download.context("mirror did not follow upstream")?.version,
+        "0.2.0".parse()?
+    );
+
+    // The mirror is read-only
+    let err = publish_component(
+        &mirror_client,
+        &name,
+        "0.3.0",
+        "(component)",
+        false,
+        &signing_key,
+    )
+    .await
+    .expect_err("publishing to a mirror should fail");
+    assert!(
+        err.to_string().contains("read-only mirror"),
+        "unexpected error: {err}"
+    );
+
+    Ok(())
+}
+
+async fn test_restart(root: &Path) -> Result<()> {
+    let name = PackageName::new("test:restarted")?;
+    let signing_key = test_signing_key();
+    let store = MemoryDataStore::default();
+
+    let (server, config) = spawn_server(root, None, Some(Box::new(store.clone())), None).await?;
+    let client = create_client(&config).await?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    let api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let ts_checkpoint = api.latest_checkpoint(None).await?;
+    drop(client);
+    drop(server);
+
+    // The restarted server resumes from its persisted log and map
+    let (server, config) = spawn_server(root, None, Some(Box::new(store.clone())), None).await?;
+    let api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    assert_eq!(
+        api.latest_checkpoint(None).await?.as_ref().checkpoint,
+        ts_checkpoint.as_ref().checkpoint
+    );
+    let client = create_client(&config).await?;
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    client.update().await?;
+    assert!(client.download(&name, &"0.2.0".parse()?).await?.is_some());
+    drop(server);
+
+    // A persisted log and map from another registry are rejected
+    let other = root.join("other");
+    let other_store = MemoryDataStore::default();
+    let (server, _) = spawn_server(&other, None, Some(Box::new(other_store.clone())), None).await?;
+    drop(server);
+    let err = spawn_server(root, None, Some(Box::new(other_store)), None)
+        .await
+        .err()
+        .expect("starting with another registry's data store should fail");
+    assert!(
+        err.to_string().contains("do not match"),
+        "unexpected error: {err}"
+    );
+
+    Ok(())
+}
+
+async fn test_mirror_forged_checkpoint(config: &Config) -> Result<()> {
+    let name = PackageName::new("test:forged")?;
+    let client = create_client(config).await?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    let (_proxy, upstream, forge) = spawn_forging_proxy(config).await?;
+    let (core, _handle) = CoreService::start(
+        None,
+        None,
+        Box::<MemoryDataStore>::default(),
+        Duration::from_secs(60),
+        None,
+        None,
+        None,
+    )
+    .await?;
+    let mut mirror = MirrorService::new(core.clone(), upstream).await?;
+
+    // A forged checkpoint is rejected without changing the mirror
+    let err = mirror
+        .sync()
+        .await
+        .expect_err("a forged checkpoint should be rejected");
+    assert!(
+        err.to_string().contains("does not match"),
+        "unexpected error: {err}"
+    );
+    assert_eq!(core.log_length().await, 0);
+    assert_eq!(core.checkpoint_length(), 0);
+    assert!(core
+        .store()
+        .get_all_validated_records()
+        .await?
+        .next()
+        .await
+        .is_none());
+
+    // The mirror still follows the genuine checkpoint
+    forge.store(false, std::sync::atomic::Ordering::SeqCst);
+    let upstream_api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let ts_checkpoint = upstream_api.latest_checkpoint(None).await?;
+    let log_length = mirror.sync().await?;
+    assert!(log_length >= ts_checkpoint.as_ref().checkpoint.log_length);
+    assert_eq!(core.checkpoint_length(), log_length);
+
+    Ok(())
+}
+
+async fn test_federation_proxy(root: &Path, config: &Config) -> Result<()> {
+    let name = PackageName::new("test:proxied")?;
+    let client = create_client(config).await?;
+    let signing_key = test_signing_key();
+    let digest =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    let (_proxy, proxy_config) = spawn_proxy(root, config).await?;
+
+    // The proxy serves the upstream logs with proofs rooted in the upstream checkpoint
+    let log_id = LogId::package_log::<Sha256>(&name);
+    let upstream_api = api::Client::new(config.home_url.as_ref().unwrap(), None)?;
+    let proxy_api = api::Client::new(proxy_config.home_url.as_ref().unwrap(), None)?;
+    let expected = upstream_api.fetch_package(None, &log_id).await?;
+    let response = proxy_api.fetch_package(None, &log_id).await?;
+    assert_eq!(
+        response.checkpoint.as_ref().checkpoint,
+        expected.checkpoint.as_ref().checkpoint
+    );
+    assert_eq!(response.records.len(), expected.records.len());
+    assert_eq!(response.map, expected.map);
+    assert_ne!(
+        response.checkpoint,
+        proxy_api.latest_checkpoint(None).await?,
+        "expected the upstream checkpoint"
+    );
+
+    // A refreshed package is served at a newer upstream checkpoint once it
+    // is proven consistent with the checkpoint pinned on first contact
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    let expected = upstream_api.fetch_package(None, &log_id).await?;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let refreshed = proxy_api.fetch_package(None, &log_id).await?;
+    assert_eq!(
+        refreshed.checkpoint.as_ref().checkpoint,
+        expected.checkpoint.as_ref().checkpoint
+    );
+    assert_eq!(refreshed.records.len(), 2);
+    assert!(
+        refreshed.checkpoint.as_ref().checkpoint.log_length
+            > response.checkpoint.as_ref().checkpoint.log_length
+    );
+
+    // The pinned upstream checkpoint is persisted with the proxy's content
+    let pins: serde_json::Value = serde_json::from_slice(&std::fs::read(
+        root.join("proxy/server/upstream-pins.json"),
+    )?)?;
+    let pinned = pins
+        .as_object()
+        .and_then(|pins| pins.values().next())
+        .context("missing upstream pin")?;
+    assert_eq!(
+        pinned["checkpoint"]["logLength"],
+        refreshed.checkpoint.as_ref().checkpoint.log_length
+    );
+
+    // The proxy caches the content of proxied packages
+    let ContentSourcesResponse { content_sources } =
+        proxy_api.content_sources(None, &digest).await?;
+    let sources = content_sources
+        .get(&digest)
+        .context("missing content sources")?;
+    let ContentSource::HttpGet { url, .. } = &sources[0];
+    assert!(
+        url.starts_with(proxy_config.home_url.as_ref().unwrap()),
+        "expected content to be served by the proxy: {url}"
+    );
+
+    // A client mapping the namespace to the upstream registry names it in the registry header
+    let upstream_url: Url = config.home_url.as_ref().unwrap().parse()?;
+    let upstream_domain = format!(
+        "{host}:{port}",
+        host = upstream_url.host_str().unwrap(),
+        port = upstream_url.port().unwrap()
+    );
+    let proxy_client = create_client(&proxy_config).await?;
+    proxy_client
+        .store_namespace("test".to_string(), upstream_domain.parse()?)
+        .await?;
+    let download = proxy_client
+        .download_with_proof(&name, &"0.1.0".parse()?)
+        .await?
+        .context("failed to resolve package from proxy")?;
+    assert_eq!(download.digest, digest);
+
+    // Registries that are not imported from are rejected
+    match proxy_api
+        .fetch_package(Some(&"example.com".parse()?), &log_id)
+        .await
+    {
+        Err(api::ClientError::Fetch(FetchError::Message { status, .. })) => {
+            assert_eq!(status, StatusCode::BAD_REQUEST.as_u16());
+        }
+        other => panic!("expected a bad request error: {other:?}"),
+    }
+
     Ok(())
 }
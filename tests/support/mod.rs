@@ -1,16 +1,30 @@
 use anyhow::{bail, Context, Result};
-use indexmap::IndexSet;
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header::CONTENT_TYPE, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use indexmap::{IndexMap, IndexSet};
 use std::{
     env,
     path::{Path, PathBuf},
-    sync::atomic::{AtomicUsize, Ordering},
-    time::Duration,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use tokio::{fs, task::JoinHandle};
 use tokio_util::sync::CancellationToken;
 use tracing::subscriber::DefaultGuard;
 use url::Url;
+use warg_api::v1::paths;
 use warg_client::{
+    identity::IdentityTokenSource,
     storage::{ContentStorage, PublishEntry, PublishInfo},
     FileSystemClient, StorageLockResult,
 };
@@ -18,7 +32,11 @@ use warg_crypto::{
     hash::AnyHash,
     signing::{KeyID, PrivateKey},
 };
-use warg_protocol::{operator, registry::PackageName};
+use warg_protocol::{
+    operator,
+    registry::{PackageName, TimestampedCheckpoint},
+    SerdeEnvelope,
+};
 use warg_server::{
     datastore::DataStore,
     policy::{content::WasmContentPolicy, record::AuthorizedKeyPolicy},
@@ -43,6 +61,53 @@ pub fn test_signing_key() -> PrivateKey {
     PrivateKey::decode(key.to_string()).unwrap()
 }
 
+/// The issuer of identity tokens trusted by the test server.
+pub const TEST_IDENTITY_ISSUER: &str = "https://issuer.test";
+
+pub fn test_identity_issuer_key() -> PrivateKey {
+    let key = "ecdsa-p256:JHaabxU6Wd8RWQFkrawHyT8FrGSCSKj0+sJWnQCWtVI=";
+    PrivateKey::decode(key.to_string()).unwrap()
+}
+
+/// A local identity issuer that mints identity tokens for a subject.
+pub struct TestIdentityIssuer {
+    pub subject: String,
+    /// Overrides the audience requested by the client.
+    pub audience: Option<String>,
+}
+
+impl TestIdentityIssuer {
+    pub fn new(subject: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            audience: None,
+        }
+    }
+}
+
+#[async_trait]
+impl IdentityTokenSource for TestIdentityIssuer {
+    async fn token(&self, audience: &str) -> Result<String> {
+        let exp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + 60;
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"ES256","typ":"JWT"}"#);
+        let payload = URL_SAFE_NO_PAD.encode(
+            serde_json::json!({
+                "iss": TEST_IDENTITY_ISSUER,
+                "sub": self.subject,
+                "aud": self.audience.as_deref().unwrap_or(audience),
+                "exp": exp,
+            })
+            .to_string(),
+        );
+        let signed = format!("{header}.{payload}");
+        let signature = test_identity_issuer_key().sign(signed.as_bytes())?;
+        Ok(format!(
+            "{signed}.{signature}",
+            signature = URL_SAFE_NO_PAD.encode(signature.raw_bytes())
+        ))
+    }
+}
+
 pub async fn create_client(config: &warg_client::Config) -> Result<FileSystemClient> {
     match FileSystemClient::try_new_with_config(None, config, None).await? {
         StorageLockResult::Acquired(client) => Ok(client),
@@ -117,6 +182,24 @@ pub async fn spawn_server(
     content_base_url: Option<Url>,
     data_store: Option<Box<dyn DataStore>>,
     authorized_keys: Option<Vec<(String, KeyID)>>,
+) -> Result<(ServerInstance, warg_client::Config)> {
+    spawn_configured_server(
+        root,
+        content_base_url,
+        data_store,
+        authorized_keys,
+        |config| config,
+    )
+    .await
+}
+
+/// Spawns a server as a background task, applying additional configuration.
+pub async fn spawn_configured_server(
+    root: &Path,
+    content_base_url: Option<Url>,
+    data_store: Option<Box<dyn DataStore>>,
+    authorized_keys: Option<Vec<(String, KeyID)>>,
+    configure: impl FnOnce(Config) -> Config,
 ) -> Result<(ServerInstance, warg_client::Config)> {
     let _subscriber_guard = thread_test_logging();
 
@@ -125,6 +208,12 @@ pub async fn spawn_server(
         .with_addr(([127, 0, 0, 1], 0))
         .with_shutdown(shutdown.clone().cancelled_owned())
         .with_checkpoint_interval(Duration::from_millis(100))
+        .with_log_dir(root.join("server").join("log"))
+        .with_map_dir(root.join("server").join("map"))
+        .with_identity_issuer(
+            TEST_IDENTITY_ISSUER,
+            test_identity_issuer_key().public_key(),
+        )
         .with_content_policy(WasmContentPolicy::default()); // For the tests, we assume only wasm content is allowed.
 
     if let Some(content_url) = content_base_url {
@@ -144,6 +233,139 @@ pub async fn spawn_server(
         config = config.with_boxed_data_store(store);
     }
 
+    start_server(root, configure(config), shutdown, _subscriber_guard).await
+}
+
+/// Spawns a read-only mirror of the given server as a background task.
+pub async fn spawn_mirror(
+    root: &Path,
+    upstream: &warg_client::Config,
+) -> Result<(ServerInstance, warg_client::Config)> {
+    let _subscriber_guard = thread_test_logging();
+
+    let root = root.join("mirror");
+    for dir in ["server", "registries", "content"] {
+        fs::create_dir_all(root.join(dir)).await?;
+    }
+
+    let shutdown = CancellationToken::new();
+    let upstream = upstream
+        .home_url
+        .as_deref()
+        .context("upstream server has no URL")?
+        .parse()?;
+    let config = Config::new_mirror(upstream, root.join("server"))
+        .with_addr(([127, 0, 0, 1], 0))
+        .with_shutdown(shutdown.clone().cancelled_owned())
+        .with_checkpoint_interval(Duration::from_millis(100));
+
+    start_server(&root, config, shutdown, _subscriber_guard).await
+}
+
+/// Spawns a proxy of the given server that serves a forged checkpoint while
+/// the returned flag is set.
+///
+/// The forged checkpoint is signed by the operator key but has the wrong map root.
+pub async fn spawn_forging_proxy(
+    upstream: &warg_client::Config,
+) -> Result<(JoinHandle<()>, Url, Arc<AtomicBool>)> {
+    let upstream: Url = upstream
+        .home_url
+        .as_deref()
+        .context("upstream server has no URL")?
+        .parse()?;
+    let forge = Arc::new(AtomicBool::new(true));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{addr}/", addr = listener.local_addr()?).parse()?;
+    let router = Router::new().fallback(forward).with_state((
+        reqwest::Client::new(),
+        upstream,
+        forge.clone(),
+    ));
+    let task = tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+
+    Ok((task, url, forge))
+}
+
+async fn forward(
+    State((client, upstream, forge)): State<(reqwest::Client, Url, Arc<AtomicBool>)>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let response = match client
+        .request(method, upstream.join(path).unwrap())
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(_) => return StatusCode::BAD_GATEWAY.into_response(),
+    };
+
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    let mut bytes = response.bytes().await.unwrap_or_default();
+    if uri.path() == format!("/{path}", path = paths::fetch_checkpoint())
+        && forge.load(Ordering::SeqCst)
+    {
+        let checkpoint: SerdeEnvelope<TimestampedCheckpoint> =
+            serde_json::from_slice(&bytes).unwrap();
+        let mut forged = checkpoint.into_contents();
+        forged.checkpoint.map_root = forged.checkpoint.log_root.clone();
+        let forged = SerdeEnvelope::signed_contents(&test_operator_key(), forged).unwrap();
+        bytes = serde_json::to_vec(&forged).unwrap().into();
+    }
+
+    let mut response = (status, bytes).into_response();
+    if let Some(content_type) = content_type {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+/// Spawns a registry that imports the `test` namespace from the given
+/// server and proxies its packages.
+pub async fn spawn_proxy(
+    root: &Path,
+    upstream: &warg_client::Config,
+) -> Result<(ServerInstance, warg_client::Config)> {
+    let _subscriber_guard = thread_test_logging();
+
+    let root = root.join("proxy");
+    for dir in ["server", "registries", "content"] {
+        fs::create_dir_all(root.join(dir)).await?;
+    }
+
+    let shutdown = CancellationToken::new();
+    let registry = upstream
+        .home_url
+        .clone()
+        .context("upstream server has no URL")?;
+    let namespaces = vec![(
+        "test".to_string(),
+        operator::NamespaceState::Imported { registry },
+    )];
+    let config = Config::new(test_operator_key(), Some(namespaces), root.join("server"))
+        .with_addr(([127, 0, 0, 1], 0))
+        .with_shutdown(shutdown.clone().cancelled_owned())
+        .with_checkpoint_interval(Duration::from_millis(100))
+        .with_federation_proxy();
+
+    start_server(&root, config, shutdown, _subscriber_guard).await
+}
+
+async fn start_server(
+    root: &Path,
+    config: Config,
+    shutdown: CancellationToken,
+    _subscriber_guard: DefaultGuard,
+) -> Result<(ServerInstance, warg_client::Config)> {
     let server = Server::new(config).initialize().await?;
 
     let addr = server.local_addr()?;
@@ -170,7 +392,13 @@ pub async fn spawn_server(
         ignore_federation_hints: false,
         auto_accept_federation_hints: false,
         disable_interactive: true,
+        offline: false,
+        vendor_dir: None,
         keyring_backend: None,
+        witness_keys: Vec::new(),
+        witness_threshold: 0,
+        signing_program: None,
+        operator_pins: IndexMap::new(),
     };
 
     Ok((instance, config))